@@ -0,0 +1,8 @@
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate gluon_language_server;
+
+fuzz_target!(|data: &[u8]| {
+    gluon_language_server::fuzz_message_stream(data);
+});
@@ -0,0 +1,125 @@
+//! Results of typechecking the workspace which are kept on disk between sessions. Each module is
+//! stored with a fingerprint of its source and the sources of the workspace modules it imports,
+//! so a module whose fingerprint is unchanged when the workspace is opened again does not need to
+//! be typechecked to publish its diagnostics.
+use std::collections::HashMap;
+use std::error::Error as StdError;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde_json;
+
+use language_server::Diagnostic;
+
+#[derive(Clone, Deserialize, Serialize)]
+struct CachedModule {
+    fingerprint: u64,
+    diagnostics: Vec<Diagnostic>,
+}
+
+#[derive(Default, Deserialize, Serialize)]
+struct CacheFile {
+    /// The version of the server which wrote the file. Files written by other versions are
+    /// ignored as their diagnostics may differ.
+    version: String,
+    modules: HashMap<String, CachedModule>,
+}
+
+struct State {
+    path: Option<PathBuf>,
+    /// The modules loaded from disk
+    previous: HashMap<String, CachedModule>,
+    /// The modules checked in this session which are written on `save`
+    current: HashMap<String, CachedModule>,
+}
+
+/// Returns the file which the cache of the workspace at `root` is stored in
+fn cache_file(root: &Path) -> PathBuf {
+    root.join("target").join("gluon").join("analysis.json")
+}
+
+fn read(path: &Path) -> Result<CacheFile, Box<StdError>> {
+    let mut contents = String::new();
+    try!(try!(File::open(path)).read_to_string(&mut contents));
+    Ok(try!(serde_json::from_str(&contents)))
+}
+
+pub struct AnalysisCache {
+    state: Mutex<State>,
+}
+
+impl AnalysisCache {
+    pub fn new() -> AnalysisCache {
+        AnalysisCache {
+            state: Mutex::new(State {
+                path: None,
+                previous: HashMap::new(),
+                current: HashMap::new(),
+            }),
+        }
+    }
+
+    /// Loads the cache of the workspace at `root`. A missing or unreadable cache is treated as
+    /// empty.
+    pub fn load(&self, root: &Path) {
+        let path = cache_file(root);
+        let file = match read(&path) {
+            Ok(ref file) if file.version != env!("CARGO_PKG_VERSION") => CacheFile::default(),
+            Ok(file) => file,
+            Err(err) => {
+                info!("No analysis cache loaded from `{}`: {}", path.display(), err);
+                CacheFile::default()
+            }
+        };
+        let mut state = self.state.lock().unwrap();
+        state.path = Some(path);
+        state.previous = file.modules;
+    }
+
+    /// Returns the diagnostics which were cached for `module` if its fingerprint is unchanged
+    pub fn lookup(&self, module: &str, fingerprint: u64) -> Option<Vec<Diagnostic>> {
+        let mut state = self.state.lock().unwrap();
+        match state.previous.remove(module) {
+            Some(cached) => {
+                if cached.fingerprint == fingerprint {
+                    let diagnostics = cached.diagnostics.clone();
+                    state.current.insert(module.into(), cached);
+                    Some(diagnostics)
+                } else {
+                    None
+                }
+            }
+            None => None,
+        }
+    }
+
+    /// Records the diagnostics of `module` so that they are written on `save`
+    pub fn record(&self, module: &str, fingerprint: u64, diagnostics: Vec<Diagnostic>) {
+        self.state.lock().unwrap().current.insert(module.into(),
+                                                  CachedModule {
+                                                      fingerprint: fingerprint,
+                                                      diagnostics: diagnostics,
+                                                  });
+    }
+
+    /// Writes the modules recorded in this session to the cache of the loaded workspace
+    pub fn save(&self) -> Result<(), Box<StdError>> {
+        let state = self.state.lock().unwrap();
+        let path = match state.path.clone() {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        if let Some(dir) = path.parent() {
+            try!(fs::create_dir_all(dir));
+        }
+        let file = CacheFile {
+            version: env!("CARGO_PKG_VERSION").into(),
+            modules: state.current.clone(),
+        };
+        let contents = try!(serde_json::to_string(&file));
+        try!(try!(File::create(&path)).write_all(contents.as_bytes()));
+        Ok(())
+    }
+}
@@ -0,0 +1,78 @@
+//! Annotating the top-level bindings of a module with their inferred types, as in
+//! `let add x y : Int -> Int -> Int = x + y`. An annotation is written after the name and the
+//! arguments of a binding, which are found by scanning the source from where the name starts.
+use gluon::base::ast::{Binding, Expr, LExpr, Pattern};
+use gluon::base::types::TcIdent;
+
+use language_server::{Range, TextEdit};
+use pipeline::{identifier_len, offset, position, skip_trivia};
+
+/// Returns the bindings of the `let` and `type` expressions which `expr` starts with
+fn top_level_bindings(mut expr: &LExpr<TcIdent>) -> Vec<&Binding<TcIdent>> {
+    let mut bindings = Vec::new();
+    loop {
+        match expr.value {
+            Expr::Let(ref binds, ref body) => {
+                bindings.extend(binds);
+                expr = body;
+            }
+            Expr::Type(_, ref body) => expr = body,
+            _ => return bindings,
+        }
+    }
+}
+
+/// Returns the offset after the name and the arguments of the binding whose name starts at or
+/// after `start`, or `None` if the binding is annotated already
+fn annotation_offset(source: &str, start: usize) -> Option<usize> {
+    let mut i = skip_trivia(source, start);
+    // An operator is bound with its name in parentheses, as in `let (+++) l r = ..`
+    if source[i..].starts_with('(') {
+        i += match source[i..].find(')') {
+            Some(end) => end + 1,
+            None => return None,
+        };
+    } else {
+        match identifier_len(&source[i..]) {
+            0 => return None,
+            len => i += len,
+        }
+    }
+    loop {
+        let end = i;
+        i = skip_trivia(source, i);
+        match identifier_len(&source[i..]) {
+            0 if source[i..].starts_with('=') => return Some(end),
+            0 => return None,
+            len => i += len,
+        }
+    }
+}
+
+/// Returns the edits which annotate the top-level bindings of the typechecked module `expr`, the
+/// module without the implicit prelude, which are not annotated yet
+pub fn annotations(source: &str, expr: &LExpr<TcIdent>) -> Vec<TextEdit> {
+    top_level_bindings(expr)
+        .into_iter()
+        .filter(|bind| bind.typ.is_none())
+        .filter_map(|bind| {
+            let id = match bind.name.value {
+                Pattern::Identifier(ref id) => id,
+                // Only variables are annotated, not the fields of a record pattern
+                _ => return None,
+            };
+            offset(source, &bind.name.location)
+                .and_then(|start| annotation_offset(source, start))
+                .map(|end| {
+                    let end = position(source, end);
+                    TextEdit {
+                        range: Range {
+                            start: end,
+                            end: end,
+                        },
+                        new_text: format!(" : {}", id.typ),
+                    }
+                })
+        })
+        .collect()
+}
@@ -0,0 +1,147 @@
+//! Benchmarks, the top-level functions of a module whose name starts with `bench_`, such as
+//! `let bench_sum _ = foldl (+) 0 numbers`. A benchmark is run by calling it with `()` from a copy
+//! of its module whose body is replaced by a function which does the call, so benchmarks which the
+//! module does not export can be run as well.
+use std::time::Duration;
+
+use gluon::base::ast::{Expr, LExpr, Pattern};
+use gluon::base::types::TcIdent;
+
+use language_server::Range;
+use pipeline::{extent, is_identifier_char, offset, position, skip_trivia};
+use query;
+
+pub const PREFIX: &'static str = "bench_";
+
+/// How many times a benchmark is run unless the `gluon.bench.iterations` setting says otherwise
+pub const DEFAULT_ITERATIONS: usize = 100;
+
+pub struct Bench {
+    pub name: String,
+    /// The range of the name of the binding
+    pub range: Range,
+}
+
+/// The statistics of the times which the runs of a benchmark took
+#[derive(Debug, PartialEq)]
+pub struct Timings {
+    pub mean: Duration,
+    pub min: Duration,
+    pub median: Duration,
+    pub p95: Duration,
+    pub max: Duration,
+}
+
+/// Returns the top-level bindings of the parsed module `expr` and the body which they are bound
+/// in
+fn top_level(expr: &LExpr<TcIdent>) -> (Vec<&LExpr<TcIdent>>, &LExpr<TcIdent>) {
+    let mut lets = Vec::new();
+    let mut expr = query::strip_implicit_prelude(expr);
+    loop {
+        match expr.value {
+            Expr::Let(_, ref body) => {
+                lets.push(expr);
+                expr = body;
+            }
+            Expr::Type(_, ref body) => expr = body,
+            _ => return (lets, expr),
+        }
+    }
+}
+
+/// Returns the benchmarks of the parsed module `expr`, which is parsed from `source`
+pub fn benches(source: &str, expr: &LExpr<TcIdent>) -> Vec<Bench> {
+    let mut benches = Vec::new();
+    for expr in top_level(expr).0 {
+        let bindings = match expr.value {
+            Expr::Let(ref bindings, _) => bindings,
+            _ => continue,
+        };
+        for bind in bindings.iter().filter(|bind| !bind.arguments.is_empty()) {
+            let name = match bind.name.value {
+                Pattern::Identifier(ref id) if id.name.as_ref().starts_with(PREFIX) => {
+                    id.name.as_ref()
+                }
+                _ => continue,
+            };
+            if let Some(start) = offset(source, &bind.name.location) {
+                let start = skip_trivia(source, start);
+                let end = source[start..]
+                    .find(|c: char| !is_identifier_char(c))
+                    .map_or(source.len(), |i| start + i);
+                benches.retain(|bench: &Bench| bench.name != name);
+                benches.push(Bench {
+                    name: name.into(),
+                    range: Range {
+                        start: position(source, start),
+                        end: position(source, end),
+                    },
+                });
+            }
+        }
+    }
+    benches
+}
+
+/// Returns the source of the module `expr`, parsed from `source`, with its body replaced by a
+/// function of type `() -> ()` which runs the benchmark `name` once
+pub fn runner(source: &str, expr: &LExpr<TcIdent>, name: &str) -> Option<String> {
+    if !benches(source, expr).iter().any(|bench| bench.name == name) {
+        return None;
+    }
+    let body = top_level(expr).1;
+    extent(source, body).map(|(start, _)| {
+        format!("{}\\__bench_unit -> let __bench_result = {} () in ()",
+                &source[..start],
+                name)
+    })
+}
+
+/// Returns the time which `percent` percent of `sorted` took at most
+fn percentile(sorted: &[Duration], percent: usize) -> Duration {
+    let rank = (sorted.len() * percent + 99) / 100;
+    sorted[rank.max(1) - 1]
+}
+
+/// Returns the statistics of the non-empty `samples`
+pub fn timings(samples: &mut [Duration]) -> Timings {
+    samples.sort();
+    let total = samples.iter().fold(Duration::new(0, 0), |total, &sample| total + sample);
+    Timings {
+        mean: total / samples.len() as u32,
+        min: samples[0],
+        median: percentile(samples, 50),
+        p95: percentile(samples, 95),
+        max: samples[samples.len() - 1],
+    }
+}
+
+pub fn millis(duration: Duration) -> f64 {
+    duration.as_secs() as f64 * 1e3 + duration.subsec_nanos() as f64 / 1e6
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    #[test]
+    fn timings_of_samples() {
+        let mut samples: Vec<_> = (1..21).rev().map(|ms| Duration::from_millis(ms)).collect();
+        let timings = timings(&mut samples);
+        assert_eq!(timings.mean, Duration::new(0, 10_500_000));
+        assert_eq!(timings.min, Duration::from_millis(1));
+        assert_eq!(timings.median, Duration::from_millis(10));
+        assert_eq!(timings.p95, Duration::from_millis(19));
+        assert_eq!(timings.max, Duration::from_millis(20));
+    }
+
+    #[test]
+    fn timings_of_one_sample() {
+        let timings = timings(&mut [Duration::from_millis(3)]);
+        assert_eq!(timings.median, Duration::from_millis(3));
+        assert_eq!(timings.p95, Duration::from_millis(3));
+        assert_eq!(millis(timings.mean), 3.0);
+    }
+}
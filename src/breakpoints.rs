@@ -0,0 +1,337 @@
+//! Breakpoints and stepping for the debug adapter. The virtual machine has no support for either
+//! so a call to a primitive function is inserted before every expression which the program can
+//! stop at, its step points, before the program is compiled. The debugger decides whether the
+//! program stops when a call is made. The message of every call to `error` is passed through
+//! another primitive as well so that the program can stop where an error is raised.
+use std::collections::HashMap;
+use std::mem;
+
+use gluon::base::ast::{self, Expr, LExpr, LPattern, LiteralEnum, Location, Pattern, Typed};
+use gluon::base::symbol::Symbol;
+use gluon::base::types::{TcIdent, TcType, TypeEnv};
+
+use query;
+
+/// Name of the primitive which is called at every step point. It takes the index of the module of
+/// the step point, its line and column and a record containing the local variables as arguments.
+pub const BREAK_FUNCTION: &'static str = "__debug_break";
+
+/// Name of the primitive which the argument of every call to `error` is passed to. It returns its
+/// argument.
+pub const RAISE_FUNCTION: &'static str = "__debug_raise";
+
+fn pattern_names(pattern: &LPattern<TcIdent>, names: &mut Vec<Symbol>) {
+    match pattern.value {
+        Pattern::Identifier(ref id) => names.push(id.name.clone()),
+        Pattern::Constructor(_, ref args) => names.extend(args.iter().map(|arg| arg.name.clone())),
+        Pattern::Record { ref fields, .. } => {
+            names.extend(fields.iter().map(|&(ref name, ref alias)| {
+                alias.as_ref().unwrap_or(name).clone()
+            }))
+        }
+    }
+}
+
+/// Calls `f` with `expr`, and whether the program can stop at it, and then visits the
+/// subexpressions of `expr`. Only expressions which get evaluated on their own (bindings, bodies,
+/// branches) are breakable, operands and arguments are not. `scope` holds the variables which are
+/// in scope at `expr`.
+fn visit<F>(expr: &mut LExpr<TcIdent>, breakable: bool, scope: &mut Vec<Symbol>, f: &mut F)
+    where F: FnMut(&mut LExpr<TcIdent>, bool, &[Symbol])
+{
+    f(expr, breakable, scope);
+    let scope_len = scope.len();
+    match expr.value {
+        Expr::Identifier(_) |
+        Expr::Literal(_) => (),
+        Expr::Call(ref mut func, ref mut args) => {
+            visit(func, false, scope, f);
+            for arg in args {
+                visit(arg, false, scope, f);
+            }
+        }
+        Expr::IfElse(ref mut pred, ref mut if_true, ref mut if_false) => {
+            visit(pred, false, scope, f);
+            visit(if_true, true, scope, f);
+            if let Some(ref mut if_false) = *if_false {
+                visit(if_false, true, scope, f);
+            }
+        }
+        Expr::Match(ref mut scrutinee, ref mut alts) => {
+            visit(scrutinee, false, scope, f);
+            for alt in alts {
+                pattern_names(&alt.pattern, scope);
+                visit(&mut alt.expression, true, scope, f);
+                scope.truncate(scope_len);
+            }
+        }
+        Expr::BinOp(ref mut lhs, _, ref mut rhs) => {
+            visit(lhs, false, scope, f);
+            visit(rhs, false, scope, f);
+        }
+        Expr::Let(ref mut bindings, ref mut body) => {
+            for bind in bindings.iter_mut() {
+                scope.extend(bind.arguments.iter().map(|arg| arg.name.clone()));
+                visit(&mut bind.expression, true, scope, f);
+                scope.truncate(scope_len);
+            }
+            for bind in bindings.iter() {
+                pattern_names(&bind.name, scope);
+            }
+            visit(body, true, scope, f);
+        }
+        Expr::FieldAccess(ref mut record, _) => visit(record, false, scope, f),
+        Expr::Array(ref mut array) => {
+            for expr in &mut array.expressions {
+                visit(expr, false, scope, f);
+            }
+        }
+        Expr::Record { ref mut exprs, .. } => {
+            for field in exprs {
+                if let Some(ref mut expr) = field.1 {
+                    visit(expr, false, scope, f);
+                }
+            }
+        }
+        Expr::Lambda(ref mut lambda) => {
+            scope.extend(lambda.arguments.iter().map(|arg| arg.name.clone()));
+            visit(&mut lambda.body, true, scope, f);
+        }
+        Expr::Tuple(ref mut exprs) => {
+            for expr in exprs {
+                visit(expr, false, scope, f);
+            }
+        }
+        Expr::Block(ref mut exprs) => {
+            for expr in exprs {
+                visit(expr, true, scope, f);
+            }
+        }
+        Expr::Type(_, ref mut body) => visit(body, true, scope, f),
+    }
+    scope.truncate(scope_len);
+}
+
+/// Calls `f` with each expression of `expr` which the program can stop at. `f` may replace the
+/// expression, and the subexpressions of the replacement are visited afterwards.
+pub fn each_step_point<F>(expr: &mut LExpr<TcIdent>, mut f: F)
+    where F: FnMut(&mut LExpr<TcIdent>)
+{
+    visit(expr, true, &mut Vec::new(), &mut |expr, breakable, _| {
+        if breakable {
+            f(expr);
+        }
+    });
+}
+
+/// Returns the location of the first breakable expression which starts at or after `line`
+/// (1-based). A breakpoint which is set on a line without any code moves to that location.
+pub fn adjusted_location(expr: &mut LExpr<TcIdent>, line: i64) -> Option<Location> {
+    let mut target: Option<Location> = None;
+    visit(expr, true, &mut Vec::new(), &mut |expr, breakable, _| {
+        let location = expr.location;
+        if breakable && location.row as i64 >= line &&
+           target.map_or(true, |target| location < target) {
+            target = Some(location);
+        }
+    });
+    target
+}
+
+/// Creates a record which contains the innermost variable of each name in `scope`
+fn locals_record(location: Location, scope: &[Symbol]) -> LExpr<TcIdent> {
+    let mut locals: Vec<&Symbol> = Vec::new();
+    for name in scope.iter().rev() {
+        if !locals.iter().any(|local| local.as_ref() == name.as_ref()) {
+            locals.push(name);
+        }
+    }
+    if locals.is_empty() {
+        return ast::located(location, Expr::Tuple(vec![]));
+    }
+    let exprs = locals.into_iter()
+        .rev()
+        .map(|name| {
+            // A fresh symbol for the field name makes sure that the record is not mistaken for
+            // a record type which happens to have the same field names
+            let value = Expr::Identifier(TcIdent::new(name.clone()));
+            (Symbol::new(name.as_ref()), Some(ast::located(location, value)))
+        })
+        .collect();
+    ast::located(location,
+                 Expr::Record {
+                     typ: TcIdent::new(Symbol::new("")),
+                     types: vec![],
+                     exprs: exprs,
+                 })
+}
+
+fn break_call(module: i64, location: Location, scope: &[Symbol]) -> LExpr<TcIdent> {
+    let function = ast::located(location,
+                                Expr::Identifier(TcIdent::new(Symbol::new(BREAK_FUNCTION))));
+    let args = vec![ast::located(location, Expr::Literal(LiteralEnum::Integer(module))),
+                    ast::located(location,
+                                 Expr::Literal(LiteralEnum::Integer(location.row as i64))),
+                    ast::located(location,
+                                 Expr::Literal(LiteralEnum::Integer(location.column as i64))),
+                    locals_record(location, scope)];
+    ast::located(location, Expr::Call(Box::new(function), args))
+}
+
+fn is_identifier(expr: &LExpr<TcIdent>, name: &str) -> bool {
+    match expr.value {
+        Expr::Identifier(ref id) => id.name.as_ref() == name,
+        _ => false,
+    }
+}
+
+/// Passes the argument of `expr` to `RAISE_FUNCTION` if `expr` is a call to `error`
+fn wrap_raise(expr: &mut LExpr<TcIdent>) {
+    if let Expr::Call(ref func, ref mut args) = expr.value {
+        if !is_identifier(func, "error") || args.len() != 1 {
+            return;
+        }
+        let wrapped = match args[0].value {
+            Expr::Call(ref func, _) => is_identifier(func, RAISE_FUNCTION),
+            _ => false,
+        };
+        if wrapped {
+            return;
+        }
+        let location = args[0].location;
+        let message = args.remove(0);
+        let function = Expr::Identifier(TcIdent::new(Symbol::new(RAISE_FUNCTION)));
+        args.push(ast::located(location,
+                               Expr::Call(Box::new(ast::located(location, function)),
+                                          vec![message])));
+    }
+}
+
+/// Inserts a call to `BREAK_FUNCTION` before every step point of the module `expr`, whose index
+/// is `module`, and the calls to `RAISE_FUNCTION`. Expressions which start at the same location
+/// share a step point.
+pub fn instrument(expr: &mut LExpr<TcIdent>, module: i64) {
+    let mut instrumented: Vec<Location> = Vec::new();
+    visit(expr, true, &mut Vec::new(), &mut |expr, breakable, scope| {
+        wrap_raise(expr);
+        if !breakable {
+            return;
+        }
+        let location = expr.location;
+        // The expression is visited again inside the block which replaces it
+        match instrumented.binary_search(&location) {
+            Ok(_) => return,
+            Err(i) => instrumented.insert(i, location),
+        }
+        let original = mem::replace(&mut expr.value, Expr::Block(vec![]));
+        expr.value = Expr::Block(vec![break_call(module, location, scope),
+                                      ast::located(location, original)]);
+    });
+}
+
+fn collect_local_types(expr: &LExpr<TcIdent>,
+                       env: &TypeEnv,
+                       types: &mut HashMap<(i64, i64, i64), TcType>) {
+    if let Expr::Call(ref func, ref args) = expr.value {
+        match (&func.value, args.get(0), args.get(1), args.get(2), args.get(3)) {
+            (&Expr::Identifier(ref id), Some(module), Some(line), Some(column), Some(locals))
+                if id.name.as_ref() == BREAK_FUNCTION => {
+                if let (&Expr::Literal(LiteralEnum::Integer(module)),
+                        &Expr::Literal(LiteralEnum::Integer(line)),
+                        &Expr::Literal(LiteralEnum::Integer(column))) = (&module.value,
+                                                                          &line.value,
+                                                                          &column.value) {
+                    types.insert((module, line, column), locals.env_type_of(env));
+                }
+            }
+            _ => (),
+        }
+    }
+    for child in query::children(expr) {
+        collect_local_types(child, env, types);
+    }
+}
+
+/// Returns the type of the record of local variables passed at each step point of the
+/// typechecked and instrumented `expr`, keyed by the module, line and column of the step point
+pub fn local_types(expr: &LExpr<TcIdent>, env: &TypeEnv) -> HashMap<(i64, i64, i64), TcType> {
+    let mut types = HashMap::new();
+    collect_local_types(expr, env, &mut types);
+    types
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use gluon::Compiler;
+
+    const SOURCE: &'static str = "let x = 1\nlet f y =\n    y + x\n\nin\nf (error \"no\")\n";
+
+    fn parse(source: &str) -> LExpr<TcIdent> {
+        Compiler::new().parse_expr("test", source).unwrap()
+    }
+
+    fn integer(expr: &LExpr<TcIdent>) -> i64 {
+        match expr.value {
+            Expr::Literal(LiteralEnum::Integer(i)) => i,
+            _ => panic!("Expected an integer literal"),
+        }
+    }
+
+    /// Collects the module, line and column of each step point of the instrumented `expr` along
+    /// with the names of the local variables which are passed to it
+    fn step_points(expr: &LExpr<TcIdent>, points: &mut Vec<(i64, i64, i64, Vec<String>)>) {
+        if let Expr::Call(ref func, ref args) = expr.value {
+            if is_identifier(func, BREAK_FUNCTION) {
+                let locals = match args[3].value {
+                    Expr::Record { ref exprs, .. } => {
+                        exprs.iter().map(|field| String::from(field.0.as_ref())).collect()
+                    }
+                    _ => Vec::new(),
+                };
+                points.push((integer(&args[0]), integer(&args[1]), integer(&args[2]), locals));
+            }
+        }
+        for child in query::children(expr) {
+            step_points(child, points);
+        }
+    }
+
+    fn raises(expr: &LExpr<TcIdent>) -> usize {
+        let raise = match expr.value {
+            Expr::Call(ref func, _) if is_identifier(func, RAISE_FUNCTION) => 1,
+            _ => 0,
+        };
+        raise + query::children(expr).into_iter().map(raises).sum::<usize>()
+    }
+
+    #[test]
+    fn instrument_step_points() {
+        let mut expr = parse(SOURCE);
+        instrument(&mut expr, 3);
+        let mut points = Vec::new();
+        step_points(&expr, &mut points);
+        let strings = |names: &[&str]| names.iter().map(|&name| String::from(name)).collect();
+        assert_eq!(points,
+                   vec![(3, 1, 1, Vec::new()),
+                        (3, 1, 9, Vec::new()),
+                        (3, 3, 5, strings(&["x", "y"])),
+                        (3, 6, 1, strings(&["x", "f"]))]);
+        assert_eq!(raises(&expr), 1);
+    }
+
+    #[test]
+    fn adjust_breakpoint_locations() {
+        let location = |line| {
+            adjusted_location(&mut parse(SOURCE), line)
+                .map(|location| (location.row, location.column))
+        };
+        assert_eq!(location(1), Some((1, 1)));
+        // The line of the binding moves to its body and lines without code to the next code
+        assert_eq!(location(2), Some((3, 5)));
+        assert_eq!(location(4), Some((6, 1)));
+        assert_eq!(location(6), Some((6, 1)));
+        assert_eq!(location(7), None);
+    }
+}
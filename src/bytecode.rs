@@ -0,0 +1,167 @@
+//! Listings of the bytecode which the compiler generates for a module. The instructions carry no
+//! source locations so each function is annotated with the line of its definition, which the
+//! typechecker appends to the names of the bindings it renames. The lines of the instructions are
+//! recovered by compiling the module a second time, without typechecking it again, with a string
+//! literal which holds the line in front of each step point. Once the `PushString` of each marker
+//! is skipped the instructions of the two compilations are paired by their position. The markers
+//! are left on the stack, which shifts the stack indices in the second compilation, but since the
+//! compiler leaves out the `Slide(0)` of the block around each marker the number of instructions
+//! stays the same. If the numbers differ the listing has no lines.
+use std::fmt::Write;
+use std::mem;
+
+use gluon::base::ast::{self, Expr, LExpr, LiteralEnum, Location};
+use gluon::base::types::TcIdent;
+use gluon::vm::compiler::CompiledFunction;
+use gluon::vm::types::Instruction;
+
+use breakpoints;
+use language_server::{Position, Range};
+use query;
+use stack_trace::parse_location;
+
+/// The start of the string literals which mark the line of the code after them
+const LINE_MARKER: &'static str = "\u{0}line ";
+
+/// Returns the name of `function` without the location suffix
+fn name(function: &CompiledFunction) -> &str {
+    let id = function.id.as_ref();
+    id.split(':').next().unwrap_or(id)
+}
+
+/// Returns what the operand of `instruction` refers to if it is an index into the strings,
+/// globals or inner functions of `function`
+fn operand_comment(function: &CompiledFunction, instruction: &Instruction) -> Option<String> {
+    match *instruction {
+        Instruction::PushString(i) => {
+            function.strings.get(i as usize).map(|string| format!("{:?}", &string[..]))
+        }
+        Instruction::PushGlobal(i) => {
+            function.module_globals.get(i as usize).map(|global| String::from(global.as_ref()))
+        }
+        Instruction::MakeClosure(i, _) |
+        Instruction::NewClosure(i, _) => {
+            function.inner_functions.get(i as usize).map(|inner| String::from(name(inner)))
+        }
+        _ => None,
+    }
+}
+
+/// Inserts a marker of the line of each step point of `expr` in front of it
+pub fn mark_lines(expr: &mut LExpr<TcIdent>) {
+    let mut marked: Vec<Location> = Vec::new();
+    breakpoints::each_step_point(expr, |expr| {
+        let location = expr.location;
+        // The marker and the expression are visited again inside the block which replaces it
+        match marked.binary_search(&location) {
+            Ok(_) => return,
+            Err(i) => marked.insert(i, location),
+        }
+        let marker = LiteralEnum::String(format!("{}{}", LINE_MARKER, location.row));
+        let original = mem::replace(&mut expr.value, Expr::Block(vec![]));
+        expr.value = Expr::Block(vec![ast::located(location, Expr::Literal(marker)),
+                                      ast::located(location, original)]);
+    });
+}
+
+/// Returns the line of each instruction of `function`, taken from the instruction at the same
+/// position in `marked` once its markers are skipped. `marked` is the same function compiled from
+/// the source with the markers of `mark_lines`. If the numbers of instructions differ no lines
+/// are returned.
+fn instruction_lines(function: &CompiledFunction, marked: &CompiledFunction) -> Vec<Option<i64>> {
+    let mut lines = Vec::new();
+    let mut line = None;
+    for instruction in &marked.instructions {
+        if let Instruction::PushString(i) = *instruction {
+            let string = marked.strings.get(i as usize).map_or("", |string| &string[..]);
+            if string.starts_with(LINE_MARKER) {
+                line = string[LINE_MARKER.len()..].parse().ok();
+                continue;
+            }
+        }
+        lines.push(line);
+    }
+    if lines.len() != function.instructions.len() {
+        return vec![None; function.instructions.len()];
+    }
+    lines
+}
+
+fn write_function(out: &mut String, function: &CompiledFunction, marked: &CompiledFunction) {
+    let _ = write!(out, "{} ({} arguments)", name(function), function.args);
+    if let Some((line, _)) = parse_location(function.id.as_ref()) {
+        let _ = write!(out, "  // line {}", line);
+    }
+    out.push('\n');
+    let width = function.instructions.len().to_string().len();
+    let lines = instruction_lines(function, marked);
+    for (i, (instruction, line)) in function.instructions.iter().zip(lines).enumerate() {
+        let _ = write!(out, "    {:>width$}  {:?}", i, instruction, width = width);
+        match (line, operand_comment(function, instruction)) {
+            (Some(line), Some(comment)) => {
+                let _ = write!(out, "  // line {}: {}", line, comment);
+            }
+            (Some(line), None) => {
+                let _ = write!(out, "  // line {}", line);
+            }
+            (None, Some(comment)) => {
+                let _ = write!(out, "  // {}", comment);
+            }
+            (None, None) => (),
+        }
+        out.push('\n');
+    }
+    for (inner, marked_inner) in function.inner_functions.iter().zip(&marked.inner_functions) {
+        out.push('\n');
+        write_function(out, inner, marked_inner);
+    }
+}
+
+/// Returns the listing of `function` followed by the listings of the functions defined in it.
+/// `marked` is the same function compiled from the source with the markers of `mark_lines`.
+pub fn listing(function: &CompiledFunction, marked: &CompiledFunction) -> String {
+    let mut out = String::new();
+    write_function(&mut out, function, marked);
+    out
+}
+
+/// Returns the function among `function` and the functions defined in it which was compiled from
+/// the binding at `location`
+pub fn find_function<'f>(function: &'f CompiledFunction,
+                         location: &Location)
+                         -> Option<&'f CompiledFunction> {
+    let defined_at = parse_location(function.id.as_ref());
+    if defined_at == Some((location.row as i64, location.column as i64)) {
+        return Some(function);
+    }
+    function.inner_functions
+        .iter()
+        .filter_map(|inner| find_function(inner, location))
+        .next()
+}
+
+/// Returns the location of the name of the innermost binding of a function which `position` lies
+/// in, in the typechecked `expr`
+pub fn function_at(expr: &LExpr<TcIdent>, position: &Position) -> Option<Location> {
+    let at = Range {
+        start: *position,
+        end: *position,
+    };
+    let mut found = None;
+    if let Expr::Let(ref bindings, _) = expr.value {
+        for bind in bindings.iter().filter(|bind| !bind.arguments.is_empty()) {
+            let range = Range {
+                start: ::location_to_position(&bind.name.location),
+                end: query::expr_range(&bind.expression).end,
+            };
+            if query::contains(&range, &at) {
+                found = Some(bind.name.location);
+            }
+        }
+    }
+    query::children(expr)
+        .into_iter()
+        .filter_map(|child| function_at(child, position))
+        .next()
+        .or(found)
+}
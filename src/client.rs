@@ -0,0 +1,68 @@
+//! Requests from the server to the client. The responses of the client arrive among the other
+//! messages from the client and the main loop passes them to `Client::handle_response`.
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use serde::Serialize;
+use serde_json::Value;
+use serde_json::builder::ObjectBuilder;
+use serde_json::value::to_value;
+
+use transport;
+
+/// Called with the result of a request, or with the error if the client failed it
+pub type Callback = Box<Fn(Result<Value, Value>) + Send>;
+
+pub struct Client {
+    next_id: AtomicUsize,
+    /// The callbacks of the requests which the client has not responded to yet
+    pending: Mutex<HashMap<String, Callback>>,
+}
+
+impl Client {
+    pub fn new() -> Client {
+        Client {
+            next_id: AtomicUsize::new(1),
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Sends the request `method` to the client and calls `callback` once the client responds
+    pub fn request<P>(&self, method: &str, params: &P, callback: Callback)
+        where P: Serialize
+    {
+        // The ids are strings so that they can't be mistaken for the ids of the client's requests
+        let id = format!("server-{}", self.next_id.fetch_add(1, Ordering::SeqCst));
+        self.pending.lock().unwrap().insert(id.clone(), callback);
+        let message = ObjectBuilder::new()
+            .insert("jsonrpc", "2.0")
+            .insert("id", id)
+            .insert("method", method)
+            .insert("params", to_value(params))
+            .unwrap();
+        transport::write_message(&message.to_string());
+    }
+
+    /// Calls the callback of the request which `message` responds to. Returns false if `message`
+    /// is not a response, in which case it is a request or notification from the client.
+    pub fn handle_response(&self, message: &Value) -> bool {
+        let is_response = message.find("method").is_none() &&
+                          (message.find("result").is_some() || message.find("error").is_some());
+        if !is_response {
+            return false;
+        }
+        let id = message.find("id").cloned().unwrap_or(Value::Null);
+        let callback = id.as_string().and_then(|id| self.pending.lock().unwrap().remove(id));
+        match callback {
+            Some(callback) => {
+                callback(match message.find("error") {
+                    Some(error) => Err(error.clone()),
+                    None => Ok(message.find("result").cloned().unwrap_or(Value::Null)),
+                })
+            }
+            None => warn!("Received a response to the unknown request {}", id),
+        }
+        true
+    }
+}
@@ -0,0 +1,59 @@
+//! The time which the server schedules its work by. The server normally follows the system clock,
+//! but tests can give it a `VirtualClock` which only moves when it is advanced, so that what
+//! happens once the debounce delay has passed can be checked without sleeping.
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+
+    /// Returns how long the main loop should wait for a message when the next scheduled work is
+    /// due in `until_due`, or `None` to wait until a message arrives
+    fn wait_time(&self, until_due: Duration) -> Option<Duration>;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn wait_time(&self, until_due: Duration) -> Option<Duration> {
+        Some(until_due)
+    }
+}
+
+/// A clock which stands still until it is advanced. Work which is due only runs once the main loop
+/// wakes up, so whoever advances the clock should also send the server a message.
+pub struct VirtualClock {
+    start: Instant,
+    elapsed: Mutex<Duration>,
+}
+
+impl VirtualClock {
+    pub fn new() -> VirtualClock {
+        VirtualClock {
+            start: Instant::now(),
+            elapsed: Mutex::new(Duration::from_millis(0)),
+        }
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        *self.elapsed.lock().unwrap() += duration;
+    }
+}
+
+impl Clock for VirtualClock {
+    fn now(&self) -> Instant {
+        self.start + *self.elapsed.lock().unwrap()
+    }
+
+    fn wait_time(&self, until_due: Duration) -> Option<Duration> {
+        if until_due == Duration::from_millis(0) {
+            Some(until_due)
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,182 @@
+//! The refactoring between a function which is bound to lambdas, `let f = \x -> \y -> e`, and one
+//! which is bound with its arguments, `let f x y = e`. The type annotation of a binding is the
+//! type of the whole function in both forms so it is kept as it is written.
+use gluon::base::ast::{Binding, Expr, LExpr, Pattern};
+use gluon::base::types::TcIdent;
+
+use language_server::{Position, Range};
+use pipeline::{identifier_len, offset, offset_of, position, skip_trivia};
+use query;
+use references;
+
+/// Returns the binding whose name is at `at` together with the offset where its name ends. A
+/// binding of a group of mutually recursive bindings, or one which refers to a variable of its
+/// own name, is left out since converting it would change which bindings can see it.
+fn binding_at<'e>(source: &str,
+                  expr: &'e LExpr<TcIdent>,
+                  at: usize)
+                  -> Option<(&'e Binding<TcIdent>, usize)> {
+    if let Expr::Let(ref bindings, _) = expr.value {
+        for bind in bindings {
+            let id = match bind.name.value {
+                Pattern::Identifier(ref id) => id,
+                _ => continue,
+            };
+            let start = match offset(source, &bind.name.location) {
+                Some(start) => skip_trivia(source, start),
+                None => continue,
+            };
+            let end = start + identifier_len(&source[start..]);
+            if start <= at && at <= end {
+                let recursive = bindings.len() != 1 ||
+                                !references::uses(&bind.expression, &id.name).is_empty();
+                return if recursive { None } else { Some((bind, end)) };
+            }
+        }
+    }
+    query::children(expr).into_iter().filter_map(|child| binding_at(source, child, at)).next()
+}
+
+/// Returns the offset after the `->` of the lambda which starts at `i`
+fn arrow_end(source: &str, mut i: usize) -> Option<usize> {
+    if !source[i..].starts_with('\\') {
+        return None;
+    }
+    i += 1;
+    loop {
+        i = skip_trivia(source, i);
+        match identifier_len(&source[i..]) {
+            0 if source[i..].starts_with("->") => return Some(i + 2),
+            0 => return None,
+            len => i += len,
+        }
+    }
+}
+
+fn edit(source: &str, start: usize, end: usize, new_text: String) -> (Range, String) {
+    let range = Range {
+        start: position(source, start),
+        end: position(source, end),
+    };
+    (range, new_text)
+}
+
+/// Returns the range and the text which bind the arguments of the lambdas, which `bind` is bound
+/// to, after its name, which ends at `name_end`
+fn arguments_edit(source: &str,
+                  bind: &Binding<TcIdent>,
+                  name_end: usize)
+                  -> Option<(Range, String)> {
+    let lambda_start = match offset(source, &bind.expression.location) {
+        Some(start) => skip_trivia(source, start),
+        None => return None,
+    };
+    let mut arguments = Vec::new();
+    let mut lambda = &bind.expression;
+    let mut end = lambda_start;
+    // Nested lambdas are merged as long as nothing but trivia is between them
+    while let Expr::Lambda(ref inner) = lambda.value {
+        match arrow_end(source, skip_trivia(source, end)) {
+            Some(arrow) => end = arrow,
+            None => break,
+        }
+        arguments.extend(inner.arguments.iter().map(|arg| arg.name.declared_name()));
+        lambda = &inner.body;
+    }
+    let before = source[name_end..lambda_start].trim_right();
+    if arguments.is_empty() || !before.ends_with('=') {
+        return None;
+    }
+    Some(edit(source, name_end, end, format!(" {}{}", arguments.join(" "), before)))
+}
+
+/// Returns the range and the text which bind the arguments of the lambdas, which the binding at
+/// `at` is bound to, after its name instead
+pub fn to_arguments(source: &str,
+                    expr: &LExpr<TcIdent>,
+                    at: &Position)
+                    -> Option<(Range, String)> {
+    match binding_at(source, expr, offset_of(source, at)) {
+        Some((bind, name_end)) if bind.arguments.is_empty() => {
+            arguments_edit(source, bind, name_end)
+        }
+        _ => None,
+    }
+}
+
+fn self_reference_at(source: &str,
+                     expr: &LExpr<TcIdent>,
+                     at: usize)
+                     -> Option<(String, (Range, String))> {
+    if let Expr::Let(ref bindings, _) = expr.value {
+        for (i, bind) in bindings.iter().enumerate() {
+            let id = match bind.name.value {
+                Pattern::Identifier(ref id) if bind.arguments.is_empty() => id,
+                _ => continue,
+            };
+            let is_at = |var: &LExpr<TcIdent>| {
+                offset(source, &var.location).map_or(false, |start| {
+                    let start = skip_trivia(source, start);
+                    start <= at && at <= start + identifier_len(&source[start..])
+                })
+            };
+            let refers_to_itself = references::uses(&bind.expression, &id.name)
+                .into_iter()
+                .any(is_at);
+            let others_have_arguments = bindings.iter()
+                .enumerate()
+                .all(|(j, other)| j == i || !other.arguments.is_empty());
+            if !refers_to_itself || !others_have_arguments {
+                continue;
+            }
+            let name_end = match offset(source, &bind.name.location) {
+                Some(start) => {
+                    let start = skip_trivia(source, start);
+                    start + identifier_len(&source[start..])
+                }
+                None => continue,
+            };
+            return arguments_edit(source, bind, name_end)
+                .map(|edit| (id.name.declared_name().into(), edit));
+        }
+    }
+    query::children(expr)
+        .into_iter()
+        .filter_map(|child| self_reference_at(source, child, at))
+        .next()
+}
+
+/// Returns the name of the binding which the variable at `at` refers to, if the variable is in
+/// the lambda which the binding is bound to, and the edit which binds the arguments of the lambda
+/// after the name so that the binding can refer to itself. Only bindings with arguments are
+/// recursive, and only if every other binding of their group has arguments as well.
+pub fn self_reference(source: &str,
+                      expr: &LExpr<TcIdent>,
+                      at: &Position)
+                      -> Option<(String, (Range, String))> {
+    self_reference_at(source, expr, offset_of(source, at))
+}
+
+/// Returns the range and the text which bind the arguments of the binding at `at` with lambdas
+/// instead
+pub fn to_lambda(source: &str, expr: &LExpr<TcIdent>, at: &Position) -> Option<(Range, String)> {
+    let (bind, name_end) = match binding_at(source, expr, offset_of(source, at)) {
+        Some(found) if !found.0.arguments.is_empty() => found,
+        _ => return None,
+    };
+    let mut arguments_end = name_end;
+    for _ in &bind.arguments {
+        let start = skip_trivia(source, arguments_end);
+        arguments_end = start + identifier_len(&source[start..]);
+    }
+    let equal_end = match source[arguments_end..].find('=') {
+        Some(i) => arguments_end + i + 1,
+        None => return None,
+    };
+    let lambdas: Vec<_> = bind.arguments
+        .iter()
+        .map(|arg| format!("\\{} ->", arg.name.declared_name()))
+        .collect();
+    let new_text = format!("{} {}", &source[arguments_end..equal_end], lambdas.join(" "));
+    Some(edit(source, name_end, equal_end, new_text))
+}
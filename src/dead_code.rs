@@ -0,0 +1,181 @@
+//! Finding the exports of a module which no other module of the workspace uses. A module exports
+//! the fields of the record which its top-level bindings end in, and another module uses them by
+//! accessing the fields of its import or by binding them with a record pattern. Both are found in
+//! the parsed modules, before the `import` macro has replaced the imports.
+use std::collections::HashMap;
+
+use gluon::base::ast::{Expr, LExpr, LiteralEnum, Pattern};
+use gluon::base::types::TcIdent;
+use gluon::filename_to_module;
+
+use language_server::Range;
+use pipeline::{is_identifier_char, offset, position, skip_trivia};
+use query;
+
+/// A use of the module `module` by another module, of its field `field` or of the whole module if
+/// `field` is `None`
+#[derive(Clone, Debug, PartialEq)]
+pub struct ModuleUse {
+    pub module: String,
+    pub field: Option<String>,
+}
+
+/// A top-level binding which its module exports as the field `field`
+pub struct Export {
+    pub field: String,
+    /// The range of the name of the binding
+    pub range: Range,
+}
+
+/// Returns the name of the module which `expr` imports if it is an `import`
+fn import_of(expr: &LExpr<TcIdent>) -> Option<String> {
+    if let Expr::Call(ref func, ref args) = expr.value {
+        if let (&Expr::Identifier(ref id), Some(arg)) = (&func.value, args.first()) {
+            if let Expr::Literal(LiteralEnum::String(ref filename)) = arg.value {
+                if id.name.as_ref() == "import" {
+                    return Some(filename_to_module(filename));
+                }
+            }
+        }
+    }
+    None
+}
+
+fn push(uses: &mut Vec<ModuleUse>, module: &str, field: Option<&str>) {
+    let module_use = ModuleUse {
+        module: module.into(),
+        field: field.map(String::from),
+    };
+    if !uses.contains(&module_use) {
+        uses.push(module_use);
+    }
+}
+
+/// Collects the uses of other modules in `expr`. `imports` are the variables in scope which are
+/// bound to an import together with the module they import.
+fn collect_uses(expr: &LExpr<TcIdent>,
+                imports: &HashMap<String, String>,
+                uses: &mut Vec<ModuleUse>) {
+    let imported = |expr: &LExpr<TcIdent>| {
+        import_of(expr).or_else(|| match expr.value {
+            Expr::Identifier(ref id) => imports.get(id.name.as_ref()).cloned(),
+            _ => None,
+        })
+    };
+    // Anything other than a field access which is done with an import may use any of its fields
+    if let Some(module) = imported(expr) {
+        push(uses, &module, None);
+        return;
+    }
+    match expr.value {
+        Expr::FieldAccess(ref record, ref field) => {
+            if let Some(module) = imported(record) {
+                push(uses, &module, Some(field.name.as_ref()));
+                return;
+            }
+        }
+        // A field without a value, such as `{ x }`, refers to the variable of its name
+        Expr::Record { ref exprs, .. } => {
+            for &(ref field, ref value) in exprs {
+                if let (None, Some(module)) = (value.as_ref(), imports.get(field.as_ref())) {
+                    push(uses, module, None);
+                }
+            }
+        }
+        Expr::Let(ref bindings, ref body) => {
+            let mut scope = imports.clone();
+            for bind in bindings {
+                let module = match import_of(&bind.expression) {
+                    Some(module) => module,
+                    None => {
+                        collect_uses(&bind.expression, imports, uses);
+                        continue;
+                    }
+                };
+                match bind.name.value {
+                    Pattern::Identifier(ref id) => {
+                        scope.insert(id.name.as_ref().into(), module);
+                    }
+                    Pattern::Record { ref fields, .. } => {
+                        for &(ref field, _) in fields {
+                            push(uses, &module, Some(field.as_ref()));
+                        }
+                    }
+                    Pattern::Constructor(..) => push(uses, &module, None),
+                }
+            }
+            collect_uses(body, &scope, uses);
+            return;
+        }
+        _ => (),
+    }
+    for child in query::children(expr) {
+        collect_uses(child, imports, uses);
+    }
+}
+
+/// Returns the uses of other modules in the parsed module `expr`
+pub fn module_uses(expr: &LExpr<TcIdent>) -> Vec<ModuleUse> {
+    let mut uses = Vec::new();
+    collect_uses(query::strip_implicit_prelude(expr), &HashMap::new(), &mut uses);
+    uses
+}
+
+/// Returns the top-level bindings of the parsed module `expr`, which is parsed from `source`,
+/// which are exported by the record it ends in. Bindings whose name starts with `_` are left out.
+pub fn exports(source: &str, expr: &LExpr<TcIdent>) -> Vec<Export> {
+    let mut bindings = HashMap::new();
+    let mut expr = query::strip_implicit_prelude(expr);
+    loop {
+        match expr.value {
+            Expr::Let(ref binds, ref body) => {
+                for bind in binds {
+                    let name = match bind.name.value {
+                        Pattern::Identifier(ref id) => id.name.as_ref(),
+                        _ => continue,
+                    };
+                    if let Some(start) = offset(source, &bind.name.location) {
+                        let start = skip_trivia(source, start);
+                        let end = source[start..]
+                            .find(|c: char| !is_identifier_char(c))
+                            .map_or(source.len(), |i| start + i);
+                        let range = Range {
+                            start: position(source, start),
+                            end: position(source, end),
+                        };
+                        bindings.insert(String::from(name), range);
+                    }
+                }
+                expr = body;
+            }
+            Expr::Type(_, ref body) => expr = body,
+            _ => break,
+        }
+    }
+    let fields = match expr.value {
+        Expr::Record { ref exprs, .. } => exprs,
+        _ => return vec![],
+    };
+    fields.iter()
+        .filter_map(|&(ref field, ref value)| {
+            let bound = match *value {
+                None => field.as_ref(),
+                Some(ref value) => {
+                    match value.value {
+                        Expr::Identifier(ref id) => id.name.as_ref(),
+                        _ => return None,
+                    }
+                }
+            };
+            if bound.starts_with('_') {
+                return None;
+            }
+            bindings.get(bound).map(|range| {
+                Export {
+                    field: field.as_ref().into(),
+                    range: *range,
+                }
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,90 @@
+//! Coalescing of the changes to documents. Typechecking on every keystroke wastes time on text
+//! which is about to change again, so diagnostics of a changed document are only run once no
+//! change has been made to it for `delay`.
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use clock::Clock;
+
+/// The delay used until the client configures `gluon.diagnostics.debounce`
+pub const DEFAULT_DELAY_MS: u64 = 250;
+
+pub struct Debouncer {
+    clock: Arc<Clock>,
+    delay: Mutex<Duration>,
+    /// The documents whose diagnostics are pending and when they should be run
+    pending: Mutex<HashMap<String, Instant>>,
+}
+
+impl Debouncer {
+    pub fn new(clock: Arc<Clock>) -> Debouncer {
+        Debouncer {
+            clock: clock,
+            delay: Mutex::new(Duration::from_millis(DEFAULT_DELAY_MS)),
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn set_delay(&self, delay: Duration) {
+        *self.delay.lock().unwrap() = delay;
+    }
+
+    /// Schedules the diagnostics of `uri`, postponing them if they are already scheduled
+    pub fn schedule(&self, uri: &str) {
+        let deadline = self.clock.now() + *self.delay.lock().unwrap();
+        self.pending.lock().unwrap().insert(uri.into(), deadline);
+    }
+
+    /// Schedules the diagnostics of `uri` to run as soon as no messages are waiting to be handled
+    pub fn schedule_now(&self, uri: &str) {
+        self.pending.lock().unwrap().insert(uri.into(), self.clock.now());
+    }
+
+    /// Returns true if the diagnostics of `uri` are waiting to be run
+    pub fn is_pending(&self, uri: &str) -> bool {
+        self.pending.lock().unwrap().contains_key(uri)
+    }
+
+    /// The number of documents whose diagnostics are pending
+    pub fn pending_count(&self) -> usize {
+        self.pending.lock().unwrap().len()
+    }
+
+    /// Removes the pending diagnostics of `uri`. Returns true if there were any, in which case
+    /// the caller is responsible for running them.
+    pub fn cancel(&self, uri: &str) -> bool {
+        self.pending.lock().unwrap().remove(uri).is_some()
+    }
+
+    /// Returns the time until the next pending diagnostics are due, if any are pending
+    pub fn next_timeout(&self) -> Option<Duration> {
+        let now = self.clock.now();
+        self.pending
+            .lock()
+            .unwrap()
+            .values()
+            .min()
+            .map(|&deadline| {
+                if deadline > now {
+                    deadline.duration_since(now)
+                } else {
+                    Duration::from_millis(0)
+                }
+            })
+    }
+
+    /// Removes and returns the documents whose diagnostics are due
+    pub fn take_due(&self) -> Vec<String> {
+        let now = self.clock.now();
+        let mut pending = self.pending.lock().unwrap();
+        let due: Vec<String> = pending.iter()
+            .filter(|&(_, &deadline)| deadline <= now)
+            .map(|(uri, _)| uri.clone())
+            .collect();
+        for uri in &due {
+            pending.remove(uri);
+        }
+        due
+    }
+}
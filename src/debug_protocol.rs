@@ -0,0 +1,632 @@
+//! Types of the debug adapter protocol
+use serde_json::Value;
+
+/// A client or server-initiated request.
+#[derive(Deserialize)]
+pub struct Request {
+    /**
+     * Sequence number.
+     */
+    pub seq: i64,
+    /**
+     * The command to execute.
+     */
+    pub command: String,
+    /**
+     * Object containing arguments for the command.
+     */
+    pub arguments: Option<Value>,
+}
+
+/// Response to a request.
+#[derive(Serialize)]
+pub struct Response {
+    /**
+     * Sequence number.
+     */
+    pub seq: i64,
+    /**
+     * Message type.
+     */
+    #[serde(rename="type")]
+    pub typ: &'static str,
+    /**
+     * Sequence number of the corresponding request.
+     */
+    pub request_seq: i64,
+    /**
+     * Outcome of the request.
+     */
+    pub success: bool,
+    /**
+     * The command requested.
+     */
+    pub command: String,
+    /**
+     * Contains error message if success == false.
+     */
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub message: Option<String>,
+    /**
+     * Contains request result if success is true and optional error details if success is false.
+     */
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub body: Option<Value>,
+}
+
+/// Server-initiated event.
+#[derive(Serialize)]
+pub struct Event {
+    /**
+     * Sequence number.
+     */
+    pub seq: i64,
+    /**
+     * Message type.
+     */
+    #[serde(rename="type")]
+    pub typ: &'static str,
+    /**
+     * Type of event.
+     */
+    pub event: String,
+    /**
+     * Event-specific information.
+     */
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub body: Option<Value>,
+}
+
+/// Information about the capabilities of a debug adapter.
+#[derive(Default, Serialize)]
+pub struct Capabilities {
+    /**
+     * The debug adapter supports the configurationDoneRequest.
+     */
+    #[serde(rename="supportsConfigurationDoneRequest")]
+    pub supports_configuration_done_request: bool,
+    /**
+     * The debug adapter supports conditional breakpoints.
+     */
+    #[serde(rename="supportsConditionalBreakpoints")]
+    pub supports_conditional_breakpoints: bool,
+    /**
+     * The debug adapter supports logpoints by interpreting the 'logMessage' attribute of the
+     * SourceBreakpoint.
+     */
+    #[serde(rename="supportsLogPoints")]
+    pub supports_log_points: bool,
+    /**
+     * The debug adapter supports the completionsRequest.
+     */
+    #[serde(rename="supportsCompletionsRequest")]
+    pub supports_completions_request: bool,
+    /**
+     * Available filters for the setExceptionBreakpoints request.
+     */
+    #[serde(rename="exceptionBreakpointFilters")]
+    pub exception_breakpoint_filters: Vec<ExceptionBreakpointsFilter>,
+    /**
+     * The debug adapter supports the exceptionInfo request.
+     */
+    #[serde(rename="supportsExceptionInfoRequest")]
+    pub supports_exception_info_request: bool,
+}
+
+/// An ExceptionBreakpointsFilter is shown in the UI as an option for configuring how exceptions
+/// are dealt with.
+#[derive(Serialize)]
+pub struct ExceptionBreakpointsFilter {
+    /**
+     * The internal ID of the filter. This value is passed to the setExceptionBreakpoints request.
+     */
+    pub filter: String,
+    /**
+     * The name of the filter. This will be shown in the UI.
+     */
+    pub label: String,
+    /**
+     * Initial value of the filter. If not specified a value 'false' is assumed.
+     */
+    pub default: bool,
+}
+
+/// Arguments for 'launch' request.
+#[derive(Deserialize)]
+pub struct LaunchRequestArguments {
+    /**
+     * The path of the gluon file to run.
+     */
+    pub program: String,
+    /**
+     * If noDebug is true the launch request should launch the program without enabling
+     * debugging.
+     */
+    #[serde(rename="noDebug")]
+    pub no_debug: Option<bool>,
+}
+
+/// Arguments for 'attach' request.
+#[derive(Deserialize)]
+pub struct AttachRequestArguments {
+    /**
+     * The address, such as `127.0.0.1:4711`, which the debug server of the embedded VM listens
+     * on.
+     */
+    pub address: String,
+}
+
+/// A Thread
+#[derive(Serialize)]
+pub struct Thread {
+    /**
+     * Unique identifier for the thread.
+     */
+    pub id: i64,
+    /**
+     * A name of the thread.
+     */
+    pub name: String,
+}
+
+/// Response to 'threads' request.
+#[derive(Serialize)]
+pub struct ThreadsResponseBody {
+    /**
+     * All threads.
+     */
+    pub threads: Vec<Thread>,
+}
+
+/// Arguments for 'stackTrace' request.
+#[derive(Deserialize)]
+pub struct StackTraceArguments {
+    /**
+     * Retrieve the stacktrace for this thread.
+     */
+    #[serde(rename="threadId")]
+    pub thread_id: i64,
+    /**
+     * The index of the first frame to return; if omitted frames start at 0.
+     */
+    #[serde(rename="startFrame")]
+    pub start_frame: Option<i64>,
+    /**
+     * The maximum number of frames to return. If levels is not specified or 0, all frames are
+     * returned.
+     */
+    pub levels: Option<i64>,
+}
+
+/// A Source is a descriptor for source code.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Source {
+    /**
+     * The short name of the source. Every source returned from the debug adapter has a name.
+     * When specifying a source to the debug adapter this name is optional.
+     */
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub name: Option<String>,
+    /**
+     * The long (absolute) path of the source. It is not guaranteed that the source exists at
+     * this location.
+     */
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub path: Option<String>,
+}
+
+/// A Stackframe contains the source location.
+#[derive(Clone, Serialize)]
+pub struct StackFrame {
+    /**
+     * An identifier for the stack frame. This id can be used to retrieve the scopes of the frame
+     * with the 'scopesRequest'.
+     */
+    pub id: i64,
+    /**
+     * The name of the stack frame, typically a method name
+     */
+    pub name: String,
+    /**
+     * The optional source of the frame.
+     */
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub source: Option<Source>,
+    /**
+     * The line within the file of the frame. If source is null or doesn't exist, line is 0 and
+     * must be ignored.
+     */
+    pub line: i64,
+    /**
+     * The column within the line. If source is null or doesn't exist, column is 0 and must be
+     * ignored.
+     */
+    pub column: i64,
+}
+
+/// Response to 'stackTrace' request.
+#[derive(Serialize)]
+pub struct StackTraceResponseBody {
+    /**
+     * The frames of the stackframe. If the array has length zero, there are no stackframes
+     * available.
+     */
+    #[serde(rename="stackFrames")]
+    pub stack_frames: Vec<StackFrame>,
+    /**
+     * The total number of frames available.
+     */
+    #[serde(rename="totalFrames")]
+    pub total_frames: i64,
+}
+
+/// Arguments for 'scopes' request.
+#[derive(Deserialize)]
+pub struct ScopesArguments {
+    /**
+     * Retrieve the scopes for this stackframe.
+     */
+    #[serde(rename="frameId")]
+    pub frame_id: i64,
+}
+
+/// A Scope is a named container for variables.
+#[derive(Serialize)]
+pub struct Scope {
+    /**
+     * Name of the scope such as 'Arguments', 'Locals'.
+     */
+    pub name: String,
+    /**
+     * The variables of this scope can be retrieved by passing the value of variablesReference to
+     * the VariablesRequest.
+     */
+    #[serde(rename="variablesReference")]
+    pub variables_reference: i64,
+    /**
+     * If true, the number of variables in this scope is large or expensive to retrieve.
+     */
+    pub expensive: bool,
+}
+
+/// Response to 'scopes' request.
+#[derive(Serialize)]
+pub struct ScopesResponseBody {
+    /**
+     * The scopes of the stackframe. If the array has length zero, there are no scopes
+     * available.
+     */
+    pub scopes: Vec<Scope>,
+}
+
+/// Event message for 'stopped' event type.
+#[derive(Serialize)]
+pub struct StoppedEventBody {
+    /**
+     * The reason for the event (such as: 'step', 'breakpoint', 'exception', 'pause').
+     */
+    pub reason: String,
+    /**
+     * The thread which was stopped.
+     */
+    #[serde(rename="threadId")]
+    pub thread_id: i64,
+    /**
+     * Additional information. E.g. if reason is 'exception', text contains the exception name.
+     */
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub text: Option<String>,
+}
+
+/// Arguments for 'setExceptionBreakpoints' request.
+#[derive(Deserialize)]
+pub struct SetExceptionBreakpointsArguments {
+    /**
+     * IDs of checked exception options. The set of IDs is returned via the
+     * 'exceptionBreakpointFilters' capability.
+     */
+    pub filters: Vec<String>,
+}
+
+/// Response to 'exceptionInfo' request.
+#[derive(Serialize)]
+pub struct ExceptionInfoResponseBody {
+    /**
+     * ID of the exception that was thrown.
+     */
+    #[serde(rename="exceptionId")]
+    pub exception_id: String,
+    /**
+     * Descriptive text for the exception provided by the debug adapter.
+     */
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub description: Option<String>,
+    /**
+     * Mode that caused the exception notification to be raised: 'always' or 'unhandled'.
+     */
+    #[serde(rename="breakMode")]
+    pub break_mode: String,
+}
+
+/// Event message for 'output' event type.
+#[derive(Serialize)]
+pub struct OutputEventBody {
+    /**
+     * The category of output (such as: 'console', 'stdout', 'stderr', 'telemetry').
+     */
+    pub category: String,
+    /**
+     * The output to report.
+     */
+    pub output: String,
+}
+
+/// Event message for 'exited' event type.
+#[derive(Serialize)]
+pub struct ExitedEventBody {
+    /**
+     * The exit code returned from the debuggee.
+     */
+    #[serde(rename="exitCode")]
+    pub exit_code: i64,
+}
+
+/// Arguments for 'setBreakpoints' request.
+#[derive(Deserialize)]
+pub struct SetBreakpointsArguments {
+    /**
+     * The source location of the breakpoints; either source.path or source.reference must be
+     * specified.
+     */
+    pub source: Source,
+    /**
+     * The code locations of the breakpoints.
+     */
+    pub breakpoints: Option<Vec<SourceBreakpoint>>,
+    /**
+     * Deprecated: The code locations of the breakpoints.
+     */
+    pub lines: Option<Vec<i64>>,
+}
+
+/// Properties of a breakpoint passed to the setBreakpoints request.
+#[derive(Deserialize)]
+pub struct SourceBreakpoint {
+    /**
+     * The source line of the breakpoint.
+     */
+    pub line: i64,
+    /**
+     * An optional source column of the breakpoint.
+     */
+    pub column: Option<i64>,
+    /**
+     * An optional expression for conditional breakpoints.
+     */
+    pub condition: Option<String>,
+    /**
+     * If this attribute exists and is non-empty, the backend must not 'break' (stop) but log the
+     * message instead. Expressions within {} are interpolated.
+     */
+    #[serde(rename="logMessage")]
+    pub log_message: Option<String>,
+}
+
+/// Information about a Breakpoint created in setBreakpoints or setFunctionBreakpoints.
+#[derive(Clone, Serialize)]
+pub struct Breakpoint {
+    /**
+     * An optional unique identifier for the breakpoint.
+     */
+    pub id: i64,
+    /**
+     * If true breakpoint could be set (but not necessarily at the desired location).
+     */
+    pub verified: bool,
+    /**
+     * An optional message about the state of the breakpoint. This is shown to the user and can
+     * be used to explain why a breakpoint could not be verified.
+     */
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub message: Option<String>,
+    /**
+     * The source where the breakpoint is located.
+     */
+    pub source: Source,
+    /**
+     * The actual line of the breakpoint.
+     */
+    pub line: i64,
+    /**
+     * An optional start column of the actual range covered by the breakpoint.
+     */
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub column: Option<i64>,
+}
+
+/// Response to 'setBreakpoints' request.
+#[derive(Serialize)]
+pub struct SetBreakpointsResponseBody {
+    /**
+     * Information about the breakpoints. The array elements are in the same order as the
+     * elements of the 'breakpoints' (or the deprecated 'lines') in the SetBreakpointsArguments.
+     */
+    pub breakpoints: Vec<Breakpoint>,
+}
+
+/// Event message for 'breakpoint' event type.
+#[derive(Serialize)]
+pub struct BreakpointEventBody {
+    /**
+     * The reason for the event (such as: 'changed', 'new').
+     */
+    pub reason: String,
+    /**
+     * The breakpoint.
+     */
+    pub breakpoint: Breakpoint,
+}
+
+/// Arguments for 'variables' request.
+#[derive(Deserialize)]
+pub struct VariablesArguments {
+    /**
+     * The Variable reference.
+     */
+    #[serde(rename="variablesReference")]
+    pub variables_reference: i64,
+    /**
+     * Optional filter to limit the child variables to either named or indexed. If ommited, both
+     * types are fetched.
+     */
+    pub filter: Option<String>,
+    /**
+     * The index of the first variable to return; if omitted children start at 0.
+     */
+    pub start: Option<i64>,
+    /**
+     * The number of variables to return. If count is missing or 0, all variables are returned.
+     */
+    pub count: Option<i64>,
+}
+
+/// A Variable is a name/value pair.
+#[derive(Serialize)]
+pub struct Variable {
+    /**
+     * The variable's name.
+     */
+    pub name: String,
+    /**
+     * The variable's value. For structured objects this can be a multi line text, e.g. for a
+     * function the body of a function.
+     */
+    pub value: String,
+    /**
+     * The type of the variable's value. Typically shown in the UI when hovering over the value.
+     */
+    #[serde(rename="type")]
+    pub typ: String,
+    /**
+     * If variablesReference is > 0, the variable is structured and its children can be retrieved
+     * by passing variablesReference to the VariablesRequest.
+     */
+    #[serde(rename="variablesReference")]
+    pub variables_reference: i64,
+    /**
+     * The number of named child variables.
+     */
+    #[serde(skip_serializing_if="Option::is_none")]
+    #[serde(rename="namedVariables")]
+    pub named_variables: Option<i64>,
+    /**
+     * The number of indexed child variables. The client can use this optional information to
+     * present the children in a paged UI and fetch them in chunks.
+     */
+    #[serde(skip_serializing_if="Option::is_none")]
+    #[serde(rename="indexedVariables")]
+    pub indexed_variables: Option<i64>,
+}
+
+/// Response to 'variables' request.
+#[derive(Serialize)]
+pub struct VariablesResponseBody {
+    /**
+     * All (or a range) of variables for the given variable reference.
+     */
+    pub variables: Vec<Variable>,
+}
+
+/// Arguments for 'evaluate' request.
+#[derive(Deserialize)]
+pub struct EvaluateArguments {
+    /**
+     * The expression to evaluate.
+     */
+    pub expression: String,
+    /**
+     * Evaluate the expression in the scope of this stack frame. If not specified, the expression
+     * is evaluated in the global scope.
+     */
+    #[serde(rename="frameId")]
+    pub frame_id: Option<i64>,
+}
+
+/// Response to 'evaluate' request.
+#[derive(Serialize)]
+pub struct EvaluateResponseBody {
+    /**
+     * The result of the evaluate.
+     */
+    pub result: String,
+    /**
+     * The optional type of the evaluate result.
+     */
+    #[serde(rename="type")]
+    pub typ: String,
+    /**
+     * If variablesReference is > 0, the evaluate result is structured and its children can be
+     * retrieved by passing variablesReference to the VariablesRequest.
+     */
+    #[serde(rename="variablesReference")]
+    pub variables_reference: i64,
+}
+
+/// Arguments for 'completions' request.
+#[derive(Deserialize)]
+pub struct CompletionsArguments {
+    /**
+     * Returns completions in the scope of this stack frame. If not specified, the completions are
+     * returned for the global scope.
+     */
+    #[serde(rename="frameId")]
+    pub frame_id: Option<i64>,
+    /**
+     * One or more source lines. Typically this is the text a user has typed into the debug
+     * console before asking for completion.
+     */
+    pub text: String,
+    /**
+     * The character position for which to determine the completion proposals.
+     */
+    pub column: i64,
+    /**
+     * An optional line for which to determine the completion proposals. If missing the first
+     * line of the text is assumed.
+     */
+    pub line: Option<i64>,
+}
+
+/// CompletionItems are the suggestions returned from the CompletionsRequest.
+#[derive(Serialize)]
+pub struct CompletionItem {
+    /**
+     * The label of this completion item. By default this is also the text that is inserted when
+     * selecting this completion.
+     */
+    pub label: String,
+    /**
+     * The item's type. Typically the client uses this information to render the item in the UI
+     * with an icon.
+     */
+    #[serde(rename="type")]
+    pub typ: String,
+    /**
+     * When present determines the start of the range to be overwritten (by default the text is
+     * added at the cursor).
+     */
+    pub start: i64,
+    /**
+     * When present determines how many characters are overwritten by the completion text.
+     */
+    pub length: i64,
+}
+
+/// Response to 'completions' request.
+#[derive(Serialize)]
+pub struct CompletionsResponseBody {
+    /**
+     * The possible completions.
+     */
+    pub targets: Vec<CompletionItem>,
+}
@@ -0,0 +1,1466 @@
+//! Debug adapter which runs gluon programs for clients of the debug adapter protocol. The adapter
+//! attaches to gluon VMs which are embedded in other applications as well. Those start a
+//! `DebugServer` which the adapter forwards the requests after an `attach` request to.
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::env;
+use std::error::Error as StdError;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufReader, Read};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{Receiver, Sender, channel};
+use std::thread;
+
+use serde;
+use serde_json::value::{from_value, to_value, Value};
+
+use gluon::{Compiler, Error as GluonError, Result as GluonResult, Thread as GluonThread,
+            filename_to_module};
+use gluon::base::metadata::Metadata;
+use gluon::base::symbol::Symbol;
+use gluon::base::types::{TcType, Type};
+use gluon::compiler_pipeline::{TypecheckValue, Typecheckable};
+use gluon::import::{DefaultImporter, Importer};
+use gluon::vm::Variants;
+use gluon::vm::api::{Generic, VMFunction, ValueRef, WithVM, primitive_f};
+use gluon::vm::api::generic::A;
+use gluon::vm::internal::Value as GluonValue;
+use gluon::vm::macros::Error as MacroError;
+use gluon::vm::thread::{RootedValue, Status, ThreadInternal};
+use gluon::vm::types::VMInt;
+
+use breakpoints::{self, BREAK_FUNCTION, RAISE_FUNCTION};
+use debug_protocol::*;
+use eval;
+use output;
+use pipeline::is_identifier_char;
+use stack_trace;
+use transport;
+use variables::Handles;
+
+/// Programs are only ever run on a single thread
+const THREAD_ID: i64 = 1;
+
+/// The filter of the exception breakpoint which stops at the errors which end the program
+const UNCAUGHT_FILTER: &'static str = "uncaught";
+
+/// The filter of the exception breakpoint which stops at every call to `error`
+const RAISED_FILTER: &'static str = "raised";
+
+/// The message of the breakpoints which are not in the main module of the program, which are
+/// the only ones that the program stops at
+const NOT_IN_PROGRAM: &'static str = "Breakpoints can only be set in the launched program";
+
+/// The environment variable which holds the address that the debug server of an embedded VM
+/// listens on
+pub const DEBUG_VAR: &'static str = "GLUON_DEBUG";
+
+/// Maps the modules of a program to the files they were loaded from
+struct Modules {
+    root: PathBuf,
+    program: PathBuf,
+    main: String,
+}
+
+impl Modules {
+    fn new(program: &Path) -> Modules {
+        let file_name = program.file_name().and_then(|name| name.to_str()).unwrap_or("");
+        Modules {
+            root: program.parent().map_or_else(PathBuf::new, |root| root.to_path_buf()),
+            program: program.to_path_buf(),
+            main: filename_to_module(file_name),
+        }
+    }
+
+    /// Resolves `module` the same way the import macro does. Modules from the standard library
+    /// are embedded in the server and have no path.
+    fn source(&self, module: &str) -> Source {
+        let file = format!("{}.glu", module.replace('.', "/"));
+        let path = if module == self.main {
+            Some(self.program.clone())
+        } else {
+            let path = self.root.join(&file);
+            if path.is_file() { Some(path) } else { None }
+        };
+        Source {
+            name: Some(file),
+            path: path.map(|path| path.to_string_lossy().into_owned()),
+        }
+    }
+}
+
+/// Returns the frames left on the stack of `vm`, innermost frame first. If the program stopped
+/// at a step point `position` is its module, line and column.
+fn stack_frames(vm: &GluonThread,
+                modules: &Modules,
+                position: Option<(&str, i64, i64)>)
+                -> Vec<StackFrame> {
+    let mut frames: Vec<_> = stack_trace::frames(vm)
+        .into_iter()
+        .enumerate()
+        .map(|(i, frame)| {
+            StackFrame {
+                id: i as i64,
+                name: frame.name,
+                source: frame.module.map(|module| modules.source(&module)),
+                line: frame.line,
+                column: frame.column,
+            }
+        })
+        .collect();
+    if let (Some((module, line, column)), Some(frame)) = (position, frames.first_mut()) {
+        frame.source = Some(modules.source(module));
+        frame.line = line;
+        frame.column = column;
+    }
+    frames
+}
+
+/// Reference of the local variables of the innermost frame while stopped at a breakpoint
+const LOCALS_REFERENCE: i64 = 1;
+
+/// Normalizes `path` so that paths from the client and the launch request can be compared
+fn normalize(path: &str) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path))
+}
+
+#[derive(Clone, Copy)]
+enum Stopped {
+    Breakpoint,
+    Step,
+    /// Stopped at a call to `error` before the error is raised
+    Raise,
+    /// Stopped after an error ended the program
+    Exception,
+}
+
+#[derive(Clone, Copy)]
+enum StepKind {
+    /// Stops at the next step point which is not in a function called from the current frame
+    Over,
+    /// Stops at the next step point
+    In,
+    /// Stops at the next step point after the current frame returns
+    Out,
+}
+
+/// A step which the program takes from the step point it stopped at
+#[derive(Clone, Copy)]
+struct Step {
+    kind: StepKind,
+    /// The number of frames on the stack when the program stopped
+    depth: usize,
+    /// The index of the module and the line which the program stopped at
+    from: (i64, i64),
+}
+
+/// Commands sent to a program which is stopped at a breakpoint. The values of the program can
+/// only be accessed from its own thread so inspecting them is done there as well.
+enum Command {
+    Continue,
+    Variables(VariablesArguments, Sender<Result<Vec<Variable>, String>>),
+    Evaluate(EvaluateArguments, Sender<Result<EvaluateResponseBody, String>>),
+    Completions(CompletionsArguments, Sender<Result<Vec<CompletionItem>, String>>),
+}
+
+/// Returns the names of the fields of `typ` if it is a record
+fn field_names(vm: &GluonThread, typ: &TcType) -> Vec<String> {
+    match *eval::remove_aliases(&*vm.get_env(), typ) {
+        Type::Record { ref fields, .. } => {
+            fields.iter().map(|field| String::from(field.name.as_ref())).collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Returns the start of the identifier which `text` ends with, including the `.` between the
+/// field accesses of a record if `fields` is true
+fn identifier_start(text: &str, fields: bool) -> usize {
+    text.char_indices()
+        .rev()
+        .take_while(|&(_, c)| is_identifier_char(c) || (fields && c == '.'))
+        .last()
+        .map_or(text.len(), |(i, _)| i)
+}
+
+/// Returns `text` unless it is empty, as the client sends empty options for breakpoints which
+/// have none
+fn non_empty(text: &Option<String>) -> Option<String> {
+    match *text {
+        Some(ref text) if !text.is_empty() => Some(text.clone()),
+        _ => None,
+    }
+}
+
+/// A breakpoint of a source file together with the options it was set with
+struct SetBreakpoint {
+    breakpoint: Breakpoint,
+    /// An expression which must be true for the program to stop at the breakpoint
+    condition: Option<String>,
+    /// A message which is logged instead of stopping, with the expressions in `{}` interpolated
+    log_message: Option<String>,
+}
+
+struct Debugger {
+    /// True if the debugger serves a VM which is embedded in another application
+    embedded: bool,
+    /// Set once the client of an embedded VM has set its breakpoints
+    configured: Mutex<bool>,
+    configured_changed: Condvar,
+    seq: AtomicUsize,
+    launch: Mutex<Option<LaunchRequestArguments>>,
+    next_breakpoint_id: AtomicUsize,
+    /// The breakpoints of each source file, adjusted to the expressions they stop at
+    breakpoints: Mutex<HashMap<PathBuf, Vec<SetBreakpoint>>>,
+    /// True while the condition or log message of a breakpoint is evaluated
+    evaluating: AtomicBool,
+    /// Why the program is stopped, if it is
+    stopped: Mutex<Option<Stopped>>,
+    /// The stack of the program if it is stopped
+    frames: Mutex<Vec<StackFrame>>,
+    /// The index of the module and the line of the step point which the program is stopped at
+    position: Mutex<(i64, i64)>,
+    /// The step which the program takes after it is continued, if it steps
+    step: Mutex<Option<Step>>,
+    /// The filters of the exception breakpoints which are enabled
+    exception_filters: Mutex<Vec<String>>,
+    /// The message and the break mode of the last error which the program stopped at
+    exception: Mutex<Option<(String, &'static str)>>,
+    commands_sender: Mutex<Sender<Command>>,
+    commands_receiver: Mutex<Receiver<Command>>,
+    /// Used to give the locals of each stop a unique global name
+    stops: AtomicUsize,
+}
+
+/// The program which is running on the current thread
+struct Program {
+    debugger: Arc<Debugger>,
+    modules: Modules,
+}
+
+/// The modules of the program on the current thread which have step points, by their index, and
+/// the type of the local variables at each step point, keyed by its module, line and column.
+/// Imported modules add to them while the program is typechecked.
+#[derive(Default)]
+struct StepPoints {
+    modules: Vec<String>,
+    local_types: HashMap<(i64, i64, i64), TcType>,
+}
+
+thread_local!(static PROGRAM: RefCell<Option<Program>> = RefCell::new(None));
+thread_local!(static STEP_POINTS: RefCell<StepPoints> = RefCell::new(StepPoints::default()));
+
+fn debug_break(module: WithVM<VMInt>, line: VMInt, column: VMInt, locals: Generic<A>) {
+    PROGRAM.with(|program| {
+        if let Some(ref program) = *program.borrow() {
+            program.debugger
+                .hit_step_point(module.vm, program, module.value, line, column, locals.0);
+        }
+    })
+}
+
+fn debug_break_wrapper(thread: &GluonThread) -> Status {
+    VMFunction::unpack_and_call(&(debug_break as fn(_, _, _, _) -> _), thread)
+}
+
+fn debug_raise(message: WithVM<Generic<A>>) -> Generic<A> {
+    PROGRAM.with(|program| {
+        if let Some(ref program) = *program.borrow() {
+            program.debugger.hit_raise(message.vm, program, message.value.0);
+        }
+    });
+    message.value
+}
+
+fn debug_raise_wrapper(thread: &GluonThread) -> Status {
+    VMFunction::unpack_and_call(&(debug_raise as fn(_) -> _), thread)
+}
+
+/// Loads the modules which a debugged program imports with step points so that stepping enters
+/// them. The modules of the standard library are loaded as they are.
+#[derive(Clone)]
+struct StepImporter;
+
+impl Importer for StepImporter {
+    fn import(&self, vm: &GluonThread, modulename: &str, input: &str) -> Result<(), MacroError> {
+        if modulename.starts_with("std.") {
+            return DefaultImporter.import(vm, modulename, input);
+        }
+        let mut compiler = Compiler::new();
+        let value = try!(typecheck_module(&mut compiler, vm, modulename, input, true));
+        let (value, typ) = try!(eval::run_expr(&mut compiler, vm, modulename, input, value));
+        try!(vm.global_env().set_global(Symbol::new(modulename), typ, Metadata::default(), *value));
+        Ok(())
+    }
+}
+
+/// The local variables of the program at the breakpoint it is stopped at
+struct Locals {
+    value: GluonValue,
+    typ: TcType,
+    /// The name of the global which holds `value` once an expression has been evaluated
+    global: Option<String>,
+}
+
+impl Debugger {
+    fn new(embedded: bool) -> Debugger {
+        let (commands_sender, commands_receiver) = channel();
+        Debugger {
+            embedded: embedded,
+            configured: Mutex::new(false),
+            configured_changed: Condvar::new(),
+            seq: AtomicUsize::new(0),
+            launch: Mutex::new(None),
+            next_breakpoint_id: AtomicUsize::new(0),
+            breakpoints: Mutex::new(HashMap::new()),
+            evaluating: AtomicBool::new(false),
+            stopped: Mutex::new(None),
+            frames: Mutex::new(Vec::new()),
+            position: Mutex::new((0, 0)),
+            step: Mutex::new(None),
+            exception_filters: Mutex::new(vec![UNCAUGHT_FILTER.into()]),
+            exception: Mutex::new(None),
+            commands_sender: Mutex::new(commands_sender),
+            commands_receiver: Mutex::new(commands_receiver),
+            stops: AtomicUsize::new(0),
+        }
+    }
+
+    fn next_seq(&self) -> i64 {
+        self.seq.fetch_add(1, Ordering::SeqCst) as i64 + 1
+    }
+
+    fn send_event<T>(&self, event: &str, body: Option<T>)
+        where T: serde::Serialize
+    {
+        let event = Event {
+            seq: self.next_seq(),
+            typ: "event",
+            event: event.into(),
+            body: body.as_ref().map(to_value),
+        };
+        ::write_message(&to_value(&event).to_string());
+    }
+
+    fn output(&self, category: &str, output: String) {
+        self.send_event("output",
+                        Some(OutputEventBody {
+                            category: category.into(),
+                            output: output,
+                        }));
+    }
+
+    fn terminate(&self, exit_code: i64) {
+        self.send_event("exited", Some(ExitedEventBody { exit_code: exit_code }));
+        self.send_event::<()>("terminated", None);
+    }
+
+    fn stop(&self, reason: Stopped, frames: Vec<StackFrame>, text: Option<String>) {
+        *self.frames.lock().unwrap() = frames;
+        *self.stopped.lock().unwrap() = Some(reason);
+        let reason = match reason {
+            Stopped::Breakpoint => "breakpoint",
+            Stopped::Step => "step",
+            Stopped::Raise |
+            Stopped::Exception => "exception",
+        };
+        self.send_event("stopped",
+                        Some(StoppedEventBody {
+                            reason: reason.into(),
+                            thread_id: THREAD_ID,
+                            text: text,
+                        }));
+    }
+
+    fn set_breakpoints(&self, args: SetBreakpointsArguments) -> Result<Vec<Breakpoint>, String> {
+        let path = match args.source.path {
+            Some(ref path) => path.clone(),
+            None => return Err("Breakpoints can only be set in files".into()),
+        };
+        let requested: Vec<_> = match args.breakpoints {
+            Some(ref breakpoints) => {
+                breakpoints.iter()
+                    .map(|breakpoint| {
+                        (breakpoint.line,
+                         non_empty(&breakpoint.condition),
+                         non_empty(&breakpoint.log_message))
+                    })
+                    .collect()
+            }
+            None => {
+                args.lines
+                    .iter()
+                    .flat_map(|lines| lines)
+                    .map(|&line| (line, None, None))
+                    .collect()
+            }
+        };
+        let mut source = String::new();
+        try!(File::open(&path)
+            .and_then(|mut file| file.read_to_string(&mut source))
+            .map_err(|err| format!("Could not read `{}`: {}", path, err)));
+        let mut expr = match Compiler::new().parse_partial_expr("", &source) {
+            Ok(expr) |
+            Err((Some(expr), _)) => Some(expr),
+            Err((None, _)) => None,
+        };
+        // The program only stops at the breakpoints of the main module of a launched program
+        let in_program = self.launch
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map_or(true, |launch| normalize(&launch.program) == normalize(&path));
+        let breakpoints: Vec<_> = requested.into_iter()
+            .map(|(line, condition, log_message)| {
+                let id = self.next_breakpoint_id.fetch_add(1, Ordering::SeqCst) as i64 + 1;
+                let location = expr.as_mut()
+                    .and_then(|expr| breakpoints::adjusted_location(expr, line));
+                let breakpoint = match location {
+                    Some(location) => {
+                        Breakpoint {
+                            id: id,
+                            verified: in_program,
+                            message: if in_program {
+                                None
+                            } else {
+                                Some(NOT_IN_PROGRAM.into())
+                            },
+                            source: args.source.clone(),
+                            line: location.row as i64,
+                            column: Some(location.column as i64),
+                        }
+                    }
+                    None => {
+                        Breakpoint {
+                            id: id,
+                            verified: false,
+                            message: Some("No code at or after this line".into()),
+                            source: args.source.clone(),
+                            line: line,
+                            column: None,
+                        }
+                    }
+                };
+                SetBreakpoint {
+                    breakpoint: breakpoint,
+                    condition: condition,
+                    log_message: log_message,
+                }
+            })
+            .collect();
+        let result = breakpoints.iter().map(|set| set.breakpoint.clone()).collect();
+        self.breakpoints.lock().unwrap().insert(normalize(&path), breakpoints);
+        Ok(result)
+    }
+
+    /// Marks the breakpoints in other files than `program` as unverified since breakpoints can
+    /// only be set in the launched program
+    fn check_breakpoints(&self, program: &Path) {
+        let mut breakpoints = self.breakpoints.lock().unwrap();
+        for (path, breakpoints) in breakpoints.iter_mut() {
+            let breakpoints = breakpoints.iter_mut()
+                .map(|set| &mut set.breakpoint)
+                .filter(|breakpoint| breakpoint.verified);
+            for breakpoint in breakpoints {
+                if *path != *program {
+                    breakpoint.verified = false;
+                    breakpoint.message = Some(NOT_IN_PROGRAM.into());
+                    self.send_event("breakpoint",
+                                    Some(BreakpointEventBody {
+                                        reason: "changed".into(),
+                                        breakpoint: breakpoint.clone(),
+                                    }));
+                }
+            }
+        }
+    }
+
+    /// Returns true if the step which the program takes stops at the step point on `line` of the
+    /// module with the index `module`
+    fn step_stops(&self, vm: &GluonThread, module: i64, line: i64) -> bool {
+        let step = match *self.step.lock().unwrap() {
+            Some(step) => step,
+            None => return false,
+        };
+        let depth = stack_trace::frames(vm).len();
+        // The other step points of the line which the program stopped at are stepped over
+        let moved = depth != step.depth || (module, line) != step.from;
+        let stops = match step.kind {
+            StepKind::Over => depth <= step.depth,
+            StepKind::In => true,
+            StepKind::Out => depth < step.depth,
+        };
+        moved && stops
+    }
+
+    /// Returns the condition and the log message of the breakpoint at `line` and `column` of the
+    /// module `name`, if there is a breakpoint
+    fn breakpoint_at(&self,
+                     program: &Program,
+                     name: &str,
+                     line: i64,
+                     column: i64)
+                     -> Option<(Option<String>, Option<String>)> {
+        if name != program.modules.main {
+            return None;
+        }
+        let breakpoints = self.breakpoints.lock().unwrap();
+        breakpoints.get(&program.modules.program)
+            .and_then(|breakpoints| {
+                breakpoints.iter().find(|set| {
+                    set.breakpoint.verified && set.breakpoint.line == line &&
+                    set.breakpoint.column == Some(column)
+                })
+            })
+            .map(|set| (set.condition.clone(), set.log_message.clone()))
+    }
+
+    /// Evaluates `expression` in the innermost frame for a breakpoint, without stopping at the
+    /// step points which the evaluation reaches
+    fn evaluate_at_breakpoint<'vm>(&self,
+                                   vm: &'vm GluonThread,
+                                   handles: &mut Handles<'vm>,
+                                   locals: &mut Locals,
+                                   expression: String)
+                                   -> Result<String, String> {
+        let args = EvaluateArguments {
+            expression: expression,
+            frame_id: Some(0),
+        };
+        self.evaluating.store(true, Ordering::SeqCst);
+        let result = self.evaluate(vm, handles, locals, &args);
+        self.evaluating.store(false, Ordering::SeqCst);
+        result.map(|body| body.result)
+    }
+
+    /// Returns true if the program stops at a breakpoint with `condition`. An error in the
+    /// condition is reported and stops the program so that it can be fixed.
+    fn condition_holds<'vm>(&self,
+                            vm: &'vm GluonThread,
+                            handles: &mut Handles<'vm>,
+                            locals: &mut Locals,
+                            condition: &str)
+                            -> bool {
+        let expression = format!("if {} then 1 else 0", condition);
+        match self.evaluate_at_breakpoint(vm, handles, locals, expression) {
+            Ok(result) => result == "1",
+            Err(err) => {
+                self.output("stderr",
+                            format!("Could not evaluate the condition `{}`: {}\n", condition, err));
+                true
+            }
+        }
+    }
+
+    /// Formats the log message of a logpoint, replacing each expression in `{}` with its value
+    fn log_message<'vm>(&self,
+                        vm: &'vm GluonThread,
+                        handles: &mut Handles<'vm>,
+                        locals: &mut Locals,
+                        message: &str)
+                        -> String {
+        let mut output = String::new();
+        let mut rest = message;
+        while let Some(start) = rest.find('{') {
+            let end = match rest[start..].find('}') {
+                Some(end) => start + end,
+                None => break,
+            };
+            output.push_str(&rest[..start]);
+            let expression = &rest[start + 1..end];
+            match self.evaluate_at_breakpoint(vm, handles, locals, expression.into()) {
+                Ok(value) => output.push_str(&value),
+                Err(err) => output.push_str(&format!("<error: {}>", err)),
+            }
+            rest = &rest[end + 1..];
+        }
+        output.push_str(rest);
+        output.push('\n');
+        output
+    }
+
+    fn hit_step_point(&self,
+                      vm: &GluonThread,
+                      program: &Program,
+                      module: VMInt,
+                      line: VMInt,
+                      column: VMInt,
+                      locals: GluonValue) {
+        // Functions called by an expression which is evaluated while the program is stopped
+        // reach step points as well but the program is already stopped
+        if self.stopped.lock().unwrap().is_some() || self.evaluating.load(Ordering::SeqCst) {
+            return;
+        }
+        let (module, line, column) = (module as i64, line as i64, column as i64);
+        let name = STEP_POINTS.with(|points| points.borrow().modules.get(module as usize).cloned());
+        let name = match name {
+            Some(name) => name,
+            None => return,
+        };
+        let step_stops = self.step_stops(vm, module, line);
+        let breakpoint = self.breakpoint_at(program, &name, line, column);
+        if !step_stops && breakpoint.is_none() {
+            return;
+        }
+        let typ = STEP_POINTS.with(|points| {
+            points.borrow().local_types.get(&(module, line, column)).cloned()
+        });
+        let mut locals = Locals {
+            value: locals,
+            typ: typ.unwrap_or_else(Type::unit),
+            global: None,
+        };
+        let mut handles = Handles::new();
+        handles.insert(locals.value, locals.typ.clone());
+        let mut breakpoint_stops = false;
+        if let Some((condition, log_message)) = breakpoint {
+            let holds = condition.map_or(true, |condition| {
+                self.condition_holds(vm, &mut handles, &mut locals, &condition)
+            });
+            match log_message {
+                Some(ref message) if holds => {
+                    let output = self.log_message(vm, &mut handles, &mut locals, message);
+                    self.output("console", output);
+                }
+                _ => breakpoint_stops = holds,
+            }
+        }
+        let reason = if step_stops {
+            Stopped::Step
+        } else if breakpoint_stops {
+            Stopped::Breakpoint
+        } else {
+            return;
+        };
+        *self.step.lock().unwrap() = None;
+        *self.position.lock().unwrap() = (module, line);
+        self.stop(reason,
+                  stack_frames(vm, &program.modules, Some((&name, line, column))),
+                  None);
+        self.wait(vm, &mut handles, &mut locals);
+    }
+
+    fn breaks_on(&self, filter: &str) -> bool {
+        self.exception_filters.lock().unwrap().iter().any(|enabled| enabled == filter)
+    }
+
+    /// Stops the program before it raises an error with `message` if it breaks on raised errors
+    fn hit_raise(&self, vm: &GluonThread, program: &Program, message: GluonValue) {
+        if self.stopped.lock().unwrap().is_some() || self.evaluating.load(Ordering::SeqCst) ||
+           !self.breaks_on(RAISED_FILTER) {
+            return;
+        }
+        let message = match unsafe { Variants::new(&message) }.as_ref() {
+            ValueRef::String(message) => String::from(message),
+            _ => "error".into(),
+        };
+        // The locals are not passed to `error` so only the globals can be inspected
+        let mut locals = Locals {
+            value: GluonValue::Int(0),
+            typ: Type::unit(),
+            global: None,
+        };
+        let mut handles = Handles::new();
+        *self.step.lock().unwrap() = None;
+        *self.exception.lock().unwrap() = Some((message.clone(), "always"));
+        self.stop(Stopped::Raise,
+                  stack_frames(vm, &program.modules, None),
+                  Some(message));
+        self.wait(vm, &mut handles, &mut locals);
+    }
+
+    /// Blocks the program which stopped until the client continues and inspects the program in
+    /// the meantime
+    fn wait<'vm>(&self, vm: &'vm GluonThread, handles: &mut Handles<'vm>, locals: &mut Locals) {
+        let commands = self.commands_receiver.lock().unwrap();
+        while let Ok(command) = commands.recv() {
+            match command {
+                Command::Continue => break,
+                Command::Variables(args, sender) => {
+                    let _ = sender.send(handles.children(&*vm.get_env(), &args));
+                }
+                Command::Evaluate(args, sender) => {
+                    let _ = sender.send(self.evaluate(vm, handles, locals, &args));
+                }
+                Command::Completions(args, sender) => {
+                    let _ = sender.send(self.completions(vm, locals, &args));
+                }
+            }
+        }
+    }
+
+    /// Returns the source which brings the local variables into scope for an expression which is
+    /// evaluated in the frame `frame_id`. The locals of outer frames are not passed to the step
+    /// point so expressions in those frames only see the globals.
+    fn locals_source(&self,
+                     vm: &GluonThread,
+                     locals: &mut Locals,
+                     frame_id: Option<i64>)
+                     -> Result<String, String> {
+        let mut source = String::new();
+        let fields = field_names(vm, &locals.typ);
+        if frame_id == Some(0) && !fields.is_empty() {
+            if locals.global.is_none() {
+                let stop = self.stops.fetch_add(1, Ordering::SeqCst);
+                let global = format!("__debug_locals_{}", stop);
+                try!(vm.global_env()
+                    .set_global(Symbol::new(&global),
+                                locals.typ.clone(),
+                                Metadata::default(),
+                                locals.value)
+                    .map_err(|err| format!("{}", err)));
+                locals.global = Some(global);
+            }
+            if let Some(ref global) = locals.global {
+                source.push_str(&format!("let {{ {} }} = {}\nin\n", fields.join(", "), global));
+            }
+        }
+        Ok(source)
+    }
+
+    /// Evaluates `args.expression` with the local variables in scope if it is evaluated in the
+    /// innermost frame
+    fn evaluate<'vm>(&self,
+                     vm: &'vm GluonThread,
+                     handles: &mut Handles<'vm>,
+                     locals: &mut Locals,
+                     args: &EvaluateArguments)
+                     -> Result<EvaluateResponseBody, String> {
+        let mut source = try!(self.locals_source(vm, locals, args.frame_id));
+        source.push_str(&args.expression);
+        let (value, typ) = try!(eval::run_expr(&mut Compiler::new(),
+                                               vm,
+                                               "evaluate",
+                                               &source,
+                                               &source[..])
+            .map_err(|err| format!("{}", err)));
+        let value = handles.root(value);
+        let variable = handles.variable(&*vm.get_env(), String::new(), value, &typ);
+        Ok(EvaluateResponseBody {
+            result: variable.value,
+            typ: variable.typ,
+            variables_reference: variable.variables_reference,
+        })
+    }
+
+    /// Returns the completions of the word before `args.column` of `args.text`. The fields of the
+    /// record are proposed after a `.` and the local variables of the frame otherwise.
+    fn completions(&self,
+                   vm: &GluonThread,
+                   locals: &mut Locals,
+                   args: &CompletionsArguments)
+                   -> Result<Vec<CompletionItem>, String> {
+        // Lines and columns start at 1
+        let line = (args.line.unwrap_or(1).max(1) - 1) as usize;
+        let line = args.text.lines().nth(line).unwrap_or("");
+        let end = line.char_indices()
+            .nth((args.column.max(1) - 1) as usize)
+            .map_or(line.len(), |(i, _)| i);
+        let before = &line[..end];
+        let start = identifier_start(before, false);
+        let (names, typ) = if before[..start].ends_with('.') {
+            let record = &before[identifier_start(&before[..start - 1], true)..start - 1];
+            if record.is_empty() {
+                return Ok(Vec::new());
+            }
+            let mut source = try!(self.locals_source(vm, locals, args.frame_id));
+            source.push_str(record);
+            let (_, record_type) = try!(Compiler::new()
+                .typecheck_str(vm, "completions", &source, None)
+                .map_err(|err| format!("{}", err)));
+            (field_names(vm, &record_type), "property")
+        } else if args.frame_id == Some(0) {
+            (field_names(vm, &locals.typ), "variable")
+        } else {
+            (Vec::new(), "variable")
+        };
+        let word = &before[start..];
+        Ok(names.into_iter()
+            .filter(|name| name.starts_with(word))
+            .map(|name| {
+                CompletionItem {
+                    label: name,
+                    typ: typ.into(),
+                    start: before[..start].chars().count() as i64 + 1,
+                    length: word.chars().count() as i64,
+                }
+            })
+            .collect())
+    }
+
+    /// Sends `command` to the program if it is stopped at a step point and waits for the reply
+    fn inspect<T, F>(&self, command: F) -> Result<T, String>
+        where F: FnOnce(Sender<Result<T, String>>) -> Command
+    {
+        match *self.stopped.lock().unwrap() {
+            Some(Stopped::Breakpoint) |
+            Some(Stopped::Raise) |
+            Some(Stopped::Step) => (),
+            Some(Stopped::Exception) => {
+                return Err("The values of a program which stopped due to an error can't be \
+                            inspected"
+                    .into())
+            }
+            None => return Err("The program is not stopped".into()),
+        }
+        let (sender, receiver) = channel();
+        let _ = self.commands_sender.lock().unwrap().send(command(sender));
+        receiver.recv().unwrap_or_else(|_| Err("The program has exited".into()))
+    }
+
+    /// Continues the program which is stopped at a step point until it reaches the step point
+    /// where `kind` stops
+    fn step(&self, kind: StepKind) -> Result<(), String> {
+        let mut stopped = self.stopped.lock().unwrap();
+        match *stopped {
+            Some(Stopped::Breakpoint) |
+            Some(Stopped::Raise) |
+            Some(Stopped::Step) => (),
+            Some(Stopped::Exception) => {
+                return Err("A program which stopped due to an error can't step".into())
+            }
+            None => return Err("The program is not stopped".into()),
+        }
+        let mut frames = self.frames.lock().unwrap();
+        *self.step.lock().unwrap() = Some(Step {
+            kind: kind,
+            depth: frames.len(),
+            from: *self.position.lock().unwrap(),
+        });
+        *stopped = None;
+        frames.clear();
+        let _ = self.commands_sender.lock().unwrap().send(Command::Continue);
+        Ok(())
+    }
+
+    /// Lets the scripts of an embedded VM run without stopping after the client disconnected
+    fn detach(&self) {
+        self.breakpoints.lock().unwrap().clear();
+        *self.step.lock().unwrap() = None;
+        self.frames.lock().unwrap().clear();
+        if self.stopped.lock().unwrap().take().is_some() {
+            let _ = self.commands_sender.lock().unwrap().send(Command::Continue);
+        }
+    }
+
+    fn stack_trace(&self, args: StackTraceArguments) -> StackTraceResponseBody {
+        let frames = self.frames.lock().unwrap();
+        let start = args.start_frame.unwrap_or(0) as usize;
+        let levels = match args.levels {
+            Some(levels) if levels > 0 => levels as usize,
+            _ => frames.len(),
+        };
+        StackTraceResponseBody {
+            stack_frames: frames.iter()
+                .skip(start)
+                .take(levels)
+                .cloned()
+                .collect(),
+            total_frames: frames.len() as i64,
+        }
+    }
+}
+
+/// Typechecks the module `name`, instrumenting it with step points first if `instrument` is true.
+/// The module and the types of the local variables at its step points are added to
+/// `STEP_POINTS`.
+fn typecheck_module(compiler: &mut Compiler,
+                    vm: &GluonThread,
+                    name: &str,
+                    source: &str,
+                    instrument: bool)
+                    -> GluonResult<TypecheckValue> {
+    let mut expr = try!(compiler.parse_expr(name, source));
+    if !instrument {
+        return expr.typecheck(compiler, vm, name, source);
+    }
+    let module = STEP_POINTS.with(|points| {
+        let mut points = points.borrow_mut();
+        points.modules.push(name.into());
+        points.modules.len() as i64 - 1
+    });
+    breakpoints::instrument(&mut expr, module);
+    let value = try!(expr.typecheck(compiler, vm, name, source));
+    let local_types = breakpoints::local_types(&value.0, &*vm.get_env());
+    STEP_POINTS.with(|points| points.borrow_mut().local_types.extend(local_types));
+    Ok(value)
+}
+
+/// Runs the main module of `modules`, whose source is `source`, on `vm`. The program stops at its
+/// step points if `instrument` is true.
+fn run_module<'vm>(debugger: &Arc<Debugger>,
+                   vm: &'vm GluonThread,
+                   modules: Modules,
+                   source: &str,
+                   instrument: bool)
+                   -> GluonResult<(RootedValue<&'vm GluonThread>, TcType)> {
+    let main = modules.main.clone();
+    // The imported modules are run while the program is typechecked so they can stop as well
+    PROGRAM.with(|program| {
+        *program.borrow_mut() = Some(Program {
+            debugger: debugger.clone(),
+            modules: modules,
+        })
+    });
+    let mut compiler = Compiler::new();
+    let value = try!(typecheck_module(&mut compiler, vm, &main, source, instrument));
+    eval::run_expr(&mut compiler, vm, &main, source, value)
+}
+
+/// Defines the primitives which the instrumented program calls
+fn define_debug_functions(vm: &GluonThread) -> Result<(), String> {
+    let break_function = primitive_f::<fn(_, _, _, _) -> _>(BREAK_FUNCTION,
+                                                             debug_break_wrapper,
+                                                             debug_break);
+    try!(vm.define_global(BREAK_FUNCTION, break_function).map_err(|err| format!("{}", err)));
+    let raise_function = primitive_f::<fn(_) -> _>(RAISE_FUNCTION,
+                                                    debug_raise_wrapper,
+                                                    debug_raise);
+    vm.define_global(RAISE_FUNCTION, raise_function).map_err(|err| format!("{}", err))
+}
+
+fn run_program(debugger: &Arc<Debugger>, program: &str) {
+    let path = normalize(program);
+    let mut source = String::new();
+    if let Err(err) = File::open(&path).and_then(|mut file| file.read_to_string(&mut source)) {
+        debugger.output("stderr", format!("Could not read `{}`: {}\n", program, err));
+        return debugger.terminate(1);
+    }
+    let modules = Modules::new(&path);
+    let no_debug = debugger.launch
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|launch| launch.no_debug)
+        .unwrap_or(false);
+    let vm = if no_debug {
+        eval::new_eval_vm(Some(&modules.root))
+    } else {
+        debugger.check_breakpoints(&path);
+        eval::new_eval_vm_with_importer(Some(&modules.root), StepImporter)
+    };
+    if let Err(err) = define_debug_functions(&vm) {
+        debugger.output("stderr", format!("{}\n", err));
+        return debugger.terminate(1);
+    }
+    match run_module(debugger, &vm, modules, &source, !no_debug) {
+        Ok((value, typ)) => {
+            let value = eval::format_rooted(&vm, &value, &typ);
+            debugger.output("console", format!("{} : {}\n", value, typ));
+            debugger.terminate(0);
+        }
+        Err(GluonError::VM(err)) => {
+            debugger.output("stderr", format!("{}\n", err));
+            if !debugger.breaks_on(UNCAUGHT_FILTER) {
+                return debugger.terminate(1);
+            }
+            let frames = PROGRAM.with(|program| {
+                program.borrow()
+                    .as_ref()
+                    .map_or(Vec::new(), |program| stack_frames(&vm, &program.modules, None))
+            });
+            *debugger.exception.lock().unwrap() = Some((format!("{}", err), "unhandled"));
+            debugger.stop(Stopped::Exception, frames, Some(format!("{}", err)));
+        }
+        Err(err) => {
+            debugger.output("stderr", format!("{}\n", err));
+            debugger.terminate(1);
+        }
+    }
+}
+
+fn arguments<T>(request: &Request) -> Result<T, String>
+    where T: serde::Deserialize
+{
+    let arguments = request.arguments.clone().unwrap_or(Value::Null);
+    from_value(arguments).map_err(|err| format!("Invalid arguments: {}", err))
+}
+
+fn handle(debugger: &Arc<Debugger>, request: &Request) -> Result<Option<Value>, String> {
+    match &request.command[..] {
+        "initialize" => {
+            Ok(Some(to_value(&Capabilities {
+                supports_configuration_done_request: true,
+                supports_conditional_breakpoints: true,
+                supports_log_points: true,
+                supports_completions_request: true,
+                exception_breakpoint_filters: vec![ExceptionBreakpointsFilter {
+                                                       filter: UNCAUGHT_FILTER.into(),
+                                                       label: "Uncaught errors".into(),
+                                                       default: true,
+                                                   },
+                                                   ExceptionBreakpointsFilter {
+                                                       filter: RAISED_FILTER.into(),
+                                                       label: "All raised errors".into(),
+                                                       default: false,
+                                                   }],
+                supports_exception_info_request: true,
+            })))
+        }
+        "launch" if debugger.embedded => {
+            Err("An embedded VM runs its own scripts so it can only be attached to".into())
+        }
+        "attach" => Ok(None),
+        "launch" => {
+            let args = try!(arguments::<LaunchRequestArguments>(request));
+            *debugger.launch.lock().unwrap() = Some(args);
+            Ok(None)
+        }
+        "setBreakpoints" => {
+            let args = try!(arguments::<SetBreakpointsArguments>(request));
+            let breakpoints = try!(debugger.set_breakpoints(args));
+            Ok(Some(to_value(&SetBreakpointsResponseBody { breakpoints: breakpoints })))
+        }
+        "configurationDone" if debugger.embedded => {
+            *debugger.configured.lock().unwrap() = true;
+            debugger.configured_changed.notify_all();
+            Ok(None)
+        }
+        "configurationDone" => {
+            let program = match *debugger.launch.lock().unwrap() {
+                Some(ref launch) => launch.program.clone(),
+                None => return Err("No program has been launched".into()),
+            };
+            let debugger = debugger.clone();
+            thread::spawn(move || {
+                let sink = debugger.clone();
+                output::capture(move |output| sink.output("stdout", output.into()),
+                                || run_program(&debugger, &program))
+            });
+            Ok(None)
+        }
+        "setExceptionBreakpoints" => {
+            let args = try!(arguments::<SetExceptionBreakpointsArguments>(request));
+            *debugger.exception_filters.lock().unwrap() = args.filters;
+            Ok(None)
+        }
+        "exceptionInfo" => {
+            match *debugger.stopped.lock().unwrap() {
+                Some(Stopped::Raise) |
+                Some(Stopped::Exception) => (),
+                _ => return Err("The program is not stopped at an error".into()),
+            }
+            let exception = debugger.exception.lock().unwrap().clone();
+            let (description, break_mode) = try!(exception
+                .ok_or_else(|| String::from("The program is not stopped at an error")));
+            Ok(Some(to_value(&ExceptionInfoResponseBody {
+                exception_id: "error".into(),
+                description: Some(description),
+                break_mode: break_mode.into(),
+            })))
+        }
+        "threads" => {
+            Ok(Some(to_value(&ThreadsResponseBody {
+                threads: vec![Thread {
+                                  id: THREAD_ID,
+                                  name: "main".into(),
+                              }],
+            })))
+        }
+        "stackTrace" => {
+            let args = try!(arguments::<StackTraceArguments>(request));
+            Ok(Some(to_value(&debugger.stack_trace(args))))
+        }
+        "scopes" => {
+            let args = try!(arguments::<ScopesArguments>(request));
+            let frames = debugger.frames.lock().unwrap();
+            if args.frame_id < 0 || args.frame_id as usize >= frames.len() {
+                return Err(format!("No stack frame with id {}", args.frame_id));
+            }
+            // Only the locals of the frame which stopped at the step point are known
+            let variables_reference = match *debugger.stopped.lock().unwrap() {
+                Some(Stopped::Breakpoint) |
+                Some(Stopped::Step) if args.frame_id == 0 => LOCALS_REFERENCE,
+                _ => 0,
+            };
+            Ok(Some(to_value(&ScopesResponseBody {
+                scopes: vec![Scope {
+                                 name: "Locals".into(),
+                                 variables_reference: variables_reference,
+                                 expensive: false,
+                             }],
+            })))
+        }
+        "variables" => {
+            let args = try!(arguments::<VariablesArguments>(request));
+            let variables = try!(debugger.inspect(|sender| Command::Variables(args, sender)));
+            Ok(Some(to_value(&VariablesResponseBody { variables: variables })))
+        }
+        "evaluate" => {
+            let args = try!(arguments::<EvaluateArguments>(request));
+            let body = try!(debugger.inspect(|sender| Command::Evaluate(args, sender)));
+            Ok(Some(to_value(&body)))
+        }
+        "completions" => {
+            let args = try!(arguments::<CompletionsArguments>(request));
+            let targets = try!(debugger.inspect(|sender| Command::Completions(args, sender)));
+            Ok(Some(to_value(&CompletionsResponseBody { targets: targets })))
+        }
+        "continue" => {
+            let stopped = debugger.stopped.lock().unwrap().take();
+            debugger.frames.lock().unwrap().clear();
+            match stopped {
+                Some(Stopped::Breakpoint) |
+                Some(Stopped::Raise) |
+                Some(Stopped::Step) => {
+                    let _ = debugger.commands_sender.lock().unwrap().send(Command::Continue);
+                }
+                // A program which stopped due to an error can't continue so continuing ends it
+                Some(Stopped::Exception) => debugger.terminate(1),
+                None => return Err("The program is not stopped".into()),
+            }
+            Ok(None)
+        }
+        "next" => debugger.step(StepKind::Over).map(|_| None),
+        "stepIn" => debugger.step(StepKind::In).map(|_| None),
+        "stepOut" => debugger.step(StepKind::Out).map(|_| None),
+        "disconnect" => Ok(None),
+        command => Err(format!("Unknown command `{}`", command)),
+    }
+}
+
+fn respond(debugger: &Debugger, request: &Request, result: Result<Option<Value>, String>) {
+    let response = Response {
+        seq: debugger.next_seq(),
+        typ: "response",
+        request_seq: request.seq,
+        success: result.is_ok(),
+        command: request.command.clone(),
+        message: result.as_ref().err().cloned(),
+        body: result.ok().and_then(|body| body),
+    };
+    ::write_message(&to_value(&response).to_string());
+}
+
+/// Connects to the debug server of the embedded VM which the `attach` request `request` attaches
+/// to
+fn connect(request: &Request) -> Result<TcpStream, String> {
+    let args = try!(arguments::<AttachRequestArguments>(request));
+    TcpStream::connect(&args.address[..])
+        .map_err(|err| format!("Could not attach to `{}`: {}", args.address, err))
+}
+
+/// Forwards the requests from `input` to the debug server at the other end of `stream`, starting
+/// with the `attach` request `attach`, and the messages of the debug server to the client until
+/// the client disconnects
+fn forward<R>(debugger: &Arc<Debugger>,
+              input: &mut R,
+              stream: TcpStream,
+              attach: &str)
+              -> Result<(), Box<StdError>>
+    where R: BufRead
+{
+    let mut writer = try!(stream.try_clone());
+    let sink = debugger.clone();
+    let reader = thread::spawn(move || {
+        let mut stream = BufReader::new(stream);
+        while let Ok(Some(json)) = ::read_message(&mut stream) {
+            ::write_message(&json);
+        }
+        // The application may exit before the client disconnects
+        sink.send_event::<()>("terminated", None);
+    });
+    try!(transport::write_to(&mut writer, attach));
+    while let Some(json) = try!(::read_message(input)) {
+        try!(transport::write_to(&mut writer, &json));
+        let disconnect = ::serde_json::from_str::<Request>(&json)
+            .map(|request| request.command == "disconnect")
+            .unwrap_or(false);
+        if disconnect {
+            break;
+        }
+    }
+    let _ = writer.shutdown(Shutdown::Write);
+    let _ = reader.join();
+    Ok(())
+}
+
+/// Handles the requests read from `input` until the client disconnects. The debug adapter
+/// forwards the requests after an `attach` request to the debug server it attaches to.
+fn serve<R>(debugger: &Arc<Debugger>, input: &mut R) -> Result<(), Box<StdError>>
+    where R: BufRead
+{
+    while let Some(json) = try!(::read_message(input)) {
+        let request: Request = match ::serde_json::from_str(&json) {
+            Ok(request) => request,
+            Err(err) => {
+                debugger.output("stderr", format!("Invalid request: {}\n", err));
+                continue;
+            }
+        };
+        if request.command == "attach" && !debugger.embedded {
+            match connect(&request) {
+                Ok(stream) => return forward(debugger, input, stream, &json),
+                Err(err) => {
+                    respond(debugger, &request, Err(err));
+                    continue;
+                }
+            }
+        }
+        let result = handle(debugger, &request);
+        respond(debugger, &request, result);
+        match &request.command[..] {
+            "initialize" => debugger.send_event::<()>("initialized", None),
+            "disconnect" => break,
+            _ => (),
+        }
+    }
+    Ok(())
+}
+
+/// Runs the debug adapter on stdin and stdout until the client disconnects
+pub fn run() -> Result<(), Box<StdError>> {
+    let stdin = io::stdin();
+    let mut stdin = stdin.lock();
+    serve(&Arc::new(Debugger::new(false)), &mut stdin)
+}
+
+/// The debug server of a gluon VM which is embedded in another application. The debug adapter
+/// attaches to it with an `attach` request, after which the scripts which the application runs
+/// with `run_script` stop at the breakpoints which are set in the editor.
+pub struct DebugServer {
+    debugger: Arc<Debugger>,
+    /// Writes the messages to the debug adapter, if it is attached
+    output: transport::Output,
+}
+
+impl DebugServer {
+    /// Starts a debug server on the address in `GLUON_DEBUG` if the variable is set
+    pub fn from_env() -> io::Result<Option<DebugServer>> {
+        match env::var(DEBUG_VAR) {
+            Ok(address) => DebugServer::listen(&address).map(Some),
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Starts a debug server which listens for the debug adapter on `address`. The adapter may
+    /// attach again after it disconnected.
+    pub fn listen(address: &str) -> io::Result<DebugServer> {
+        let listener = try!(TcpListener::bind(address));
+        let debugger = Arc::new(Debugger::new(true));
+        let connection: Arc<Mutex<Option<TcpStream>>> = Arc::new(Mutex::new(None));
+        let writer = connection.clone();
+        let output: transport::Output = Arc::new(move |message: &str| {
+            if let Some(ref mut stream) = *writer.lock().unwrap() {
+                let _ = transport::write_to(stream, message);
+            }
+        });
+        let server = DebugServer {
+            debugger: debugger.clone(),
+            output: output.clone(),
+        };
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let accepted = stream.and_then(|stream| {
+                    let writer = try!(stream.try_clone());
+                    Ok((stream, writer))
+                });
+                let (stream, writer) = match accepted {
+                    Ok(accepted) => accepted,
+                    Err(err) => {
+                        error!("Could not accept the debug adapter: {}", err);
+                        continue;
+                    }
+                };
+                *connection.lock().unwrap() = Some(writer);
+                let mut input = BufReader::new(stream);
+                if let Err(err) = transport::with_output(output.clone(),
+                                                         || serve(&debugger, &mut input)) {
+                    error!("{}", err);
+                }
+                debugger.detach();
+                if let Some(stream) = connection.lock().unwrap().take() {
+                    let _ = stream.shutdown(Shutdown::Both);
+                }
+            }
+        });
+        Ok(server)
+    }
+
+    /// Runs the script at `path`, whose source is `source`, on `vm` and returns its value. The
+    /// script stops at the breakpoints and steps of the debug adapter. Until the adapter has
+    /// attached for the first time and set its breakpoints the script waits for it. The modules
+    /// which the script imports are loaded by the importer of `vm` so they have no step points.
+    pub fn run_script<'vm>(&self,
+                           vm: &'vm GluonThread,
+                           path: &Path,
+                           source: &str)
+                           -> GluonResult<(RootedValue<&'vm GluonThread>, TcType)> {
+        {
+            let mut configured = self.debugger.configured.lock().unwrap();
+            while !*configured {
+                configured = self.debugger.configured_changed.wait(configured).unwrap();
+            }
+        }
+        // A VM which ran a script before already has the primitive
+        let _ = define_debug_functions(vm);
+        let path = normalize(&path.to_string_lossy());
+        self.debugger.check_breakpoints(&path);
+        transport::with_output(self.output.clone(), || {
+            run_module(&self.debugger, vm, Modules::new(&path), source, true)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Write;
+
+    use serde_json::builder::ObjectBuilder;
+
+    fn request(command: &str, arguments: Value) -> Request {
+        Request {
+            seq: 1,
+            command: command.into(),
+            arguments: Some(arguments),
+        }
+    }
+
+    /// Evaluates the record `record` to use its fields as the local variables of a breakpoint.
+    /// The returned value keeps the record alive.
+    fn locals<'vm>(vm: &'vm GluonThread,
+                   record: &str)
+                   -> (RootedValue<&'vm GluonThread>, Locals) {
+        let (value, typ) = eval::run_expr(&mut Compiler::new(), vm, "locals", record, record)
+            .unwrap();
+        let locals = Locals {
+            value: *value,
+            typ: typ,
+            global: None,
+        };
+        (value, locals)
+    }
+
+    #[test]
+    fn breakpoint_conditions() {
+        let vm = eval::new_eval_vm(None);
+        let debugger = Debugger::new(false);
+        let (_record, mut locals) = locals(&vm, "{ x = 3 }");
+        let mut handles = Handles::new();
+        handles.insert(locals.value, locals.typ.clone());
+        assert!(debugger.condition_holds(&vm, &mut handles, &mut locals, "x > 2"));
+        assert!(!debugger.condition_holds(&vm, &mut handles, &mut locals, "x > 5"));
+        // The program stops at a condition which can't be evaluated
+        assert!(debugger.condition_holds(&vm, &mut handles, &mut locals, "y"));
+    }
+
+    #[test]
+    fn logpoint_messages() {
+        let vm = eval::new_eval_vm(None);
+        let debugger = Debugger::new(false);
+        let (_record, mut locals) = locals(&vm, "{ x = 3 }");
+        let mut handles = Handles::new();
+        handles.insert(locals.value, locals.typ.clone());
+        assert_eq!(debugger.log_message(&vm, &mut handles, &mut locals, "x is {x}, then {x + 1}"),
+                   "x is 3, then 4\n");
+        let message = debugger.log_message(&vm, &mut handles, &mut locals, "{y} and {");
+        assert!(message.starts_with("<error: "), "{}", message);
+        assert!(message.ends_with(" and {\n"), "{}", message);
+    }
+
+    #[test]
+    fn complete_locals_and_fields() {
+        assert_eq!(identifier_start("f rec.fie", false), 6);
+        assert_eq!(identifier_start("f rec.fie", true), 2);
+        assert_eq!(identifier_start("f ", false), 2);
+        let vm = eval::new_eval_vm(None);
+        let debugger = Debugger::new(false);
+        let (_record, mut locals) = locals(&vm, "{ length = 1, lines = 2, size = { width = 3 } }");
+        let mut complete = |text: &str, frame_id| {
+            let args = CompletionsArguments {
+                frame_id: Some(frame_id),
+                text: text.into(),
+                column: text.chars().count() as i64 + 1,
+                line: None,
+            };
+            debugger.completions(&vm, &mut locals, &args)
+                .unwrap()
+                .into_iter()
+                .map(|item| (item.label, item.typ, item.start, item.length))
+                .collect::<Vec<(String, String, i64, i64)>>()
+        };
+        let item = |label: &str, typ: &str, start| -> (String, String, i64, i64) {
+            (label.into(), typ.into(), start, 1)
+        };
+        assert_eq!(complete("1 + l", 0),
+                   vec![item("length", "variable", 5), item("lines", "variable", 5)]);
+        assert_eq!(complete("size.w", 0), vec![item("width", "property", 6)]);
+        // The locals of outer frames are not known
+        assert!(complete("l", 1).is_empty());
+    }
+
+    #[test]
+    fn exception_filters() {
+        let debugger = Arc::new(Debugger::new(false));
+        let capabilities = handle(&debugger, &request("initialize", Value::Null)).unwrap().unwrap();
+        let filters = capabilities.find("exceptionBreakpointFilters")
+            .and_then(|filters| filters.as_array())
+            .unwrap();
+        let defaults: Vec<_> = filters.iter()
+            .map(|filter| {
+                (filter.find("filter").and_then(|filter| filter.as_string()),
+                 filter.find("default").and_then(|default| default.as_bool()))
+            })
+            .collect();
+        assert_eq!(defaults,
+                   vec![(Some(UNCAUGHT_FILTER), Some(true)), (Some(RAISED_FILTER), Some(false))]);
+        assert!(debugger.breaks_on(UNCAUGHT_FILTER));
+        assert!(!debugger.breaks_on(RAISED_FILTER));
+
+        let arguments = ObjectBuilder::new()
+            .insert_array("filters", |filters| filters.push(RAISED_FILTER))
+            .unwrap();
+        handle(&debugger, &request("setExceptionBreakpoints", arguments)).unwrap();
+        assert!(!debugger.breaks_on(UNCAUGHT_FILTER));
+        assert!(debugger.breaks_on(RAISED_FILTER));
+    }
+
+    #[test]
+    fn embedded_vms_are_attached_to() {
+        let debugger = Arc::new(Debugger::new(true));
+        let launch = ObjectBuilder::new().insert("program", "main.glu").unwrap();
+        assert!(handle(&debugger, &request("launch", launch)).is_err());
+        assert_eq!(handle(&debugger, &request("attach", Value::Null)), Ok(None));
+        assert!(!*debugger.configured.lock().unwrap());
+        // The scripts of the VM start running once the client has set its breakpoints
+        handle(&debugger, &request("configurationDone", Value::Null)).unwrap();
+        assert!(*debugger.configured.lock().unwrap());
+    }
+
+    #[test]
+    fn breakpoints_outside_the_program_are_not_verified() {
+        let dir = env::temp_dir().join("gluon_debugger_breakpoints");
+        fs::create_dir_all(&dir).unwrap();
+        for &(name, source) in &[("main.glu", "let x = 1\nx"), ("other.glu", "let y = 2\ny")] {
+            File::create(dir.join(name)).unwrap().write_all(source.as_bytes()).unwrap();
+        }
+        let path = |name| dir.join(name).to_string_lossy().into_owned();
+        let debugger = Arc::new(Debugger::new(false));
+        let launch = ObjectBuilder::new().insert("program", path("main.glu")).unwrap();
+        handle(&debugger, &request("launch", launch)).unwrap();
+        let set_breakpoint = |name| {
+            let arguments = ObjectBuilder::new()
+                .insert_object("source", |source| source.insert("path", path(name)))
+                .insert_array("breakpoints",
+                              |breakpoints| breakpoints.push_object(|b| b.insert("line", 2)))
+                .unwrap();
+            handle(&debugger, &request("setBreakpoints", arguments)).unwrap().unwrap()
+        };
+        let main = set_breakpoint("main.glu");
+        assert_eq!(main.pointer("/breakpoints/0/verified").and_then(|v| v.as_bool()),
+                   Some(true));
+        let other = set_breakpoint("other.glu");
+        assert_eq!(other.pointer("/breakpoints/0/verified").and_then(|v| v.as_bool()),
+                   Some(false));
+        assert_eq!(other.pointer("/breakpoints/0/message").and_then(|v| v.as_string()),
+                   Some(NOT_IN_PROGRAM));
+        assert_eq!(other.pointer("/breakpoints/0/line").and_then(|v| v.as_u64()), Some(2));
+    }
+}
@@ -0,0 +1,409 @@
+//! Finding where the names of typechecked modules are bound. A name which is bound to an import,
+//! or to a field of one, is followed into the module which exports it.
+use std::collections::HashMap;
+
+use gluon::base::ast::{ASTType, Expr, LExpr, LPattern, Location, Pattern};
+use gluon::base::symbol::Symbol;
+use gluon::base::types::{TcIdent, Type};
+
+use query;
+
+/// How many imports are followed before giving up, which also stops at modules which re-export
+/// each other's bindings
+const MAX_IMPORTS: usize = 8;
+
+/// The place where a name is bound
+#[derive(Clone, Debug, PartialEq)]
+pub struct Definition {
+    /// The module which binds the name, the uri of a document or the name of an imported module
+    /// such as `std.prelude`
+    pub module: String,
+    pub location: Location,
+}
+
+/// A binding which was found in a module
+struct Binding<'e> {
+    location: Location,
+    /// The expression which the name is bound to, or which the name is a field of, if it may be
+    /// an import
+    source: Option<(&'e LExpr<TcIdent>, Option<&'e str>)>,
+}
+
+fn is_import_source(expr: &LExpr<TcIdent>) -> bool {
+    match expr.value {
+        Expr::Identifier(_) |
+        Expr::FieldAccess(..) => true,
+        _ => false,
+    }
+}
+
+fn pattern_binds<F>(pattern: &LPattern<TcIdent>, is_name: &F) -> bool
+    where F: Fn(&Symbol) -> bool
+{
+    match pattern.value {
+        Pattern::Identifier(ref id) => is_name(&id.name),
+        Pattern::Record { ref fields, .. } => {
+            fields.iter().any(|&(ref field, ref alias)| is_name(alias.as_ref().unwrap_or(field)))
+        }
+        Pattern::Constructor(_, ref args) => args.iter().any(|arg| is_name(&arg.name)),
+    }
+}
+
+/// Returns the binding of the name which `is_name` accepts in the `let` or `type` expression
+/// `expr`, without looking into its subexpressions
+fn binding_in<'e, F>(expr: &'e LExpr<TcIdent>, is_name: &F) -> Option<Binding<'e>>
+    where F: Fn(&Symbol) -> bool
+{
+    match expr.value {
+        Expr::Let(ref bindings, _) => {
+            bindings.iter()
+                .filter(|bind| pattern_binds(&bind.name, is_name))
+                .map(|bind| {
+                    let source = match bind.name.value {
+                        Pattern::Identifier(_) => Some((&bind.expression, None)),
+                        Pattern::Record { ref fields, .. } => {
+                            fields.iter()
+                                .find(|&&(ref field, ref alias)| {
+                                    is_name(alias.as_ref().unwrap_or(field))
+                                })
+                                .map(|&(ref field, _)| {
+                                    (&bind.expression, Some(field.declared_name()))
+                                })
+                        }
+                        Pattern::Constructor(..) => None,
+                    };
+                    Binding {
+                        location: bind.name.location,
+                        source: source.and_then(|source| if is_import_source(source.0) {
+                            Some(source)
+                        } else {
+                            None
+                        }),
+                    }
+                })
+                .next()
+        }
+        Expr::Type(ref bindings, _) => {
+            let binds_constructor = |typ: &Option<ASTType<Symbol>>| {
+                match typ.as_ref().map(|typ| &**typ) {
+                    Some(&Type::Variants(ref variants)) => {
+                        variants.iter().any(|&(ref constructor, _)| is_name(constructor))
+                    }
+                    _ => false,
+                }
+            };
+            bindings.iter()
+                .find(|bind| binds_constructor(&bind.alias.typ))
+                .map(|_| {
+                    Binding {
+                        location: expr.location,
+                        source: None,
+                    }
+                })
+        }
+        _ => None,
+    }
+}
+
+/// Returns the binding of the type `name` in the `type` expression `expr`
+fn type_binding<'e>(expr: &'e LExpr<TcIdent>, name: &str) -> Option<Binding<'e>> {
+    match expr.value {
+        Expr::Type(ref bindings, _) if bindings.iter()
+            .any(|bind| bind.name.declared_name() == name) => {
+            Some(Binding {
+                location: expr.location,
+                source: None,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Returns the binding of `name` anywhere in `expr`
+fn find_binding<'e>(expr: &'e LExpr<TcIdent>, name: &Symbol) -> Option<Binding<'e>> {
+    let is_name = |symbol: &Symbol| symbol == name;
+    if let Some(binding) = binding_in(expr, &is_name) {
+        return Some(binding);
+    }
+    let function = match expr.value {
+        Expr::Let(ref bindings, _) => {
+            bindings.iter()
+                .find(|bind| bind.arguments.iter().any(|arg| arg.name == *name))
+                .map(|bind| bind.name.location)
+        }
+        Expr::Lambda(ref lambda) if lambda.arguments.iter().any(|arg| arg.name == *name) => {
+            Some(expr.location)
+        }
+        Expr::Match(_, ref alts) => {
+            alts.iter()
+                .find(|alt| pattern_binds(&alt.pattern, &is_name))
+                .map(|alt| alt.pattern.location)
+        }
+        _ => None,
+    };
+    if let Some(location) = function {
+        return Some(Binding {
+            location: location,
+            source: None,
+        });
+    }
+    query::children(expr).into_iter().filter_map(|child| find_binding(child, name)).next()
+}
+
+/// The `let` and `type` expressions which the implicit prelude adds in front of `expr`
+fn prelude_lets(expr: &LExpr<TcIdent>) -> Vec<&LExpr<TcIdent>> {
+    let user_expr = query::strip_implicit_prelude(expr);
+    let mut lets = Vec::new();
+    let mut current = expr;
+    while current as *const _ != user_expr as *const _ {
+        match current.value {
+            Expr::Let(_, ref body) |
+            Expr::Type(_, ref body) => {
+                lets.push(current);
+                current = body;
+            }
+            _ => break,
+        }
+    }
+    lets
+}
+
+/// The first line of `module`
+pub fn module_start(module: &str) -> Definition {
+    Definition {
+        module: module.into(),
+        location: Location {
+            row: 1,
+            column: 1,
+            absolute: 0,
+        },
+    }
+}
+
+struct Resolver<'m> {
+    modules: &'m HashMap<String, LExpr<TcIdent>>,
+}
+
+impl<'m> Resolver<'m> {
+    /// Returns the definition of the field `field` of `source`, or of `source` itself, if
+    /// `source` is an imported module or a field of one
+    fn import(&self,
+              module: &str,
+              source: &LExpr<TcIdent>,
+              field: Option<&str>,
+              depth: usize)
+              -> Option<Definition> {
+        if depth == MAX_IMPORTS {
+            return None;
+        }
+        match source.value {
+            Expr::Identifier(ref id) => {
+                if let Some(imported) = self.modules.get(id.name.as_ref()) {
+                    let imported_module = id.name.as_ref();
+                    return Some(match field {
+                        Some(field) => {
+                            self.export(imported_module, imported, field, depth + 1)
+                                .unwrap_or_else(|| module_start(imported_module))
+                        }
+                        None => module_start(imported_module),
+                    });
+                }
+                // A variable which is bound to a module, such as `__implicit_prelude`
+                let expr = match self.modules.get(module) {
+                    Some(expr) => expr,
+                    None => return None,
+                };
+                find_binding(expr, &id.name).and_then(|binding| match binding.source {
+                    Some((source, None)) => self.import(module, source, field, depth + 1),
+                    _ => None,
+                })
+            }
+            // The fields of an instance, such as `(+)` of `prelude.num_Int`, are defined where
+            // the instance is
+            Expr::FieldAccess(ref record, ref instance) => {
+                self.import(module, record, Some(instance.name.declared_name()), depth)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the definition of the binding which the module `module` exports as `field`
+    fn export(&self,
+              module: &str,
+              expr: &LExpr<TcIdent>,
+              field: &str,
+              depth: usize)
+              -> Option<Definition> {
+        let mut lets = Vec::new();
+        let mut body = query::strip_implicit_prelude(expr);
+        loop {
+            match body.value {
+                Expr::Let(_, ref next) |
+                Expr::Type(_, ref next) => {
+                    lets.push(body);
+                    body = next;
+                }
+                _ => break,
+            }
+        }
+        let exported = match body.value {
+            Expr::Record { ref exprs, ref types, .. } => {
+                if types.iter().any(|&(ref name, _)| name.declared_name() == field) {
+                    None
+                } else {
+                    match exprs.iter().find(|&&(ref name, _)| name.declared_name() == field) {
+                        Some(&(_, Some(ref value))) => {
+                            match value.value {
+                                Expr::Identifier(ref id) => Some(id.name.declared_name()),
+                                _ => {
+                                    return Some(Definition {
+                                        module: module.into(),
+                                        location: value.location,
+                                    })
+                                }
+                            }
+                        }
+                        Some(&(_, None)) => Some(field),
+                        None => return None,
+                    }
+                }
+            }
+            _ => return None,
+        };
+        let is_name = |symbol: &Symbol| Some(symbol.declared_name()) == exported;
+        // The last binding of the name is the one which is in scope of the record
+        lets.iter()
+            .rev()
+            .filter_map(|expr| match exported {
+                Some(_) => binding_in(expr, &is_name),
+                None => type_binding(expr, field),
+            })
+            .next()
+            .map(|binding| {
+                binding.source
+                    .and_then(|(source, field)| self.import(module, source, field, depth))
+                    .unwrap_or_else(|| {
+                        Definition {
+                            module: module.into(),
+                            location: binding.location,
+                        }
+                    })
+            })
+    }
+}
+
+/// Returns where `name`, a variable of the module `module`, is bound. Bindings of imports and of
+/// the implicit prelude are followed into the modules they are imported from.
+pub fn definition(modules: &HashMap<String, LExpr<TcIdent>>,
+                  module: &str,
+                  name: &Symbol)
+                  -> Option<Definition> {
+    let expr = match modules.get(module) {
+        Some(expr) => expr,
+        None => return None,
+    };
+    let resolver = Resolver { modules: modules };
+    let resolve = |binding: Binding| {
+        binding.source.and_then(|(source, field)| resolver.import(module, source, field, 0))
+    };
+    if let Some(binding) = find_binding(query::strip_implicit_prelude(expr), name) {
+        let location = binding.location;
+        return Some(resolve(binding).unwrap_or_else(|| {
+            Definition {
+                module: module.into(),
+                location: location,
+            }
+        }));
+    }
+    // The implicit prelude is not part of the document so only the modules it imports are
+    // useful definitions
+    let is_name = |symbol: &Symbol| symbol == name;
+    prelude_lets(expr)
+        .into_iter()
+        .filter_map(|expr| binding_in(expr, &is_name))
+        .next()
+        .and_then(resolve)
+}
+
+fn type_declaration<'e>(expr: &'e LExpr<TcIdent>, name: &str) -> Option<&'e LExpr<TcIdent>> {
+    if type_binding(expr, name).is_some() {
+        return Some(expr);
+    }
+    query::children(expr).into_iter().filter_map(|child| type_declaration(child, name)).next()
+}
+
+/// Returns where the type `name` is declared, looking in `module` first and then in the modules
+/// it may have been imported from
+pub fn type_definition(modules: &HashMap<String, LExpr<TcIdent>>,
+                       module: &str,
+                       name: &str)
+                       -> Option<Definition> {
+    let mut others: Vec<&String> = modules.keys().filter(|other| *other != module).collect();
+    others.sort();
+    let declared_in = |module: &str| {
+        modules.get(module)
+            .and_then(|expr| type_declaration(query::strip_implicit_prelude(expr), name))
+            .map(|declaration| {
+                Definition {
+                    module: module.into(),
+                    location: declaration.location,
+                }
+            })
+    };
+    declared_in(module).or_else(|| others.into_iter().filter_map(|other| declared_in(other)).next())
+}
+
+/// Returns the expression which `name` is a field of if `name` is bound by a record pattern, such
+/// as `__implicit_prelude.num_Int` for `let { (+) } = __implicit_prelude.num_Int`.
+/// Gluon 0.1 has no implicit arguments so this is how an instance is passed.
+pub fn instance_source<'e>(expr: &'e LExpr<TcIdent>, name: &Symbol) -> Option<&'e LExpr<TcIdent>> {
+    instance_field(expr, name).map(|(source, _)| source)
+}
+
+/// Returns the expression which `name` is a field of, as `instance_source` does, and the name of
+/// the field, which differs from `name` if the pattern renames it as in `let { show = s } = ..`
+pub fn instance_field<'e>(expr: &'e LExpr<TcIdent>,
+                          name: &Symbol)
+                          -> Option<(&'e LExpr<TcIdent>, &'e Symbol)> {
+    if let Expr::Let(ref bindings, _) = expr.value {
+        for bind in bindings {
+            if let Pattern::Record { ref fields, .. } = bind.name.value {
+                let field = fields.iter()
+                    .find(|&&(ref field, ref alias)| alias.as_ref().unwrap_or(field) == name);
+                if let Some(&(ref field, _)) = field {
+                    return Some((&bind.expression, field));
+                }
+            }
+        }
+    }
+    query::children(expr).into_iter().filter_map(|child| instance_field(child, name)).next()
+}
+
+/// Returns where the instances which `source`, the expression returned by `instance_source`, is
+/// made of are bound. gluon picks one of the bindings of an overloaded name by its type, so these
+/// are the instances which a use of the name passes implicitly.
+pub fn instances(modules: &HashMap<String, LExpr<TcIdent>>,
+                 module: &str,
+                 source: &LExpr<TcIdent>)
+                 -> Vec<Definition> {
+    let resolver = Resolver { modules: modules };
+    match source.value {
+        // Destructuring a whole module, as the implicit prelude does, does not pass an instance
+        Expr::Identifier(ref id) => {
+            definition(modules, module, &id.name)
+                .into_iter()
+                .filter(|definition| *definition != module_start(&definition.module))
+                .collect()
+        }
+        Expr::FieldAccess(..) => resolver.import(module, source, None, 0).into_iter().collect(),
+        // An instance which is made from other instances, such as `make_Ord ord_Int`
+        Expr::Call(ref func, ref args) => {
+            let mut made_of = instances(modules, module, func);
+            for arg in args {
+                made_of.extend(instances(modules, module, arg));
+            }
+            made_of
+        }
+        _ => vec![],
+    }
+}
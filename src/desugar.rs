@@ -0,0 +1,262 @@
+//! Printing of typechecked gluon ASTs back to gluon source. The printed source is what the
+//! compiler sees: imports are replaced by the globals of the imported modules and operators are
+//! applied as functions, using the names which the typechecker resolved them to.
+use std::fmt::Write;
+
+use gluon::base::ast::{Expr, LExpr, LPattern, LiteralEnum, Pattern};
+use gluon::base::symbol::Symbol;
+use gluon::base::types::TcIdent;
+
+/// Removes the location suffix which the typechecker adds to the names of some bindings
+fn name(symbol: &Symbol) -> &str {
+    let name: &str = symbol.as_ref();
+    name.split(':').next().unwrap_or(name)
+}
+
+fn is_atom(expr: &LExpr<TcIdent>) -> bool {
+    match expr.value {
+        Expr::Identifier(_) |
+        Expr::Literal(_) |
+        Expr::FieldAccess(..) |
+        Expr::Array(_) |
+        Expr::Record { .. } |
+        Expr::Tuple(_) => true,
+        _ => false,
+    }
+}
+
+struct Printer {
+    indent: usize,
+    out: String,
+}
+
+impl Printer {
+    fn newline(&mut self) {
+        self.out.push('\n');
+        for _ in 0..self.indent {
+            self.out.push_str("    ");
+        }
+    }
+
+    fn indented<F>(&mut self, f: F)
+        where F: FnOnce(&mut Printer)
+    {
+        self.indent += 1;
+        self.newline();
+        f(self);
+        self.indent -= 1;
+    }
+
+    fn atom(&mut self, expr: &LExpr<TcIdent>) {
+        if is_atom(expr) {
+            self.expr(expr);
+        } else {
+            self.out.push('(');
+            self.expr(expr);
+            self.out.push(')');
+        }
+    }
+
+    fn pattern(&mut self, pattern: &LPattern<TcIdent>) {
+        match pattern.value {
+            Pattern::Identifier(ref id) => self.out.push_str(name(&id.name)),
+            Pattern::Constructor(ref id, ref args) => {
+                self.out.push_str(name(&id.name));
+                for arg in args {
+                    write!(self.out, " {}", name(&arg.name)).unwrap();
+                }
+            }
+            Pattern::Record { ref types, ref fields, .. } => {
+                let mut names: Vec<String> = types.iter()
+                    .map(|&(ref typ, _)| String::from(name(typ)))
+                    .collect();
+                names.extend(fields.iter().map(|&(ref field, ref alias)| {
+                    match *alias {
+                        Some(ref alias) => format!("{} = {}", name(field), name(alias)),
+                        None => String::from(name(field)),
+                    }
+                }));
+                write!(self.out, "{{ {} }}", names.join(", ")).unwrap();
+            }
+        }
+    }
+
+    fn expr(&mut self, expr: &LExpr<TcIdent>) {
+        match expr.value {
+            Expr::Identifier(ref id) => self.out.push_str(name(&id.name)),
+            Expr::Literal(ref lit) => {
+                match *lit {
+                    LiteralEnum::Byte(b) => write!(self.out, "{}b", b).unwrap(),
+                    LiteralEnum::Integer(i) => write!(self.out, "{}", i).unwrap(),
+                    LiteralEnum::Float(f) => write!(self.out, "{:?}", f).unwrap(),
+                    LiteralEnum::String(ref s) => write!(self.out, "{:?}", s).unwrap(),
+                    LiteralEnum::Char(c) => write!(self.out, "{:?}", c).unwrap(),
+                }
+            }
+            Expr::Call(ref func, ref args) => {
+                self.atom(func);
+                for arg in args {
+                    self.out.push(' ');
+                    self.atom(arg);
+                }
+            }
+            Expr::IfElse(ref pred, ref if_true, ref if_false) => {
+                self.out.push_str("if ");
+                self.expr(pred);
+                self.out.push_str(" then");
+                self.indented(|p| p.expr(if_true));
+                if let Some(ref if_false) = *if_false {
+                    self.newline();
+                    self.out.push_str("else");
+                    self.indented(|p| p.expr(if_false));
+                }
+            }
+            Expr::Match(ref scrutinee, ref alts) => {
+                self.out.push_str("match ");
+                self.expr(scrutinee);
+                self.out.push_str(" with");
+                for alt in alts {
+                    self.newline();
+                    self.out.push_str("| ");
+                    self.pattern(&alt.pattern);
+                    self.out.push_str(" ->");
+                    self.indented(|p| p.expr(&alt.expression));
+                }
+            }
+            Expr::BinOp(ref lhs, ref op, ref rhs) => {
+                write!(self.out, "({}) ", name(&op.name)).unwrap();
+                self.atom(lhs);
+                self.out.push(' ');
+                self.atom(rhs);
+            }
+            Expr::Let(ref bindings, ref body) => {
+                for (i, bind) in bindings.iter().enumerate() {
+                    if i != 0 {
+                        self.newline();
+                    }
+                    self.out.push_str(if i == 0 { "let " } else { "and " });
+                    self.pattern(&bind.name);
+                    for arg in &bind.arguments {
+                        write!(self.out, " {}", name(&arg.name)).unwrap();
+                    }
+                    if let Some(ref typ) = bind.typ {
+                        write!(self.out, " : {}", typ).unwrap();
+                    }
+                    self.out.push_str(" =");
+                    self.indented(|p| p.expr(&bind.expression));
+                }
+                self.newline();
+                self.out.push_str("in");
+                self.newline();
+                self.expr(body);
+            }
+            Expr::FieldAccess(ref record, ref field) => {
+                self.atom(record);
+                write!(self.out, ".{}", name(&field.name)).unwrap();
+            }
+            Expr::Array(ref array) => {
+                self.out.push('[');
+                for (i, expr) in array.expressions.iter().enumerate() {
+                    if i != 0 {
+                        self.out.push_str(", ");
+                    }
+                    self.expr(expr);
+                }
+                self.out.push(']');
+            }
+            Expr::Record { ref types, ref exprs, .. } => {
+                if types.is_empty() && exprs.is_empty() {
+                    self.out.push_str("{}");
+                    return;
+                }
+                self.out.push('{');
+                self.indent += 1;
+                let fields = types.len() + exprs.len();
+                let mut i = 0;
+                for &(ref field, _) in types {
+                    i += 1;
+                    self.newline();
+                    self.out.push_str(name(field));
+                    if i != fields {
+                        self.out.push(',');
+                    }
+                }
+                for &(ref field, ref expr) in exprs {
+                    i += 1;
+                    self.newline();
+                    self.out.push_str(name(field));
+                    if let Some(ref expr) = *expr {
+                        self.out.push_str(" = ");
+                        self.expr(expr);
+                    }
+                    if i != fields {
+                        self.out.push(',');
+                    }
+                }
+                self.indent -= 1;
+                self.newline();
+                self.out.push('}');
+            }
+            Expr::Lambda(ref lambda) => {
+                self.out.push('\\');
+                for arg in &lambda.arguments {
+                    write!(self.out, "{} ", name(&arg.name)).unwrap();
+                }
+                self.out.push_str("->");
+                self.indented(|p| p.expr(&lambda.body));
+            }
+            Expr::Tuple(ref exprs) => {
+                self.out.push('(');
+                for (i, expr) in exprs.iter().enumerate() {
+                    if i != 0 {
+                        self.out.push_str(", ");
+                    }
+                    self.expr(expr);
+                }
+                self.out.push(')');
+            }
+            Expr::Type(ref bindings, ref body) => {
+                for (i, bind) in bindings.iter().enumerate() {
+                    if i != 0 {
+                        self.newline();
+                    }
+                    write!(self.out,
+                           "{} {}",
+                           if i == 0 { "type" } else { "and" },
+                           name(&bind.name))
+                        .unwrap();
+                    for arg in &bind.alias.args {
+                        write!(self.out, " {}", arg.id).unwrap();
+                    }
+                    match bind.alias.typ {
+                        Some(ref typ) => write!(self.out, " = {}", typ).unwrap(),
+                        None => self.out.push_str(" = <abstract>"),
+                    }
+                }
+                self.newline();
+                self.out.push_str("in");
+                self.newline();
+                self.expr(body);
+            }
+            Expr::Block(ref exprs) => {
+                for (i, expr) in exprs.iter().enumerate() {
+                    if i != 0 {
+                        self.newline();
+                    }
+                    self.expr(expr);
+                }
+            }
+        }
+    }
+}
+
+/// Prints `expr` as gluon source
+pub fn print(expr: &LExpr<TcIdent>) -> String {
+    let mut printer = Printer {
+        indent: 0,
+        out: String::new(),
+    };
+    printer.expr(expr);
+    printer.out.push('\n');
+    printer.out
+}
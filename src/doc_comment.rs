@@ -0,0 +1,181 @@
+//! The conventional sections of documentation comments. Lines which start with a tag document a
+//! part of the binding rather than the binding as a whole:
+//!
+//! ```gluon
+//! /// Applies `f` to each element of `xs`
+//! /// @param f The function to apply
+//! /// @param xs The list to map over
+//! /// @return The results in the order of `xs`
+//! /// @example
+//! /// map (\x -> x + 1) (Cons 1 Nil)
+//! let map f xs = ...
+//! ```
+//!
+//! The lines after a `@param` or `@return` line continue it until a blank line or another tag.
+//! The lines after `@example` are the example up to the next tag.
+use docs;
+
+const PARAM: &'static str = "@param";
+const RETURN: &'static str = "@return";
+const EXAMPLE: &'static str = "@example";
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DocComment {
+    /// The untagged lines of the comment
+    pub description: String,
+    /// The name and description of each `@param`, in order
+    pub params: Vec<(String, String)>,
+    pub returns: Option<String>,
+    pub examples: Vec<String>,
+    /// The note of the `@deprecated` line
+    pub deprecated: Option<String>,
+}
+
+enum Section {
+    Description,
+    Param,
+    Return,
+    Example,
+}
+
+fn append(text: &mut String, line: &str) {
+    if !text.is_empty() {
+        text.push(' ');
+    }
+    text.push_str(line);
+}
+
+fn tag<'a>(line: &'a str, tag: &str) -> Option<&'a str> {
+    if !line.starts_with(tag) {
+        return None;
+    }
+    let rest = &line[tag.len()..];
+    if rest.chars().next().map_or(true, char::is_whitespace) {
+        Some(rest.trim())
+    } else {
+        None
+    }
+}
+
+/// Splits `comment` into its sections
+pub fn parse(comment: &str) -> DocComment {
+    let mut doc = DocComment {
+        deprecated: docs::deprecation(comment).map(String::from),
+        ..DocComment::default()
+    };
+    let mut description = Vec::new();
+    let mut section = Section::Description;
+    let comment = docs::without_deprecation(comment);
+    for line in comment.lines() {
+        let trimmed = line.trim();
+        if let Some(rest) = tag(trimmed, PARAM) {
+            let mut words = rest.splitn(2, char::is_whitespace);
+            let name = words.next().unwrap_or("").into();
+            let text = words.next().unwrap_or("").trim().into();
+            doc.params.push((name, text));
+            section = Section::Param;
+        } else if let Some(rest) = tag(trimmed, RETURN) {
+            doc.returns = Some(rest.into());
+            section = Section::Return;
+        } else if let Some(rest) = tag(trimmed, EXAMPLE) {
+            doc.examples.push(rest.into());
+            section = Section::Example;
+        } else {
+            match section {
+                Section::Param | Section::Return if trimmed.is_empty() => {
+                    section = Section::Description;
+                    description.push(line);
+                }
+                Section::Param => {
+                    let last = doc.params.len() - 1;
+                    append(&mut doc.params[last].1, trimmed);
+                }
+                Section::Return => append(doc.returns.as_mut().unwrap(), trimmed),
+                Section::Example => {
+                    let last = doc.examples.len() - 1;
+                    let example = &mut doc.examples[last];
+                    if !example.is_empty() {
+                        example.push('\n');
+                    }
+                    example.push_str(line);
+                }
+                Section::Description => description.push(line),
+            }
+        }
+    }
+    doc.description = description.join("\n").trim().into();
+    for example in &mut doc.examples {
+        *example = example.trim_right().into();
+    }
+    doc
+}
+
+impl DocComment {
+    /// Returns the description of the parameter `name`
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params.iter().find(|param| param.0 == name).map(|param| &param.1[..])
+    }
+
+    /// Renders the sections of the comment as markdown
+    pub fn to_markdown(&self) -> String {
+        let mut sections = Vec::new();
+        if let Some(ref note) = self.deprecated {
+            sections.push(if note.is_empty() {
+                "**Deprecated**".into()
+            } else {
+                format!("**Deprecated**: {}", note)
+            });
+        }
+        if !self.description.is_empty() {
+            sections.push(self.description.clone());
+        }
+        if !self.params.is_empty() {
+            let params: Vec<_> = self.params
+                .iter()
+                .map(|&(ref name, ref text)| if text.is_empty() {
+                    format!("- `{}`", name)
+                } else {
+                    format!("- `{}`: {}", name, text)
+                })
+                .collect();
+            sections.push(format!("**Parameters**\n\n{}", params.join("\n")));
+        }
+        if let Some(ref returns) = self.returns {
+            sections.push(format!("**Returns** {}", returns));
+        }
+        for example in &self.examples {
+            sections.push(format!("**Example**\n\n```gluon\n{}\n```", example));
+        }
+        sections.join("\n\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_sections() {
+        let doc = parse("Applies `f` to each element\n@param f The function\n  to apply\n@param \
+                         xs\n@return The results\n\nMore about it\n@example\nmap f (Cons 1 \
+                         Nil)\n  == Cons (f 1) Nil\n");
+        assert_eq!(doc.description, "Applies `f` to each element\n\nMore about it");
+        assert_eq!(doc.params,
+                   vec![("f".into(), "The function to apply".into()), ("xs".into(), "".into())]);
+        assert_eq!(doc.param("f"), Some("The function to apply"));
+        assert_eq!(doc.returns, Some("The results".into()));
+        assert_eq!(doc.examples, vec![String::from("map f (Cons 1 Nil)\n  == Cons (f 1) Nil")]);
+        assert_eq!(doc.deprecated, None);
+    }
+
+    #[test]
+    fn render_markdown() {
+        assert_eq!(parse("Plain comment").to_markdown(), "Plain comment");
+        let doc = parse("Adds\n@deprecated Use `plus`\n@param x The left side\n@return The sum");
+        assert_eq!(doc.to_markdown(),
+                   "**Deprecated**: Use `plus`\n\nAdds\n\n**Parameters**\n\n- `x`: The left \
+                    side\n\n**Returns** The sum");
+        // Tags have to be followed by whitespace
+        assert_eq!(parse("@parameters").description, "@parameters");
+    }
+}
@@ -0,0 +1,86 @@
+//! Generation of markdown documentation for the modules of a workspace. Each module gets a file
+//! which lists the fields of the record it evaluates to together with their types and
+//! documentation comments.
+use std::fmt::Write as FmtWrite;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+use gluon::base::metadata::Metadata;
+use gluon::base::types::{TcType, Type};
+use gluon::check::metadata;
+use gluon::{Compiler, Thread, filename_to_module};
+
+use eval;
+use glob::Exclude;
+use indexer::source_files;
+
+/// Formats the documentation of `module` as markdown
+fn render(module: &str, typ: &TcType, metadata: &Metadata) -> String {
+    let mut out = format!("# {}\n\n", module);
+    if let Some(ref comment) = metadata.comment {
+        let _ = write!(out, "{}\n\n", comment);
+    }
+    if let Type::Record { ref types, ref fields } = **typ {
+        for field in types {
+            let _ = write!(out, "### type {}\n\n", field.name.declared_name());
+        }
+        for field in fields {
+            let name = field.name.declared_name();
+            let _ = write!(out, "### {}\n\n```gluon\n{} : {}\n```\n\n", name, name, field.typ);
+            let comment = metadata.module.get(name).and_then(|field| field.comment.as_ref());
+            if let Some(comment) = comment {
+                let _ = write!(out, "{}\n\n", comment);
+            }
+        }
+    }
+    out
+}
+
+fn generate_module(vm: &Thread, module: &str, source: &str) -> Result<String, String> {
+    let (mut expr, typ) = try!(Compiler::new()
+        .typecheck_str(vm, module, source, None)
+        .map_err(|err| format!("{}", err)));
+    let env = vm.get_env();
+    let metadata = metadata::metadata(&*env, &mut expr);
+    Ok(render(module, &eval::remove_aliases(&*env, &typ), &metadata))
+}
+
+/// Writes the documentation of every module below `root` which is not excluded by `exclude` to
+/// `out_dir`. `progress` is called
+/// before each module with the number of modules which are done, the total number of modules
+/// and the name of the module. Returns the modules which were documented; modules which fail to
+/// typecheck are skipped and passed to `failed` with the error.
+pub fn generate<P, E>(root: &Path,
+                      exclude: &Exclude,
+                      out_dir: &Path,
+                      mut progress: P,
+                      mut failed: E)
+                      -> io::Result<Vec<String>>
+    where P: FnMut(usize, usize, &str),
+          E: FnMut(&str, String)
+{
+    let mut files = Vec::new();
+    try!(source_files(root, exclude, &mut files));
+    files.sort();
+    try!(fs::create_dir_all(out_dir));
+    let vm = eval::new_eval_vm(Some(root));
+    let mut modules = Vec::new();
+    for (i, file) in files.iter().enumerate() {
+        let relative = file.strip_prefix(root).unwrap_or(file);
+        let module = filename_to_module(&relative.to_string_lossy());
+        progress(i, files.len(), &module);
+        let mut source = String::new();
+        try!(try!(File::open(file)).read_to_string(&mut source));
+        match generate_module(&vm, &module, &source) {
+            Ok(doc) => {
+                let mut out = try!(File::create(out_dir.join(format!("{}.md", module))));
+                try!(out.write_all(doc.as_bytes()));
+                modules.push(module);
+            }
+            Err(err) => failed(&module, err),
+        }
+    }
+    progress(files.len(), files.len(), "");
+    Ok(modules)
+}
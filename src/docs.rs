@@ -0,0 +1,231 @@
+//! Documentation comments of the bindings in a module. A binding is deprecated if a line of its
+//! documentation comment starts with `@deprecated`, the rest of that line explains what to use
+//! instead.
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use gluon::base::ast::{Expr, LExpr, Pattern};
+use gluon::base::metadata::{Metadata, MetadataEnv};
+use gluon::base::types::TcIdent;
+use gluon::check::metadata;
+
+use memory;
+use query;
+
+const DEPRECATED: &'static str = "@deprecated";
+
+/// Returns the note of the `@deprecated` line in `comment` if there is one
+pub fn deprecation(comment: &str) -> Option<&str> {
+    comment.lines()
+        .map(|line| line.trim())
+        .find(|line| line.starts_with(DEPRECATED))
+        .map(|line| line[DEPRECATED.len()..].trim())
+}
+
+/// Returns `comment` without its `@deprecated` line
+pub fn without_deprecation(comment: &str) -> String {
+    let lines: Vec<_> = comment.lines()
+        .filter(|line| !line.trim().starts_with(DEPRECATED))
+        .collect();
+    lines.join("\n").trim().into()
+}
+
+/// Where a field is declared in the source of a record type and the documentation comment above
+/// it
+pub struct FieldDeclaration {
+    /// The zero-based line of the field's name
+    pub line: usize,
+    /// The character of the line which the field's name starts at
+    pub column: usize,
+    pub comment: Option<String>,
+}
+
+/// Finds the declaration of `field` in the declaration of the record type `name` in `source`.
+/// gluon does not keep the locations or comments of fields so the source of the declaration is
+/// read instead.
+pub fn field_declaration(source: &str, name: &str, field: &str) -> Option<FieldDeclaration> {
+    let declaration = format!("type {}", name);
+    let start = source.match_indices(&declaration[..])
+        .map(|(i, _)| i)
+        .find(|&i| {
+            // The name may be followed by type parameters, such as in `type Num a =`
+            let rest = &source[i + declaration.len()..];
+            let parameters = &rest[..rest.find('=').unwrap_or(0)];
+            rest.contains('=') && rest.starts_with(|c: char| c == '=' || c.is_whitespace()) &&
+            parameters.chars().all(|c| c.is_alphanumeric() || c == '_' || c.is_whitespace())
+        });
+    let start = match start {
+        Some(start) => start,
+        None => return None,
+    };
+    let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+    let first_line = source[..start].matches('\n').count();
+    // The fields start after the `=` of the declaration
+    let body = start + declaration.len() + source[start + declaration.len()..].find('=').unwrap() +
+               1 - line_start;
+    let mut comment: Vec<&str> = Vec::new();
+    for (i, line) in source[line_start..].lines().enumerate() {
+        let offset = if i == 0 { body } else { 0 };
+        let text = &line[offset..];
+        let trimmed = text.trim();
+        if trimmed.starts_with("///") {
+            comment.push(trimmed[3..].trim());
+            continue;
+        }
+        let mut segment_start = offset;
+        for segment in text.split(',') {
+            let declared = segment.trim_left_matches(|c: char| c == '{' || c.is_whitespace());
+            if declared.starts_with(field) && declared[field.len()..].trim_left().starts_with(':') {
+                let column = segment_start + segment.len() - declared.len();
+                return Some(FieldDeclaration {
+                    line: first_line + i,
+                    column: line[..column].chars().count(),
+                    comment: if comment.is_empty() {
+                        None
+                    } else {
+                        Some(comment.join("\n"))
+                    },
+                });
+            }
+            segment_start += segment.len() + 1;
+        }
+        // The declaration ends where the expression which the type is declared in starts
+        if i > 0 && (trimmed == "in" || trimmed.starts_with("in ") || trimmed.starts_with("let ")) {
+            return None;
+        }
+        comment.clear();
+    }
+    None
+}
+
+/// Returns the documentation comment of `field` in the declaration of the record type `name` in
+/// `source`
+pub fn field_comment(source: &str, name: &str, field: &str) -> Option<String> {
+    field_declaration(source, name, field).and_then(|declaration| declaration.comment)
+}
+
+/// Returns the metadata of the typechecked `module`
+pub fn module_metadata(env: &MetadataEnv, module: &LExpr<TcIdent>) -> Metadata {
+    metadata::metadata(env, &mut module.clone())
+}
+
+/// Returns true if `module` is part of the standard library
+pub fn is_std_module(module: &str) -> bool {
+    module.starts_with("std.")
+}
+
+/// Documentation of the modules of the standard library, keyed by module name. The std modules
+/// are only loaded once something imports them and the documentation of each is extracted the
+/// first time it is needed, then kept for the rest of the session.
+pub struct StdDocs {
+    modules: Mutex<HashMap<String, Metadata>>,
+}
+
+impl StdDocs {
+    pub fn new() -> StdDocs {
+        StdDocs { modules: Mutex::new(HashMap::new()) }
+    }
+
+    /// Returns the documentation of `module`, extracting it from `expr`, the typechecked source
+    /// of the module, if it has not been extracted yet
+    pub fn get(&self,
+               env: &MetadataEnv,
+               module: &str,
+               expr: Option<&LExpr<TcIdent>>)
+               -> Option<Metadata> {
+        let mut modules = self.modules.lock().unwrap();
+        if !modules.contains_key(module) {
+            match expr {
+                Some(expr) => {
+                    modules.insert(module.into(), module_metadata(env, expr));
+                }
+                None => return None,
+            }
+        }
+        modules.get(module).cloned()
+    }
+
+    /// Returns the number of documented modules and the approximate number of bytes used by
+    /// their documentation
+    pub fn usage(&self) -> (usize, usize) {
+        let modules = self.modules.lock().unwrap();
+        let bytes = modules.iter()
+            .map(|(name, metadata)| name.len() + memory::metadata_size(metadata))
+            .sum();
+        (modules.len(), bytes)
+    }
+}
+
+/// The metadata of the bindings of a typechecked module, keyed by the (unique) names which the
+/// typechecker gives them. Bindings of imported modules, or of fields of them, get the
+/// metadata of the module.
+pub struct Bindings {
+    bindings: HashMap<String, Metadata>,
+}
+
+impl Bindings {
+    /// Collects the metadata of the bindings in `expr`. `modules` returns the metadata of the
+    /// modules which `expr` imports.
+    pub fn new<F>(expr: &LExpr<TcIdent>, modules: F) -> Bindings
+        where F: Fn(&str) -> Option<Metadata>
+    {
+        let mut bindings = Bindings { bindings: HashMap::new() };
+        bindings.collect(expr, &modules);
+        bindings
+    }
+
+    fn collect<F>(&mut self, expr: &LExpr<TcIdent>, modules: &F)
+        where F: Fn(&str) -> Option<Metadata>
+    {
+        if let Expr::Let(ref bindings, _) = expr.value {
+            for bind in bindings {
+                // Imports are expanded into an identifier which refers to the global of the
+                // imported module. The implicit prelude is bound to a variable first and then
+                // destructured.
+                let imported = match bind.expression.value {
+                    Expr::Identifier(ref id) => {
+                        modules(id.name.as_ref())
+                            .or_else(|| self.bindings.get(id.name.as_ref()).cloned())
+                    }
+                    _ => None,
+                };
+                match bind.name.value {
+                    Pattern::Identifier(ref id) => {
+                        let metadata = match bind.comment {
+                            Some(ref comment) => {
+                                Some(Metadata {
+                                    comment: Some(comment.clone()),
+                                    ..Metadata::default()
+                                })
+                            }
+                            None => imported,
+                        };
+                        if let Some(metadata) = metadata {
+                            self.bindings.insert(String::from(id.name.as_ref()), metadata);
+                        }
+                    }
+                    Pattern::Record { ref fields, .. } => {
+                        if let Some(mut imported) = imported {
+                            for &(ref field, ref alias) in fields {
+                                let name = alias.as_ref().unwrap_or(field);
+                                if let Some(metadata) = imported.module
+                                    .remove(field.declared_name()) {
+                                    self.bindings.insert(String::from(name.as_ref()), metadata);
+                                }
+                            }
+                        }
+                    }
+                    Pattern::Constructor(..) => (),
+                }
+            }
+        }
+        for child in query::children(expr) {
+            self.collect(child, modules);
+        }
+    }
+
+    /// Returns the metadata of the binding `name`
+    pub fn get(&self, name: &str) -> Option<&Metadata> {
+        self.bindings.get(name)
+    }
+}
@@ -0,0 +1,345 @@
+//! In-memory representation of the text documents opened by the client. The client counts the
+//! characters of a line in UTF-16 code units while gluon counts them in unicode scalar values, so
+//! positions are converted here whenever they pass between the two.
+use language_server::{Position, Range};
+use operators;
+
+pub struct Document {
+    pub text: String,
+    /// Byte offset of the start of each line
+    line_starts: Vec<usize>,
+}
+
+fn line_starts(text: &str) -> Vec<usize> {
+    Some(0)
+        .into_iter()
+        .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+        .collect()
+}
+
+impl Document {
+    pub fn new(text: String) -> Document {
+        Document {
+            line_starts: line_starts(&text),
+            text: text,
+        }
+    }
+
+    /// Replaces the text within `range` with `text`, or the whole text if `range` is `None`.
+    /// Positions past the end of a line refer to the end of the line, before its `\r\n` or `\n`,
+    /// so an edit never leaves a lone `\r` behind.
+    pub fn apply_change(&mut self, range: Option<&Range>, text: &str) {
+        match range {
+            Some(range) => {
+                let start = self.clamped_offset(&range.start);
+                let end = ::std::cmp::max(start, self.clamped_offset(&range.end));
+                let mut new_text = String::with_capacity(self.text.len() + text.len());
+                new_text.push_str(&self.text[..start]);
+                new_text.push_str(text);
+                new_text.push_str(&self.text[end..]);
+                self.text = new_text;
+            }
+            None => self.text = text.into(),
+        }
+        self.line_starts = line_starts(&self.text);
+    }
+
+    /// Returns the byte offset of `position`, moving positions outside of the document to the
+    /// nearest offset inside it
+    fn clamped_offset(&self, position: &Position) -> usize {
+        if position.line as usize >= self.line_starts.len() {
+            return self.text.len();
+        }
+        self.offset(position).unwrap_or_else(|| {
+            let start = self.line_starts[position.line as usize];
+            start + self.line(position.line).map_or(0, |line| line.len())
+        })
+    }
+
+    /// Returns the text of `line` without its line ending
+    fn line(&self, line: u64) -> Option<&str> {
+        let start = match self.line_starts.get(line as usize) {
+            Some(&start) => start,
+            None => return None,
+        };
+        let end = self.line_starts
+            .get(line as usize + 1)
+            .map_or(self.text.len(), |&end| end);
+        Some(self.text[start..end].trim_right_matches(|c| c == '\n' || c == '\r'))
+    }
+
+    /// Returns the byte offset of `position` or `None` if it is outside of the document. A
+    /// position inside a surrogate pair is moved past the pair, to the character after it.
+    pub fn offset(&self, position: &Position) -> Option<usize> {
+        let start = match self.line_starts.get(position.line as usize) {
+            Some(&start) => start,
+            None => return None,
+        };
+        let line = self.line(position.line).unwrap_or("");
+        let mut units = 0;
+        for (i, c) in line.char_indices() {
+            if units >= position.character as usize {
+                return Some(start + i);
+            }
+            units += c.len_utf16();
+        }
+        if units >= position.character as usize {
+            Some(start + line.len())
+        } else {
+            None
+        }
+    }
+
+    /// Returns the position of the byte `offset`
+    pub fn position(&self, offset: usize) -> Position {
+        let line = match self.line_starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(line) => line - 1,
+        };
+        let start = self.line_starts[line];
+        Position {
+            line: line as u64,
+            character: self.text[start..offset].chars().map(char::len_utf16).sum::<usize>() as u64,
+        }
+    }
+
+    /// Converts a position from gluon, which counts the characters of `position.line`, to a
+    /// position for the client
+    pub fn to_utf16(&self, position: &Position) -> Position {
+        let character = self.line(position.line).map_or(position.character, |line| {
+            let chars = position.character as usize;
+            let units: usize = line.chars().take(chars).map(char::len_utf16).sum();
+            // Characters past the end of the line are counted as one unit each
+            (units + chars.saturating_sub(line.chars().count())) as u64
+        });
+        Position {
+            line: position.line,
+            character: character,
+        }
+    }
+
+    /// Converts a position from the client to a position for gluon, which counts the characters
+    /// of `position.line`
+    pub fn from_utf16(&self, position: &Position) -> Position {
+        let character = self.line(position.line).map_or(position.character, |line| {
+            let mut units = 0;
+            let mut chars = 0;
+            for c in line.chars() {
+                if units >= position.character as usize {
+                    return chars;
+                }
+                units += c.len_utf16();
+                chars += 1;
+            }
+            chars + (position.character as usize).saturating_sub(units) as u64
+        });
+        Position {
+            line: position.line,
+            character: character,
+        }
+    }
+
+    pub fn range_to_utf16(&self, range: &Range) -> Range {
+        Range {
+            start: self.to_utf16(&range.start),
+            end: self.to_utf16(&range.end),
+        }
+    }
+
+    pub fn range_from_utf16(&self, range: &Range) -> Range {
+        Range {
+            start: self.from_utf16(&range.start),
+            end: self.from_utf16(&range.end),
+        }
+    }
+
+    /// Returns the identifier, possibly empty, which ends at `position` and whether it follows a
+    /// `.`
+    pub fn identifier_before(&self, position: &Position) -> Option<(&str, bool)> {
+        self.offset(position).map(|end| {
+            let before = &self.text[..end];
+            let start = before.char_indices()
+                .rev()
+                .take_while(|&(_, c)| c.is_alphanumeric() || c == '_')
+                .last()
+                .map_or(end, |(i, _)| i);
+            (&before[start..], before[..start].ends_with('.'))
+        })
+    }
+
+    /// Returns the range of the identifier, possibly empty, which contains `position`, such as all
+    /// of `list` when `position` is in the middle of it
+    pub fn identifier_range(&self, position: &Position) -> Option<Range> {
+        self.offset(position).map(|offset| {
+            let is_identifier = |c: char| c.is_alphanumeric() || c == '_';
+            let start = self.text[..offset]
+                .char_indices()
+                .rev()
+                .take_while(|&(_, c)| is_identifier(c))
+                .last()
+                .map_or(offset, |(i, _)| i);
+            let end = self.text[offset..]
+                .char_indices()
+                .find(|&(_, c)| !is_identifier(c))
+                .map_or(self.text.len(), |(i, _)| offset + i);
+            Range {
+                start: self.position(start),
+                end: self.position(end),
+            }
+        })
+    }
+
+    /// Returns the operator, possibly empty, which ends at `position`
+    pub fn operator_before(&self, position: &Position) -> Option<&str> {
+        self.offset(position).map(|end| {
+            let before = &self.text[..end];
+            let start = before.char_indices()
+                .rev()
+                .take_while(|&(_, c)| operators::is_operator_char(c))
+                .last()
+                .map_or(end, |(i, _)| i);
+            &before[start..]
+        })
+    }
+
+    /// Returns the text which lies within `range`
+    pub fn text_in(&self, range: &Range) -> Option<&str> {
+        match (self.offset(&range.start), self.offset(&range.end)) {
+            (Some(start), Some(end)) if start <= end => Some(&self.text[start..end]),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use language_server::{Position, Range};
+
+    fn position(line: u64, character: u64) -> Position {
+        Position {
+            line: line,
+            character: character,
+        }
+    }
+
+    #[test]
+    fn ascii_columns_are_unchanged() {
+        let document = Document::new("let x = 1\nin x\n".into());
+        assert_eq!(document.to_utf16(&position(1, 3)).character, 3);
+        assert_eq!(document.from_utf16(&position(1, 3)).character, 3);
+        assert_eq!(document.offset(&position(1, 3)), Some(13));
+        assert_eq!(document.position(13).character, 3);
+    }
+
+    #[test]
+    fn multibyte_characters() {
+        // Each of `åäö` is two bytes in UTF-8 but a single UTF-16 code unit
+        let document = Document::new("let s = \"åäö\" in s".into());
+        assert_eq!(document.to_utf16(&position(0, 14)).character, 14);
+        assert_eq!(document.from_utf16(&position(0, 14)).character, 14);
+        let offset = document.offset(&position(0, 14)).unwrap();
+        assert_eq!(&document.text[offset..offset + 2], "in");
+        assert_eq!(document.position(offset).character, 14);
+    }
+
+    #[test]
+    fn surrogate_pairs() {
+        // `😀` is a single character but two UTF-16 code units
+        let document = Document::new("let s = \"😀\" in s".into());
+        assert_eq!(document.to_utf16(&position(0, 12)).character, 13);
+        assert_eq!(document.from_utf16(&position(0, 13)).character, 12);
+        let offset = document.offset(&position(0, 13)).unwrap();
+        assert_eq!(&document.text[offset..offset + 2], "in");
+        assert_eq!(document.position(offset).character, 13);
+    }
+
+    #[test]
+    fn position_inside_surrogate_pair() {
+        let document = Document::new("let s = \"😀\" in s".into());
+        let offset = document.offset(&position(0, 10)).unwrap();
+        assert_eq!(&document.text[offset..offset + 1], "\"");
+        assert_eq!(document.position(offset).character, 11);
+    }
+
+    #[test]
+    fn identifier_before_surrogate_pair() {
+        let document = Document::new("\"😀\".len".into());
+        let (prefix, field) = document.identifier_before(&position(0, 8)).unwrap();
+        assert_eq!(prefix, "len");
+        assert!(field);
+    }
+
+    #[test]
+    fn identifier_range() {
+        let document = Document::new("std.list x".into());
+        let range = document.identifier_range(&position(0, 6)).unwrap();
+        assert_eq!((range.start.character, range.end.character), (4, 8));
+        let range = document.identifier_range(&position(0, 8)).unwrap();
+        assert_eq!((range.start.character, range.end.character), (4, 8));
+    }
+
+    #[test]
+    fn operator_before() {
+        let document = Document::new("x <| f".into());
+        assert_eq!(document.operator_before(&position(0, 4)), Some("<|"));
+        assert_eq!(document.operator_before(&position(0, 3)), Some("<"));
+        assert_eq!(document.operator_before(&position(0, 1)), Some(""));
+    }
+
+    #[test]
+    fn positions_outside_of_the_line() {
+        let document = Document::new("ä\nb".into());
+        assert_eq!(document.offset(&position(0, 2)), None);
+        assert_eq!(document.offset(&position(2, 0)), None);
+        assert_eq!(document.to_utf16(&position(0, 3)).character, 3);
+    }
+
+    fn range(start: Position, end: Position) -> Range {
+        Range {
+            start: start,
+            end: end,
+        }
+    }
+
+    #[test]
+    fn crlf_line_ends_before_carriage_return() {
+        let document = Document::new("let x = 1\r\nin x\r\n".into());
+        assert_eq!(document.offset(&position(0, 9)), Some(9));
+        assert_eq!(document.offset(&position(0, 10)), None);
+        assert_eq!(document.offset(&position(1, 0)), Some(11));
+        assert_eq!(document.position(11).line, 1);
+        assert_eq!(document.text_in(&range(position(1, 0), position(1, 4))), Some("in x"));
+    }
+
+    #[test]
+    fn change_spanning_crlf() {
+        let mut document = Document::new("let x = 1\r\nin x".into());
+        document.apply_change(Some(&range(position(0, 9), position(1, 0))), " ");
+        assert_eq!(document.text, "let x = 1 in x");
+        assert_eq!(document.line_starts, vec![0]);
+    }
+
+    #[test]
+    fn change_inserting_crlf() {
+        let mut document = Document::new("let x = 1 in x".into());
+        document.apply_change(Some(&range(position(0, 9), position(0, 10))), "\r\n");
+        assert_eq!(document.text, "let x = 1\r\nin x");
+        assert_eq!(document.offset(&position(1, 1)), Some(12));
+    }
+
+    #[test]
+    fn change_past_the_end_of_a_crlf_line_keeps_the_line_ending() {
+        let mut document = Document::new("a\r\nb".into());
+        document.apply_change(Some(&range(position(0, 5), position(0, 5))), "c");
+        assert_eq!(document.text, "ac\r\nb");
+    }
+
+    #[test]
+    fn full_change() {
+        let mut document = Document::new("a\r\nb".into());
+        document.apply_change(None, "c\nd");
+        assert_eq!(document.text, "c\nd");
+        assert_eq!(document.offset(&position(1, 0)), Some(2));
+    }
+}
@@ -0,0 +1,233 @@
+//! Gluon code embedded in Rust source files, such as a script passed to `Compiler::run_expr` as a
+//! raw string literal. Each snippet is analysed as a module of its own, whose uri is derived from
+//! the uri of the Rust file, and positions in it are offset by where the snippet starts.
+use std::cmp;
+use std::iter;
+
+use language_server::Position;
+
+/// The functions whose raw string arguments are gluon code
+pub const FUNCTIONS: &'static [&'static str] = &["compile_script",
+                                                 "load_script",
+                                                 "parse_expr",
+                                                 "run_expr",
+                                                 "run_io_expr",
+                                                 "typecheck_str"];
+
+const SNIPPET_SEPARATOR: &'static str = ".snippet-";
+
+pub struct Snippet {
+    /// Where the contents of the literal start in the Rust file, in characters
+    pub start: Position,
+    /// Where the contents of the literal end in the Rust file, in characters
+    pub end: Position,
+    pub text: String,
+}
+
+pub fn is_rust(uri: &str) -> bool {
+    uri.ends_with(".rs")
+}
+
+/// The uri of the module which the snippet `index` of the Rust file `uri` is analysed as
+pub fn snippet_uri(uri: &str, index: usize) -> String {
+    format!("{}{}{}.glu", uri, SNIPPET_SEPARATOR, index)
+}
+
+/// Returns the uri of the Rust file which the snippet `uri` is in, or `uri` if it is not the uri
+/// of a snippet
+pub fn host_uri(uri: &str) -> &str {
+    match uri.rfind(SNIPPET_SEPARATOR) {
+        Some(i) if is_rust(&uri[..i]) => &uri[..i],
+        _ => uri,
+    }
+}
+
+/// Converts `position` in the snippet which starts at `start` to a position in the Rust file
+pub fn to_host(start: &Position, position: &Position) -> Position {
+    if position.line == 0 {
+        Position {
+            line: start.line,
+            character: start.character + position.character,
+        }
+    } else {
+        Position {
+            line: start.line + position.line,
+            character: position.character,
+        }
+    }
+}
+
+impl Snippet {
+    /// Converts `position` in the Rust file to a position in the snippet. Returns `None` if the
+    /// snippet does not contain `position`.
+    pub fn to_snippet(&self, position: &Position) -> Option<Position> {
+        let key = |position: &Position| (position.line, position.character);
+        if key(position) < key(&self.start) || key(position) > key(&self.end) {
+            return None;
+        }
+        Some(if position.line == self.start.line {
+            Position {
+                line: 0,
+                character: position.character - self.start.character,
+            }
+        } else {
+            Position {
+                line: position.line - self.start.line,
+                character: position.character,
+            }
+        })
+    }
+}
+
+fn is_identifier_byte(byte: u8) -> bool {
+    byte == b'_' || (byte as char).is_alphanumeric()
+}
+
+/// Returns true if `code` calls `function`, directly or with a turbofish
+fn calls(code: &str, function: &str) -> bool {
+    code.match_indices(function).any(|(i, _)| {
+        let before = code[..i].bytes().last();
+        let after = code[i + function.len()..].trim_left();
+        !before.map_or(false, is_identifier_byte) &&
+        (after.starts_with('(') || after.starts_with("::"))
+    })
+}
+
+/// Returns the position of the byte `offset` of `text`, counting the characters of the line
+fn position(text: &str, offset: usize) -> Position {
+    let before = &text[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    Position {
+        line: before.matches('\n').count() as u64,
+        character: before[line_start..].chars().count() as u64,
+    }
+}
+
+/// Returns the end of the literal starting at `start` which begins with `"`, or with `'` if
+/// `quote` is `b'\''`
+fn skip_quoted(bytes: &[u8], start: usize, quote: u8) -> usize {
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b if b == quote => return i + 1,
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+/// Returns the gluon snippets of the Rust source `source`, which are the raw string literals
+/// passed to one of `FUNCTIONS`
+pub fn snippets(source: &str) -> Vec<Snippet> {
+    let bytes = source.as_bytes();
+    let mut snippets = Vec::new();
+    // Where the statement containing `i` starts
+    let mut statement = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b';' | b'{' | b'}' => {
+                statement = i + 1;
+                i += 1;
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                i = source[i..].find('\n').map_or(bytes.len(), |end| i + end);
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i = source[i + 2..].find("*/").map_or(bytes.len(), |end| i + 2 + end + 2);
+            }
+            b'"' => i = skip_quoted(bytes, i, b'"'),
+            // A character literal, as opposed to a lifetime
+            b'\'' if bytes.get(i + 1) == Some(&b'\\') || bytes.get(i + 2) == Some(&b'\'') => {
+                i = skip_quoted(bytes, i, b'\'');
+            }
+            b'r' if i == 0 || !is_identifier_byte(bytes[i - 1]) => {
+                let hashes = bytes[i + 1..].iter().take_while(|&&b| b == b'#').count();
+                if bytes.get(i + 1 + hashes) != Some(&b'"') {
+                    i += 1;
+                    continue;
+                }
+                let content_start = i + 1 + hashes + 1;
+                let terminator: String = Some('"')
+                    .into_iter()
+                    .chain(iter::repeat('#').take(hashes))
+                    .collect();
+                let content_end = source[content_start..]
+                    .find(&terminator[..])
+                    .map_or(bytes.len(), |end| content_start + end);
+                if FUNCTIONS.iter().any(|function| calls(&source[statement..i], function)) {
+                    snippets.push(Snippet {
+                        start: position(source, content_start),
+                        end: position(source, content_end),
+                        text: source[content_start..content_end].into(),
+                    });
+                }
+                i = cmp::min(content_end + terminator.len(), bytes.len());
+            }
+            _ => i += 1,
+        }
+    }
+    snippets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use language_server::Position;
+
+    fn position(line: u64, character: u64) -> Position {
+        Position {
+            line: line,
+            character: character,
+        }
+    }
+
+    #[test]
+    fn find_snippets() {
+        let source = r####"
+fn main() {
+    // run_expr(r#"1"#)
+    let vm = new_vm();
+    let x = Compiler::new().run_expr::<i32>(&vm, "x", r#"let x = 1
+in x"#).unwrap();
+    let text = r"not gluon";
+    Compiler::new().load_script(&vm, "s", r##"let s = "}" in s"##);
+}
+"####;
+        let snippets = snippets(source);
+        assert_eq!(snippets.len(), 2);
+        assert_eq!(snippets[0].text, "let x = 1\nin x");
+        assert_eq!((snippets[0].start.line, snippets[0].start.character), (4, 57));
+        assert_eq!((snippets[0].end.line, snippets[0].end.character), (5, 4));
+        assert_eq!(snippets[1].text, r#"let s = "}" in s"#);
+    }
+
+    #[test]
+    fn map_positions() {
+        let snippet = Snippet {
+            start: position(4, 56),
+            end: position(5, 4),
+            text: "let x = 1\nin x".into(),
+        };
+        let inside = snippet.to_snippet(&position(4, 60)).unwrap();
+        assert_eq!((inside.line, inside.character), (0, 4));
+        let inside = snippet.to_snippet(&position(5, 3)).unwrap();
+        assert_eq!((inside.line, inside.character), (1, 3));
+        assert!(snippet.to_snippet(&position(4, 10)).is_none());
+        assert!(snippet.to_snippet(&position(6, 0)).is_none());
+
+        let host = to_host(&snippet.start, &position(0, 4));
+        assert_eq!((host.line, host.character), (4, 60));
+        let host = to_host(&snippet.start, &position(1, 3));
+        assert_eq!((host.line, host.character), (5, 3));
+    }
+
+    #[test]
+    fn snippet_uris() {
+        let uri = snippet_uri("file:///src/main.rs", 1);
+        assert_eq!(host_uri(&uri), "file:///src/main.rs");
+        assert_eq!(host_uri("file:///src/main.glu"), "file:///src/main.glu");
+    }
+}
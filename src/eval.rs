@@ -0,0 +1,266 @@
+//! Evaluation of gluon code on behalf of the client
+use std::fmt::Write;
+use std::path::Path;
+
+use gluon::base::ast::{Expr, LExpr, LPattern, LiteralEnum, Pattern};
+use gluon::base::instantiate::{AliasInstantiator, Instantiator};
+use gluon::base::types::{BuiltinType, Field, TcIdent, TcType, Type, TypeEnv, arg_iter};
+use gluon::compiler_pipeline::Executable;
+use gluon::import::{DefaultImporter, Import, Importer};
+use gluon::vm::Variants;
+use gluon::vm::api::ValueRef;
+use gluon::vm::thread::RootedValue;
+use gluon::{Compiler, Result as GluonResult, RootedThread, Thread, new_vm};
+
+use output;
+
+/// Nesting depth after which values are elided when formatted
+const MAX_DEPTH: usize = 8;
+
+/// Creates a virtual machine which can run code, resolving imports relative to `root`
+pub fn new_eval_vm(root: Option<&Path>) -> RootedThread {
+    new_eval_vm_with_importer(root, DefaultImporter)
+}
+
+/// Creates a virtual machine like `new_eval_vm` whose imported modules are loaded by `importer`
+pub fn new_eval_vm_with_importer<I>(root: Option<&Path>, importer: I) -> RootedThread
+    where I: Importer
+{
+    let vm = new_vm();
+    let import = Import::new(importer);
+    if let Some(root) = root {
+        import.add_path(root);
+    }
+    vm.get_macros().insert("import".into(), import);
+    output::redirect(&vm).expect("Redirected the output of the io primitives");
+    vm
+}
+
+fn import_file(expr: &LExpr<TcIdent>) -> Option<&str> {
+    match expr.value {
+        Expr::Call(ref func, ref args) if args.len() == 1 => {
+            match (&func.value, &args[0].value) {
+                (&Expr::Identifier(ref id), &Expr::Literal(LiteralEnum::String(ref file)))
+                    if id.name.as_ref() == "import" => Some(file),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn pattern_source(pattern: &LPattern<TcIdent>) -> String {
+    match pattern.value {
+        Pattern::Identifier(ref id) => id.name.declared_name().into(),
+        Pattern::Constructor(ref id, ref args) => {
+            let mut source = String::from(id.name.declared_name());
+            for arg in args {
+                write!(source, " {}", arg.name.declared_name()).unwrap();
+            }
+            source
+        }
+        Pattern::Record { ref types, ref fields, .. } => {
+            let names: Vec<_> = types.iter()
+                .chain(fields)
+                .map(|&(ref name, ref alias)| {
+                    match *alias {
+                        Some(ref alias) => {
+                            format!("{} = {}", name.declared_name(), alias.declared_name())
+                        }
+                        None => name.declared_name().into(),
+                    }
+                })
+                .collect();
+            format!("{{ {} }}", names.join(", "))
+        }
+    }
+}
+
+/// Returns the source of the `let` bindings which import other modules at the top level of the
+/// (unexpanded) module `expr`
+pub fn import_bindings(expr: &LExpr<TcIdent>) -> String {
+    let mut source = String::new();
+    let mut expr = expr;
+    loop {
+        match expr.value {
+            Expr::Let(ref bindings, ref body) => {
+                for bind in bindings.iter().filter(|bind| bind.arguments.is_empty()) {
+                    if let Some(file) = import_file(&bind.expression) {
+                        writeln!(source,
+                                 "let {} = import \"{}\"\nin",
+                                 pattern_source(&bind.name),
+                                 file)
+                            .unwrap();
+                    }
+                }
+                expr = &**body;
+            }
+            Expr::Type(_, ref body) => expr = &**body,
+            _ => return source,
+        }
+    }
+}
+
+/// Returns the source of the `let` bindings which bring the modules imported by `module_source`
+/// into scope
+pub fn module_imports(module_name: &str, module_source: &str) -> String {
+    match Compiler::new().parse_partial_expr(module_name, module_source) {
+        Ok(module) |
+        Err((Some(module), _)) => import_bindings(&module),
+        Err((None, _)) => String::new(),
+    }
+}
+
+/// Compiles and runs `source`. Returns the formatted value together with its type.
+pub fn eval(vm: &Thread, name: &str, source: &str) -> GluonResult<(String, TcType)> {
+    eval_expr(&mut Compiler::new(), vm, name, source, source)
+}
+
+/// Compiles and runs `expr` which is either the source code in `source` or an expression parsed
+/// from it
+pub fn run_expr<'vm, 'a, E>(compiler: &mut Compiler,
+                            vm: &'vm Thread,
+                            name: &str,
+                            source: &'a str,
+                            expr: E)
+                            -> GluonResult<(RootedValue<&'vm Thread>, TcType)>
+    where E: Executable<(&'a str, Option<&'a TcType>)>
+{
+    expr.run_expr(compiler, vm, name, (source, None))
+}
+
+/// Compiles and runs `expr` like `run_expr` and formats the resulting value
+pub fn eval_expr<'a, E>(compiler: &mut Compiler,
+                        vm: &Thread,
+                        name: &str,
+                        source: &'a str,
+                        expr: E)
+                        -> GluonResult<(String, TcType)>
+    where E: Executable<(&'a str, Option<&'a TcType>)>
+{
+    let (value, typ) = try!(run_expr(compiler, vm, name, source, expr));
+    Ok((format_rooted(vm, &value, &typ), typ))
+}
+
+/// Formats the value which running an expression of type `typ` returned
+pub fn format_rooted(vm: &Thread, value: &RootedValue<&Thread>, typ: &TcType) -> String {
+    let env = vm.get_env();
+    let variants = unsafe { Variants::new(value) };
+    format_value(&*env, variants.as_ref(), typ)
+}
+
+/// Expands the type aliases at the top of `typ`
+pub fn remove_aliases(env: &TypeEnv, typ: &TcType) -> TcType {
+    AliasInstantiator::new(&Instantiator::new(), env).remove_aliases(typ.clone())
+}
+
+/// Replaces the aliases in `typ` by the types they alias. Aliases which come from an expansion
+/// are expanded as well, up to `depth` expansions deep.
+pub fn expand_aliases(env: &TypeEnv, typ: &TcType, depth: usize) -> TcType {
+    if depth == 0 {
+        return typ.clone();
+    }
+    let instantiator = Instantiator::new();
+    if let Ok(Some(expanded)) = AliasInstantiator::new(&instantiator, env)
+        .maybe_remove_alias(typ) {
+        return expand_aliases(env, &expanded, depth - 1);
+    }
+    let expand = |typ: &TcType| expand_aliases(env, typ, depth);
+    match **typ {
+        Type::Data(ref id, ref args) => Type::data(id.clone(), args.iter().map(&expand).collect()),
+        Type::App(ref l, ref r) => Type::app(expand(l), expand(r)),
+        Type::Array(ref inner) => Type::array(expand(inner)),
+        Type::Function(ref args, ref ret) => {
+            Type::function(args.iter().map(&expand).collect(), expand(ret))
+        }
+        Type::Record { ref types, ref fields } => {
+            let fields = fields.iter()
+                .map(|field| {
+                    Field {
+                        name: field.name.clone(),
+                        typ: expand(&field.typ),
+                    }
+                })
+                .collect();
+            Type::record(types.clone(), fields)
+        }
+        _ => typ.clone(),
+    }
+}
+
+/// Formats `value` as gluon source, using `typ` to recover field and constructor names
+pub fn format_value(env: &TypeEnv, value: ValueRef, typ: &TcType) -> String {
+    let mut out = String::new();
+    write_value(&mut out, env, value, typ, 0);
+    out
+}
+
+fn write_value(out: &mut String, env: &TypeEnv, value: ValueRef, typ: &TcType, depth: usize) {
+    if depth > MAX_DEPTH {
+        out.push_str("...");
+        return;
+    }
+    let typ = remove_aliases(env, typ);
+    match (value, &*typ) {
+        (ValueRef::Byte(b), _) => write!(out, "{}b", b).unwrap(),
+        (ValueRef::Int(i), &Type::Builtin(BuiltinType::Char)) => {
+            match ::std::char::from_u32(i as u32) {
+                Some(c) => write!(out, "{:?}", c).unwrap(),
+                None => write!(out, "{}", i).unwrap(),
+            }
+        }
+        (ValueRef::Int(_), &Type::Builtin(BuiltinType::Unit)) => out.push_str("()"),
+        (ValueRef::Int(i), _) => write!(out, "{}", i).unwrap(),
+        (ValueRef::Float(f), _) => write!(out, "{}", f).unwrap(),
+        (ValueRef::String(s), _) => write!(out, "{:?}", s).unwrap(),
+        (ValueRef::Tag(tag), &Type::Variants(ref variants)) => {
+            match variants.get(tag as usize) {
+                Some(variant) => out.push_str(variant.0.declared_name()),
+                None => write!(out, "<tag {}>", tag).unwrap(),
+            }
+        }
+        (ValueRef::Data(data), &Type::Variants(ref variants)) => {
+            match variants.get(data.tag() as usize) {
+                Some(variant) => {
+                    if depth != 0 {
+                        out.push('(');
+                    }
+                    out.push_str(variant.0.declared_name());
+                    for (i, arg_type) in arg_iter(&variant.1).enumerate() {
+                        out.push(' ');
+                        match data.get(i) {
+                            Some(arg) => write_value(out, env, arg, arg_type, depth + 1),
+                            None => out.push_str("?"),
+                        }
+                    }
+                    if depth != 0 {
+                        out.push(')');
+                    }
+                }
+                None => write!(out, "<tag {}>", data.tag()).unwrap(),
+            }
+        }
+        (ValueRef::Data(data), &Type::Record { ref fields, .. }) => {
+            out.push_str("{");
+            for (i, field) in fields.iter().enumerate() {
+                if i != 0 {
+                    out.push(',');
+                }
+                write!(out, " {} = ", field.name.declared_name()).unwrap();
+                match data.get(i) {
+                    Some(value) => write_value(out, env, value, &field.typ, depth + 1),
+                    None => out.push_str("?"),
+                }
+            }
+            out.push_str(" }");
+        }
+        (ValueRef::Data(data), _) => {
+            write!(out, "<data {} ({} fields)>", data.tag(), data.len()).unwrap()
+        }
+        (ValueRef::Userdata(data), _) => write!(out, "<userdata {:?}>", data).unwrap(),
+        (ValueRef::Internal, &Type::Function(..)) => out.push_str("<function>"),
+        (ValueRef::Internal, &Type::Array(_)) => out.push_str("<array>"),
+        (ValueRef::Tag(tag), _) => write!(out, "<tag {}>", tag).unwrap(),
+        (ValueRef::Internal, _) => out.push_str("<internal>"),
+    }
+}
@@ -19,15 +19,30 @@ export function activate(context: ExtensionContext) {
 		options: {}
 	}
 
+	// Rust documents are only sent to the server if the gluon code embedded in them is analysed
+	let documentSelector = ['gluon'];
+	if (config.get("embedded.enable", false)) {
+		documentSelector.push('rust');
+	}
+
 	// Options to control the language client
 	let clientOptions: LanguageClientOptions = {
 		// Register the server for plain text documents
-		documentSelector: ['gluon'],
+		documentSelector: documentSelector,
+		// The std directory is needed before the prelude is loaded
+		initializationOptions: {
+			stdPath: config.get("std.path", ""),
+			filesExclude: config.get("files.exclude")
+		},
 		synchronize: {
 			// Synchronize the setting section 'languageServerExample' to the server
 			configurationSection: 'gluon',
-			// Notify the server about file changes to '.clientrc files contain in the workspace
-			fileEvents: workspace.createFileSystemWatcher('**/.clientrc')
+			// Notify the server about changes to the project manifest and to modules which are
+			// not open, which the workspace symbols are kept up to date with
+			fileEvents: [
+				workspace.createFileSystemWatcher('**/glu-project.toml'),
+				workspace.createFileSystemWatcher('**/*.glu')
+			]
 		}
 	}
 	
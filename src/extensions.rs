@@ -0,0 +1,258 @@
+//! Types for the gluon specific extensions to the language server protocol
+use language_server::{Position, Range, TextDocumentIdentifier};
+
+/// Parameters for the `gluon/syntaxTree` request
+#[derive(Deserialize)]
+pub struct SyntaxTreeParams {
+    /// The document to print the syntax tree of.
+    #[serde(rename="textDocument")]
+    pub text_document: TextDocumentIdentifier,
+    /// If set, only the nodes overlapping this range are returned.
+    pub range: Option<Range>,
+}
+
+/// Parameters for the `gluon/typeOf` request
+#[derive(Deserialize)]
+pub struct TypeOfParams {
+    /// The document containing the selection.
+    #[serde(rename="textDocument")]
+    pub text_document: TextDocumentIdentifier,
+    /// The selected range. The type of the smallest expression containing it is returned.
+    pub range: Range,
+}
+
+/// The result of a `gluon/typeOf` request
+#[derive(Serialize)]
+pub struct TypeOfResult {
+    /// The inferred type of the expression.
+    #[serde(rename="type")]
+    pub typ: String,
+    /// The range of the expression which the type belongs to.
+    pub range: Range,
+}
+
+/// The result of the `gluon.evalSelection` command
+#[derive(Serialize)]
+pub struct EvalResult {
+    /// The value which the selection evaluated to, formatted as gluon source.
+    pub value: String,
+    /// The type of the value.
+    #[serde(rename="type")]
+    pub typ: String,
+}
+
+/// Parameters for the `gluon/repl/start` request
+#[derive(Deserialize)]
+pub struct ReplStartParams {
+    /// If set, the modules imported by this document are in scope in the session.
+    #[serde(rename="textDocument")]
+    pub text_document: Option<TextDocumentIdentifier>,
+}
+
+/// The result of a `gluon/repl/start` request
+#[derive(Serialize)]
+pub struct ReplStartResult {
+    /// Identifies the session in `gluon/repl/eval` and `gluon/repl/stop` requests.
+    pub session: u64,
+}
+
+/// Parameters for the `gluon/repl/eval` request
+#[derive(Deserialize)]
+pub struct ReplEvalParams {
+    pub session: u64,
+    /// An expression to evaluate or a `let` or `type` binding (without `in`) to add to the
+    /// session.
+    pub input: String,
+}
+
+/// The result of a `gluon/repl/eval` request. Both fields are `null` if the input was a binding.
+#[derive(Serialize)]
+pub struct ReplEvalResult {
+    pub value: Option<String>,
+    #[serde(rename="type")]
+    pub typ: Option<String>,
+}
+
+/// Parameters for the `gluon/repl/stop` request
+#[derive(Deserialize)]
+pub struct ReplStopParams {
+    pub session: u64,
+}
+
+/// Parameters for the `gluon/output` notification which streams the output of a program run by
+/// `gluon.runFile` to the client
+#[derive(Serialize)]
+pub struct OutputParams {
+    /// Either `stdout` or `stderr`.
+    pub category: String,
+    pub output: String,
+}
+
+/// The result of the `gluon.runFile` command
+#[derive(Serialize)]
+pub struct RunFileResult {
+    /// 0 if the program ran successfully, 1 if it failed to compile or run.
+    #[serde(rename="exitCode")]
+    pub exit_code: i64,
+    /// The value which the program evaluated to, if it succeeded.
+    pub value: Option<String>,
+    #[serde(rename="type")]
+    pub typ: Option<String>,
+}
+
+/// Parameters for the `gluon/progress` notification which reports the progress of long running
+/// commands such as `gluon.generateDocs`
+#[derive(Serialize)]
+pub struct ProgressParams {
+    /// The name of the command which is running.
+    pub title: String,
+    /// What the command is currently doing.
+    pub message: String,
+    /// How much of the work is done, from 0 to 100.
+    pub percentage: u64,
+}
+
+/// The result of the `gluon.generateDocs` command
+#[derive(Serialize)]
+pub struct GenerateDocsResult {
+    /// The directory which the documentation was written to.
+    pub directory: String,
+    /// The modules which were documented.
+    pub modules: Vec<String>,
+}
+
+/// The result of the `gluon.viewDesugared` command
+#[derive(Serialize)]
+pub struct DesugaredResult {
+    /// A uri which the client can show the desugared source under as a read-only document.
+    pub uri: String,
+    /// The desugared source of the module.
+    pub text: String,
+}
+
+/// Parameters for the `gluon/viewBytecode` request
+#[derive(Deserialize)]
+pub struct ViewBytecodeParams {
+    /// The document to list the bytecode of.
+    #[serde(rename="textDocument")]
+    pub text_document: TextDocumentIdentifier,
+    /// If set, only the function which the position is in is listed.
+    pub position: Option<Position>,
+}
+
+/// The result of the `gluon.viewBytecode` command and the `gluon/viewBytecode` request
+#[derive(Serialize)]
+pub struct BytecodeResult {
+    /// A uri which the client can show the listing under as a read-only document.
+    pub uri: String,
+    /// The listing of the bytecode of the module or function.
+    pub text: String,
+}
+
+/// The result of the `gluon.runBench` command. The times are in milliseconds.
+#[derive(Serialize)]
+pub struct BenchResult {
+    pub name: String,
+    pub iterations: u64,
+    pub mean: f64,
+    pub min: f64,
+    pub median: f64,
+    /// The time which 95% of the runs took at most.
+    pub p95: f64,
+    pub max: f64,
+}
+
+/// The result of the `gluon.showType` command
+#[derive(Serialize)]
+pub struct ShowTypeResult {
+    /// A uri which the client can show the type under as a read-only document.
+    pub uri: String,
+    /// The whole type.
+    pub text: String,
+}
+
+/// Parameters for the `gluon/memoryUsage` request
+#[derive(Deserialize)]
+pub struct MemoryUsageParams {}
+
+/// The memory used by one part of the server
+#[derive(Serialize)]
+pub struct SubsystemMemory {
+    pub name: String,
+    /// The number of documents, modules or sessions which the memory is used for.
+    pub entries: u64,
+    /// The approximate number of bytes used, `null` if it is not known.
+    pub bytes: Option<u64>,
+}
+
+/// The result of a `gluon/memoryUsage` request
+#[derive(Serialize)]
+pub struct MemoryUsageResult {
+    pub subsystems: Vec<SubsystemMemory>,
+    /// The sum of the known sizes of the subsystems.
+    pub total: u64,
+}
+
+/// Parameters for the `gluon/serverStatistics` request
+#[derive(Deserialize)]
+pub struct ServerStatisticsParams {}
+
+/// The latencies of the requests of one method
+#[derive(Serialize)]
+pub struct MethodStatistics {
+    pub method: String,
+    /// The number of requests which have been handled.
+    pub count: u64,
+    #[serde(rename="meanMs")]
+    pub mean_ms: u64,
+    #[serde(rename="maxMs")]
+    pub max_ms: u64,
+    /// The number of requests which took at most `bucketsMs[i]` milliseconds and more than
+    /// `bucketsMs[i - 1]`. The last element counts the requests which took longer than the last
+    /// bound.
+    pub histogram: Vec<u64>,
+}
+
+/// The result of a `gluon/serverStatistics` request
+#[derive(Serialize)]
+pub struct ServerStatisticsResult {
+    /// The upper bounds of the buckets of the histograms.
+    #[serde(rename="bucketsMs")]
+    pub buckets_ms: Vec<u64>,
+    pub methods: Vec<MethodStatistics>,
+}
+
+/// Parameters for the `gluon/status` notification which is sent whenever the server starts doing
+/// something else, so that the client can show what the server is doing
+#[derive(Clone, PartialEq, Serialize)]
+pub struct StatusParams {
+    /// One of `idle`, `indexing`, `checking` or `error`.
+    pub state: String,
+    /// The uri of the document which is being checked if the state is `checking`, or the error if
+    /// it is `error`.
+    pub message: Option<String>,
+}
+
+/// Parameters for the `gluon/serverStatus` request
+#[derive(Deserialize)]
+pub struct ServerStatusParams {}
+
+/// The result of a `gluon/serverStatus` request
+#[derive(Serialize)]
+pub struct ServerStatusResult {
+    /// The version of the language server.
+    pub version: String,
+    /// Milliseconds since the server started.
+    #[serde(rename="uptimeMs")]
+    pub uptime_ms: u64,
+    /// The number of modules which have been typechecked and not evicted.
+    #[serde(rename="indexedModules")]
+    pub indexed_modules: u64,
+    /// The number of documents whose diagnostics are waiting to be run.
+    #[serde(rename="pendingDiagnostics")]
+    pub pending_diagnostics: u64,
+    /// The last error which kept the server from working properly, even if it has been fixed
+    /// since.
+    #[serde(rename="lastError")]
+    pub last_error: Option<String>,
+}
@@ -0,0 +1,103 @@
+//! Fuzzy matching of completion candidates against the identifier which is being typed. The
+//! characters of the pattern have to appear in the candidate in order, starting at the beginning
+//! of one of its words, so that `flm` matches `fold_left_map` and `foldLeftMap`.
+use std::cmp;
+
+/// The score of a character which starts a word of the candidate
+const WORD_START: u32 = 8;
+/// The score of a character which directly follows the previously matched character
+const CONSECUTIVE: u32 = 8;
+/// The score of matching the first character of the candidate
+const CANDIDATE_START: u32 = 4;
+/// The score of a character whose case is the same as in the pattern
+const SAME_CASE: u32 = 1;
+
+fn starts_word(candidate: &[char], i: usize) -> bool {
+    i == 0 || candidate[i - 1] == '_' ||
+    (candidate[i].is_uppercase() && candidate[i - 1].is_lowercase()) ||
+    (candidate[i].is_alphabetic() && candidate[i - 1].is_numeric())
+}
+
+fn same_letter(l: char, r: char) -> bool {
+    l == r || l.to_lowercase().eq(r.to_lowercase())
+}
+
+/// Returns how well `pattern` matches `candidate`, higher is better, or `None` if it does not
+/// match. Matches at the start of the candidate or of its words and runs of consecutive characters
+/// score higher.
+pub fn score(pattern: &str, candidate: &str) -> Option<u32> {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let candidate: Vec<char> = candidate.chars().collect();
+    if pattern.is_empty() {
+        return Some(0);
+    }
+    // `best[j]` is the best score of matching the pattern so far with its last character
+    // matched at `candidate[j]`
+    let mut best: Vec<Option<u32>> = vec![None; candidate.len()];
+    for (i, &p) in pattern.iter().enumerate() {
+        let mut next = vec![None; candidate.len()];
+        // The best score of the previous characters matched before `j`
+        let mut before: Option<u32> = None;
+        for (j, &c) in candidate.iter().enumerate() {
+            let previous = if i == 0 {
+                if j == 0 {
+                    Some(CANDIDATE_START)
+                } else if starts_word(&candidate, j) {
+                    Some(0)
+                } else {
+                    None
+                }
+            } else {
+                let consecutive = if j > 0 {
+                    best[j - 1].map(|score| score + CONSECUTIVE)
+                } else {
+                    None
+                };
+                // `None` is less than any score
+                cmp::max(before, consecutive)
+            };
+            if same_letter(p, c) {
+                next[j] = previous.map(|score| {
+                    let mut score = score + 1;
+                    if starts_word(&candidate, j) {
+                        score += WORD_START;
+                    }
+                    if p == c {
+                        score += SAME_CASE;
+                    }
+                    score
+                });
+            }
+            if i > 0 {
+                before = cmp::max(before, best[j]);
+            }
+        }
+        best = next;
+    }
+    best.into_iter().max().and_then(|score| score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn match_word_starts() {
+        assert!(score("flm", "fold_left_map").is_some());
+        assert!(score("flm", "foldLeftMap").is_some());
+        assert!(score("FLM", "fold_left_map").is_some());
+        assert!(score("flm", "map").is_none());
+        assert!(score("lm", "fold_left_map").is_some());
+        // The first character has to start a word
+        assert!(score("old", "fold_left_map").is_none());
+        assert_eq!(score("", "map"), Some(0));
+    }
+
+    #[test]
+    fn rank_matches() {
+        let score = |candidate| score("flm", candidate).unwrap();
+        assert!(score("fold_left_map") > score("filter_map"));
+        assert!(score("filter_map") > score("file_name"));
+        assert!(super::score("ma", "map") > super::score("ma", "fold_map_all"));
+    }
+}
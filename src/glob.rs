@@ -0,0 +1,98 @@
+//! Glob patterns which exclude files and directories of the workspace from indexing. A pattern
+//! is matched against the path of a file relative to the root of the workspace, with `/` between
+//! its components. `*` matches any characters of a component except `/`, `?` matches any one
+//! character and `**` matches any number of components. A pattern without a `/` matches the name
+//! of a file or directory at any depth, so `target` excludes every directory named `target`.
+
+/// The patterns which are excluded unless the client configures `gluon.files.exclude`
+pub const DEFAULT_EXCLUDE: &'static [&'static str] = &["target", "node_modules"];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Exclude {
+    /// The components of each pattern
+    patterns: Vec<Vec<String>>,
+}
+
+impl Default for Exclude {
+    fn default() -> Exclude {
+        Exclude::new(DEFAULT_EXCLUDE)
+    }
+}
+
+impl Exclude {
+    pub fn new<S>(patterns: &[S]) -> Exclude
+        where S: AsRef<str>
+    {
+        Exclude {
+            patterns: patterns.iter()
+                .map(|pattern| {
+                    let pattern = pattern.as_ref().trim_matches('/');
+                    if pattern.contains('/') {
+                        pattern.split('/').map(String::from).collect()
+                    } else {
+                        vec!["**".into(), pattern.into()]
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns true if the file or directory at `relative`, a path relative to the root of the
+    /// workspace, is excluded
+    pub fn is_excluded(&self, relative: &str) -> bool {
+        let components: Vec<&str> = relative.split(|c| c == '/' || c == '\\')
+            .filter(|component| !component.is_empty())
+            .collect();
+        self.patterns.iter().any(|pattern| matches_path(pattern, &components))
+    }
+}
+
+fn matches_path(pattern: &[String], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((first, rest)) if *first == "**" => {
+            matches_path(rest, path) || (!path.is_empty() && matches_path(pattern, &path[1..]))
+        }
+        Some((first, rest)) => {
+            !path.is_empty() && matches_component(first.as_bytes(), path[0].as_bytes()) &&
+            matches_path(rest, &path[1..])
+        }
+    }
+}
+
+fn matches_component(pattern: &[u8], name: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some((&b'*', rest)) => {
+            matches_component(rest, name) ||
+            (!name.is_empty() && matches_component(pattern, &name[1..]))
+        }
+        Some((&b'?', rest)) => !name.is_empty() && matches_component(rest, &name[1..]),
+        Some((&c, rest)) => name.first() == Some(&c) && matches_component(rest, &name[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_match_at_any_depth() {
+        let exclude = Exclude::default();
+        assert!(exclude.is_excluded("target"));
+        assert!(exclude.is_excluded("examples/node_modules"));
+        assert!(!exclude.is_excluded("src/targets.glu"));
+    }
+
+    #[test]
+    fn wildcards() {
+        let exclude = Exclude::new(&["**/*.gen.glu", "vendor/**", "src/test?.glu"]);
+        assert!(exclude.is_excluded("parser.gen.glu"));
+        assert!(exclude.is_excluded("src/syntax/parser.gen.glu"));
+        assert!(!exclude.is_excluded("src/parser.glu"));
+        assert!(exclude.is_excluded("vendor"));
+        assert!(!exclude.is_excluded("src/vendor"));
+        assert!(exclude.is_excluded("src/test1.glu"));
+        assert!(!exclude.is_excluded("src/test10.glu"));
+    }
+}
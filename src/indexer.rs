@@ -0,0 +1,209 @@
+//! Typechecking of every module in the workspace. Modules are typechecked in the order of their
+//! imports, and the modules which do not depend on each other are typechecked in parallel.
+use std::collections::{HashMap, HashSet};
+use std::collections::hash_map::DefaultHasher;
+use std::fs::{self, File};
+use std::hash::{Hash, Hasher};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use gluon::base::ast::{Expr, LExpr, LiteralEnum};
+use gluon::base::types::TcIdent;
+use gluon::{Compiler, RootedThread, Thread, filename_to_module};
+
+use dead_code::{self, ModuleUse};
+use glob::Exclude;
+use project::Project;
+use query;
+use transport;
+
+/// The number of modules which are typechecked at the same time
+pub const WORKERS: usize = 4;
+
+pub struct Module {
+    pub path: PathBuf,
+    /// The name which the module is imported as
+    pub name: String,
+    pub source: String,
+    /// The modules which this module imports
+    imports: Vec<String>,
+    /// The fields of other modules which this module uses
+    pub uses: Vec<ModuleUse>,
+}
+
+/// Returns the gluon source files below `root`, skipping hidden directories and the files and
+/// directories which are excluded by `exclude`. A file or directory which is reachable through
+/// several paths because of symlinks is only returned through the first of them.
+pub fn source_files(root: &Path, exclude: &Exclude, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    let mut visited = HashSet::new();
+    collect_source_files(root, root, exclude, &mut visited, files)
+}
+
+fn collect_source_files(root: &Path,
+                        dir: &Path,
+                        exclude: &Exclude,
+                        visited: &mut HashSet<PathBuf>,
+                        files: &mut Vec<PathBuf>)
+                        -> io::Result<()> {
+    if !visited.insert(fs::canonicalize(dir).unwrap_or_else(|_| dir.into())) {
+        return Ok(());
+    }
+    let mut entries = Vec::new();
+    for entry in try!(fs::read_dir(dir)) {
+        entries.push(try!(entry).path());
+    }
+    entries.sort();
+    for path in entries {
+        let hidden = path.file_name()
+            .and_then(|name| name.to_str())
+            .map_or(true, |name| name.starts_with('.'));
+        let excluded = {
+            let relative = path.strip_prefix(root).unwrap_or(&path);
+            exclude.is_excluded(&relative.to_string_lossy())
+        };
+        if excluded {
+            continue;
+        }
+        if path.is_dir() {
+            if !hidden {
+                try!(collect_source_files(root, &path, exclude, visited, files));
+            }
+        } else if path.extension().map_or(false, |ext| ext == "glu") {
+            if visited.insert(fs::canonicalize(&path).unwrap_or_else(|_| path.clone())) {
+                files.push(path);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Collects the modules imported in `expr` before its imports have been expanded
+fn imports(expr: &LExpr<TcIdent>, imports: &mut Vec<String>) {
+    if let Expr::Call(ref func, ref args) = expr.value {
+        if let (&Expr::Identifier(ref id), Some(arg)) = (&func.value, args.first()) {
+            if let Expr::Literal(LiteralEnum::String(ref filename)) = arg.value {
+                if id.name.as_ref() == "import" {
+                    imports.push(filename_to_module(filename));
+                }
+            }
+        }
+    }
+    for child in query::children(expr) {
+        self::imports(child, imports);
+    }
+}
+
+/// Reads the modules in the source directories of `project` in the workspace at `root` which are
+/// not excluded by `exclude`
+pub fn modules(root: &Path, project: &Project, exclude: &Exclude) -> io::Result<Vec<Module>> {
+    let mut files = Vec::new();
+    // A source directory may be in another one so the files are only collected once
+    let mut visited = HashSet::new();
+    for dir in project.source_dirs(root) {
+        let mut dir_files = Vec::new();
+        try!(collect_source_files(root, &dir, exclude, &mut visited, &mut dir_files));
+        dir_files.sort();
+        files.extend(dir_files.into_iter().map(|path| (dir.clone(), path)));
+    }
+    let mut modules = Vec::new();
+    for (dir, path) in files {
+        let name = project.module_name(&dir, &path);
+        let mut source = String::new();
+        try!(try!(File::open(&path)).read_to_string(&mut source));
+        let mut module_imports = Vec::new();
+        let mut uses = Vec::new();
+        // Modules which do not parse are typechecked anyway to report the parse errors
+        if let Ok(expr) = Compiler::new().parse_expr(&name, &source) {
+            imports(&expr, &mut module_imports);
+            uses = dead_code::module_uses(&expr);
+        }
+        modules.push(Module {
+            path: path,
+            name: name,
+            source: source,
+            imports: module_imports,
+            uses: uses,
+        });
+    }
+    Ok(modules)
+}
+
+/// Groups `modules` into levels where each module only imports modules of earlier levels or
+/// modules outside of the workspace. Modules which import each other end up in the last level.
+pub fn levels(modules: Vec<Module>) -> Vec<Vec<Module>> {
+    let names: HashSet<String> = modules.iter().map(|module| module.name.clone()).collect();
+    let mut remaining = modules;
+    let mut done = HashSet::new();
+    let mut levels = Vec::new();
+    while !remaining.is_empty() {
+        let (ready, blocked): (Vec<_>, Vec<_>) = remaining.into_iter().partition(|module| {
+            module.imports
+                .iter()
+                .all(|import| !names.contains(import) || done.contains(import))
+        });
+        if ready.is_empty() {
+            levels.push(blocked);
+            break;
+        }
+        done.extend(ready.iter().map(|module| module.name.clone()));
+        levels.push(ready);
+        remaining = blocked;
+    }
+    levels
+}
+
+/// Returns a fingerprint of every module in `levels` which changes whenever the source of the
+/// module or of one of the workspace modules it imports, directly or indirectly, changes
+pub fn fingerprints(levels: &[Vec<Module>]) -> HashMap<String, u64> {
+    let mut fingerprints = HashMap::new();
+    for level in levels {
+        let mut level_fingerprints = Vec::new();
+        for module in level {
+            let mut hasher = DefaultHasher::new();
+            module.name.hash(&mut hasher);
+            module.source.hash(&mut hasher);
+            // Modules which import each other are only in the same level if they form a cycle so
+            // their fingerprints only include the imports of earlier levels
+            for import in &module.imports {
+                fingerprints.get(import).hash(&mut hasher);
+            }
+            level_fingerprints.push((module.name.clone(), hasher.finish()));
+        }
+        fingerprints.extend(level_fingerprints);
+    }
+    fingerprints
+}
+
+/// Calls `check` for every module in `levels`, level by level, with up to `WORKERS` modules of a
+/// level being checked at the same time. Each worker gets its own child thread of `vm`.
+pub fn check_all<F>(vm: &Thread, levels: Vec<Vec<Module>>, check: F)
+    where F: Fn(&Thread, &Module) + Send + Sync + 'static
+{
+    let check = Arc::new(check);
+    for level in levels {
+        let workers = ::std::cmp::min(WORKERS, level.len());
+        let queue = Arc::new(Mutex::new(level));
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                let queue = queue.clone();
+                let check = check.clone();
+                let vm: RootedThread = vm.new_thread();
+                transport::spawn(move || {
+                    loop {
+                        let module = match queue.lock().unwrap().pop() {
+                            Some(module) => module,
+                            None => break,
+                        };
+                        check(&vm, &module);
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            if handle.join().is_err() {
+                error!("A worker panicked while typechecking the workspace");
+            }
+        }
+    }
+}
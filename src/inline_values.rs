@@ -0,0 +1,127 @@
+//! Inline values which let the client show the values of the local variables next to the source
+//! while a program is stopped in the debug adapter. The values are looked up by name in the
+//! locals of the stopped frame, so only the bindings which are in scope at the stopped location
+//! are returned.
+use gluon::base::ast::{Expr, LExpr, LPattern, Location, Pattern};
+use gluon::base::symbol::Symbol;
+use gluon::base::types::TcIdent;
+
+use language_server::{InlineValueVariableLookup, Position, Range};
+use query;
+
+fn contains_position(expr: &LExpr<TcIdent>, position: &Position) -> bool {
+    query::contains(&query::expr_range(expr),
+                    &Range {
+                        start: *position,
+                        end: *position,
+                    })
+}
+
+fn pattern_names(pattern: &LPattern<TcIdent>, scope: &mut Vec<Symbol>) {
+    match pattern.value {
+        Pattern::Identifier(ref id) => scope.push(id.name.clone()),
+        Pattern::Constructor(_, ref args) => scope.extend(args.iter().map(|arg| arg.name.clone())),
+        Pattern::Record { ref fields, .. } => {
+            scope.extend(fields.iter().map(|&(ref name, ref alias)| {
+                alias.as_ref().unwrap_or(name).clone()
+            }))
+        }
+    }
+}
+
+/// Collects the variables which are in scope at `position`
+fn scope_at(expr: &LExpr<TcIdent>, position: &Position, scope: &mut Vec<Symbol>) {
+    match expr.value {
+        Expr::Let(ref bindings, ref body) => {
+            let bind = bindings.iter().find(|bind| contains_position(&bind.expression, position));
+            match bind {
+                Some(bind) => {
+                    scope.extend(bind.arguments.iter().map(|arg| arg.name.clone()));
+                    scope_at(&bind.expression, position, scope);
+                }
+                None => {
+                    for bind in bindings {
+                        pattern_names(&bind.name, scope);
+                    }
+                    scope_at(body, position, scope);
+                }
+            }
+        }
+        Expr::Lambda(ref lambda) => {
+            scope.extend(lambda.arguments.iter().map(|arg| arg.name.clone()));
+            scope_at(&lambda.body, position, scope);
+        }
+        Expr::Match(ref scrutinee, ref alts) => {
+            match alts.iter().find(|alt| contains_position(&alt.expression, position)) {
+                Some(alt) => {
+                    pattern_names(&alt.pattern, scope);
+                    scope_at(&alt.expression, position, scope);
+                }
+                None => scope_at(scrutinee, position, scope),
+            }
+        }
+        _ => {
+            let child = query::children(expr)
+                .into_iter()
+                .find(|child| contains_position(child, position));
+            if let Some(child) = child {
+                scope_at(child, position, scope);
+            }
+        }
+    }
+}
+
+/// Calls `f` with each identifier in `expr`, including the identifiers bound by `let`
+fn identifiers<F>(expr: &LExpr<TcIdent>, f: &mut F)
+    where F: FnMut(Location, &TcIdent)
+{
+    match expr.value {
+        Expr::Identifier(ref id) => f(expr.location, id),
+        Expr::Let(ref bindings, _) => {
+            for bind in bindings {
+                if let Pattern::Identifier(ref id) = bind.name.value {
+                    f(bind.name.location, id);
+                }
+            }
+        }
+        _ => (),
+    }
+    for child in query::children(expr) {
+        identifiers(child, f);
+    }
+}
+
+/// Returns a lookup for each occurrence of a variable which is in scope at `stopped_location`.
+/// Occurrences are limited to `range` and to the lines up to the one where the program stopped
+/// as the values of later lines are not computed yet.
+pub fn variable_lookups(expr: &LExpr<TcIdent>,
+                        range: &Range,
+                        stopped_location: &Range)
+                        -> Vec<InlineValueVariableLookup> {
+    let mut scope = Vec::new();
+    scope_at(expr, &stopped_location.start, &mut scope);
+    let mut lookups = Vec::new();
+    identifiers(expr, &mut |location, id| {
+        if !scope.iter().any(|name| *name == id.name) {
+            return;
+        }
+        let name = id.name.declared_name();
+        let start = ::location_to_position(&location);
+        let end = Position {
+            line: start.line,
+            character: start.character + name.chars().count() as u64,
+        };
+        let id_range = Range {
+            start: start,
+            end: end,
+        };
+        if start.line <= stopped_location.end.line && query::contains(range, &id_range) {
+            lookups.push(InlineValueVariableLookup {
+                range: id_range,
+                variable_name: Some(String::from(name)),
+                case_sensitive_lookup: true,
+            });
+        }
+    });
+    lookups
+}
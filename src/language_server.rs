@@ -8,7 +8,7 @@ pub struct CancelParams {
     /**
      * The request id to cancel.
      */
-    pub id: String,
+    pub id: Value, // number | string
 }
 
 #[derive(Deserialize)]
@@ -120,6 +120,27 @@ pub struct DidSaveTextDocumentParams {
      */
     #[serde(rename="textDocument")]
     pub text_document: TextDocumentIdentifier,
+    /**
+     * Optional the content when saved. Depends on the includeText value
+     * when the save notifcation was requested.
+     */
+    pub text: Option<String>,
+}
+
+/**
+ * The parameters send in a will save text document notification.
+ */
+#[derive(Deserialize)]
+pub struct WillSaveTextDocumentParams {
+    /**
+     * The document that will be saved.
+     */
+    #[serde(rename="textDocument")]
+    pub text_document: TextDocumentIdentifier,
+    /**
+     * The 'TextDocumentSaveReason'.
+     */
+    pub reason: u64,
 }
 
 #[derive(Deserialize)]
@@ -173,6 +194,7 @@ pub struct FileEvent {
     /**
      * The change type.
      */
+    #[serde(rename="type")]
     pub typ: FileChangeType,
 }
 
@@ -194,6 +216,7 @@ pub struct Range {
 }
 
 /// Represents a location inside a resource, such as a line inside a text file.
+#[derive(Serialize)]
 pub struct Location {
     pub uri: String,
     pub range: Range,
@@ -222,7 +245,7 @@ pub struct InitializeParams {
      * the server.
      */
     #[serde(rename="processId")]
-    pub process_id: u64,
+    pub process_id: Option<u64>,
 
     /**
      * The rootPath of the workspace. Is null
@@ -231,15 +254,180 @@ pub struct InitializeParams {
     #[serde(rename="rootPath")]
     pub root_path: Option<String>,
 
+    /**
+     * User provided initialization options.
+     */
+    #[serde(rename="initializationOptions")]
+    pub initialization_options: Option<Value>,
+
     /**
      * The capabilities provided by the client (editor)
      */
     pub capabilities: ClientCapabilities,
+
+    /**
+     * The workspace folders configured in the client when the server starts.
+     * This property is only available if the client supports workspace folders.
+     * It can be `null` if the client supports workspace folders but none are
+     * configured.
+     */
+    #[serde(rename="workspaceFolders")]
+    pub workspace_folders: Option<Vec<WorkspaceFolder>>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct WorkspaceFolder {
+    /**
+     * The associated URI for this workspace folder.
+     */
+    pub uri: String,
+
+    /**
+     * The name of the workspace folder. Defaults to the
+     * uri's basename.
+     */
+    pub name: String,
 }
 
 #[derive(Deserialize)]
 pub struct ClientCapabilities {
-    _dummy: Option<()>,
+    /**
+     * Workspace specific client capabilities.
+     */
+    pub workspace: Option<WorkspaceClientCapabilities>,
+
+    /**
+     * Text document specific client capabilities.
+     */
+    #[serde(rename="textDocument")]
+    pub text_document: Option<TextDocumentClientCapabilities>,
+}
+
+/**
+ * Workspace specific client capabilities.
+ */
+#[derive(Deserialize)]
+pub struct WorkspaceClientCapabilities {
+    /**
+     * The client has support for workspace folders.
+     */
+    #[serde(rename="workspaceFolders")]
+    pub workspace_folders: Option<bool>,
+
+    /**
+     * The client supports `workspace/configuration` requests.
+     */
+    pub configuration: Option<bool>,
+
+    /**
+     * Capabilities specific to `WorkspaceEdit`s.
+     */
+    #[serde(rename="workspaceEdit")]
+    pub workspace_edit: Option<WorkspaceEditCapabilities>,
+}
+
+#[derive(Deserialize)]
+pub struct WorkspaceEditCapabilities {
+    /**
+     * The client supports versioned document changes in `WorkspaceEdit`s.
+     */
+    #[serde(rename="documentChanges")]
+    pub document_changes: Option<bool>,
+    /**
+     * The resource operations the client supports, such as `create`, `rename` and `delete`.
+     */
+    #[serde(rename="resourceOperations")]
+    pub resource_operations: Option<Vec<String>>,
+}
+
+/**
+ * Text document specific client capabilities.
+ */
+#[derive(Deserialize)]
+pub struct TextDocumentClientCapabilities {
+    pub synchronization: Option<SynchronizationCapabilities>,
+    /**
+     * Capabilities specific to the `textDocument/completion`
+     */
+    pub completion: Option<CompletionCapabilities>,
+    /**
+     * Capabilities specific to the `textDocument/hover`
+     */
+    pub hover: Option<HoverCapabilities>,
+    /**
+     * Capabilities specific to the `textDocument/documentSymbol`
+     */
+    #[serde(rename="documentSymbol")]
+    pub document_symbol: Option<DocumentSymbolCapabilities>,
+}
+
+#[derive(Deserialize)]
+pub struct DocumentSymbolCapabilities {
+    /**
+     * The client supports hierarchical document symbols.
+     */
+    #[serde(rename="hierarchicalDocumentSymbolSupport")]
+    pub hierarchical_document_symbol_support: Option<bool>,
+}
+
+#[derive(Deserialize)]
+pub struct HoverCapabilities {
+    /**
+     * The content formats, `plaintext` or `markdown`, which the client supports for
+     * the content property, in order of preference.
+     */
+    #[serde(rename="contentFormat")]
+    pub content_format: Option<Vec<String>>,
+}
+
+#[derive(Deserialize)]
+pub struct CompletionCapabilities {
+    /**
+     * The client supports the following `CompletionItem` specific
+     * capabilities.
+     */
+    #[serde(rename="completionItem")]
+    pub completion_item: Option<CompletionItemCapabilities>,
+}
+
+#[derive(Deserialize)]
+pub struct CompletionItemCapabilities {
+    /**
+     * Client supports snippets as insert text.
+     *
+     * A snippet can define tab stops and placeholders with `$1`, `$2`
+     * and `${3:foo}`. `$0` defines the final tab stop, it defaults to
+     * the end of the snippet. Placeholders with equal identifiers are linked,
+     * that is typing in one will update others too.
+     */
+    #[serde(rename="snippetSupport")]
+    pub snippet_support: Option<bool>,
+}
+
+#[derive(Deserialize)]
+pub struct SynchronizationCapabilities {
+    /**
+     * Whether text document synchronization supports dynamic registration.
+     */
+    #[serde(rename="dynamicRegistration")]
+    pub dynamic_registration: Option<bool>,
+    /**
+     * The client supports sending will save notifications.
+     */
+    #[serde(rename="willSave")]
+    pub will_save: Option<bool>,
+    /**
+     * The client supports sending a will save request and
+     * waits for a response providing text edits which will
+     * be applied to the document before it is saved.
+     */
+    #[serde(rename="willSaveWaitUntil")]
+    pub will_save_wait_until: Option<bool>,
+    /**
+     * The client supports did save notifications.
+     */
+    #[serde(rename="didSave")]
+    pub did_save: Option<bool>,
 }
 
 #[derive(Default, Serialize)]
@@ -264,7 +452,7 @@ pub struct ServerCapabilities {
      */
     #[serde(skip_serializing_if="Option::is_none")]
     #[serde(rename="textDocumentSync")]
-    pub text_document_sync: Option<TextDocumentSyncKind>,
+    pub text_document_sync: Option<TextDocumentSyncOptions>,
     /**
      * The server provides hover support.
      */
@@ -349,6 +537,89 @@ pub struct ServerCapabilities {
     #[serde(skip_serializing_if="Option::is_none")]
     #[serde(rename="renameProvider")]
     pub rename_provider: Option<bool>,
+    /**
+     * The server provides execute command support.
+     */
+    #[serde(skip_serializing_if="Option::is_none")]
+    #[serde(rename="executeCommandProvider")]
+    pub execute_command_provider: Option<ExecuteCommandOptions>,
+    /**
+     * The server provides inline values.
+     */
+    #[serde(skip_serializing_if="Option::is_none")]
+    #[serde(rename="inlineValueProvider")]
+    pub inline_value_provider: Option<bool>,
+    /**
+     * The server provides folding provider support.
+     */
+    #[serde(skip_serializing_if="Option::is_none")]
+    #[serde(rename="foldingRangeProvider")]
+    pub folding_range_provider: Option<bool>,
+    /**
+     * Workspace specific server capabilities.
+     */
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub workspace: Option<WorkspaceServerCapabilities>,
+}
+
+#[derive(Default, Serialize)]
+pub struct WorkspaceServerCapabilities {
+    /**
+     * The server is interested in file operations.
+     */
+    #[serde(skip_serializing_if="Option::is_none")]
+    #[serde(rename="fileOperations")]
+    pub file_operations: Option<FileOperationsServerCapabilities>,
+}
+
+#[derive(Default, Serialize)]
+pub struct FileOperationsServerCapabilities {
+    /**
+     * The server is interested in receiving `workspace/willRenameFiles` requests.
+     */
+    #[serde(skip_serializing_if="Option::is_none")]
+    #[serde(rename="willRename")]
+    pub will_rename: Option<FileOperationRegistrationOptions>,
+}
+
+/**
+ * The options to register for file operations.
+ */
+#[derive(Serialize)]
+pub struct FileOperationRegistrationOptions {
+    /**
+     * The actual filters.
+     */
+    pub filters: Vec<FileOperationFilter>,
+}
+
+/**
+ * A filter to describe in which file operation requests or notifications
+ * the server is interested in.
+ */
+#[derive(Serialize)]
+pub struct FileOperationFilter {
+    /**
+     * A Uri like `file` or `untitled`.
+     */
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub scheme: Option<String>,
+    /**
+     * The actual file operation pattern.
+     */
+    pub pattern: FileOperationPattern,
+}
+
+/**
+ * A pattern to describe in which file operation requests or notifications
+ * the server is interested in.
+ */
+#[derive(Serialize)]
+pub struct FileOperationPattern {
+    /**
+     * The glob pattern to match.
+     */
+    pub glob: String,
 }
 
 /**
@@ -379,6 +650,52 @@ impl serde::Serialize for TextDocumentSyncKind {
     }
 }
 
+#[derive(Default, Serialize)]
+pub struct TextDocumentSyncOptions {
+    /**
+     * Open and close notifications are sent to the server.
+     */
+    #[serde(skip_serializing_if="Option::is_none")]
+    #[serde(rename="openClose")]
+    pub open_close: Option<bool>,
+    /**
+     * Change notificatins are sent to the server. See TextDocumentSyncKind.None,
+     * TextDocumentSyncKind.Full and TextDocumentSyncKindIncremental.
+     */
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub change: Option<TextDocumentSyncKind>,
+    /**
+     * Will save notifications are sent to the server.
+     */
+    #[serde(skip_serializing_if="Option::is_none")]
+    #[serde(rename="willSave")]
+    pub will_save: Option<bool>,
+    /**
+     * Will save wait until requests are sent to the server.
+     */
+    #[serde(skip_serializing_if="Option::is_none")]
+    #[serde(rename="willSaveWaitUntil")]
+    pub will_save_wait_until: Option<bool>,
+    /**
+     * Save notifications are sent to the server.
+     */
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub save: Option<SaveOptions>,
+}
+
+/**
+ * Save options.
+ */
+#[derive(Default, Serialize)]
+pub struct SaveOptions {
+    /**
+     * The client is supposed to include the content on save.
+     */
+    #[serde(skip_serializing_if="Option::is_none")]
+    #[serde(rename="includeText")]
+    pub include_text: Option<bool>,
+}
+
 /**
  * Completion options.
  */
@@ -396,6 +713,17 @@ pub struct CompletionOptions {
      */
     #[serde(rename="triggerCharacters")]
     pub trigger_characters: Vec<String>,
+
+    /**
+     * The list of all possible characters that commit a completion. This field can be used
+     * if clients don't support individual commit characters per completion item.
+     *
+     * If a server provides both `allCommitCharacters` and commit characters on an individual
+     * completion item the ones on the completion item win.
+     */
+    #[serde(skip_serializing_if="Vec::is_empty")]
+    #[serde(rename="allCommitCharacters")]
+    pub all_commit_characters: Vec<String>,
 }
 
 /**
@@ -441,6 +769,17 @@ pub struct DocumentOnTypeFormattingOptions {
     pub more_trigger_character: Vec<String>,
 }
 
+/**
+ * Execute command options.
+ */
+#[derive(Serialize)]
+pub struct ExecuteCommandOptions {
+    /**
+     * The commands to be executed on the server
+     */
+    pub commands: Vec<String>,
+}
+
 /// A textual edit applicable to a text document.
 #[derive(Default, Serialize)]
 pub struct TextEdit {
@@ -464,6 +803,110 @@ pub struct WorkspaceEdit {
      * Holds changes to existing resources.
      */
     pub changes: HashMap<String, Vec<TextEdit>>,
+    /**
+     * Text edits to documents and resource operations, such as renaming a file, which are
+     * applied in order. Clients which support them use these instead of `changes`.
+     */
+    #[serde(skip_serializing_if="Vec::is_empty")]
+    #[serde(rename="documentChanges")]
+    pub document_changes: Vec<DocumentChange>,
+}
+
+pub enum DocumentChange {
+    Edit(TextDocumentEdit),
+    Create(CreateFile),
+    Rename(RenameFile),
+}
+
+impl serde::Serialize for DocumentChange {
+    fn serialize<S>(&self, serializer: &mut S) -> Result<(), S::Error>
+        where S: serde::Serializer
+    {
+        match *self {
+            DocumentChange::Edit(ref edit) => edit.serialize(serializer),
+            DocumentChange::Create(ref create) => create.serialize(serializer),
+            DocumentChange::Rename(ref rename) => rename.serialize(serializer),
+        }
+    }
+}
+
+/**
+ * Describes textual changes on a single text document.
+ */
+#[derive(Serialize)]
+pub struct TextDocumentEdit {
+    /**
+     * The text document to change.
+     */
+    #[serde(rename="textDocument")]
+    pub text_document: OptionalVersionedTextDocumentIdentifier,
+    /**
+     * The edits to be applied.
+     */
+    pub edits: Vec<TextEdit>,
+}
+
+#[derive(Serialize)]
+pub struct OptionalVersionedTextDocumentIdentifier {
+    /**
+     * The text document's URI.
+     */
+    pub uri: String,
+    /**
+     * The version number of this document. `null` if the document is not open and the one on
+     * disk is edited.
+     */
+    pub version: Option<u64>,
+}
+
+/**
+ * Create file operation
+ */
+#[derive(Serialize)]
+pub struct CreateFile {
+    /**
+     * A create, always `create`.
+     */
+    pub kind: String,
+    /**
+     * The resource to create.
+     */
+    pub uri: String,
+}
+
+/**
+ * Rename file operation
+ */
+#[derive(Serialize)]
+pub struct RenameFile {
+    /**
+     * A rename, always `rename`.
+     */
+    pub kind: String,
+    /**
+     * The old (existing) location.
+     */
+    #[serde(rename="oldUri")]
+    pub old_uri: String,
+    /**
+     * The new location.
+     */
+    #[serde(rename="newUri")]
+    pub new_uri: String,
+}
+
+#[derive(Serialize)]
+pub struct ApplyWorkspaceEditParams {
+    /**
+     * An optional label of the workspace edit. This label is presented in the user interface
+     * for example on an undo stack to undo the workspace edit.
+     */
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub label: Option<String>,
+    /**
+     * The edits to apply.
+     */
+    pub edit: WorkspaceEdit,
 }
 
 /**
@@ -509,6 +952,16 @@ pub struct CompletionItem {
      */
     #[serde(skip_serializing_if="Option::is_none")]
     pub documentation: Option<String>,
+    /**
+     * Indicates if this item is deprecated.
+     */
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub deprecated: Option<bool>,
+    /**
+     * Tags for this completion item.
+     */
+    #[serde(skip_serializing_if="Vec::is_empty")]
+    pub tags: Vec<CompletionItemTag>,
     /**
      * A string that shoud be used when comparing this item
      * with other items. When `falsy` the label is used.
@@ -530,6 +983,13 @@ pub struct CompletionItem {
     #[serde(skip_serializing_if="Option::is_none")]
     #[serde(rename="insertText")]
     pub insert_text: Option<String>,
+    /**
+     * The format of the insert text. The format applies to both the `insertText` property
+     * and the `newText` property of a provided `textEdit`.
+     */
+    #[serde(skip_serializing_if="Option::is_none")]
+    #[serde(rename="insertTextFormat")]
+    pub insert_text_format: Option<InsertTextFormat>,
     /**
      * An edit which is applied to a document when selecting
      * this completion. When an edit is provided the value of
@@ -538,6 +998,13 @@ pub struct CompletionItem {
     #[serde(skip_serializing_if="Option::is_none")]
     #[serde(rename="textEdit")]
     pub text_edit: Option<TextEdit>,
+    /**
+     * An optional set of characters that when pressed while this completion is active will
+     * accept it first and then type that character.
+     */
+    #[serde(skip_serializing_if="Option::is_none")]
+    #[serde(rename="commitCharacters")]
+    pub commit_characters: Option<Vec<String>>,
     /**
      * An data entry field that is preserved on a completion item between
      * a completion and a completion resolve request.
@@ -569,6 +1036,13 @@ pub enum CompletionItemKind {
     Color = 16,
     File = 17,
     Reference = 18,
+    Folder = 19,
+    EnumMember = 20,
+    Constant = 21,
+    Struct = 22,
+    Event = 23,
+    Operator = 24,
+    TypeParameter = 25,
 }
 
 impl serde::Serialize for CompletionItemKind {
@@ -579,6 +1053,50 @@ impl serde::Serialize for CompletionItemKind {
     }
 }
 
+/**
+ * Defines whether the insert text in a completion item should be interpreted as
+ * plain text or a snippet.
+ */
+#[derive(Clone, Copy)]
+pub enum InsertTextFormat {
+    /**
+     * The primary text to be inserted is treated as a plain string.
+     */
+    PlainText = 1,
+    /**
+     * The primary text to be inserted is treated as a snippet.
+     */
+    Snippet = 2,
+}
+
+impl serde::Serialize for InsertTextFormat {
+    fn serialize<S>(&self, serializer: &mut S) -> Result<(), S::Error>
+        where S: serde::Serializer
+    {
+        serializer.serialize_u8(*self as u8)
+    }
+}
+
+/**
+ * Completion item tags are extra annotations that tweak the rendering of a completion
+ * item.
+ */
+#[derive(Clone, Copy)]
+pub enum CompletionItemTag {
+    /**
+     * Render a completion as obsolete, usually using a strike-out.
+     */
+    Deprecated = 1,
+}
+
+impl serde::Serialize for CompletionItemTag {
+    fn serialize<S>(&self, serializer: &mut S) -> Result<(), S::Error>
+        where S: serde::Serializer
+    {
+        serializer.serialize_u8(*self as u8)
+    }
+}
+
 /**
  * The result of a hove request.
  */
@@ -592,6 +1110,7 @@ pub struct Hover {
     /**
      * An optional range
      */
+    #[serde(skip_serializing_if="Option::is_none")]
     pub range: Option<Range>,
 }
 
@@ -776,10 +1295,43 @@ pub struct DocumentSymbolParams {
     pub text_document: TextDocumentIdentifier,
 }
 
+#[derive(Deserialize)]
+pub struct FoldingRangeParams {
+    /**
+     * The text document.
+     */
+    #[serde(rename="textDocument")]
+    pub text_document: TextDocumentIdentifier,
+}
+
+/**
+ * Represents a folding range.
+ */
+#[derive(Serialize)]
+pub struct FoldingRange {
+    /**
+     * The zero-based line number from where the folded range starts.
+     */
+    #[serde(rename="startLine")]
+    pub start_line: u64,
+    /**
+     * The zero-based line number where the folded range ends.
+     */
+    #[serde(rename="endLine")]
+    pub end_line: u64,
+    /**
+     * Describes the kind of the folding range such as `comment` or `region`. The kind
+     * is used to categorize folding ranges and used by commands like 'Fold all comments'.
+     */
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub kind: Option<String>,
+}
+
 /**
  * Represents information about programming constructs like variables, classes,
  * interfaces etc.
  */
+#[derive(Serialize)]
 pub struct SymbolInformation {
     /**
      * The name of this symbol.
@@ -803,6 +1355,69 @@ pub struct SymbolInformation {
     pub container_name: String,
 }
 
+/**
+ * Represents programming constructs like variables, classes, interfaces etc. that appear in a
+ * document. Document symbols can be hierarchical and they have two ranges: one that encloses its
+ * definition and one that points to its most interesting range, e.g. the range of an identifier.
+ */
+#[derive(Serialize)]
+pub struct DocumentSymbol {
+    /**
+     * The name of this symbol.
+     */
+    pub name: String,
+
+    /**
+     * More detail for this symbol, e.g the signature of a function.
+     */
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub detail: Option<String>,
+
+    /**
+     * The kind of this symbol.
+     */
+    pub kind: SymbolKind,
+
+    /**
+     * The range enclosing this symbol not including leading/trailing whitespace but everything
+     * else like comments. This information is typically used to determine if the clients cursor
+     * is inside the symbol to reveal in the symbol in the UI.
+     */
+    pub range: Range,
+
+    /**
+     * The range that should be selected and revealed when this symbol is being picked, e.g the
+     * name of a function. Must be contained by the `range`.
+     */
+    #[serde(rename="selectionRange")]
+    pub selection_range: Range,
+
+    /**
+     * Children of this symbol, e.g. properties of a class.
+     */
+    #[serde(skip_serializing_if="Vec::is_empty")]
+    pub children: Vec<DocumentSymbol>,
+}
+
+/**
+ * The result of a document symbol request, hierarchical symbols if the client supports them.
+ */
+pub enum DocumentSymbolResponse {
+    Flat(Vec<SymbolInformation>),
+    Nested(Vec<DocumentSymbol>),
+}
+
+impl serde::Serialize for DocumentSymbolResponse {
+    fn serialize<S>(&self, serializer: &mut S) -> Result<(), S::Error>
+        where S: serde::Serializer
+    {
+        match *self {
+            DocumentSymbolResponse::Flat(ref symbols) => symbols.serialize(serializer),
+            DocumentSymbolResponse::Nested(ref symbols) => symbols.serialize(serializer),
+        }
+    }
+}
+
 /**
  * A symbol kind.
  */
@@ -855,6 +1470,7 @@ pub struct CodeActionParams {
     /**
      * The document in which the command was invoked.
      */
+    #[serde(rename="textDocument")]
     pub text_document: TextDocumentIdentifier,
 
     /**
@@ -880,6 +1496,33 @@ pub struct CodeActionContext {
     pub diagnostics: Vec<Diagnostic>,
 }
 
+/**
+ * A code action represents a change that can be performed in code, e.g. to fix a problem or
+ * to refactor code.
+ */
+#[derive(Serialize)]
+pub struct CodeAction {
+    /**
+     * A short, human-readable, title for this code action.
+     */
+    pub title: String,
+    /**
+     * The kind of the code action, such as `refactor.rewrite`.
+     */
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub kind: Option<String>,
+    /**
+     * The diagnostics that this code action resolves.
+     */
+    #[serde(skip_serializing_if="Vec::is_empty")]
+    pub diagnostics: Vec<Diagnostic>,
+    /**
+     * The workspace edit this code action performs.
+     */
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub edit: Option<WorkspaceEdit>,
+}
+
 #[derive(Deserialize)]
 pub struct CodeLensParams {
     /**
@@ -937,6 +1580,36 @@ pub struct RenameParams {
     pub new_name: String,
 }
 
+/**
+ * The parameters sent in notifications/requests for user-initiated renames of
+ * files.
+ */
+#[derive(Deserialize)]
+pub struct RenameFilesParams {
+    /**
+     * An array of all files/folders renamed in this operation. When a folder
+     * is renamed, only the folder will be included, and not its children.
+     */
+    pub files: Vec<FileRename>,
+}
+
+/**
+ * Represents information on a file/folder rename.
+ */
+#[derive(Deserialize)]
+pub struct FileRename {
+    /**
+     * A file:// URI for the original location of the file/folder being renamed.
+     */
+    #[serde(rename="oldUri")]
+    pub old_uri: String,
+    /**
+     * A file:// URI for the new location of the file/folder being renamed.
+     */
+    #[serde(rename="newUri")]
+    pub new_uri: String,
+}
+
 #[derive(Serialize)]
 pub struct ShowMessageParams {
     /**
@@ -993,7 +1666,7 @@ pub struct LogMessageParams {
     pub message: String,
 }
 
-#[derive(Serialize)]
+#[derive(Deserialize, Serialize)]
 pub struct DidChangeConfigurationParams {
     /**
      * The actual changed settings
@@ -1001,6 +1674,27 @@ pub struct DidChangeConfigurationParams {
     pub settings: Value,
 }
 
+#[derive(Serialize)]
+pub struct ConfigurationParams {
+    pub items: Vec<ConfigurationItem>,
+}
+
+#[derive(Serialize)]
+pub struct ConfigurationItem {
+    /**
+     * The scope to get the configuration section for.
+     */
+    #[serde(rename="scopeUri")]
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub scope_uri: Option<String>,
+
+    /**
+     * The configuration section asked for.
+     */
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub section: Option<String>,
+}
+
 #[derive(Clone, Copy)]
 pub enum MessageType {
     /**
@@ -1036,13 +1730,19 @@ pub struct PublishDiagnosticsParams {
      */
     pub uri: String,
 
+    /**
+     * The version number of the document the diagnostics are published for.
+     */
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub version: Option<u64>,
+
     /**
      * An array of diagnostic information items.
      */
     pub diagnostics: Vec<Diagnostic>,
 }
 
-#[derive(Default, Deserialize, Serialize)]
+#[derive(Clone, Default, Deserialize, Serialize)]
 pub struct Diagnostic {
     /**
      * The range at which the message applies
@@ -1053,17 +1753,20 @@ pub struct Diagnostic {
      * The diagnostic's severity. Can be omitted. If omitted it is up to the
      * client to interpret diagnostics as error, warning, info or hint.
      */
+    #[serde(skip_serializing_if="Option::is_none")]
     pub severity: Option<DiagnosticSeverity>,
 
     /**
      * The diagnostic's code. Can be omitted.
      */
-    pub code: String, // number | string;
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub code: Option<String>, // number | string;
 
     /**
      * A human-readable string describing the source of this
      * diagnostic, e.g. 'typescript' or 'super lint'.
      */
+    #[serde(skip_serializing_if="Option::is_none")]
     pub source: Option<String>,
 
     /**
@@ -1132,3 +1835,77 @@ pub struct Command {
     #[serde(skip_serializing_if="Vec::is_empty")]
     pub arguments: Vec<Value>,
 }
+
+#[derive(Deserialize)]
+pub struct ExecuteCommandParams {
+    /**
+     * The identifier of the actual command handler.
+     */
+    pub command: String,
+    /**
+     * Arguments that the command should be invoked with.
+     */
+    #[serde(default)]
+    pub arguments: Vec<Value>,
+}
+
+/**
+ * A parameter literal used in inline value requests.
+ */
+#[derive(Deserialize)]
+pub struct InlineValueParams {
+    /**
+     * The text document.
+     */
+    #[serde(rename="textDocument")]
+    pub text_document: TextDocumentIdentifier,
+    /**
+     * The document range for which inline values should be computed.
+     */
+    pub range: Range,
+    /**
+     * Additional information about the context in which inline values were requested.
+     */
+    pub context: InlineValueContext,
+}
+
+#[derive(Deserialize)]
+pub struct InlineValueContext {
+    /**
+     * The stack frame (as a DAP Id) where the execution has stopped.
+     */
+    #[serde(rename="frameId")]
+    pub frame_id: i64,
+    /**
+     * The document range where execution has stopped.
+     * Typically the end position of the range denotes the line where the inline values are shown.
+     */
+    #[serde(rename="stoppedLocation")]
+    pub stopped_location: Range,
+}
+
+/**
+ * Provide inline value through a variable lookup.
+ *
+ * If only a range is specified, the variable name will be extracted from the underlying document.
+ *
+ * An optional variable name can be used to override the extracted name.
+ */
+#[derive(Serialize)]
+pub struct InlineValueVariableLookup {
+    /**
+     * The document range for which the inline value applies.
+     * The range is used to extract the variable name from the underlying document.
+     */
+    pub range: Range,
+    /**
+     * If specified the name of the variable to look up.
+     */
+    #[serde(rename="variableName")]
+    pub variable_name: Option<String>,
+    /**
+     * How to perform the lookup.
+     */
+    #[serde(rename="caseSensitiveLookup")]
+    pub case_sensitive_lookup: bool,
+}
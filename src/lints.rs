@@ -0,0 +1,440 @@
+//! The lints which are found by walking a typechecked module: `let` bindings which are never used,
+//! parameters which a function never uses, `let` bindings which shadow a name in scope and match
+//! arms which the arms before them leave nothing to match, either since one of them matches
+//! anything or since they match every constructor of the type which is matched on. Names which
+//! start with `_` are expected to be unused or to shadow others. Unused parameters and the style
+//! lints, parentheses around an expression which needs none and lambdas such as `\x -> f x` which
+//! only pass their arguments on, come with a fix.
+use gluon::base::ast::{Alternative, Binding, Expr, Lambda, LExpr, LPattern, Pattern, Typed};
+use gluon::base::types::{TcIdent, TcType, Type, TypeEnv};
+
+use eval;
+use language_server::{Range, TextEdit};
+use pipeline::{close_brackets, extent, identifier_len, is_atomic, is_identifier_char, offset,
+               parenthesized_extent, position, skip_trivia};
+use query;
+use references;
+
+pub const UNUSED_BINDING: &'static str = "unused-binding";
+pub const SHADOWING: &'static str = "shadowing";
+pub const UNREACHABLE_ARM: &'static str = "unreachable-arm";
+pub const REDUNDANT_PARENTHESES: &'static str = "redundant-parens";
+pub const REDUNDANT_LAMBDA: &'static str = "redundant-lambda";
+pub const UNUSED_PARAMETER: &'static str = "unused-parameter";
+
+/// An edit which fixes a finding
+pub struct Fix {
+    pub title: String,
+    pub edit: TextEdit,
+}
+
+/// A diagnostic of one of the lints
+pub struct Finding {
+    /// The code of the lint
+    pub code: &'static str,
+    pub range: Range,
+    pub message: String,
+    pub fix: Option<Fix>,
+}
+
+fn range(source: &str, start: usize, end: usize) -> Range {
+    Range {
+        start: position(source, start),
+        end: position(source, end),
+    }
+}
+
+/// Returns the range of the variable, constructor or record which `pattern` starts with
+fn pattern_range(source: &str, pattern: &LPattern<TcIdent>) -> Option<Range> {
+    offset(source, &pattern.location).and_then(|start| {
+        let start = skip_trivia(source, start);
+        let end = if source[start..].starts_with('{') {
+            close_brackets(source, start, start + 1)
+        } else {
+            match identifier_len(&source[start..]) {
+                0 => None,
+                len => Some(start + len),
+            }
+        };
+        end.map(|end| range(source, start, end))
+    })
+}
+
+/// Returns the names which `pattern` binds
+fn pattern_names(pattern: &LPattern<TcIdent>) -> Vec<&str> {
+    match pattern.value {
+        Pattern::Identifier(ref id) => vec![id.name.declared_name()],
+        Pattern::Constructor(_, ref args) => {
+            args.iter().map(|arg| arg.name.declared_name()).collect()
+        }
+        Pattern::Record { ref fields, .. } => {
+            fields.iter()
+                .map(|&(ref field, ref alias)| alias.as_ref().unwrap_or(field).declared_name())
+                .collect()
+        }
+    }
+}
+
+fn unused_bindings<F>(source: &str,
+                      bindings: &[Binding<TcIdent>],
+                      body: &LExpr<TcIdent>,
+                      is_module: &F,
+                      findings: &mut Vec<Finding>)
+    where F: Fn(&str) -> bool
+{
+    for bind in bindings {
+        let id = match bind.name.value {
+            Pattern::Identifier(ref id) => id,
+            _ => continue,
+        };
+        let name = id.name.declared_name();
+        // Unused imports are reported by their own lint
+        let is_import = match bind.expression.value {
+            Expr::Identifier(ref module) => is_module(module.name.as_ref()),
+            _ => false,
+        };
+        if is_import || name.starts_with('_') || !references::uses(body, &id.name).is_empty() ||
+           bindings.iter().any(|bind| !references::uses(&bind.expression, &id.name).is_empty()) {
+            continue;
+        }
+        if let Some(range) = pattern_range(source, &bind.name) {
+            findings.push(Finding {
+                code: UNUSED_BINDING,
+                range: range,
+                message: format!("`{}` is bound but never used", name),
+                fix: None,
+            });
+        }
+    }
+}
+
+/// Reports the `parameters` of a function whose body is `body` which the body never uses. The
+/// parameters are written one after another from `start`.
+fn unused_parameters(source: &str,
+                     parameters: &[TcIdent],
+                     body: &LExpr<TcIdent>,
+                     mut start: usize,
+                     findings: &mut Vec<Finding>) {
+    for parameter in parameters {
+        start = skip_trivia(source, start);
+        let end = start + identifier_len(&source[start..]);
+        let name = parameter.name.declared_name();
+        if &source[start..end] != name {
+            return;
+        }
+        if !name.starts_with('_') && references::uses(body, &parameter.name).is_empty() {
+            let range = range(source, start, end);
+            findings.push(Finding {
+                code: UNUSED_PARAMETER,
+                range: range,
+                message: format!("The parameter `{}` is never used", name),
+                fix: Some(Fix {
+                    title: format!("Rename `{}` to `_{}`", name, name),
+                    edit: TextEdit {
+                        range: range,
+                        new_text: format!("_{}", name),
+                    },
+                }),
+            });
+        }
+        start = end;
+    }
+}
+
+/// Returns the offset after the name of `bind`
+fn name_end(source: &str, bind: &Binding<TcIdent>) -> Option<usize> {
+    offset(source, &bind.name.location).and_then(|start| {
+        let start = skip_trivia(source, start);
+        // An operator is bound with its name in parentheses, as in `let (+++) l r = ..`
+        if source[start..].starts_with('(') {
+            source[start..].find(')').map(|end| start + end + 1)
+        } else {
+            Some(start + identifier_len(&source[start..]))
+        }
+    })
+}
+
+/// Returns the names of the constructors of each variant type which is defined in `expr`
+fn local_variants(expr: &LExpr<TcIdent>) -> Vec<(String, Vec<String>)> {
+    let mut found = Vec::new();
+    if let Expr::Type(ref bindings, _) = expr.value {
+        for bind in bindings {
+            if let Some(&Type::Variants(ref variants)) = bind.alias.typ.as_ref().map(|typ| &**typ) {
+                let constructors = variants.iter()
+                    .map(|&(ref constructor, _)| constructor.declared_name().into())
+                    .collect();
+                found.push((bind.name.declared_name().into(), constructors));
+            }
+        }
+    }
+    for child in query::children(expr) {
+        found.extend(local_variants(child));
+    }
+    found
+}
+
+/// Returns the names of the constructors of `typ` if it is a variant type. A type which is
+/// defined in the module may only be known from `local`.
+fn variants(env: &TypeEnv, local: &[(String, Vec<String>)], typ: &TcType) -> Vec<String> {
+    let typ = eval::remove_aliases(env, typ);
+    if let Type::Variants(ref variants) = *typ {
+        return variants.iter()
+            .map(|&(ref constructor, _)| constructor.declared_name().into())
+            .collect();
+    }
+    typ.as_alias()
+        .and_then(|(id, _)| local.iter().find(|&&(ref name, _)| name == id.declared_name()))
+        .map_or_else(Vec::new, |&(_, ref constructors)| constructors.clone())
+}
+
+fn unreachable_arms(source: &str,
+                    variants: &[String],
+                    alts: &[Alternative<TcIdent>],
+                    findings: &mut Vec<Finding>) {
+    let mut constructors = Vec::new();
+    let mut exhausted = false;
+    for alt in alts {
+        let unreachable = match alt.pattern.value {
+            // The arguments of a constructor pattern are variables which match anything
+            Pattern::Constructor(ref id, _) => {
+                let name = id.name.declared_name();
+                let unreachable = exhausted || constructors.contains(&name);
+                constructors.push(name);
+                exhausted = exhausted ||
+                            !variants.is_empty() &&
+                            variants.iter().all(|variant| constructors.contains(&&variant[..]));
+                unreachable
+            }
+            Pattern::Identifier(_) |
+            Pattern::Record { .. } => {
+                let unreachable = exhausted;
+                exhausted = true;
+                unreachable
+            }
+        };
+        if !unreachable {
+            continue;
+        }
+        if let Some(range) = pattern_range(source, &alt.pattern) {
+            findings.push(Finding {
+                code: UNREACHABLE_ARM,
+                range: range,
+                message: "This arm is never reached since the arms before it match everything \
+                          it matches"
+                    .into(),
+                fix: None,
+            });
+        }
+    }
+}
+
+/// Walks `expr` with the names which are in scope in it, reporting the bindings which shadow one
+/// of them
+fn shadowing<'e>(source: &str,
+                 expr: &'e LExpr<TcIdent>,
+                 scope: &mut Vec<&'e str>,
+                 findings: &mut Vec<Finding>) {
+    let outer = scope.len();
+    match expr.value {
+        Expr::Let(ref bindings, ref body) => {
+            for bind in bindings {
+                if let Pattern::Identifier(ref id) = bind.name.value {
+                    let name = id.name.declared_name();
+                    if name.starts_with('_') || !scope.contains(&name) {
+                        continue;
+                    }
+                    if let Some(range) = pattern_range(source, &bind.name) {
+                        findings.push(Finding {
+                            code: SHADOWING,
+                            range: range,
+                            message: format!("`{}` shadows a variable which is in scope", name),
+                            fix: None,
+                        });
+                    }
+                }
+            }
+            for bind in bindings {
+                scope.extend(pattern_names(&bind.name));
+            }
+            for bind in bindings {
+                let names = scope.len();
+                scope.extend(bind.arguments.iter().map(|arg| arg.name.declared_name()));
+                shadowing(source, &bind.expression, scope, findings);
+                scope.truncate(names);
+            }
+            shadowing(source, body, scope, findings);
+        }
+        Expr::Lambda(ref lambda) => {
+            scope.extend(lambda.arguments.iter().map(|arg| arg.name.declared_name()));
+            shadowing(source, &lambda.body, scope, findings);
+        }
+        Expr::Match(ref scrutinee, ref alts) => {
+            shadowing(source, scrutinee, scope, findings);
+            for alt in alts {
+                scope.extend(pattern_names(&alt.pattern));
+                shadowing(source, &alt.expression, scope, findings);
+                scope.truncate(outer);
+            }
+        }
+        _ => {
+            for child in query::children(expr) {
+                shadowing(source, child, scope, findings);
+            }
+        }
+    }
+    scope.truncate(outer);
+}
+
+/// Returns the text which replaces the text from `start` to `end` by `text`, with spaces around it
+/// where the text would otherwise run into the text before or after it
+fn spaced(source: &str, start: usize, end: usize, text: &str) -> String {
+    let runs_into = |c: Option<char>| {
+        c.map_or(false, |c| is_identifier_char(c) || c == '"' || c == '\'')
+    };
+    let before = if runs_into(source[..start].chars().next_back()) { " " } else { "" };
+    let after = if runs_into(source[end..].chars().next()) { " " } else { "" };
+    format!("{}{}{}", before, text, after)
+}
+
+fn redundant_parentheses(source: &str, expr: &LExpr<TcIdent>, findings: &mut Vec<Finding>) {
+    let (start, end) = match extent(source, expr) {
+        Some(extent) => extent,
+        None => return,
+    };
+    // The parentheses of an operator, such as `(+)`, and of a tuple belong to the expression and
+    // those around a negative literal keep it from being a subtraction
+    let is_tuple = match expr.value {
+        Expr::Tuple(_) => true,
+        _ => false,
+    };
+    if is_tuple || source[start..].starts_with('(') || source[start..].starts_with('-') {
+        return;
+    }
+    let (outer_start, outer_end) = match parenthesized_extent(source, expr) {
+        Some(outer) if outer != (start, end) => outer,
+        _ => return,
+    };
+    let range = range(source, outer_start, outer_end);
+    findings.push(Finding {
+        code: REDUNDANT_PARENTHESES,
+        range: range,
+        message: "These parentheses are redundant".into(),
+        fix: Some(Fix {
+            title: "Remove the parentheses".into(),
+            edit: TextEdit {
+                range: range,
+                new_text: spaced(source, outer_start, outer_end, &source[start..end]),
+            },
+        }),
+    });
+}
+
+/// Reports `expr`, the lambda `lambda`, if it only calls a function with its arguments, as in
+/// `\x y -> f a x y`
+fn redundant_lambda(source: &str,
+                    expr: &LExpr<TcIdent>,
+                    lambda: &Lambda<TcIdent>,
+                    findings: &mut Vec<Finding>) {
+    let (func, args) = match lambda.body.value {
+        Expr::Call(ref func, ref args) => (func, args),
+        _ => return,
+    };
+    let count = lambda.arguments.len();
+    if count == 0 || args.len() < count {
+        return;
+    }
+    let (kept, passed) = args.split_at(args.len() - count);
+    let passes_arguments = passed.iter().zip(&lambda.arguments).all(|(arg, param)| {
+        match arg.value {
+            Expr::Identifier(ref id) => id.name == param.name,
+            _ => false,
+        }
+    });
+    let uses_arguments = |expr: &LExpr<TcIdent>| {
+        lambda.arguments.iter().any(|param| !references::uses(expr, &param.name).is_empty())
+    };
+    if !passes_arguments || uses_arguments(func) || kept.iter().any(&uses_arguments) {
+        return;
+    }
+    let function = match (parenthesized_extent(source, func),
+                          parenthesized_extent(source, kept.last().unwrap_or(func))) {
+        (Some((start, _)), Some((_, end))) => &source[start..end],
+        _ => return,
+    };
+    let ((start, end), (outer_start, outer_end)) = match (extent(source, expr),
+                                                          parenthesized_extent(source, expr)) {
+        (Some(extent), Some(outer)) => (extent, outer),
+        _ => return,
+    };
+    // The parentheses around the lambda are kept for a function which is applied to arguments
+    let (start, end, new_text) = if kept.is_empty() && (start, end) != (outer_start, outer_end) {
+        (outer_start, outer_end, spaced(source, outer_start, outer_end, function))
+    } else {
+        (start, end, function.to_string())
+    };
+    let range = range(source, start, end);
+    findings.push(Finding {
+        code: REDUNDANT_LAMBDA,
+        range: range,
+        message: format!("This lambda only passes its arguments on to `{}`", function),
+        fix: Some(Fix {
+            title: format!("Replace the lambda by `{}`", function),
+            edit: TextEdit {
+                range: range,
+                new_text: new_text,
+            },
+        }),
+    });
+}
+
+fn walk<F>(source: &str,
+           expr: &LExpr<TcIdent>,
+           env: &TypeEnv,
+           local: &[(String, Vec<String>)],
+           is_module: &F,
+           findings: &mut Vec<Finding>)
+    where F: Fn(&str) -> bool
+{
+    if is_atomic(expr) {
+        redundant_parentheses(source, expr, findings);
+    }
+    match expr.value {
+        Expr::Let(ref bindings, ref body) => {
+            unused_bindings(source, bindings, body, is_module, findings);
+            for bind in bindings {
+                if let Some(start) = name_end(source, bind) {
+                    unused_parameters(source, &bind.arguments, &bind.expression, start, findings);
+                }
+            }
+        }
+        Expr::Lambda(ref lambda) => {
+            match offset(source, &expr.location).map(|start| skip_trivia(source, start)) {
+                Some(start) if source[start..].starts_with('\\') => {
+                    unused_parameters(source, &lambda.arguments, &lambda.body, start + 1, findings)
+                }
+                _ => (),
+            }
+            redundant_lambda(source, expr, lambda, findings)
+        }
+        Expr::Match(ref scrutinee, ref alts) => {
+            let variants = variants(env, local, &scrutinee.env_type_of(env));
+            unreachable_arms(source, &variants, alts, findings)
+        }
+        _ => (),
+    }
+    for child in query::children(expr) {
+        walk(source, child, env, local, is_module, findings);
+    }
+}
+
+/// Returns the findings of the lints in the typechecked module `expr`, the module without the
+/// implicit prelude, which is parsed from `source`. `env` resolves the types of the module and
+/// `is_module` tells if a variable is one which replaced an `import`.
+pub fn check<F>(source: &str, expr: &LExpr<TcIdent>, env: &TypeEnv, is_module: &F) -> Vec<Finding>
+    where F: Fn(&str) -> bool
+{
+    let mut findings = Vec::new();
+    walk(source, expr, env, &local_variants(expr), is_module, &mut findings);
+    shadowing(source, expr, &mut Vec::new(), &mut findings);
+    findings
+}
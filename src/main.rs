@@ -1,18 +1,7 @@
-#![cfg_attr(feature = "serde_macros", feature(custom_derive, plugin))]
-#![cfg_attr(feature = "serde_macros", plugin(serde_macros))]
-
-extern crate serde;
-extern crate serde_json;
-
-extern crate jsonrpc_core;
-
-#[macro_use]
-extern crate log;
 extern crate env_logger;
-extern crate gluon;
-
-#[cfg(feature = "serde_macros")]
-include!("main.rs.in");
+extern crate gluon_language_server;
 
-#[cfg(not(feature = "serde_macros"))]
-include!(concat!(env!("OUT_DIR"), "/main.rs"));
+fn main() {
+    ::env_logger::init().unwrap();
+    gluon_language_server::run();
+}
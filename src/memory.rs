@@ -0,0 +1,120 @@
+//! Accounting of the memory used by the analysis results of each document, so that the results
+//! of closed documents can be dropped once they exceed a budget, least recently used first. The
+//! sizes are approximations computed from the number of nodes in the syntax trees.
+use std::collections::HashMap;
+use std::mem;
+use std::sync::Mutex;
+
+use gluon::base::ast::LExpr;
+use gluon::base::metadata::Metadata;
+use gluon::base::types::TcIdent;
+
+use query;
+
+/// The budget used until the client configures `gluon.memory.budget`
+pub const DEFAULT_BUDGET_MB: usize = 512;
+
+/// Returns the approximate number of bytes used by `expr`
+pub fn expr_size(expr: &LExpr<TcIdent>) -> usize {
+    // Nodes also own their symbols, types and vectors of children so count each node twice
+    2 * mem::size_of::<LExpr<TcIdent>>() +
+    query::children(expr).into_iter().map(expr_size).sum::<usize>()
+}
+
+/// Returns the approximate number of bytes used by `metadata`
+pub fn metadata_size(metadata: &Metadata) -> usize {
+    mem::size_of::<Metadata>() + metadata.comment.as_ref().map_or(0, |comment| comment.len()) +
+    metadata.module
+        .iter()
+        .map(|(name, metadata)| name.len() + metadata_size(metadata))
+        .sum::<usize>()
+}
+
+struct Usage {
+    size: usize,
+    last_used: u64,
+}
+
+struct State {
+    documents: HashMap<String, Usage>,
+    /// Incremented every time a document is used
+    clock: u64,
+}
+
+pub struct MemoryBudget {
+    budget: Mutex<usize>,
+    state: Mutex<State>,
+}
+
+impl MemoryBudget {
+    pub fn new() -> MemoryBudget {
+        MemoryBudget {
+            budget: Mutex::new(DEFAULT_BUDGET_MB * 1024 * 1024),
+            state: Mutex::new(State {
+                documents: HashMap::new(),
+                clock: 0,
+            }),
+        }
+    }
+
+    pub fn set_budget(&self, bytes: usize) {
+        *self.budget.lock().unwrap() = bytes;
+    }
+
+    /// Records that the results for `uri` use `size` bytes and have just been used
+    pub fn record(&self, uri: &str, size: usize) {
+        let mut state = self.state.lock().unwrap();
+        state.clock += 1;
+        let clock = state.clock;
+        state.documents.insert(uri.into(),
+                               Usage {
+                                   size: size,
+                                   last_used: clock,
+                               });
+    }
+
+    /// Forgets the results of `uri`
+    pub fn forget(&self, uri: &str) {
+        self.state.lock().unwrap().documents.remove(uri);
+    }
+
+    /// Forgets the results of all documents
+    pub fn clear(&self) {
+        self.state.lock().unwrap().documents.clear();
+    }
+
+    /// Returns the number of bytes used by the results of all documents
+    pub fn total(&self) -> usize {
+        self.state.lock().unwrap().documents.values().map(|usage| usage.size).sum()
+    }
+
+    /// Forgets the least recently used documents for which `can_evict` returns true until the
+    /// results fit in the budget. Returns the documents whose results should be dropped.
+    pub fn evict<F>(&self, can_evict: F) -> Vec<String>
+        where F: Fn(&str) -> bool
+    {
+        let budget = *self.budget.lock().unwrap();
+        let mut state = self.state.lock().unwrap();
+        let mut total: usize = state.documents.values().map(|usage| usage.size).sum();
+        if total <= budget {
+            return vec![];
+        }
+        let mut candidates: Vec<(u64, String)> = state.documents
+            .iter()
+            .filter(|&(uri, _)| can_evict(uri))
+            .map(|(uri, usage)| (usage.last_used, uri.clone()))
+            .collect();
+        candidates.sort();
+        let mut evicted = Vec::new();
+        for (_, uri) in candidates {
+            if total <= budget {
+                break;
+            }
+            if let Some(usage) = state.documents.remove(&uri) {
+                total -= usage.size;
+            }
+            evicted.push(uri);
+        }
+        evicted
+    }
+}
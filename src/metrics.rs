@@ -0,0 +1,109 @@
+//! Latency statistics of the requests handled by the server. The latencies of each method are
+//! counted in a histogram and requests which take longer than a threshold are logged together
+//! with a summary of their parameters.
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde_json::Value;
+
+/// The threshold used until the client configures `gluon.requests.slowThreshold`
+pub const DEFAULT_SLOW_THRESHOLD_MS: u64 = 500;
+
+/// The upper bounds in milliseconds of the buckets of the histograms. Requests which take longer
+/// than the last bound are counted in an extra bucket.
+pub const BUCKETS_MS: &'static [u64] = &[1, 5, 10, 50, 100, 500, 1000, 5000];
+
+/// The number of characters of the parameters which are logged for a slow request
+const SUMMARY_LENGTH: usize = 200;
+
+pub fn millis(duration: Duration) -> u64 {
+    duration.as_secs() * 1000 + duration.subsec_nanos() as u64 / 1_000_000
+}
+
+/// Returns the parameters of `request` as JSON, truncated to `SUMMARY_LENGTH` characters
+fn summary(request: &Value) -> String {
+    let params = match request.find("params") {
+        Some(params) => params.to_string(),
+        None => return String::new(),
+    };
+    if params.chars().count() <= SUMMARY_LENGTH {
+        params
+    } else {
+        let mut summary: String = params.chars().take(SUMMARY_LENGTH).collect();
+        summary.push_str("...");
+        summary
+    }
+}
+
+pub struct Histogram {
+    /// The number of requests in each bucket of `BUCKETS_MS`
+    pub buckets: Vec<u64>,
+    pub count: u64,
+    pub total_ms: u64,
+    pub max_ms: u64,
+}
+
+impl Histogram {
+    fn new() -> Histogram {
+        Histogram {
+            buckets: vec![0; BUCKETS_MS.len() + 1],
+            count: 0,
+            total_ms: 0,
+            max_ms: 0,
+        }
+    }
+
+    fn record(&mut self, ms: u64) {
+        let bucket = BUCKETS_MS.iter().position(|&bound| ms <= bound).unwrap_or(BUCKETS_MS.len());
+        self.buckets[bucket] += 1;
+        self.count += 1;
+        self.total_ms += ms;
+        if ms > self.max_ms {
+            self.max_ms = ms;
+        }
+    }
+}
+
+pub struct Metrics {
+    slow_threshold: Mutex<Duration>,
+    methods: Mutex<HashMap<String, Histogram>>,
+}
+
+impl Metrics {
+    pub fn new() -> Metrics {
+        Metrics {
+            slow_threshold: Mutex::new(Duration::from_millis(DEFAULT_SLOW_THRESHOLD_MS)),
+            methods: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn set_slow_threshold(&self, threshold: Duration) {
+        *self.slow_threshold.lock().unwrap() = threshold;
+    }
+
+    /// Records that handling `request` took `elapsed`. Messages without a method are ignored.
+    pub fn record(&self, request: &Value, elapsed: Duration) {
+        let method = match request.find("method").and_then(|method| method.as_string()) {
+            Some(method) => method,
+            None => return,
+        };
+        let ms = millis(elapsed);
+        if elapsed > *self.slow_threshold.lock().unwrap() {
+            warn!("Slow request `{}` took {} ms: {}", method, ms, summary(request));
+        }
+        self.methods
+            .lock()
+            .unwrap()
+            .entry(method.into())
+            .or_insert_with(Histogram::new)
+            .record(ms);
+    }
+
+    /// Calls `f` with the histogram of every method which has been handled
+    pub fn with_histograms<F, R>(&self, f: F) -> R
+        where F: FnOnce(&HashMap<String, Histogram>) -> R
+    {
+        f(&self.methods.lock().unwrap())
+    }
+}
@@ -0,0 +1,363 @@
+//! Moving a top-level binding into another module. The binding's source is cut out of its module
+//! from the `let` up to the `in` after it, the target module binds it first and exports it from
+//! the record it ends with, and the record patterns which take the binding out of an import of
+//! its old module import it from the new one instead.
+use gluon::base::ast::{Expr, LExpr, Pattern};
+use gluon::base::symbol::Symbol;
+use gluon::base::types::{TcIdent, Type};
+
+use language_server::{Position, Range, TextEdit};
+use pipeline::{close_brackets, identifier_len, offset, position, skip_trivia};
+use query;
+use references::{self, skip_quoted};
+
+/// Returns the offset after the name which starts at or after `start`, which is in parentheses
+/// if it is an operator
+fn name_end(source: &str, start: usize) -> Option<usize> {
+    if source[start..].starts_with('(') {
+        source[start..].find(')').map(|end| start + end + 1)
+    } else {
+        match identifier_len(&source[start..]) {
+            0 => None,
+            len => Some(start + len),
+        }
+    }
+}
+
+/// Returns the offset of the keyword `keyword` if it is the last token before `end`
+pub fn keyword_before(source: &str, end: usize, keyword: &str) -> Option<usize> {
+    let before = source[..end].trim_right();
+    if !before.ends_with(keyword) {
+        return None;
+    }
+    let start = before.len() - keyword.len();
+    if before[..start].ends_with(|c: char| c == '_' || c.is_alphanumeric()) {
+        None
+    } else {
+        Some(start)
+    }
+}
+
+/// Returns the offset of the `in` which ends the `let` binding whose expression starts at
+/// `start`, skipping the `in`s of the `let` and `type` expressions in the binding
+pub fn in_after(source: &str, start: usize) -> Option<usize> {
+    let bytes = source.as_bytes();
+    let mut depth = 0;
+    let mut nested = 0;
+    let mut i = start;
+    loop {
+        i = skip_trivia(source, i);
+        if i >= bytes.len() {
+            return None;
+        }
+        match bytes[i] {
+            b'"' | b'\'' => i = skip_quoted(bytes, i),
+            b'(' | b'[' | b'{' => {
+                depth += 1;
+                i += 1;
+            }
+            b')' | b']' | b'}' => {
+                depth -= 1;
+                i += 1;
+            }
+            _ => {
+                let len = identifier_len(&source[i..]);
+                if len == 0 {
+                    i += source[i..].chars().next().map_or(1, char::len_utf8);
+                    continue;
+                }
+                match &source[i..i + len] {
+                    "let" | "type" if depth == 0 => nested += 1,
+                    "in" if depth == 0 && nested == 0 => return Some(i),
+                    "in" if depth == 0 => nested -= 1,
+                    _ => (),
+                }
+                i += len;
+            }
+        }
+    }
+}
+
+/// Returns the offset after the `in` at `in_start` and the whitespace after it, including the end
+/// of the line, so that removing a binding up to there leaves no empty line
+pub fn after_in(source: &str, in_start: usize) -> usize {
+    let mut end = in_start + "in".len();
+    let rest = &source[end..];
+    end += rest.len() - rest.trim_left_matches(|c| c == ' ' || c == '\t').len();
+    if source[end..].starts_with('\n') {
+        end += 1;
+    }
+    end
+}
+
+fn line_start(source: &str, offset: usize) -> usize {
+    source[..offset].rfind('\n').map_or(0, |i| i + 1)
+}
+
+fn edit(source: &str, start: usize, end: usize, new_text: String) -> TextEdit {
+    TextEdit {
+        range: Range {
+            start: position(source, start),
+            end: position(source, end),
+        },
+        new_text: new_text,
+    }
+}
+
+/// The text of a top-level binding which is moved out of its module
+pub struct MovedBinding {
+    pub name: String,
+    /// The binding from its documentation comment up to, but not including, the `in` after it
+    pub text: String,
+    /// The range of the binding and of the `in` after it
+    pub range: Range,
+    /// True if the rest of the module uses the binding, so that the module has to import it
+    pub used: bool,
+}
+
+/// Returns the variables which `expr` binds at its top-level, before the expression which its
+/// `let` and `type` expressions end with
+fn top_level_names(expr: &LExpr<TcIdent>) -> Vec<&Symbol> {
+    let mut names = Vec::new();
+    let mut expr = expr;
+    loop {
+        match expr.value {
+            Expr::Let(ref bindings, ref body) => {
+                for bind in bindings {
+                    match bind.name.value {
+                        Pattern::Identifier(ref id) => names.push(&id.name),
+                        Pattern::Record { ref fields, .. } => {
+                            names.extend(fields.iter()
+                                .map(|&(ref field, ref alias)| alias.as_ref().unwrap_or(field)))
+                        }
+                        Pattern::Constructor(_, ref args) => {
+                            names.extend(args.iter().map(|arg| &arg.name))
+                        }
+                    }
+                }
+                expr = body;
+            }
+            Expr::Type(ref bindings, ref body) => {
+                for bind in bindings {
+                    if let Some(&Type::Variants(ref variants)) =
+                        bind.alias.typ.as_ref().map(|typ| &**typ) {
+                        names.extend(variants.iter().map(|&(ref constructor, _)| constructor));
+                    }
+                }
+                expr = body;
+            }
+            _ => return names,
+        }
+    }
+}
+
+/// Returns the `let` expressions which `expr` starts with and the expression they end with
+fn top_level(expr: &LExpr<TcIdent>) -> (Vec<&LExpr<TcIdent>>, &LExpr<TcIdent>) {
+    let mut lets = Vec::new();
+    let mut expr = expr;
+    loop {
+        match expr.value {
+            Expr::Let(_, ref body) => {
+                lets.push(expr);
+                expr = body;
+            }
+            Expr::Type(_, ref body) => expr = body,
+            _ => return (lets, expr),
+        }
+    }
+}
+
+/// Returns the top-level binding of the typechecked module `expr` whose name is at `at`, or the
+/// reason why it can't be moved. The binding may only use variables which are bound outside of
+/// the module, as the module can't be imported from the module it moves to.
+pub fn moved_binding(source: &str,
+                     expr: &LExpr<TcIdent>,
+                     at: &Position)
+                     -> Result<MovedBinding, String> {
+    let bound = top_level_names(expr);
+    let (lets, _) = top_level(expr);
+    for let_expr in lets {
+        let (bindings, body) = match let_expr.value {
+            Expr::Let(ref bindings, ref body) => (bindings, body),
+            _ => continue,
+        };
+        for bind in bindings {
+            let id = match bind.name.value {
+                Pattern::Identifier(ref id) => id,
+                _ => continue,
+            };
+            let start = match offset(source, &bind.name.location) {
+                Some(start) => skip_trivia(source, start),
+                None => continue,
+            };
+            let end = match name_end(source, start) {
+                Some(end) => end,
+                None => continue,
+            };
+            if query::before(at, &position(source, start)) ||
+               query::before(&position(source, end), at) {
+                continue;
+            }
+            let name = String::from(id.name.declared_name());
+            if bindings.len() > 1 {
+                return Err(format!("`{}` is bound together with other bindings by `and`", name));
+            }
+            let uses_bound = bound.iter().find(|bound| {
+                ***bound != id.name && !references::uses(&bind.expression, bound).is_empty()
+            });
+            if let Some(other) = uses_bound {
+                return Err(format!("`{}` uses `{}`, which is bound in the module",
+                                   name,
+                                   other.declared_name()));
+            }
+            let let_start = try!(keyword_before(source, start, "let")
+                .ok_or_else(|| format!("`{}` is not bound by `let`", name)));
+            let in_start = try!(in_after(source, end)
+                .ok_or_else(|| format!("The `in` after `{}` was not found", name)));
+            // The documentation comment above the binding moves with it
+            let mut text_start = let_start;
+            let mut line = line_start(source, let_start);
+            if source[line..let_start].trim().is_empty() {
+                text_start = line;
+                while line > 0 {
+                    let previous = line_start(source, line - 1);
+                    if !source[previous..line].trim_left().starts_with("///") {
+                        break;
+                    }
+                    line = previous;
+                    text_start = line;
+                }
+            }
+            let end = after_in(source, in_start);
+            return Ok(MovedBinding {
+                name: name,
+                text: source[text_start..in_start].trim_right().into(),
+                range: Range {
+                    start: position(source, text_start),
+                    end: position(source, end),
+                },
+                used: !references::uses(body, &id.name).is_empty(),
+            });
+        }
+    }
+    Err("Only top-level bindings can be moved".into())
+}
+
+/// Returns the source of a new module which only has the binding `text` of `name`
+pub fn new_module(name: &str, text: &str) -> String {
+    format!("{} in\n{{ {} }}\n", text, name)
+}
+
+/// Returns the edits which bind `name` to `text` at the start of the module `expr`, which is
+/// parsed from `source`, and add it to the record which the module ends with
+pub fn add_to_module(source: &str,
+                     expr: &LExpr<TcIdent>,
+                     name: &str,
+                     text: &str)
+                     -> Result<Vec<TextEdit>, String> {
+    if top_level_names(expr).iter().any(|bound| bound.declared_name() == name) {
+        return Err(format!("The module binds `{}` already", name));
+    }
+    let (_, exports) = top_level(expr);
+    let record_start = offset(source, &exports.location).map(|start| skip_trivia(source, start));
+    let record_end = match (&exports.value, record_start) {
+        (&Expr::Record { .. }, Some(start)) if source[start..].starts_with('{') => {
+            close_brackets(source, start, start + 1).map(|end| end - 1)
+        }
+        _ => None,
+    };
+    let (record_start, record_end) = match (record_start, record_end) {
+        (Some(start), Some(end)) => (start, end),
+        _ => return Err("The module does not end with a record of its exports".into()),
+    };
+    let fields = source[record_start + 1..record_end].trim_right();
+    let field = if fields.trim().is_empty() || fields.ends_with(',') {
+        format!(" {}", name)
+    } else {
+        format!(", {}", name)
+    };
+    let field_offset = record_start + 1 + fields.len();
+    let start = offset(source, &expr.location).map_or(0, |start| {
+        line_start(source, skip_trivia(source, start))
+    });
+    Ok(vec![edit(source, start, start, format!("{} in\n", text)),
+            edit(source, field_offset, field_offset, field)])
+}
+
+/// Returns the edits which make the record patterns in `source`, the module `expr`, which take
+/// `name` out of an import of `module` take it out of an import of `path` instead
+pub fn import_edits(source: &str,
+                    expr: &LExpr<TcIdent>,
+                    module: &str,
+                    name: &str,
+                    path: &str)
+                    -> Vec<TextEdit> {
+    let mut edits = Vec::new();
+    if let Expr::Let(ref bindings, _) = expr.value {
+        for bind in bindings {
+            let imports_module = match bind.expression.value {
+                // The `import` macro is replaced by a variable named after the module
+                Expr::Identifier(ref id) => id.name.as_ref() == module,
+                _ => false,
+            };
+            let imports_name = match bind.name.value {
+                Pattern::Record { ref fields, .. } => {
+                    fields.iter().any(|&(ref field, _)| field.declared_name() == name)
+                }
+                _ => false,
+            };
+            if !imports_module || !imports_name {
+                continue;
+            }
+            let pattern_start = match offset(source, &bind.name.location) {
+                Some(start) => skip_trivia(source, start),
+                None => continue,
+            };
+            let pattern_end = match close_brackets(source, pattern_start, pattern_start + 1) {
+                Some(end) if source[pattern_start..].starts_with('{') => end,
+                _ => continue,
+            };
+            let fields: Vec<_> = source[pattern_start + 1..pattern_end - 1]
+                .split(',')
+                .map(str::trim)
+                .filter(|field| !field.is_empty())
+                .collect();
+            let (moved, rest): (Vec<&str>, Vec<&str>) = fields.into_iter()
+                .partition(|field| &field[..identifier_len(field)] == name);
+            if rest.is_empty() {
+                let import = references::imports(source, module)
+                    .into_iter()
+                    .find(|range| !query::before(&range.start, &position(source, pattern_end)));
+                if let Some(range) = import {
+                    edits.push(TextEdit {
+                        range: range,
+                        new_text: path.into(),
+                    });
+                }
+                continue;
+            }
+            edits.push(edit(source,
+                            pattern_start,
+                            pattern_end,
+                            format!("{{ {} }}", rest.join(", "))));
+            let group_start = offset(source, &bindings[0].name.location)
+                .and_then(|start| keyword_before(source, skip_trivia(source, start), "let"));
+            if let Some(group_start) = group_start {
+                let line = line_start(source, group_start);
+                let import = format!("let {{ {} }} = import \"{}\" in", moved.join(", "), path);
+                edits.push(if source[line..group_start].trim().is_empty() {
+                    edit(source,
+                         line,
+                         line,
+                         format!("{}{}\n", &source[line..group_start], import))
+                } else {
+                    edit(source, group_start, group_start, format!("{} ", import))
+                });
+            }
+        }
+    }
+    for child in query::children(expr) {
+        edits.extend(import_edits(source, child, module, name, path));
+    }
+    edits
+}
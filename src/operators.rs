@@ -0,0 +1,91 @@
+//! Completion of operators. Operators are bound like any other name, mostly by record patterns
+//! such as the ones of the implicit prelude, but their precedence and associativity are fixed by
+//! the parser. gluon does not expose the parser's table so it is mirrored here.
+use gluon::base::ast::LExpr;
+use gluon::base::types::{TcIdent, TcType, TypeEnv};
+
+use language_server::Position;
+use scope;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Fixity {
+    Left,
+    Right,
+}
+
+/// Returns true if `c` can be part of an operator, as decided by gluon's lexer
+pub fn is_operator_char(c: char) -> bool {
+    "+-*/&|=<>".contains(c)
+}
+
+pub fn is_operator(name: &str) -> bool {
+    name.chars().next().map_or(false, is_operator_char)
+}
+
+/// The precedence of `op`, operators with a higher precedence bind tighter
+pub fn precedence(op: &str) -> i32 {
+    match op {
+        "*" | "/" | "%" => 7,
+        "+" | "-" => 6,
+        ":" | "++" => 5,
+        "==" | "/=" | "<" | ">" | "<=" | ">=" => 4,
+        "&&" => 3,
+        "||" => 2,
+        "$" => 0,
+        _ => 9,
+    }
+}
+
+pub fn fixity(op: &str) -> Fixity {
+    match op {
+        ":" | "++" | "&&" | "||" | "$" => Fixity::Right,
+        _ => Fixity::Left,
+    }
+}
+
+/// Describes the precedence and associativity of `op` the way Haskell declares them, such as
+/// `infixl 6`
+pub fn fixity_declaration(op: &str) -> String {
+    let fixity = match fixity(op) {
+        Fixity::Left => "infixl",
+        Fixity::Right => "infixr",
+    };
+    format!("{} {}", fixity, precedence(op))
+}
+
+/// The operators which are bound in a scope and the types of each. An operator which is bound
+/// several times is overloaded, as gluon resolves each use by its type.
+#[derive(Default)]
+struct Operators {
+    operators: Vec<(String, Vec<TcType>)>,
+}
+
+impl Operators {
+    fn add(&mut self, name: &str, typ: &TcType) {
+        if !is_operator(name) {
+            return;
+        }
+        match self.operators.iter().position(|&(ref op, _)| op == name) {
+            Some(i) => {
+                let types = &mut self.operators[i].1;
+                if !types.iter().any(|t| t == typ) {
+                    types.push(typ.clone());
+                }
+            }
+            None => self.operators.push((name.into(), vec![typ.clone()])),
+        }
+    }
+}
+
+/// Returns the operators which are in scope at `position` in the typechecked expression `expr`
+/// and the types they are bound with
+pub fn in_scope(env: &TypeEnv,
+                expr: &LExpr<TcIdent>,
+                position: &Position)
+                -> Vec<(String, Vec<TcType>)> {
+    let mut operators = Operators::default();
+    for ident in scope::in_scope(env, expr, position) {
+        operators.add(ident.name.declared_name(), &ident.typ);
+    }
+    operators.operators
+}
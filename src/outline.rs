@@ -0,0 +1,302 @@
+//! Information about a document which can be read from its syntax tree alone. It is available as
+//! soon as the document parses, before it has been typechecked, and is more precise once the
+//! typechecked expression is used instead.
+use gluon::base::ast::{self, Expr, LExpr, Pattern};
+use gluon::base::types::{TcIdent, arg_iter};
+
+use language_server::{DocumentSymbol, FoldingRange, Location, Position, Range, SymbolInformation,
+                      SymbolKind};
+use query;
+
+pub const KEYWORDS: &'static [&'static str] = &["and", "else", "if", "in", "let", "match",
+                                                 "then", "type", "with"];
+
+fn is_function(bind: &ast::Binding<TcIdent>) -> bool {
+    if !bind.arguments.is_empty() {
+        return true;
+    }
+    if let Expr::Lambda(_) = bind.expression.value {
+        return true;
+    }
+    // Only set once the expression has been typechecked
+    match bind.name.value {
+        Pattern::Identifier(ref id) => arg_iter(&id.typ).next().is_some(),
+        _ => false,
+    }
+}
+
+/// Returns the range of a name which starts at `location`
+fn name_range(location: &ast::Location, name: &str) -> Range {
+    let start = ::location_to_position(location);
+    Range {
+        start: start,
+        end: Position {
+            line: start.line,
+            character: start.character + name.chars().count() as u64,
+        },
+    }
+}
+
+fn symbol(uri: &str,
+          container: &str,
+          name: &str,
+          kind: SymbolKind,
+          range: Range)
+          -> SymbolInformation {
+    SymbolInformation {
+        name: name.into(),
+        kind: kind,
+        location: Location {
+            uri: uri.into(),
+            range: range,
+        },
+        container_name: container.into(),
+    }
+}
+
+fn add_symbols(uri: &str,
+               expr: &LExpr<TcIdent>,
+               container: &str,
+               symbols: &mut Vec<SymbolInformation>) {
+    match expr.value {
+        Expr::Let(ref bindings, ref body) => {
+            for bind in bindings {
+                match bind.name.value {
+                    Pattern::Identifier(ref id) => {
+                        let name = id.name.declared_name();
+                        let kind = if is_function(bind) {
+                            SymbolKind::Function
+                        } else {
+                            SymbolKind::Variable
+                        };
+                        let range = Range {
+                            start: ::location_to_position(&bind.name.location),
+                            end: query::expr_range(&bind.expression).end,
+                        };
+                        symbols.push(symbol(uri, container, name, kind, range));
+                        add_symbols(uri, &bind.expression, name, symbols);
+                        continue;
+                    }
+                    Pattern::Record { ref fields, .. } => {
+                        for &(ref field, ref alias) in fields {
+                            let name = alias.as_ref().unwrap_or(field).declared_name();
+                            let range = name_range(&bind.name.location, name);
+                            symbols.push(symbol(uri, container, name, SymbolKind::Variable, range));
+                        }
+                    }
+                    Pattern::Constructor(..) => (),
+                }
+                add_symbols(uri, &bind.expression, container, symbols);
+            }
+            add_symbols(uri, body, container, symbols);
+        }
+        Expr::Type(ref bindings, ref body) => {
+            for bind in bindings {
+                let name = bind.name.declared_name();
+                let range = name_range(&expr.location, name);
+                symbols.push(symbol(uri, container, name, SymbolKind::Class, range));
+            }
+            add_symbols(uri, body, container, symbols);
+        }
+        _ => {
+            for child in query::children(expr) {
+                add_symbols(uri, child, container, symbols);
+            }
+        }
+    }
+}
+
+/// Returns the `let` and `type` bindings in `expr`, each with the name of the binding it is
+/// nested in
+pub fn symbols(uri: &str, expr: &LExpr<TcIdent>) -> Vec<SymbolInformation> {
+    let mut symbols = Vec::new();
+    add_symbols(uri, query::strip_implicit_prelude(expr), "", &mut symbols);
+    symbols
+}
+
+/// Returns the range of a whole binding, from the start of the line of `name` to `end`. gluon keeps
+/// no location for the `let` or `type` keyword so the line of the name is taken instead, moved up
+/// to the first line of the binding's documentation comment.
+fn binding_range(name: &ast::Location, comment: &Option<String>, end: Position) -> Range {
+    let line = ::location_to_position(name).line;
+    let comment_lines = comment.as_ref().map_or(0, |comment| comment.lines().count() as u64);
+    Range {
+        start: Position {
+            line: if line > comment_lines {
+                line - comment_lines
+            } else {
+                0
+            },
+            character: 0,
+        },
+        end: end,
+    }
+}
+
+fn document_symbol(name: &str,
+                   kind: SymbolKind,
+                   range: Range,
+                   selection_range: Range,
+                   children: Vec<DocumentSymbol>)
+                   -> DocumentSymbol {
+    DocumentSymbol {
+        name: name.into(),
+        detail: None,
+        kind: kind,
+        range: range,
+        selection_range: selection_range,
+        children: children,
+    }
+}
+
+fn add_document_symbols(expr: &LExpr<TcIdent>, symbols: &mut Vec<DocumentSymbol>) {
+    match expr.value {
+        Expr::Let(ref bindings, ref body) => {
+            for bind in bindings {
+                let range = binding_range(&bind.name.location,
+                                          &bind.comment,
+                                          query::expr_range(&bind.expression).end);
+                let mut children = Vec::new();
+                add_document_symbols(&bind.expression, &mut children);
+                match bind.name.value {
+                    Pattern::Identifier(ref id) => {
+                        let name = id.name.declared_name();
+                        let kind = if is_function(bind) {
+                            SymbolKind::Function
+                        } else {
+                            SymbolKind::Variable
+                        };
+                        let selection_range = name_range(&bind.name.location, name);
+                        symbols.push(document_symbol(name, kind, range, selection_range, children));
+                        continue;
+                    }
+                    Pattern::Record { ref fields, .. } => {
+                        for &(ref field, ref alias) in fields {
+                            let name = alias.as_ref().unwrap_or(field).declared_name();
+                            let selection_range = name_range(&bind.name.location, name);
+                            symbols.push(document_symbol(name,
+                                                         SymbolKind::Variable,
+                                                         range,
+                                                         selection_range,
+                                                         vec![]));
+                        }
+                    }
+                    Pattern::Constructor(..) => (),
+                }
+                // The bindings of an expression which is destructured are not nested in a name
+                symbols.extend(children);
+            }
+            add_document_symbols(body, symbols);
+        }
+        Expr::Type(ref bindings, ref body) => {
+            for bind in bindings {
+                let name = bind.name.declared_name();
+                let range = binding_range(&expr.location,
+                                          &bind.comment,
+                                          query::expr_range(body).start);
+                let selection_range = name_range(&expr.location, name);
+                symbols.push(document_symbol(name,
+                                             SymbolKind::Class,
+                                             range,
+                                             selection_range,
+                                             vec![]));
+            }
+            add_document_symbols(body, symbols);
+        }
+        _ => {
+            for child in query::children(expr) {
+                add_document_symbols(child, symbols);
+            }
+        }
+    }
+}
+
+/// Returns the `let` and `type` bindings in `expr` with the bindings in their expressions as
+/// their children
+pub fn document_symbols(expr: &LExpr<TcIdent>) -> Vec<DocumentSymbol> {
+    let mut symbols = Vec::new();
+    add_document_symbols(query::strip_implicit_prelude(expr), &mut symbols);
+    symbols
+}
+
+fn add_folding_ranges(expr: &LExpr<TcIdent>, ranges: &mut Vec<FoldingRange>) {
+    let foldable = match expr.value {
+        Expr::Let(..) | Expr::Type(..) | Expr::Match(..) | Expr::Record { .. } |
+        Expr::Array(..) | Expr::Block(..) | Expr::Lambda(..) => true,
+        _ => false,
+    };
+    if foldable {
+        let range = query::expr_range(expr);
+        if range.start.line < range.end.line {
+            ranges.push(FoldingRange {
+                start_line: range.start.line,
+                end_line: range.end.line,
+                kind: None,
+            });
+        }
+    }
+    for child in query::children(expr) {
+        add_folding_ranges(child, ranges);
+    }
+}
+
+/// Returns the ranges of the expressions in `expr` which span several lines
+pub fn folding_ranges(expr: &LExpr<TcIdent>) -> Vec<FoldingRange> {
+    let mut ranges = Vec::new();
+    add_folding_ranges(query::strip_implicit_prelude(expr), &mut ranges);
+    ranges
+}
+
+fn add_identifiers(expr: &LExpr<TcIdent>, prefix: &str, identifiers: &mut Vec<String>) {
+    {
+        let mut add = |name: &str| {
+            if name.starts_with(prefix) && !identifiers.iter().any(|id| id == name) {
+                identifiers.push(name.into());
+            }
+        };
+        match expr.value {
+            Expr::Let(ref bindings, _) => {
+                for bind in bindings {
+                    match bind.name.value {
+                        Pattern::Identifier(ref id) => add(id.name.declared_name()),
+                        Pattern::Record { ref fields, .. } => {
+                            for &(ref field, ref alias) in fields {
+                                add(alias.as_ref().unwrap_or(field).declared_name());
+                            }
+                        }
+                        Pattern::Constructor(_, ref args) => {
+                            for arg in args {
+                                add(arg.name.declared_name());
+                            }
+                        }
+                    }
+                    for arg in &bind.arguments {
+                        add(arg.name.declared_name());
+                    }
+                }
+            }
+            Expr::Lambda(ref lambda) => {
+                for arg in &lambda.arguments {
+                    add(arg.name.declared_name());
+                }
+            }
+            Expr::Type(ref bindings, _) => {
+                for bind in bindings {
+                    add(bind.name.declared_name());
+                }
+            }
+            _ => (),
+        }
+    }
+    for child in query::children(expr) {
+        add_identifiers(child, prefix, identifiers);
+    }
+}
+
+/// Returns the names bound anywhere in `expr` which start with `prefix`. Without types the scope
+/// of the names is not known so this includes names which are not in scope at the cursor.
+pub fn identifiers(expr: &LExpr<TcIdent>, prefix: &str) -> Vec<String> {
+    let mut identifiers = Vec::new();
+    add_identifiers(expr, prefix, &mut identifiers);
+    identifiers
+}
@@ -0,0 +1,109 @@
+//! Redirection of the output of programs run by the server. Standard input and output are used to
+//! talk to the client so the `io` primitives which use them are replaced by ones which pass the
+//! output to the sink installed on the current thread and which fail to read any input.
+use std::cell::RefCell;
+
+use gluon::{Compiler, Result as GluonResult, Thread};
+use gluon::base::metadata::Metadata;
+use gluon::base::symbol::Symbol;
+use gluon::vm::api::{IO, VMFunction, primitive_f};
+use gluon::vm::thread::{Status, ThreadInternal};
+use gluon::vm::types::VMInt;
+
+use eval;
+
+thread_local!(static SINK: RefCell<Option<Box<Fn(&str)>>> = RefCell::new(None));
+
+fn write(output: &str) {
+    SINK.with(|sink| {
+        if let Some(ref sink) = *sink.borrow() {
+            sink(output);
+        }
+    })
+}
+
+fn print(s: String) -> IO<()> {
+    write(&format!("{}\n", s));
+    IO::Value(())
+}
+
+fn print_int(i: VMInt) -> IO<()> {
+    write(&i.to_string());
+    IO::Value(())
+}
+
+fn read_char() -> IO<char> {
+    IO::Exception("Programs run by the language server can't read from stdin".into())
+}
+
+fn read_line() -> IO<String> {
+    IO::Exception("Programs run by the language server can't read from stdin".into())
+}
+
+fn print_wrapper(thread: &Thread) -> Status {
+    VMFunction::unpack_and_call(&(print as fn(_) -> _), thread)
+}
+
+fn print_int_wrapper(thread: &Thread) -> Status {
+    VMFunction::unpack_and_call(&(print_int as fn(_) -> _), thread)
+}
+
+fn read_char_wrapper(thread: &Thread) -> Status {
+    VMFunction::unpack_and_call(&(read_char as fn() -> _), thread)
+}
+
+fn read_line_wrapper(thread: &Thread) -> Status {
+    VMFunction::unpack_and_call(&(read_line as fn() -> _), thread)
+}
+
+/// Replaces the `io` record of `vm` with one where printing goes to the sink of the current
+/// thread. The fields are in the same order as in the original record so the new record keeps
+/// the type of the original.
+pub fn redirect(vm: &Thread) -> GluonResult<()> {
+    try!(vm.define_global("__output_print",
+                          primitive_f::<fn(_) -> _>("io.print", print_wrapper, print)));
+    try!(vm.define_global("__output_print_int",
+                          primitive_f::<fn(_) -> _>("io.print_int", print_int_wrapper, print_int)));
+    try!(vm.define_global("__output_read_char",
+                          primitive_f::<fn() -> _>("io.read_char", read_char_wrapper, read_char)));
+    try!(vm.define_global("__output_read_line",
+                          primitive_f::<fn() -> _>("io.read_line", read_line_wrapper, read_line)));
+    let source = r#"
+{
+    print_int = __output_print_int,
+    open_file = io.open_file,
+    read_file = io.read_file,
+    read_file_to_string = io.read_file_to_string,
+    read_char = __output_read_char,
+    read_line = __output_read_line,
+    print = __output_print,
+    catch = io.catch,
+    run_expr = io.run_expr,
+    load_script = io.load_script
+}
+"#;
+    let typ = {
+        let env = vm.get_env();
+        let (_, typ) = try!(env.get_binding("io"));
+        typ.into_owned()
+    };
+    let (value, _) = try!(eval::run_expr(&mut Compiler::new().implicit_prelude(false),
+                                         vm,
+                                         "io",
+                                         source,
+                                         source));
+    try!(vm.global_env().set_global(Symbol::new("io"), typ, Metadata::default(), *value));
+    Ok(())
+}
+
+/// Runs `f` with the output of programs on the current thread passed to `sink`
+pub fn capture<S, F, R>(sink: S, f: F) -> R
+    where S: Fn(&str) + 'static,
+          F: FnOnce() -> R
+{
+    let previous = SINK.with(|current| current.borrow_mut().take());
+    SINK.with(|current| *current.borrow_mut() = Some(Box::new(sink)));
+    let result = f();
+    SINK.with(|current| *current.borrow_mut() = previous);
+    result
+}
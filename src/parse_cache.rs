@@ -0,0 +1,112 @@
+//! Cache of parsed documents keyed by a hash of their text. Switching between documents, undoing
+//! back to an earlier state or touching a file without changing it then typechecks the cached
+//! expression instead of parsing the same text again.
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use gluon::Compiler;
+use gluon::base::ast::LExpr;
+use gluon::base::error::Errors;
+use gluon::base::types::TcIdent;
+use gluon::parser::Error as ParseError;
+
+use memory;
+
+struct Entry {
+    name: String,
+    source: String,
+    expr: LExpr<TcIdent>,
+}
+
+pub struct ParseCache {
+    entries: Mutex<HashMap<u64, Entry>>,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+}
+
+fn hash(name: &str, source: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    name.hash(&mut hasher);
+    source.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl ParseCache {
+    pub fn new() -> ParseCache {
+        ParseCache {
+            entries: Mutex::new(HashMap::new()),
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+        }
+    }
+
+    /// Parses `source` as the module `name` unless the same text has been parsed for it before.
+    /// Only sources without parse errors are cached since the errors can't be copied.
+    pub fn parse(&self,
+                 compiler: &mut Compiler,
+                 name: &str,
+                 source: &str)
+                 -> Result<LExpr<TcIdent>, (Option<LExpr<TcIdent>>, Errors<ParseError>)> {
+        let key = hash(name, source);
+        let cached = self.entries
+            .lock()
+            .unwrap()
+            .get(&key)
+            .and_then(|entry| {
+                if entry.name == name && entry.source == source {
+                    Some(entry.expr.clone())
+                } else {
+                    None
+                }
+            });
+        if let Some(expr) = cached {
+            self.hits.fetch_add(1, Ordering::SeqCst);
+            self.log(name, "hit");
+            return Ok(expr);
+        }
+        self.misses.fetch_add(1, Ordering::SeqCst);
+        self.log(name, "miss");
+        let result = compiler.parse_partial_expr(name, source);
+        if let Ok(ref expr) = result {
+            self.entries.lock().unwrap().insert(key,
+                                                Entry {
+                                                    name: name.into(),
+                                                    source: source.into(),
+                                                    expr: expr.clone(),
+                                                });
+        }
+        result
+    }
+
+    /// Removes the cached expressions of the module `name`
+    pub fn remove(&self, name: &str) {
+        self.entries.lock().unwrap().retain(|_, entry| entry.name != name);
+    }
+
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+
+    /// Returns the number of cached expressions and the approximate number of bytes they use
+    pub fn usage(&self) -> (usize, usize) {
+        let entries = self.entries.lock().unwrap();
+        let bytes = entries.values()
+            .map(|entry| entry.source.len() + memory::expr_size(&entry.expr))
+            .sum();
+        (entries.len(), bytes)
+    }
+
+    fn log(&self, name: &str, outcome: &str) {
+        let hits = self.hits.load(Ordering::SeqCst);
+        let misses = self.misses.load(Ordering::SeqCst);
+        info!("Parse cache {} for `{}` ({} hits, {} misses, {:.1}% hit rate)",
+              outcome,
+              name,
+              hits,
+              misses,
+              100.0 * hits as f64 / (hits + misses) as f64);
+    }
+}
@@ -0,0 +1,404 @@
+//! The refactoring between nested applications, `h (g (f x))`, and a pipeline of the same
+//! functions, `x |> f |> g |> h`. The parser only records where expressions start, and often the
+//! whitespace before them, so the text of each part is found by scanning the source from there
+//! and keeps the parentheses which are written around it, such as those of an operator section
+//! like `(+) 1`.
+use gluon::base::ast::{self, Expr, LExpr};
+use gluon::base::types::TcIdent;
+
+use language_server::{Position, Range};
+use operators;
+use query;
+use references::skip_quoted;
+
+const PIPE: &'static str = "|>";
+
+pub fn is_identifier_char(c: char) -> bool {
+    c == '_' || c.is_alphanumeric()
+}
+
+/// Returns the length of the identifier which `source` starts with
+pub fn identifier_len(source: &str) -> usize {
+    source.find(|c: char| !is_identifier_char(c)).unwrap_or(source.len())
+}
+
+/// Returns the byte offset of `location` in `source`
+pub fn offset(source: &str, location: &ast::Location) -> Option<usize> {
+    if location.row < 1 || location.column < 1 {
+        return None;
+    }
+    let mut line_start = 0;
+    for _ in 1..location.row {
+        line_start += match source[line_start..].find('\n') {
+            Some(end) => end + 1,
+            None => return None,
+        };
+    }
+    let line = &source[line_start..];
+    line.char_indices()
+        .map(|(i, _)| i)
+        .chain(Some(line.len()))
+        .nth(location.column as usize - 1)
+        .map(|i| line_start + i)
+}
+
+pub fn position(source: &str, offset: usize) -> Position {
+    let before = &source[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    Position {
+        line: before.matches('\n').count() as u64,
+        character: before[line_start..].chars().count() as u64,
+    }
+}
+
+/// Returns the offset of the first character at or after `i` which is not whitespace or in a
+/// comment
+pub fn skip_trivia(source: &str, mut i: usize) -> usize {
+    loop {
+        let rest = &source[i..];
+        let trimmed = rest.trim_left();
+        i += rest.len() - trimmed.len();
+        if trimmed.starts_with("//") {
+            i += trimmed.find('\n').unwrap_or(trimmed.len());
+        } else if trimmed.starts_with("/*") {
+            i += trimmed.find("*/").map_or(trimmed.len(), |end| end + 2);
+        } else {
+            return i;
+        }
+    }
+}
+
+/// Scans `source` from `start`, returning the first offset after `end` where the brackets opened
+/// since `start` are closed again, or `None` if one of them is left unclosed. Brackets in
+/// comments and literals are skipped, as are closing brackets of brackets opened before `start`.
+pub fn close_brackets(source: &str, start: usize, end: usize) -> Option<usize> {
+    let bytes = source.as_bytes();
+    let mut depth = 0;
+    let mut i = start;
+    while i < end || depth > 0 {
+        if i >= bytes.len() {
+            return None;
+        }
+        match bytes[i] {
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' if depth > 0 => depth -= 1,
+            b'"' | b'\'' => {
+                i = skip_quoted(bytes, i);
+                continue;
+            }
+            b'/' if source[i..].starts_with("//") || source[i..].starts_with("/*") => {
+                i = skip_trivia(source, i);
+                continue;
+            }
+            _ => (),
+        }
+        i += 1;
+    }
+    Some(i)
+}
+
+/// Returns the number of closing brackets in `text` which are not opened in it
+fn unopened_brackets(text: &str) -> usize {
+    let bytes = text.as_bytes();
+    let mut depth = 0;
+    let mut unopened = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' if depth == 0 => unopened += 1,
+            b')' | b']' | b'}' => depth -= 1,
+            b'"' | b'\'' => {
+                i = skip_quoted(bytes, i);
+                continue;
+            }
+            _ => (),
+        }
+        i += 1;
+    }
+    unopened
+}
+
+fn start(source: &str, expr: &LExpr<TcIdent>) -> Option<usize> {
+    offset(source, &expr.location).map(|i| skip_trivia(source, i))
+}
+
+/// Returns the offset of the end of the last token of `expr`
+fn token_end(source: &str, expr: &LExpr<TcIdent>, start: usize) -> Option<usize> {
+    let last = |exprs: &[LExpr<TcIdent>]| exprs.last().and_then(|expr| end(source, expr));
+    match expr.value {
+        Expr::Identifier(_) |
+        Expr::Literal(_) => {
+            let bytes = source.as_bytes();
+            let is_literal = match expr.value {
+                Expr::Literal(_) => true,
+                _ => false,
+            };
+            match bytes.get(start) {
+                // An operator, such as `(+)`
+                Some(&b'(') => Some(start + 1),
+                Some(&b'"') | Some(&b'\'') => Some(skip_quoted(bytes, start)),
+                _ => {
+                    // The `.` of a float literal
+                    let rest = &source[start..];
+                    let len = rest.find(|c: char| {
+                            !is_identifier_char(c) && !(is_literal && c == '.')
+                        })
+                        .unwrap_or(rest.len());
+                    Some(start + len)
+                }
+            }
+        }
+        Expr::Call(ref func, ref args) => {
+            if args.is_empty() { end(source, func) } else { last(args) }
+        }
+        Expr::IfElse(_, ref if_true, ref if_false) => {
+            end(source, if_false.as_ref().unwrap_or(if_true))
+        }
+        Expr::Match(_, ref alts) => alts.last().and_then(|alt| end(source, &alt.expression)),
+        Expr::BinOp(_, _, ref rhs) => end(source, rhs),
+        Expr::Let(_, ref body) |
+        Expr::Type(_, ref body) => end(source, body),
+        Expr::FieldAccess(ref record, _) => {
+            end(source, record).map(|record_end| {
+                let close = close_brackets(source, start, record_end).unwrap_or(record_end);
+                let dot = skip_trivia(source, close);
+                let field = skip_trivia(source, dot + 1);
+                let rest = &source[field..];
+                field + rest.find(|c: char| !is_identifier_char(c)).unwrap_or(rest.len())
+            })
+        }
+        // The brackets around the elements are closed by `end`
+        Expr::Array(_) |
+        Expr::Record { .. } => Some(start + 1),
+        Expr::Lambda(ref lambda) => end(source, &lambda.body),
+        Expr::Tuple(ref exprs) |
+        Expr::Block(ref exprs) => last(exprs),
+    }
+}
+
+/// Returns the offset after the end of `expr`, including the brackets which are opened in it
+fn end(source: &str, expr: &LExpr<TcIdent>) -> Option<usize> {
+    let start = match start(source, expr) {
+        Some(start) => start,
+        None => return None,
+    };
+    token_end(source, expr, start).and_then(|end| close_brackets(source, start, end))
+}
+
+/// Returns the start and end of the text of `expr`. The location of an expression may be inside
+/// the parentheses of its first subexpression, as in `(f x).y`, so the range is widened until its
+/// brackets are balanced.
+pub fn extent(source: &str, expr: &LExpr<TcIdent>) -> Option<(usize, usize)> {
+    let (mut start, end) = match (start(source, expr), end(source, expr)) {
+        (Some(start), Some(end)) => (start, end),
+        _ => return None,
+    };
+    for _ in 0..unopened_brackets(&source[start..end]) {
+        start = match source[..start].trim_right().rfind('(') {
+            Some(open) if source[open + 1..start].trim().is_empty() => open,
+            _ => return None,
+        };
+    }
+    Some((start, end))
+}
+
+/// Returns the extent of `expr` and the parentheses which are written around it
+pub fn parenthesized_extent(source: &str, expr: &LExpr<TcIdent>) -> Option<(usize, usize)> {
+    extent(source, expr).map(|(mut start, mut end)| {
+        loop {
+            let before = source[..start].trim_right();
+            let after = skip_trivia(source, end);
+            if before.ends_with('(') && source[after..].starts_with(')') {
+                start = before.len() - 1;
+                end = after + 1;
+            } else {
+                return (start, end);
+            }
+        }
+    })
+}
+
+/// Returns true if `expr` can be an argument of a call or the start of a pipeline without
+/// parentheses
+pub fn is_atomic(expr: &LExpr<TcIdent>) -> bool {
+    match expr.value {
+        Expr::Identifier(_) |
+        Expr::Literal(_) |
+        Expr::FieldAccess(..) |
+        Expr::Array(_) |
+        Expr::Record { .. } |
+        Expr::Tuple(_) => true,
+        _ => false,
+    }
+}
+
+/// Returns the text of `expr` with parentheses around it unless it is atomic
+fn operand(source: &str, expr: &LExpr<TcIdent>) -> Option<String> {
+    if is_atomic(expr) {
+        return extent(source, expr).map(|(start, end)| source[start..end].into());
+    }
+    parenthesized_extent(source, expr).map(|(start, end)| {
+        let text = &source[start..end];
+        if text.starts_with('(') && extent(source, expr).map(|e| e.0) != Some(start) {
+            text.into()
+        } else {
+            format!("({})", text)
+        }
+    })
+}
+
+fn is_pipe(expr: &LExpr<TcIdent>) -> bool {
+    match expr.value {
+        Expr::BinOp(_, ref op, _) => op.name.declared_name() == PIPE,
+        _ => false,
+    }
+}
+
+/// Returns true if `expr` is a call whose last argument is also a call
+fn is_nested_call(expr: &LExpr<TcIdent>) -> bool {
+    match expr.value {
+        Expr::Call(_, ref args) => {
+            args.last().map_or(false, |arg| match arg.value {
+                Expr::Call(..) => true,
+                _ => false,
+            })
+        }
+        _ => false,
+    }
+}
+
+/// Returns the outermost expression in `expr` which contains `offset` and for which `is_candidate`
+/// holds, and the binary operator it is an operand of
+fn outermost<'e, F>(source: &str,
+                    expr: &'e LExpr<TcIdent>,
+                    offset: usize,
+                    operator: Option<&'e str>,
+                    is_candidate: &F)
+                    -> Option<(&'e LExpr<TcIdent>, Option<&'e str>)>
+    where F: Fn(&LExpr<TcIdent>) -> bool
+{
+    let contains = extent(source, expr)
+        .map_or(false, |(start, end)| start <= offset && offset <= end);
+    if contains && is_candidate(expr) {
+        return Some((expr, operator));
+    }
+    let operator = match expr.value {
+        Expr::BinOp(_, ref op, _) => Some(op.name.declared_name()),
+        _ => None,
+    };
+    query::children(expr)
+        .into_iter()
+        .filter_map(|child| outermost(source, child, offset, operator, is_candidate))
+        .next()
+}
+
+/// Returns the byte offset of `position` in `source`
+pub fn offset_of(source: &str, position: &Position) -> usize {
+    let mut line_start = 0;
+    for _ in 0..position.line {
+        line_start += source[line_start..].find('\n').map_or(source.len() - line_start, |i| i + 1);
+    }
+    let line = &source[line_start..];
+    line_start +
+    line.char_indices().nth(position.character as usize).map_or(line.len(), |(i, _)| i)
+}
+
+fn edit(source: &str, expr: &LExpr<TcIdent>, new_text: String) -> Option<(Range, String)> {
+    extent(source, expr).map(|(start, end)| {
+        let range = Range {
+            start: position(source, start),
+            end: position(source, end),
+        };
+        (range, new_text)
+    })
+}
+
+/// Returns the text of the function, and the arguments before the last one, of the call `expr`
+fn stage<'s>(source: &'s str, expr: &LExpr<TcIdent>) -> Option<&'s str> {
+    let (func, args) = match expr.value {
+        Expr::Call(ref func, ref args) => (func, args),
+        _ => return None,
+    };
+    let last = if args.len() < 2 { &**func } else { &args[args.len() - 2] };
+    match (parenthesized_extent(source, func), parenthesized_extent(source, last)) {
+        (Some((start, _)), Some((_, end))) => Some(&source[start..end]),
+        _ => None,
+    }
+}
+
+/// Returns the range and the text of the pipeline which replaces the outermost nested
+/// application around `position`
+pub fn to_pipeline(source: &str,
+                   expr: &LExpr<TcIdent>,
+                   position: &Position)
+                   -> Option<(Range, String)> {
+    let offset = offset_of(source, position);
+    let (call, operator) = match outermost(source, expr, offset, None, &is_nested_call) {
+        Some(found) => found,
+        None => return None,
+    };
+    let mut stages = Vec::new();
+    let mut current = call;
+    while let Expr::Call(_, ref args) = current.value {
+        match stage(source, current) {
+            Some(stage) => stages.push(stage),
+            None => return None,
+        }
+        current = args.last().unwrap_or(current);
+    }
+    let mut pipeline = match operand(source, current) {
+        Some(operand) => operand,
+        None => return None,
+    };
+    for stage in stages.iter().rev() {
+        pipeline.push_str(&format!(" {} {}", PIPE, stage));
+    }
+    // An operator which binds as tightly as `|>` would take the start of the pipeline
+    if operator.map_or(false, |op| operators::precedence(op) >= operators::precedence(PIPE)) {
+        pipeline = format!("({})", pipeline);
+    }
+    edit(source, call, pipeline)
+}
+
+/// Returns the range and the text of the nested application which replaces the outermost
+/// pipeline around `position`
+pub fn from_pipeline(source: &str,
+                     expr: &LExpr<TcIdent>,
+                     position: &Position)
+                     -> Option<(Range, String)> {
+    let offset = offset_of(source, position);
+    let pipeline = match outermost(source, expr, offset, None, &is_pipe) {
+        Some((pipeline, _)) => pipeline,
+        None => return None,
+    };
+    let mut stages = Vec::new();
+    let mut current = pipeline;
+    while is_pipe(current) {
+        if let Expr::BinOp(ref lhs, _, ref rhs) = current.value {
+            stages.push(&**rhs);
+            current = lhs;
+        }
+    }
+    let mut call = match operand(source, current) {
+        Some(operand) => operand,
+        None => return None,
+    };
+    for (i, stage) in stages.iter().rev().enumerate() {
+        // A call is applied to one more argument without parentheses around it
+        let function = match stage.value {
+            Expr::Call(..) => extent(source, stage).map(|(start, end)| source[start..end].into()),
+            _ => operand(source, stage),
+        };
+        let function = match function {
+            Some(function) => function,
+            None => return None,
+        };
+        call = if i == 0 {
+            format!("{} {}", function, call)
+        } else {
+            format!("{} ({})", function, call)
+        };
+    }
+    edit(source, pipeline, call)
+}
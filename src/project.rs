@@ -0,0 +1,598 @@
+//! The project manifest, `glu-project.toml`, at the root of the workspace. It declares where the
+//! modules of the project are, where else modules are imported from, the namespace the modules
+//! of the project are named in and how diagnostics are reported:
+//!
+//! ```toml
+//! source-dirs = ["src"]
+//! import-paths = ["vendor"]
+//! namespace = "app"
+//! entry-modules = ["app.main"]
+//!
+//! [lints]
+//! type = "warn"
+//! ```
+//!
+//! The keys of `lints` are the codes of the lints in `LINTS` and their values are `allow`, `hint`,
+//! `info`, `warn` or `deny`, which set the severity the diagnostics of the lint are published
+//! with. The `gluon.lints` setting, of the client or of a workspace folder, overrides the lint
+//! levels, and a lint which is configured nowhere has its default level.
+//!
+//! The manifest is read with a parser of the part of TOML which it needs: tables of keys with
+//! strings and arrays of strings as their values, and comments.
+use std::collections::HashMap;
+use std::error::Error as StdError;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use gluon::filename_to_module;
+
+use language_server::{Diagnostic, DiagnosticSeverity};
+
+pub const MANIFEST: &'static str = "glu-project.toml";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LintLevel {
+    /// The diagnostics are not reported
+    Allow,
+    /// The diagnostics are reported as hints
+    Hint,
+    /// The diagnostics are reported as information
+    Info,
+    /// The diagnostics are reported as warnings
+    Warn,
+    /// The diagnostics are reported as errors
+    Deny,
+}
+
+impl LintLevel {
+    pub fn parse(level: &str) -> Option<LintLevel> {
+        match level {
+            "allow" => Some(LintLevel::Allow),
+            "hint" => Some(LintLevel::Hint),
+            "info" => Some(LintLevel::Info),
+            "warn" => Some(LintLevel::Warn),
+            "deny" => Some(LintLevel::Deny),
+            _ => None,
+        }
+    }
+}
+
+/// A kind of diagnostic whose level can be configured
+pub struct Lint {
+    /// The code of the diagnostics of the lint
+    pub code: &'static str,
+    /// The level of the lint unless it is configured
+    pub default: LintLevel,
+    pub description: &'static str,
+}
+
+/// The lints which the server reports
+pub const LINTS: &'static [Lint] = &[Lint {
+                                         code: "syntax",
+                                         default: LintLevel::Deny,
+                                         description: "The module can't be parsed",
+                                     },
+                                     Lint {
+                                         code: "type",
+                                         default: LintLevel::Deny,
+                                         description: "The module does not typecheck",
+                                     },
+                                     Lint {
+                                         code: "macro",
+                                         default: LintLevel::Deny,
+                                         description: "A macro, such as `import`, failed",
+                                     },
+                                     Lint {
+                                         code: "unused-import",
+                                         default: LintLevel::Warn,
+                                         description: "An import is never used",
+                                     },
+                                     Lint {
+                                         code: "unused-binding",
+                                         default: LintLevel::Warn,
+                                         description: "A variable bound by `let` is never used",
+                                     },
+                                     Lint {
+                                         code: "unused-parameter",
+                                         default: LintLevel::Warn,
+                                         description: "A function never uses a parameter",
+                                     },
+                                     Lint {
+                                         code: "shadowing",
+                                         default: LintLevel::Allow,
+                                         description: "A `let` binds a name which is in scope",
+                                     },
+                                     Lint {
+                                         code: "unreachable-arm",
+                                         default: LintLevel::Warn,
+                                         description: "A match arm is never reached",
+                                     },
+                                     Lint {
+                                         code: "redundant-parens",
+                                         default: LintLevel::Warn,
+                                         description: "Parentheses which change nothing",
+                                     },
+                                     Lint {
+                                         code: "redundant-lambda",
+                                         default: LintLevel::Warn,
+                                         description: "A lambda which only calls a function",
+                                     },
+                                     Lint {
+                                         code: "dead-code",
+                                         default: LintLevel::Warn,
+                                         description: "An export which no other module uses",
+                                     },
+                                     Lint {
+                                         code: "todo",
+                                         default: LintLevel::Allow,
+                                         description: "A comment with a marker such as `TODO`",
+                                     }];
+
+/// Returns the lint whose diagnostics have the code `code`
+pub fn lint(code: &str) -> Option<&'static Lint> {
+    LINTS.iter().find(|lint| lint.code == code)
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Project {
+    /// The directories which contain the modules of the project. The root of the workspace is the
+    /// only source directory if none are declared.
+    pub source_dirs: Vec<PathBuf>,
+    /// The directories which modules are imported from besides the source directories
+    pub import_paths: Vec<PathBuf>,
+    /// The prefix of the names of the modules in the source directories
+    pub namespace: Option<String>,
+    /// The modules whose exports are used from outside of the workspace. The modules which no
+    /// module of the workspace imports are the entry modules if none are declared.
+    pub entry_modules: Vec<String>,
+    /// The level of each diagnostic code which is configured
+    pub lints: HashMap<String, LintLevel>,
+}
+
+impl Project {
+    /// Reads the manifest in `root`. A workspace without a manifest is the default project.
+    pub fn load(root: &Path) -> Result<Project, Box<StdError>> {
+        let mut text = String::new();
+        match File::open(root.join(MANIFEST)) {
+            Ok(mut file) => {
+                try!(file.read_to_string(&mut text));
+            }
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => {
+                return Ok(Project::default())
+            }
+            Err(err) => return Err(err.into()),
+        }
+        Project::parse(root, &text)
+    }
+
+    /// Parses the manifest `text`. Relative directories are relative to `root`.
+    pub fn parse(root: &Path, text: &str) -> Result<Project, Box<StdError>> {
+        let table = try!(Parser::new(text).parse());
+        let mut project = Project::default();
+        for &(ref key, ref value) in &table {
+            match &key[..] {
+                "source-dirs" => project.source_dirs = try!(dirs(root, key, value)),
+                "import-paths" => project.import_paths = try!(dirs(root, key, value)),
+                "namespace" => project.namespace = Some(try!(string(key, value)).into()),
+                "entry-modules" => {
+                    let values = try!(value.as_slice()
+                        .ok_or_else(|| expected(key, "an array of module names")));
+                    for value in values {
+                        project.entry_modules.push(try!(string(key, value)).into());
+                    }
+                }
+                "lints" => {
+                    let lints = try!(value.as_table().ok_or_else(|| expected(key, "a table")));
+                    for &(ref code, ref level) in lints {
+                        project.lints.insert(code.clone(), try!(lint_level(code, level)));
+                    }
+                }
+                _ => return Err(format!("Unknown key `{}`", key).into()),
+            }
+        }
+        Ok(project)
+    }
+
+    /// The directories which contain the modules of the project in the workspace at `root`
+    pub fn source_dirs(&self, root: &Path) -> Vec<PathBuf> {
+        if self.source_dirs.is_empty() {
+            vec![root.into()]
+        } else {
+            self.source_dirs.clone()
+        }
+    }
+
+    /// Returns the name of the module at `path` in the source directory `dir`
+    pub fn module_name(&self, dir: &Path, path: &Path) -> String {
+        let relative = path.strip_prefix(dir).unwrap_or(path);
+        let name = filename_to_module(&relative.to_string_lossy());
+        match self.namespace {
+            Some(ref namespace) => format!("{}.{}", namespace, name),
+            None => name,
+        }
+    }
+}
+
+/// Removes the diagnostics whose code is allowed by `lints`, or by default if `lints` does not
+/// configure it, and sets the severity of the other diagnostics whose code has a level
+pub fn apply_lints(lints: &HashMap<String, LintLevel>,
+                   diagnostics: Vec<Diagnostic>)
+                   -> Vec<Diagnostic> {
+    diagnostics.into_iter()
+        .filter_map(|mut diagnostic| {
+            let level = diagnostic.code.as_ref().and_then(|code| {
+                lints.get(code).cloned().or_else(|| lint(code).map(|lint| lint.default))
+            });
+            let severity = match level {
+                Some(LintLevel::Allow) => return None,
+                Some(LintLevel::Hint) => Some(DiagnosticSeverity::Hint),
+                Some(LintLevel::Info) => Some(DiagnosticSeverity::Information),
+                Some(LintLevel::Warn) => Some(DiagnosticSeverity::Warning),
+                Some(LintLevel::Deny) => Some(DiagnosticSeverity::Error),
+                None => diagnostic.severity,
+            };
+            diagnostic.severity = severity;
+            Some(diagnostic)
+        })
+        .collect()
+}
+
+/// A value of the manifest
+#[derive(Debug, PartialEq)]
+enum Value {
+    String(String),
+    Array(Vec<Value>),
+    Table(Vec<(String, Value)>),
+}
+
+impl Value {
+    fn as_str(&self) -> Option<&str> {
+        match *self {
+            Value::String(ref string) => Some(string),
+            _ => None,
+        }
+    }
+
+    fn as_slice(&self) -> Option<&[Value]> {
+        match *self {
+            Value::Array(ref values) => Some(values),
+            _ => None,
+        }
+    }
+
+    fn as_table(&self) -> Option<&[(String, Value)]> {
+        match *self {
+            Value::Table(ref entries) => Some(entries),
+            _ => None,
+        }
+    }
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    offset: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(text: &'a str) -> Parser<'a> {
+        Parser {
+            text: text,
+            offset: 0,
+        }
+    }
+
+    /// Returns an error at the current offset, prefixed with its line and column
+    fn error(&self, desc: &str) -> Box<StdError> {
+        let before = &self.text[..self.offset];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        format!("{}:{}: {}",
+                before.matches('\n').count() + 1,
+                before[line_start..].chars().count() + 1,
+                desc)
+            .into()
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.offset..].chars().next()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.offset += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), Box<StdError>> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("Expected `{}`", c)))
+        }
+    }
+
+    /// Skips spaces and tabs, and newlines and comments as well if `lines` is true
+    fn skip_whitespace(&mut self, lines: bool) {
+        while let Some(c) = self.peek() {
+            match c {
+                ' ' | '\t' | '\r' => self.offset += 1,
+                '\n' if lines => self.offset += 1,
+                '#' if lines => {
+                    self.offset = self.text[self.offset..]
+                        .find('\n')
+                        .map_or(self.text.len(), |i| self.offset + i)
+                }
+                _ => return,
+            }
+        }
+    }
+
+    /// Skips the rest of a line which has a key and its value or a table header on it
+    fn end_of_line(&mut self) -> Result<(), Box<StdError>> {
+        self.skip_whitespace(false);
+        match self.peek() {
+            None | Some('\n') | Some('#') => Ok(()),
+            Some(_) => Err(self.error("Expected the end of the line")),
+        }
+    }
+
+    fn key(&mut self) -> Result<String, Box<StdError>> {
+        if self.peek() == Some('"') {
+            return self.string();
+        }
+        let rest = &self.text[self.offset..];
+        let end = rest.find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-'))
+            .unwrap_or(rest.len());
+        if end == 0 {
+            return Err(self.error("Expected a key"));
+        }
+        self.offset += end;
+        Ok(rest[..end].into())
+    }
+
+    fn string(&mut self) -> Result<String, Box<StdError>> {
+        try!(self.expect('"'));
+        let mut string = String::new();
+        loop {
+            let c = match self.peek() {
+                None | Some('\n') => return Err(self.error("Unterminated string")),
+                Some(c) => c,
+            };
+            self.offset += c.len_utf8();
+            match c {
+                '"' => return Ok(string),
+                '\\' => {
+                    let escaped = match self.peek() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        _ => return Err(self.error("Unknown escape sequence")),
+                    };
+                    self.offset += 1;
+                    string.push(escaped);
+                }
+                c => string.push(c),
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, Box<StdError>> {
+        match self.peek() {
+            Some('"') => self.string().map(Value::String),
+            Some('[') => {
+                self.offset += 1;
+                let mut values = Vec::new();
+                loop {
+                    self.skip_whitespace(true);
+                    if self.eat(']') {
+                        return Ok(Value::Array(values));
+                    }
+                    values.push(try!(self.value()));
+                    self.skip_whitespace(true);
+                    if !self.eat(',') {
+                        self.skip_whitespace(true);
+                        try!(self.expect(']'));
+                        return Ok(Value::Array(values));
+                    }
+                }
+            }
+            _ => Err(self.error("Expected a string or an array")),
+        }
+    }
+
+    /// Parses the manifest into the table of its top-level keys. The tables which are declared
+    /// by headers are values of the top-level table.
+    fn parse(&mut self) -> Result<Vec<(String, Value)>, Box<StdError>> {
+        let mut root = Vec::new();
+        // The table whose header came last, with its keys
+        let mut table: Option<(String, Vec<(String, Value)>)> = None;
+        loop {
+            self.skip_whitespace(true);
+            if self.peek().is_none() {
+                break;
+            }
+            let start = self.offset;
+            if self.eat('[') {
+                self.skip_whitespace(false);
+                let name = try!(self.key());
+                self.skip_whitespace(false);
+                try!(self.expect(']'));
+                try!(self.end_of_line());
+                if let Some((name, entries)) = table.take() {
+                    root.push((name, Value::Table(entries)));
+                }
+                if root.iter().any(|&(ref key, _)| *key == name) {
+                    self.offset = start;
+                    return Err(self.error(&format!("Duplicate key `{}`", name)));
+                }
+                table = Some((name, Vec::new()));
+                continue;
+            }
+            let key = try!(self.key());
+            self.skip_whitespace(false);
+            try!(self.expect('='));
+            self.skip_whitespace(false);
+            let value = try!(self.value());
+            try!(self.end_of_line());
+            let entries = match table {
+                Some((_, ref mut entries)) => entries,
+                None => &mut root,
+            };
+            if entries.iter().any(|&(ref existing, _)| *existing == key) {
+                self.offset = start;
+                return Err(self.error(&format!("Duplicate key `{}`", key)));
+            }
+            entries.push((key, value));
+        }
+        if let Some((name, entries)) = table {
+            root.push((name, Value::Table(entries)));
+        }
+        Ok(root)
+    }
+}
+
+fn expected(key: &str, what: &str) -> Box<StdError> {
+    format!("Expected `{}` to be {}", key, what).into()
+}
+
+fn string<'a>(key: &str, value: &'a Value) -> Result<&'a str, Box<StdError>> {
+    value.as_str().ok_or_else(|| expected(key, "a string"))
+}
+
+fn dirs(root: &Path, key: &str, value: &Value) -> Result<Vec<PathBuf>, Box<StdError>> {
+    let values = try!(value.as_slice().ok_or_else(|| expected(key, "an array of paths")));
+    let mut dirs = Vec::new();
+    for value in values {
+        dirs.push(root.join(try!(string(key, value))));
+    }
+    Ok(dirs)
+}
+
+fn lint_level(code: &str, value: &Value) -> Result<LintLevel, Box<StdError>> {
+    if lint(code).is_none() {
+        return Err(format!("Unknown lint `{}`", code).into());
+    }
+    let level = try!(string(code, value));
+    LintLevel::parse(level).ok_or_else(|| {
+        format!("Unknown lint level `{}` for `{}`, expected `allow`, `hint`, `info`, `warn` or \
+                 `deny`",
+                level,
+                code)
+            .into()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashMap;
+    use std::path::{Path, PathBuf};
+
+    use language_server::{Diagnostic, DiagnosticSeverity};
+
+    #[test]
+    fn parse_manifest() {
+        let project = Project::parse(Path::new("/project"),
+                                     r#"
+source-dirs = ["src", "examples"]
+import-paths = ["/usr/share/gluon"]
+namespace = "app"
+entry-modules = ["app.main"]
+
+[lints]
+type = "warn"
+syntax = "deny"
+shadowing = "hint"
+"#)
+            .unwrap();
+        assert_eq!(project.source_dirs,
+                   vec![PathBuf::from("/project/src"), PathBuf::from("/project/examples")]);
+        assert_eq!(project.import_paths, vec![PathBuf::from("/usr/share/gluon")]);
+        assert_eq!(project.entry_modules, vec![String::from("app.main")]);
+        assert_eq!(project.lints.get("type"), Some(&LintLevel::Warn));
+        assert_eq!(project.lints.get("syntax"), Some(&LintLevel::Deny));
+        assert_eq!(project.lints.get("shadowing"), Some(&LintLevel::Hint));
+        assert_eq!(project.module_name(Path::new("/project/src"),
+                                       Path::new("/project/src/data/map.glu")),
+                   "app.data.map");
+    }
+
+    #[test]
+    fn default_project() {
+        let project = Project::parse(Path::new("/project"), "").unwrap();
+        assert_eq!(project, Project::default());
+        assert_eq!(project.source_dirs(Path::new("/project")),
+                   vec![PathBuf::from("/project")]);
+        assert_eq!(project.module_name(Path::new("/project"), Path::new("/project/main.glu")),
+                   "main");
+    }
+
+    #[test]
+    fn default_lint_levels() {
+        let diagnostic = |code: &str| {
+            Diagnostic {
+                code: Some(code.into()),
+                severity: Some(DiagnosticSeverity::Information),
+                ..Diagnostic::default()
+            }
+        };
+        let mut lints = HashMap::new();
+        lints.insert("unused-binding".to_string(), LintLevel::Deny);
+        lints.insert("type".to_string(), LintLevel::Hint);
+        let diagnostics = apply_lints(&lints,
+                                      vec![diagnostic("unused-import"),
+                                           diagnostic("shadowing"),
+                                           diagnostic("unused-binding"),
+                                           diagnostic("type"),
+                                           diagnostic("unknown")]);
+        let severities: Vec<_> = diagnostics.iter()
+            .map(|diagnostic| diagnostic.severity.map(|severity| severity as u8))
+            .collect();
+        assert_eq!(severities,
+                   vec![Some(DiagnosticSeverity::Warning as u8),
+                        Some(DiagnosticSeverity::Error as u8),
+                        Some(DiagnosticSeverity::Hint as u8),
+                        Some(DiagnosticSeverity::Information as u8)]);
+    }
+
+    #[test]
+    fn invalid_manifest() {
+        let root = Path::new("/project");
+        assert!(Project::parse(root, "source-dirs = \"src\"").is_err());
+        assert!(Project::parse(root, "[lints]\ntype = \"ignore\"").is_err());
+        assert!(Project::parse(root, "[lints]\nunused = \"allow\"").is_err());
+        assert!(Project::parse(root, "sources = []").is_err());
+        assert!(Project::parse(root, "namespace = ").is_err());
+    }
+
+    #[test]
+    fn manifest_syntax() {
+        let project = Project::parse(Path::new("/project"),
+                                     "# The project\n\"namespace\" = \"a\\\\b\" # comment\n\
+                                      source-dirs = [\n    \"src\", # main\n    \"tests\",\n]\n\
+                                      [ lints ]\n")
+            .unwrap();
+        assert_eq!(project.namespace, Some("a\\b".into()));
+        assert_eq!(project.source_dirs,
+                   vec![PathBuf::from("/project/src"), PathBuf::from("/project/tests")]);
+        assert!(project.lints.is_empty());
+    }
+
+    #[test]
+    fn manifest_syntax_errors() {
+        let error = |text| Project::parse(Path::new("/project"), text).unwrap_err().to_string();
+        assert_eq!(error("namespace = \"app\"\nsource-dirs = [\"src\" \"tests\"]"),
+                   "2:22: Expected `]`");
+        assert_eq!(error("namespace = \"app\" extra"), "1:19: Expected the end of the line");
+        assert_eq!(error("namespace = 1"), "1:13: Expected a string or an array");
+        assert_eq!(error("namespace = \"app"), "1:17: Unterminated string");
+        assert_eq!(error("[lints]\ntype = \"warn\"\n[lints]"), "3:1: Duplicate key `lints`");
+        assert_eq!(error("namespace = \"a\"\nnamespace = \"b\""),
+                   "2:1: Duplicate key `namespace`");
+    }
+}
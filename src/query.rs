@@ -0,0 +1,149 @@
+//! Functions for finding the AST nodes which correspond to positions and ranges in a document
+use gluon::base::ast::{self, Expr, LExpr};
+use gluon::base::types::TcIdent;
+
+use language_server::{Position, Range};
+
+/// Returns true if `l` comes strictly before `r`
+pub fn before(l: &Position, r: &Position) -> bool {
+    (l.line, l.character) < (r.line, r.character)
+}
+
+/// Returns true if `inner` lies completely within `outer`
+pub fn contains(outer: &Range, inner: &Range) -> bool {
+    !before(&inner.start, &outer.start) && !before(&outer.end, &inner.end)
+}
+
+pub fn expr_range(expr: &LExpr<TcIdent>) -> Range {
+    ::span_to_range(&expr.span(&ast::EmptyEnv::new()))
+}
+
+/// Returns the direct subexpressions of `expr` in source order
+pub fn children(expr: &LExpr<TcIdent>) -> Vec<&LExpr<TcIdent>> {
+    match expr.value {
+        Expr::Identifier(_) |
+        Expr::Literal(_) => vec![],
+        Expr::Call(ref func, ref args) => {
+            let mut children = vec![&**func];
+            children.extend(args);
+            children
+        }
+        Expr::IfElse(ref pred, ref if_true, ref if_false) => {
+            let mut children = vec![&**pred, &**if_true];
+            children.extend(if_false.as_ref().map(|e| &**e));
+            children
+        }
+        Expr::Match(ref scrutinee, ref alts) => {
+            let mut children = vec![&**scrutinee];
+            children.extend(alts.iter().map(|alt| &alt.expression));
+            children
+        }
+        Expr::BinOp(ref lhs, _, ref rhs) => vec![&**lhs, &**rhs],
+        Expr::Let(ref bindings, ref body) => {
+            let mut children: Vec<_> = bindings.iter().map(|bind| &bind.expression).collect();
+            children.push(&**body);
+            children
+        }
+        Expr::FieldAccess(ref record, _) => vec![&**record],
+        Expr::Array(ref array) => array.expressions.iter().collect(),
+        Expr::Record { ref exprs, .. } => exprs.iter().filter_map(|t| t.1.as_ref()).collect(),
+        Expr::Lambda(ref lambda) => vec![&*lambda.body],
+        Expr::Tuple(ref exprs) |
+        Expr::Block(ref exprs) => exprs.iter().collect(),
+        Expr::Type(_, ref body) => vec![&**body],
+    }
+}
+
+fn references_prelude(expr: &LExpr<TcIdent>) -> bool {
+    match expr.value {
+        Expr::Identifier(ref id) => id.name.declared_name() == "__implicit_prelude",
+        Expr::FieldAccess(ref record, _) => references_prelude(record),
+        Expr::Call(ref func, ref args) => {
+            references_prelude(func) || args.iter().any(references_prelude)
+        }
+        _ => false,
+    }
+}
+
+/// Skips the `let` bindings which the compiler inserts to bring the implicit prelude into scope.
+/// These bindings carry locations from the prelude source and would otherwise be matched against
+/// positions in the user's document.
+pub fn strip_implicit_prelude(mut expr: &LExpr<TcIdent>) -> &LExpr<TcIdent> {
+    loop {
+        match expr.value {
+            Expr::Let(ref bindings, ref body) if bindings.iter().all(|bind| {
+                let binds_prelude = match bind.name.value {
+                    ast::Pattern::Identifier(ref id) => {
+                        id.name.declared_name() == "__implicit_prelude"
+                    }
+                    _ => false,
+                };
+                binds_prelude || references_prelude(&bind.expression)
+            }) => expr = &**body,
+            _ => return expr,
+        }
+    }
+}
+
+/// Returns the innermost expression which contains all of `range`
+pub fn enclosing_expr<'e>(expr: &'e LExpr<TcIdent>,
+                          range: &Range)
+                          -> Option<&'e LExpr<TcIdent>> {
+    if !contains(&expr_range(expr), range) {
+        return None;
+    }
+    let inner = children(expr)
+        .into_iter()
+        .filter_map(|child| enclosing_expr(child, range))
+        .next();
+    Some(inner.unwrap_or(expr))
+}
+
+/// Returns the `type` expression which starts at `position`
+pub fn type_expr_at<'e>(expr: &'e LExpr<TcIdent>,
+                        position: &Position)
+                        -> Option<&'e LExpr<TcIdent>> {
+    if let Expr::Type(..) = expr.value {
+        let start = expr_range(expr).start;
+        if (start.line, start.character) == (position.line, position.character) {
+            return Some(expr);
+        }
+    }
+    children(expr).into_iter().filter_map(|child| type_expr_at(child, position)).next()
+}
+
+/// Returns the innermost call which `position` gives an argument of and the index of that
+/// argument. `position` gives an argument if it is after the function and either in the call or
+/// after it on the line where the call ends, where the next argument would be typed.
+pub fn call_at<'e>(expr: &'e LExpr<TcIdent>,
+                   position: &Position)
+                   -> Option<(&'e LExpr<TcIdent>, usize)> {
+    let range = expr_range(expr);
+    let point = Range {
+        start: *position,
+        end: *position,
+    };
+    let inside = contains(&range, &point);
+    if !inside && !(before(&range.end, position) && range.end.line == position.line) {
+        return None;
+    }
+    let call = match expr.value {
+        Expr::Call(ref func, ref args) if before(&expr_range(func).end, position) => {
+            let argument = args.iter()
+                .take_while(|arg| before(&expr_range(arg).end, position))
+                .count();
+            Some((expr, argument))
+        }
+        _ => None,
+    };
+    if !inside {
+        return call;
+    }
+    let children = children(expr);
+    let containing = children.iter().cloned().find(|child| contains(&expr_range(child), &point));
+    let inner = match containing {
+        Some(child) => call_at(child, position),
+        None => children.into_iter().rev().filter_map(|child| call_at(child, position)).next(),
+    };
+    inner.or(call)
+}
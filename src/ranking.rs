@@ -0,0 +1,277 @@
+//! Ranking of completion candidates by the type which is expected at the cursor
+use std::cmp;
+
+use gluon::base::ast::{Expr, LExpr, LPattern, Pattern, Typed};
+use gluon::base::symbol::Symbol;
+use gluon::base::types::{Field, TcIdent, TcType, Type, TypeEnv, arg_iter};
+
+use eval;
+use language_server::{Position, Range};
+use query;
+
+/// The number of completion candidates which are returned when the scope contains more. The list
+/// is then marked as incomplete so that the client asks again as the identifier is typed.
+pub const MAX_COMPLETION_ITEMS: usize = 200;
+
+/// The fuzzy scores which are distinguished when ranking candidates
+const MAX_SCORE: u32 = 999;
+
+fn contains_position(expr: &LExpr<TcIdent>, position: &Position) -> bool {
+    query::contains(&query::expr_range(expr),
+                    &Range {
+                        start: *position,
+                        end: *position,
+                    })
+}
+
+/// Returns the type which `typ` returns after `arguments` arguments have been applied
+fn applied_type(typ: &TcType, arguments: usize) -> TcType {
+    let mut iter = arg_iter(typ);
+    for _ in 0..arguments {
+        if iter.next().is_none() {
+            break;
+        }
+    }
+    iter.typ.clone()
+}
+
+/// Returns the type of the values which `pattern` matches
+fn pattern_type(pattern: &LPattern<TcIdent>) -> Option<TcType> {
+    match pattern.value {
+        Pattern::Identifier(ref id) => Some(id.typ.clone()),
+        Pattern::Constructor(ref id, _) => Some(id.typ.return_type().clone()),
+        Pattern::Record { .. } => None,
+    }
+}
+
+/// Returns the fields of the record type `typ`, or nothing if it is not a record
+fn record_fields(env: &TypeEnv, typ: &TcType) -> Vec<Field<Symbol, TcType>> {
+    match *eval::remove_aliases(env, typ) {
+        Type::Record { ref fields, .. } => fields.clone(),
+        _ => vec![],
+    }
+}
+
+/// Returns the children of `expr` and the type which is expected of each, given that `expected`
+/// is expected of `expr`
+fn children_expected<'e>(env: &TypeEnv,
+                         expr: &'e LExpr<TcIdent>,
+                         expected: Option<TcType>)
+                         -> Vec<(&'e LExpr<TcIdent>, Option<TcType>)> {
+    match expr.value {
+        Expr::Call(ref func, ref args) => {
+            let func_type = func.env_type_of(env);
+            let mut arg_types = arg_iter(&func_type);
+            let mut children = vec![(&**func, None)];
+            children.extend(args.iter().map(|arg| (arg, arg_types.next().cloned())));
+            children
+        }
+        Expr::Let(ref bindings, ref body) => {
+            let mut children: Vec<_> = bindings.iter()
+                .map(|bind| {
+                    let annotation = bind.typ
+                        .as_ref()
+                        .map(|typ| applied_type(typ, bind.arguments.len()));
+                    (&bind.expression, annotation)
+                })
+                .collect();
+            children.push((&**body, expected.clone()));
+            children
+        }
+        Expr::Match(ref scrutinee, ref alts) => {
+            let scrutinee_type = alts.iter().filter_map(|alt| pattern_type(&alt.pattern)).next();
+            let mut children = vec![(&**scrutinee, scrutinee_type)];
+            children.extend(alts.iter().map(|alt| (&alt.expression, expected.clone())));
+            children
+        }
+        Expr::IfElse(ref pred, ref if_true, ref if_false) => {
+            let mut children = vec![(&**pred, None), (&**if_true, expected.clone())];
+            children.extend(if_false.as_ref().map(|if_false| (&**if_false, expected.clone())));
+            children
+        }
+        Expr::Block(ref exprs) => {
+            let last = exprs.len().saturating_sub(1);
+            exprs.iter()
+                .enumerate()
+                .map(|(i, expr)| (expr, if i == last { expected.clone() } else { None }))
+                .collect()
+        }
+        Expr::Record { ref exprs, .. } => {
+            let fields = expected.map_or(vec![], |expected| record_fields(env, &expected));
+            exprs.iter()
+                .filter_map(|&(ref name, ref value)| {
+                    value.as_ref().map(|value| {
+                        let field = fields.iter()
+                            .find(|field| field.name.declared_name() == name.declared_name());
+                        (value, field.map(|field| field.typ.clone()))
+                    })
+                })
+                .collect()
+        }
+        Expr::Type(_, ref body) => vec![(&**body, expected.clone())],
+        _ => query::children(expr).into_iter().map(|child| (child, None)).collect(),
+    }
+}
+
+/// Returns the expressions which contain `position`, outermost first, and the type which is
+/// expected of each if it is known
+fn enclosing<'e>(env: &TypeEnv,
+                 expr: &'e LExpr<TcIdent>,
+                 position: &Position)
+                 -> Vec<(&'e LExpr<TcIdent>, Option<TcType>)> {
+    let mut enclosing = vec![(expr, None)];
+    loop {
+        let next = {
+            let &(expr, ref expected) = enclosing.last().unwrap();
+            children_expected(env, expr, expected.clone())
+                .into_iter()
+                .find(|&(child, _)| contains_position(child, position))
+        };
+        match next {
+            Some(next) => enclosing.push(next),
+            None => return enclosing,
+        }
+    }
+}
+
+/// Returns the type which is expected of an expression at `position`, if it is known. The type
+/// is known for function arguments, the bodies of annotated bindings, match scrutinees and the
+/// fields of records whose type is expected.
+pub fn expected_type(env: &TypeEnv, expr: &LExpr<TcIdent>, position: &Position) -> Option<TcType> {
+    enclosing(env, expr, position).pop().and_then(|(_, expected)| expected)
+}
+
+/// A record expression whose type is expected
+pub struct ExpectedRecord {
+    /// The fields which the record is expected to have but does not give yet and their types
+    pub missing: Vec<(String, TcType)>,
+    /// True if the position is where the name of a field goes rather than in the value of a field
+    pub at_field_name: bool,
+}
+
+/// Returns the innermost record expression containing `position` if its type is expected, such as
+/// a record argument of a call or the value of an annotated binding. Gluon 0.1 has no implicit
+/// arguments so the record arguments are what a call can be completed with.
+pub fn expected_record(env: &TypeEnv,
+                       expr: &LExpr<TcIdent>,
+                       position: &Position)
+                       -> Option<ExpectedRecord> {
+    let enclosing = enclosing(env, expr, position);
+    let innermost = enclosing.len() - 1;
+    for (i, (expr, expected)) in enclosing.into_iter().enumerate().rev() {
+        if let Expr::Record { ref exprs, .. } = expr.value {
+            let fields = expected.map_or(vec![], |expected| record_fields(env, &expected));
+            if fields.is_empty() {
+                return None;
+            }
+            let missing = fields.into_iter()
+                .filter(|field| {
+                    !exprs.iter().any(|&(ref name, _)| {
+                        name.declared_name() == field.name.declared_name()
+                    })
+                })
+                .map(|field| (field.name.declared_name().into(), field.typ))
+                .collect();
+            // Punned fields have no expression so a position in one is in the record itself
+            return Some(ExpectedRecord {
+                missing: missing,
+                at_field_name: i == innermost,
+            });
+        }
+    }
+    None
+}
+
+/// Returns true if `l` and `r` could be unified. Type variables and generic types are assumed to
+/// unify with any type.
+pub fn unifies(env: &TypeEnv, l: &TcType, r: &TcType) -> bool {
+    let (l, r) = (eval::remove_aliases(env, l), eval::remove_aliases(env, r));
+    match (&*l, &*r) {
+        (&Type::Variable(_), _) |
+        (_, &Type::Variable(_)) |
+        (&Type::Generic(_), _) |
+        (_, &Type::Generic(_)) => true,
+        (&Type::App(ref l_f, ref l_arg), &Type::App(ref r_f, ref r_arg)) => {
+            unifies(env, l_f, r_f) && unifies(env, l_arg, r_arg)
+        }
+        (&Type::Data(ref l_id, ref l_args), &Type::Data(ref r_id, ref r_args)) => {
+            l_args.len() == r_args.len() && unifies(env, l_id, r_id) &&
+            l_args.iter().zip(r_args).all(|(l, r)| unifies(env, l, r))
+        }
+        (&Type::Function(ref l_args, ref l_ret), &Type::Function(ref r_args, ref r_ret)) => {
+            l_args.len() == r_args.len() && unifies(env, l_ret, r_ret) &&
+            l_args.iter().zip(r_args).all(|(l, r)| unifies(env, l, r))
+        }
+        (&Type::Array(ref l), &Type::Array(ref r)) => unifies(env, l, r),
+        (&Type::Record { fields: ref l_fields, .. },
+         &Type::Record { fields: ref r_fields, .. }) => {
+            l_fields.len() == r_fields.len() &&
+            l_fields.iter().zip(r_fields).all(|(l, r)| {
+                l.name.declared_name() == r.name.declared_name() && unifies(env, &l.typ, &r.typ)
+            })
+        }
+        _ => l == r,
+    }
+}
+
+/// Returns the type constructor which `typ` applies to its last argument, such as `IO` for
+/// `IO Int`, if it is known
+fn monad_of(env: &TypeEnv, typ: &TcType) -> Option<TcType> {
+    match *eval::remove_aliases(env, typ) {
+        Type::App(ref monad, _) => Some(monad.clone()),
+        Type::Data(ref id, ref args) if !args.is_empty() => {
+            Some(Type::data(id.clone(), args[..args.len() - 1].to_vec()))
+        }
+        _ => None,
+    }
+}
+
+/// Returns the monad of the chain of `>>=` and `>>` which `position` is in. The lambdas on the
+/// right of `>>=` are what `do` blocks are in other languages.
+pub fn bind_monad(env: &TypeEnv, expr: &LExpr<TcIdent>, position: &Position) -> Option<TcType> {
+    enclosing(env, expr, position)
+        .into_iter()
+        .rev()
+        .filter_map(|(expr, _)| {
+            match expr.value {
+                Expr::BinOp(_, ref op, _) if op.name.declared_name() == ">>=" ||
+                                             op.name.declared_name() == ">>" => {
+                    arg_iter(&op.typ).next().and_then(|action| monad_of(env, action))
+                }
+                _ => None,
+            }
+        })
+        .find(|monad| {
+            // A monad which the typechecker has not resolved does not tell the candidates apart
+            match **monad {
+                Type::Variable(_) |
+                Type::Generic(_) => false,
+                _ => true,
+            }
+        })
+}
+
+/// Returns true if `typ` is an action of `monad` or a function which returns one
+pub fn returns_in(env: &TypeEnv, monad: &TcType, typ: &TcType) -> bool {
+    monad_of(env, typ.return_type()).map_or(false, |returned| unifies(env, monad, &returned))
+}
+
+/// Returns true if a completion candidate of type `typ` is what is expected at the cursor, either
+/// because it unifies with the `expected` type or because the cursor is in a chain of binds and
+/// it returns an action of the chain's `monad`
+pub fn is_preferred(env: &TypeEnv,
+                    expected: Option<&TcType>,
+                    monad: Option<&TcType>,
+                    typ: &TcType)
+                    -> bool {
+    expected.map_or(false, |expected| unifies(env, expected, typ)) ||
+    monad.map_or(false, |monad| returns_in(env, monad, typ))
+}
+
+/// Returns the `sort_text` of a completion candidate called `label` which places the `preferred`
+/// candidates before the others and then the candidates which `score` says match the typed prefix
+/// best
+pub fn sort_text(preferred: bool, label: &str, score: u32) -> String {
+    let rank = if preferred { 0 } else { 1 };
+    format!("{}{:03}{}", rank, MAX_SCORE - cmp::min(score, MAX_SCORE), label)
+}
@@ -0,0 +1,312 @@
+//! Finding the uses of a type. gluon keeps no locations for types, so the type declarations say
+//! which names refer to a type and its constructors while the source of each module is searched
+//! for where those names are written. Imports are found in the source as well since the `import`
+//! macro replaces them before the expression is typechecked.
+use gluon::base::ast::{ASTType, Expr, LExpr, Pattern};
+use gluon::base::symbol::Symbol;
+use gluon::base::types::{TcIdent, Type};
+use gluon::filename_to_module;
+
+use language_server::{Position, Range};
+use pipeline::is_identifier_char;
+use query;
+
+/// Returns true if `name` is a type or a constructor, which gluon starts with an uppercase letter
+pub fn is_type_name(name: &str) -> bool {
+    name.chars().next().map_or(false, char::is_uppercase)
+}
+
+/// Returns the identifier which `position` is in or at the end of
+pub fn word_at<'s>(source: &'s str, position: &Position) -> Option<&'s str> {
+    let line = match source.lines().nth(position.line as usize) {
+        Some(line) => line,
+        None => return None,
+    };
+    let offset = line.char_indices().nth(position.character as usize).map_or(line.len(), |c| c.0);
+    let start = line[..offset].rfind(|c: char| !is_identifier_char(c)).map_or(0, |i| i + 1);
+    let end = line[offset..].find(|c: char| !is_identifier_char(c)).map_or(line.len(), |i| {
+        offset + i
+    });
+    if start == end { None } else { Some(&line[start..end]) }
+}
+
+/// Returns the end of the string or character literal which starts at `start`
+pub fn skip_quoted(bytes: &[u8], start: usize) -> usize {
+    let quote = bytes[start];
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'\n' if quote == b'\'' => return i,
+            b if b == quote => return i + 1,
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+/// Returns the ranges, in characters, where one of `names` is written as an identifier in the
+/// gluon `source`. Names in comments and literals are skipped.
+pub fn occurrences(source: &str, names: &[String]) -> Vec<Range> {
+    let bytes = source.as_bytes();
+    let mut ranges = Vec::new();
+    let mut line = 0;
+    let mut line_start = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\n' => {
+                line += 1;
+                line_start = i + 1;
+                i += 1;
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                i = source[i..].find('\n').map_or(bytes.len(), |end| i + end);
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                let end = source[i + 2..].find("*/").map_or(bytes.len(), |end| i + 2 + end + 2);
+                let comment = &source[i..end];
+                if let Some(last) = comment.rfind('\n') {
+                    line += comment.matches('\n').count() as u64;
+                    line_start = i + last + 1;
+                }
+                i = end;
+            }
+            b'"' | b'\'' => i = skip_quoted(bytes, i),
+            _ => {
+                let word_len = source[i..].find(|c: char| !is_identifier_char(c))
+                    .unwrap_or(bytes.len() - i);
+                if word_len == 0 {
+                    i += source[i..].chars().next().map_or(1, char::len_utf8);
+                    continue;
+                }
+                let word = &source[i..i + word_len];
+                if names.iter().any(|name| name == word) {
+                    let character = source[line_start..i].chars().count() as u64;
+                    ranges.push(Range {
+                        start: Position {
+                            line: line,
+                            character: character,
+                        },
+                        end: Position {
+                            line: line,
+                            character: character + word.chars().count() as u64,
+                        },
+                    });
+                }
+                i += word_len;
+            }
+        }
+    }
+    ranges
+}
+
+/// Returns true if the text before a string literal is the `import` macro, or `import!`
+fn is_import(before: &str) -> bool {
+    let before = before.trim_right();
+    let before = if before.ends_with('!') { &before[..before.len() - 1] } else { before };
+    before.ends_with("import") &&
+    !before[..before.len() - "import".len()].ends_with(is_identifier_char)
+}
+
+/// Returns the ranges, in characters, of the paths in the `import`s of `module` in the gluon
+/// `source`, without the quotes around them
+pub fn imports(source: &str, module: &str) -> Vec<Range> {
+    let bytes = source.as_bytes();
+    let mut ranges = Vec::new();
+    let mut line = 0;
+    let mut line_start = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\n' => {
+                line += 1;
+                line_start = i + 1;
+                i += 1;
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                i = source[i..].find('\n').map_or(bytes.len(), |end| i + end);
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                let end = source[i + 2..].find("*/").map_or(bytes.len(), |end| i + 2 + end + 2);
+                let comment = &source[i..end];
+                if let Some(last) = comment.rfind('\n') {
+                    line += comment.matches('\n').count() as u64;
+                    line_start = i + last + 1;
+                }
+                i = end;
+            }
+            b'"' => {
+                let end = skip_quoted(bytes, i);
+                let closed = end > i + 1 && bytes[end - 1] == b'"';
+                let path = &source[i + 1..if closed { end - 1 } else { end }];
+                if closed && is_import(&source[line_start..i]) && !path.contains('\n') &&
+                   filename_to_module(path) == module {
+                    let character = source[line_start..i + 1].chars().count() as u64;
+                    ranges.push(Range {
+                        start: Position {
+                            line: line,
+                            character: character,
+                        },
+                        end: Position {
+                            line: line,
+                            character: character + path.chars().count() as u64,
+                        },
+                    });
+                }
+                line += path.matches('\n').count() as u64;
+                if let Some(last) = path.rfind('\n') {
+                    line_start = i + 1 + last + 1;
+                }
+                i = end;
+            }
+            b'\'' => i = skip_quoted(bytes, i),
+            _ => i += source[i..].chars().next().map_or(1, char::len_utf8),
+        }
+    }
+    ranges
+}
+
+/// Returns the path which imports the module `name`, which is either a module name, such as
+/// `geometry.shapes`, or already a path, such as `geometry/shapes.glu`
+pub fn import_path(name: &str) -> Option<String> {
+    let path = if name.ends_with(".glu") {
+        name.into()
+    } else {
+        format!("{}.glu", name.replace('.', "/"))
+    };
+    let valid = path[..path.len() - ".glu".len()]
+        .split('/')
+        .all(|part| !part.is_empty() && part.chars().all(is_identifier_char));
+    if valid { Some(path) } else { None }
+}
+
+fn constructor_names(typ: &Option<ASTType<Symbol>>) -> Vec<String> {
+    match typ.as_ref().map(|typ| &**typ) {
+        Some(&Type::Variants(ref variants)) => {
+            variants.iter()
+                .map(|&(ref constructor, _)| constructor.declared_name().into())
+                .collect()
+        }
+        _ => vec![],
+    }
+}
+
+/// Returns the names of the type `name`, which may be the name of a constructor of the type, and
+/// of its constructors if `expr` declares it
+pub fn declared_type(expr: &LExpr<TcIdent>, name: &str) -> Option<Vec<String>> {
+    if let Expr::Type(ref bindings, _) = expr.value {
+        for bind in bindings {
+            let constructors = constructor_names(&bind.alias.typ);
+            if bind.name.declared_name() == name || constructors.iter().any(|c| c == name) {
+                let mut names = vec![String::from(bind.name.declared_name())];
+                names.extend(constructors);
+                return Some(names);
+            }
+        }
+    }
+    query::children(expr).into_iter().filter_map(|child| declared_type(child, name)).next()
+}
+
+/// Returns the name which the module `expr` gives the type `name` if it imports the type from
+/// another module with a record pattern, as in `let { Point } = import "point.glu"`
+pub fn imported_type(expr: &LExpr<TcIdent>, name: &str) -> Option<String> {
+    if let Expr::Let(ref bindings, _) = expr.value {
+        for bind in bindings {
+            if let Pattern::Record { ref types, .. } = bind.name.value {
+                let imported = types.iter().find(|&&(ref typ, _)| typ.declared_name() == name);
+                if let Some(&(ref typ, ref alias)) = imported {
+                    return Some(alias.as_ref().unwrap_or(typ).declared_name().into());
+                }
+            }
+        }
+    }
+    query::children(expr).into_iter().filter_map(|child| imported_type(child, name)).next()
+}
+
+/// How a use of a variable accesses it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Access {
+    Read,
+    /// The variable is a reference cell which is assigned to with `<-`
+    Write,
+}
+
+/// Returns the variables of `expr` which refer to `name` and how each of them accesses it
+pub fn accesses<'e>(expr: &'e LExpr<TcIdent>, name: &Symbol) -> Vec<(&'e LExpr<TcIdent>, Access)> {
+    match expr.value {
+        Expr::Identifier(ref id) if id.name == *name => return vec![(expr, Access::Read)],
+        Expr::BinOp(ref lhs, ref op, ref rhs) if op.name.declared_name() == "<-" => {
+            if let Expr::Identifier(ref id) = lhs.value {
+                if id.name == *name {
+                    let mut found = vec![(&**lhs, Access::Write)];
+                    found.extend(accesses(rhs, name));
+                    return found;
+                }
+            }
+        }
+        _ => (),
+    }
+    query::children(expr).into_iter().flat_map(|child| accesses(child, name)).collect()
+}
+
+/// Returns the variables of `expr` which refer to `name`
+pub fn uses<'e>(expr: &'e LExpr<TcIdent>, name: &Symbol) -> Vec<&'e LExpr<TcIdent>> {
+    accesses(expr, name).into_iter().map(|(expr, _)| expr).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn start(range: &Range) -> (u64, u64) {
+        (range.start.line, range.start.character)
+    }
+
+    #[test]
+    fn word_at_position() {
+        let source = "let p : Point = origin\nin p";
+        let position = |line, character| {
+            Position {
+                line: line,
+                character: character,
+            }
+        };
+        assert_eq!(word_at(source, &position(0, 10)), Some("Point"));
+        assert_eq!(word_at(source, &position(0, 13)), Some("Point"));
+        assert_eq!(word_at(source, &position(0, 14)), None);
+        assert_eq!(word_at(source, &position(1, 3)), Some("p"));
+        assert_eq!(word_at(source, &position(2, 0)), None);
+    }
+
+    #[test]
+    fn occurrences_skip_comments_and_literals() {
+        let source = "type Point = | Point Int Int in\n// Point\nlet s = \"Point\" in\n/* a\n \
+                      Point */ let p : Point = Point 1 2 in PointLike";
+        let names = vec!["Point".into()];
+        let found: Vec<_> = occurrences(source, &names).iter().map(start).collect();
+        assert_eq!(found, vec![(0, 5), (0, 15), (4, 18), (4, 26)]);
+        assert_eq!(occurrences(source, &names)[0].end.character, 10);
+    }
+
+    #[test]
+    fn imports_of_module() {
+        let source = "let s = import \"shapes.glu\"\n// import \"shapes.glu\"\nlet t = \
+                      \"shapes.glu\" in\nlet { area } =  import!  \"shapes.glu\" in\nimport \
+                      \"shapes2.glu\"";
+        let found: Vec<_> = imports(source, "shapes").iter().map(start).collect();
+        assert_eq!(found, vec![(0, 16), (3, 26)]);
+        assert_eq!(imports(source, "shapes")[0].end.character, 26);
+        assert_eq!(imports("import \"geometry/shapes.glu\"", "geometry.shapes").len(), 1);
+    }
+
+    #[test]
+    fn import_path_of_name() {
+        assert_eq!(import_path("geometry.shapes"), Some("geometry/shapes.glu".into()));
+        assert_eq!(import_path("geometry/shapes.glu"), Some("geometry/shapes.glu".into()));
+        assert_eq!(import_path("shapes"), Some("shapes.glu".into()));
+        assert_eq!(import_path("geometry..shapes"), None);
+        assert_eq!(import_path("my shapes"), None);
+        assert_eq!(import_path(".glu"), None);
+    }
+}
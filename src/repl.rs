@@ -0,0 +1,91 @@
+//! REPL sessions which keep their own virtual machine alive between evaluations
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+
+use gluon::{Compiler, Result as GluonResult, RootedThread};
+use gluon::base::types::TcType;
+
+use eval;
+
+pub enum Output {
+    /// The input was an expression which evaluated to a value
+    Value(String, TcType),
+    /// The input was a `let` or `type` binding which is now in scope for the rest of the session
+    Definition,
+}
+
+pub struct Session {
+    vm: RootedThread,
+    /// Source of the bindings which every input is evaluated under, each one terminated by `in`
+    definitions: String,
+}
+
+impl Session {
+    pub fn new(root: Option<&Path>, imports: String) -> Session {
+        Session {
+            vm: eval::new_eval_vm(root),
+            definitions: imports,
+        }
+    }
+
+    /// Evaluates `input`. Bindings without an `in` clause are added to the session if they
+    /// compile, everything else is evaluated as an expression.
+    pub fn eval(&mut self, input: &str) -> GluonResult<Output> {
+        let mut compiler = Compiler::new();
+        let is_definition = compiler.parse_expr("repl", input).is_err() &&
+                            compiler.parse_expr("repl", &format!("{}\nin ()", input)).is_ok();
+        if is_definition {
+            let definitions = format!("{}{}\nin\n", self.definitions, input);
+            try!(eval::eval(&self.vm, "repl", &format!("{}()", definitions)));
+            self.definitions = definitions;
+            Ok(Output::Definition)
+        } else {
+            let source = format!("{}{}", self.definitions, input);
+            let (value, typ) = try!(eval::eval(&self.vm, "repl", &source));
+            Ok(Output::Value(value, typ))
+        }
+    }
+}
+
+pub struct Sessions {
+    next_id: Mutex<u64>,
+    sessions: Mutex<HashMap<u64, Session>>,
+}
+
+impl Sessions {
+    pub fn new() -> Sessions {
+        Sessions {
+            next_id: Mutex::new(0),
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Stores `session` and returns the id which identifies it in later requests
+    pub fn start(&self, session: Session) -> u64 {
+        let id = {
+            let mut next_id = self.next_id.lock().unwrap();
+            *next_id += 1;
+            *next_id
+        };
+        self.sessions.lock().unwrap().insert(id, session);
+        id
+    }
+
+    /// Calls `f` with the session `id` if it exists
+    pub fn with_session<F, R>(&self, id: u64, f: F) -> Option<R>
+        where F: FnOnce(&mut Session) -> R
+    {
+        self.sessions.lock().unwrap().get_mut(&id).map(f)
+    }
+
+    /// Returns the number of running sessions
+    pub fn len(&self) -> usize {
+        self.sessions.lock().unwrap().len()
+    }
+
+    /// Removes the session `id`, returning false if there was no such session
+    pub fn stop(&self, id: u64) -> bool {
+        self.sessions.lock().unwrap().remove(&id).is_some()
+    }
+}
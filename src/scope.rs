@@ -0,0 +1,119 @@
+//! The names which are bound at a position of a typechecked expression, found the way gluon's
+//! completion finds them but without filtering them by what has been typed
+use gluon::base::ast::{Expr, LExpr, LPattern, Pattern, Typed};
+use gluon::base::types::{TcIdent, Type, TypeEnv};
+
+use eval;
+use language_server::{Position, Range};
+use query;
+
+fn contains_position(expr: &LExpr<TcIdent>, position: &Position) -> bool {
+    query::contains(&query::expr_range(expr),
+                    &Range {
+                        start: *position,
+                        end: *position,
+                    })
+}
+
+fn add_pattern(env: &TypeEnv, pattern: &LPattern<TcIdent>, names: &mut Vec<TcIdent>) {
+    match pattern.value {
+        Pattern::Identifier(ref id) => names.push(id.clone()),
+        Pattern::Record { ref id, ref fields, .. } => {
+            if let Type::Record { fields: ref field_types, .. } =
+                   *eval::remove_aliases(env, &id.typ) {
+                for &(ref field, ref alias) in fields {
+                    let field_type = field_types.iter().find(|typ| typ.name == *field);
+                    if let Some(field_type) = field_type {
+                        names.push(TcIdent {
+                            name: alias.as_ref().unwrap_or(field).clone(),
+                            typ: field_type.typ.clone(),
+                        });
+                    }
+                }
+            }
+        }
+        Pattern::Constructor(_, ref args) => names.extend(args.iter().cloned()),
+    }
+}
+
+fn add_in_scope(env: &TypeEnv,
+                expr: &LExpr<TcIdent>,
+                position: &Position,
+                names: &mut Vec<TcIdent>) {
+    match expr.value {
+        // The bindings of a `let` are in scope in each other's expressions as well as the body
+        Expr::Let(ref bindings, ref body) => {
+            let in_bindings =
+                bindings.iter().any(|bind| contains_position(&bind.expression, position));
+            if in_bindings || contains_position(body, position) {
+                for bind in bindings {
+                    add_pattern(env, &bind.name, names);
+                }
+            }
+            for bind in bindings {
+                if contains_position(&bind.expression, position) {
+                    names.extend(bind.arguments.iter().cloned());
+                }
+            }
+        }
+        Expr::Lambda(ref lambda) if contains_position(&lambda.body, position) => {
+            names.extend(lambda.arguments.iter().cloned());
+        }
+        _ => (),
+    }
+    for child in query::children(expr) {
+        if contains_position(child, position) {
+            add_in_scope(env, child, position, names);
+        }
+    }
+}
+
+/// Returns the names which are in scope at `position` in the typechecked expression `expr`, outer
+/// bindings first. A name which is bound several times is returned once for each binding.
+pub fn in_scope(env: &TypeEnv, expr: &LExpr<TcIdent>, position: &Position) -> Vec<TcIdent> {
+    let mut names = Vec::new();
+    // The bindings of the implicit prelude have locations in the prelude but are in scope in the
+    // whole document
+    let user_expr = query::strip_implicit_prelude(expr);
+    let mut prelude = expr;
+    while prelude as *const _ != user_expr as *const _ {
+        match prelude.value {
+            Expr::Let(ref bindings, ref body) => {
+                for bind in bindings {
+                    add_pattern(env, &bind.name, &mut names);
+                }
+                prelude = &**body;
+            }
+            _ => break,
+        }
+    }
+    add_in_scope(env, user_expr, position, &mut names);
+    names
+}
+
+/// Returns the names which could complete the identifier at `position`: the fields of the record
+/// if it is a field access and the names in scope otherwise
+pub fn candidates(env: &TypeEnv, expr: &LExpr<TcIdent>, position: &Position) -> Vec<TcIdent> {
+    let range = Range {
+        start: *position,
+        end: *position,
+    };
+    match query::enclosing_expr(expr, &range).map(|expr| &expr.value) {
+        Some(&Expr::FieldAccess(ref record, _)) => {
+            match *eval::remove_aliases(env, &record.env_type_of(env)) {
+                Type::Record { ref fields, .. } => {
+                    fields.iter()
+                        .map(|field| {
+                            TcIdent {
+                                name: field.name.clone(),
+                                typ: field.typ.clone(),
+                            }
+                        })
+                        .collect()
+                }
+                _ => vec![],
+            }
+        }
+        _ => in_scope(env, expr, position),
+    }
+}
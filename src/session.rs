@@ -0,0 +1,166 @@
+//! Sessions recorded as fixtures for golden tests. A fixture has one JSON object per line, either
+//! `{"send": message}` for a message from the client or `{"expect": message}` for a message from
+//! the server. A session is recorded when the server is started with `GLUON_RECORD_SESSION` set
+//! to the file to write, and `test::replay` checks that the server still answers the same way.
+//!
+//! The root of the workspace is written as `${root}` and its uri as `${rootUri}` so a fixture
+//! can be replayed in another directory. Values which differ between runs, such as timings, can
+//! be replaced by `${any}` in the expected messages by hand.
+use std::error::Error as StdError;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+use serde_json::{self, Value};
+
+use uri;
+
+pub const ROOT: &'static str = "${root}";
+pub const ROOT_URI: &'static str = "${rootUri}";
+/// Matches any value in an expected message
+pub const ANY: &'static str = "${any}";
+
+pub enum Step {
+    Send(Value),
+    Expect(Value),
+}
+
+/// Replaces the placeholders in the strings of `value`. Paths are only replaced in whole strings
+/// while uris are also replaced at the start of a string, as they are followed by the path of the
+/// file in the workspace.
+fn substitute(value: &mut Value, root: &str, root_uri: &str) {
+    match *value {
+        Value::String(ref mut s) => {
+            if s == ROOT {
+                *s = root.into();
+            } else if s.starts_with(ROOT_URI) {
+                *s = format!("{}{}", root_uri, &s[ROOT_URI.len()..]);
+            }
+        }
+        Value::Array(ref mut values) => {
+            for value in values {
+                substitute(value, root, root_uri);
+            }
+        }
+        Value::Object(ref mut map) => {
+            for (_, value) in map.iter_mut() {
+                substitute(value, root, root_uri);
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Reads the steps of the fixture at `path`, with the workspace root placeholders replaced by
+/// `root`
+pub fn read(path: &Path, root: &Path) -> Result<Vec<Step>, Box<StdError>> {
+    let root_uri = uri::from_path(root);
+    let root = root.to_string_lossy().into_owned();
+    let mut steps = Vec::new();
+    for line in BufReader::new(try!(File::open(path))).lines() {
+        let line = try!(line);
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut step: Value = try!(serde_json::from_str(&line));
+        substitute(&mut step, &root, &root_uri);
+        let step = match (step.find("send"), step.find("expect")) {
+            (Some(message), None) => Step::Send(message.clone()),
+            (None, Some(message)) => Step::Expect(message.clone()),
+            _ => return Err(format!("Invalid step `{}` in `{}`", line, path.display()).into()),
+        };
+        steps.push(step);
+    }
+    Ok(steps)
+}
+
+/// Returns true if `actual` equals `expected`, where `${any}` in `expected` matches any value
+pub fn matches(expected: &Value, actual: &Value) -> bool {
+    match (expected, actual) {
+        (&Value::String(ref s), _) if s == ANY => true,
+        (&Value::Array(ref expected), &Value::Array(ref actual)) => {
+            expected.len() == actual.len() &&
+            expected.iter().zip(actual).all(|(expected, actual)| matches(expected, actual))
+        }
+        (&Value::Object(ref expected), &Value::Object(ref actual)) => {
+            expected.len() == actual.len() &&
+            expected.iter().all(|(key, expected)| {
+                actual.get(key).map_or(false, |actual| matches(expected, actual))
+            })
+        }
+        _ => expected == actual,
+    }
+}
+
+/// Returns true if `message` is the response or notification which `expected` stands for. The
+/// messages are matched on their id, or on their method and document if they have no id, as
+/// unrelated notifications such as progress can arrive in between.
+pub fn is_counterpart(expected: &Value, message: &Value) -> bool {
+    let id = |message: &Value| message.find("id").cloned();
+    let method = |message: &Value| message.find("method").cloned();
+    let document = |message: &Value| message.find_path(&["params", "uri"]).cloned();
+    match id(expected) {
+        Some(expected_id) => id(message) == Some(expected_id),
+        None => {
+            id(message).is_none() && method(message) == method(expected) &&
+            document(message) == document(expected)
+        }
+    }
+}
+
+struct Roots {
+    root: String,
+    root_uri: String,
+}
+
+/// Writes the messages of a session to a fixture
+pub struct Recorder {
+    file: Mutex<File>,
+    roots: Mutex<Option<Roots>>,
+}
+
+impl Recorder {
+    pub fn create(path: &Path) -> Result<Recorder, Box<StdError>> {
+        Ok(Recorder {
+            file: Mutex::new(try!(File::create(path))),
+            roots: Mutex::new(None),
+        })
+    }
+
+    fn record(&self, kind: &str, message: &str) {
+        let mut message = message.to_string();
+        if let Some(ref roots) = *self.roots.lock().unwrap() {
+            message = message.replace(&roots.root_uri, ROOT_URI)
+                .replace(&format!("\"{}\"", roots.root), &format!("\"{}\"", ROOT));
+        }
+        // The message is parsed again so that it is written on a single line
+        let message = match serde_json::from_str::<Value>(&message) {
+            Ok(message) => message,
+            Err(_) => return,
+        };
+        let line = format!("{{\"{}\": {}}}\n", kind, message);
+        if let Err(err) = self.file.lock().unwrap().write_all(line.as_bytes()) {
+            error!("Failed to record a message: {}", err);
+        }
+    }
+
+    /// Records `message` from the client. The root of the workspace is learned from `initialize`.
+    pub fn record_sent(&self, message: &str) {
+        if let Ok(value) = serde_json::from_str::<Value>(message) {
+            let root = value.find_path(&["params", "rootPath"]).and_then(|root| root.as_string());
+            if let Some(root) = root {
+                *self.roots.lock().unwrap() = Some(Roots {
+                    root: root.into(),
+                    root_uri: uri::from_path(Path::new(root)),
+                });
+            }
+        }
+        self.record("send", message);
+    }
+
+    /// Records `message` from the server
+    pub fn record_received(&self, message: &str) {
+        self.record("expect", message);
+    }
+}
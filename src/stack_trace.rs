@@ -0,0 +1,94 @@
+//! Stack traces recovered from the frames which the virtual machine leaves on its stack when a
+//! program fails or is stopped
+use std::fmt::Write;
+
+use gluon::Thread;
+use gluon::vm::stack::State;
+
+use breakpoints::{BREAK_FUNCTION, RAISE_FUNCTION};
+
+pub struct Frame {
+    /// The name of the function without the location suffix
+    pub name: String,
+    /// The module which the function is defined in, if it is known
+    pub module: Option<String>,
+    /// Line of the definition of the function (1-based), or 0 if the location is unknown
+    pub line: i64,
+    /// Column of the definition of the function, or 0 if the location is unknown
+    pub column: i64,
+}
+
+/// Parses the location which the typechecker appends to the names of bindings
+/// (`name:Line: 1, Column: 5`)
+pub fn parse_location(name: &str) -> Option<(i64, i64)> {
+    let location = match name.find(':') {
+        Some(i) => &name[i + 1..],
+        None => return None,
+    };
+    let mut numbers = location.split(", ")
+        .map(|part| part.split(": ").nth(1).and_then(|n| n.parse().ok()));
+    match (numbers.next(), numbers.next()) {
+        (Some(Some(line)), Some(Some(column))) => Some((line, column)),
+        _ => None,
+    }
+}
+
+/// Returns the frames left on the stack of `vm`, innermost frame first
+pub fn frames(vm: &Thread) -> Vec<Frame> {
+    let mut frames = Vec::new();
+    let mut module = None;
+    let stack = vm.get_stack();
+    for frame in stack.get_frames() {
+        let name = match frame.state {
+            State::Closure(ref closure) => String::from(closure.function.name.as_ref()),
+            State::Extern(ref function) => String::from(function.id.as_ref()),
+            State::Unknown | State::Lock | State::Excess => continue,
+        };
+        // The primitives of the debugger are not part of the program
+        if name == BREAK_FUNCTION || name == RAISE_FUNCTION {
+            continue;
+        }
+        let (frame_module, line, column) = match parse_location(&name) {
+            Some((line, column)) => (module.clone(), line, column),
+            None => {
+                // Functions without a location are either extern functions or the top level
+                // function of a module
+                if let State::Closure(_) = frame.state {
+                    module = Some(name.clone());
+                    (module.clone(), 1, 1)
+                } else {
+                    (None, 0, 0)
+                }
+            }
+        };
+        frames.push(Frame {
+            name: String::from(name.split(':').next().unwrap_or(&name)),
+            module: frame_module,
+            line: line,
+            column: column,
+        });
+    }
+    frames.reverse();
+    frames
+}
+
+/// Formats `frames` with one line per frame, innermost frame first
+pub fn format(frames: &[Frame]) -> String {
+    let mut out = String::new();
+    for frame in frames {
+        match frame.module {
+            Some(ref module) => {
+                let _ = writeln!(out,
+                                 "    at {} ({}:{}:{})",
+                                 frame.name,
+                                 module,
+                                 frame.line,
+                                 frame.column);
+            }
+            None => {
+                let _ = writeln!(out, "    at {} (extern)", frame.name);
+            }
+        }
+    }
+    out
+}
@@ -0,0 +1,119 @@
+//! What the server is doing, which is reported to the client with `gluon/status` notifications.
+//! Checking an open document is reported over indexing the workspace, which is reported over an
+//! error, so the status shows the work which the user is most likely waiting for.
+use std::sync::Mutex;
+
+use serde_json::builder::ObjectBuilder;
+use serde_json::value::to_value;
+
+use extensions::StatusParams;
+use transport;
+
+#[derive(Default)]
+struct State {
+    /// The number of indexing runs which have not finished
+    indexing: usize,
+    /// The document which is being checked
+    checking: Option<String>,
+    /// An error which keeps the server from working properly, such as an invalid manifest
+    error: Option<String>,
+    /// The last error which was reported, which is kept once it is fixed
+    last_error: Option<String>,
+    /// The status which the client was sent last
+    sent: Option<StatusParams>,
+}
+
+impl State {
+    fn params(&self) -> StatusParams {
+        let (state, message) = if let Some(ref uri) = self.checking {
+            ("checking", Some(uri.clone()))
+        } else if self.indexing > 0 {
+            ("indexing", None)
+        } else if let Some(ref error) = self.error {
+            ("error", Some(error.clone()))
+        } else {
+            ("idle", None)
+        };
+        StatusParams {
+            state: state.into(),
+            message: message,
+        }
+    }
+}
+
+pub struct Status {
+    state: Mutex<State>,
+}
+
+impl Status {
+    pub fn new() -> Status {
+        Status { state: Mutex::new(State::default()) }
+    }
+
+    /// Records that indexing the workspace has started or finished
+    pub fn set_indexing(&self, indexing: bool) {
+        self.update(|state| if indexing {
+            state.indexing += 1
+        } else {
+            state.indexing = state.indexing.saturating_sub(1)
+        });
+    }
+
+    /// Records that the document at `uri` is being checked, or that no document is if `uri` is
+    /// `None`
+    pub fn set_checking(&self, uri: Option<&str>) {
+        self.update(|state| state.checking = uri.map(String::from));
+    }
+
+    /// Records the error which keeps the server from working properly, or that it has been fixed
+    pub fn set_error(&self, error: Option<String>) {
+        self.update(|state| {
+            if error.is_some() {
+                state.last_error = error.clone();
+            }
+            state.error = error;
+        });
+    }
+
+    pub fn last_error(&self) -> Option<String> {
+        self.state.lock().unwrap().last_error.clone()
+    }
+
+    /// Applies `f` to the state and notifies the client if the status changed
+    fn update<F>(&self, f: F)
+        where F: FnOnce(&mut State)
+    {
+        let mut state = self.state.lock().unwrap();
+        f(&mut state);
+        let params = state.params();
+        if state.sent.as_ref() == Some(&params) {
+            return;
+        }
+        // Sent while the state is locked so that the notifications can't be reordered
+        let message = ObjectBuilder::new()
+            .insert("jsonrpc", "2.0")
+            .insert("method", "gluon/status")
+            .insert("params", to_value(&params))
+            .unwrap();
+        transport::write_message(&message.to_string());
+        state.sent = Some(params);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checking_is_reported_over_indexing() {
+        let mut state = State::default();
+        state.error = Some("Invalid manifest".into());
+        assert_eq!(state.params().state, "error");
+        state.indexing = 1;
+        assert_eq!(state.params().state, "indexing");
+        state.checking = Some("file:///test.glu".into());
+        let params = state.params();
+        assert_eq!(params.state, "checking");
+        assert_eq!(params.message, Some("file:///test.glu".into()));
+    }
+}
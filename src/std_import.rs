@@ -0,0 +1,130 @@
+//! The `import` macro used for analysis. The modules of the standard library are normally the ones
+//! compiled into gluon, but they can be read from a checkout of the std directory instead so that
+//! a modified standard library, or the one of another version of gluon, is analysed. `std.types`
+//! is loaded when the virtual machine is created and always comes from gluon.
+//!
+//! Other modules are looked up in the import paths of the workspace before the paths of the
+//! wrapped `Import`. The macro environment hands out a clone of the macro whenever it is looked
+//! up, and a clone of an `Import` has its own copy of the paths, so the std directory and the
+//! import paths, which are changed after the macro has been registered, are shared between the
+//! clones instead.
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use gluon::base::ast;
+use gluon::base::symbol::Symbol;
+use gluon::base::types::TcIdent;
+use gluon::import::{CheckImporter, Error as ImportError, Import, Importer};
+use gluon::vm::macros::{Error as MacroError, Macro};
+use gluon::vm::thread::{Thread, ThreadInternal};
+use gluon::filename_to_module;
+
+/// The environment variable which sets the std directory if the client does not
+pub const STD_PATH_VAR: &'static str = "GLUON_STD_PATH";
+
+pub struct StdImport {
+    pub inner: Import<CheckImporter>,
+    /// The directory which the std modules are read from, if they are not the compiled in ones
+    dir: Arc<RwLock<Option<PathBuf>>>,
+    /// The import paths of the workspace
+    paths: Arc<RwLock<Vec<PathBuf>>>,
+}
+
+impl StdImport {
+    pub fn new(inner: Import<CheckImporter>) -> StdImport {
+        StdImport {
+            inner: inner,
+            dir: Arc::new(RwLock::new(None)),
+            paths: Arc::new(RwLock::new(Vec::new())),
+        }
+    }
+
+    pub fn dir(&self) -> Option<PathBuf> {
+        self.dir.read().unwrap().clone()
+    }
+
+    /// Sets the directory which std modules are read from. Modules which are already loaded keep
+    /// the source they were loaded from.
+    pub fn set_dir(&self, dir: Option<PathBuf>) {
+        *self.dir.write().unwrap() = dir;
+    }
+
+    /// Adds `dir` to the import paths which modules are looked up in
+    pub fn add_path(&self, dir: PathBuf) {
+        self.paths.write().unwrap().push(dir);
+    }
+
+    /// Reads the source of the std module `filename`, a path starting with `std/`, from the std
+    /// directory. Returns `None` if there is no std directory or it does not have the module.
+    fn read_std(&self, filename: &str) -> Option<io::Result<String>> {
+        self.dir().and_then(|dir| read_source(&dir.join(&filename["std/".len()..])))
+    }
+
+    /// Reads the source of the module `filename` from the first import path which has it
+    fn read_imported(&self, filename: &str) -> Option<io::Result<String>> {
+        self.paths
+            .read()
+            .unwrap()
+            .iter()
+            .filter_map(|dir| read_source(&dir.join(filename)))
+            .next()
+    }
+}
+
+/// Reads the file at `path`, returning `None` if there is no such file
+fn read_source(path: &Path) -> Option<io::Result<String>> {
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => return None,
+        Err(err) => return Some(Err(err)),
+    };
+    let mut source = String::new();
+    Some(file.read_to_string(&mut source).map(|_| source))
+}
+
+impl Macro for StdImport {
+    fn expand(&self,
+              vm: &Thread,
+              arguments: &mut [ast::LExpr<TcIdent>])
+              -> Result<ast::LExpr<TcIdent>, MacroError> {
+        let filename = match arguments.first().map(|arg| &arg.value) {
+            Some(&ast::Expr::Literal(ast::LiteralEnum::String(ref filename)))
+                if arguments.len() == 1 => filename.clone(),
+            _ => return self.inner.expand(vm, arguments),
+        };
+        let modulename = filename_to_module(&filename);
+        if vm.global_env().global_exists(&modulename) {
+            return self.inner.expand(vm, arguments);
+        }
+        let source = if filename.starts_with("std/") {
+            self.read_std(&filename)
+        } else {
+            self.read_imported(&filename)
+        };
+        match source {
+            Some(Ok(source)) => {
+                try!(self.inner.importer.import(vm, &modulename, &source));
+                Ok(ast::located(arguments[0].location,
+                                ast::Expr::Identifier(TcIdent::new(Symbol::new(&modulename)))))
+            }
+            Some(Err(err)) => Err(ImportError::IO(err).into()),
+            // Modules which the std directory does not have are the compiled in ones, and the
+            // modules which are in no import path may still be found by `inner`
+            None => self.inner.expand(vm, arguments),
+        }
+    }
+
+    fn clone(&self) -> Box<Macro> {
+        let inner = Macro::clone(&self.inner)
+            .downcast::<Import<CheckImporter>>()
+            .ok()
+            .expect("Macro::clone to return the same type");
+        Box::new(StdImport {
+            inner: *inner,
+            dir: self.dir.clone(),
+            paths: self.paths.clone(),
+        })
+    }
+}
@@ -0,0 +1,127 @@
+//! Comments which allow lints in a part of a module:
+//!
+//! ```gluon
+//! // gluon-lint: allow(unused-binding, shadowing)
+//! let f x =
+//!     let unused = 1
+//!     x
+//! ```
+//!
+//! A comment on a line of its own allows the lints in the item which starts on the next line of
+//! code, that line and the lines after it which are indented further. The comments at the top of
+//! the module which are followed by a blank line allow the lints in the whole module instead.
+//! Codes may be written with `_` in place of `-`, such as `unused_binding`.
+const PREFIX: &'static str = "gluon-lint:";
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Suppressions {
+    /// The codes which are allowed in the whole module
+    module: Vec<String>,
+    /// The codes which are allowed in each item with its first and last line
+    items: Vec<(Vec<String>, u64, u64)>,
+}
+
+/// Returns the codes which the comment `line` allows, if it is a suppression comment
+fn allowed_codes(line: &str) -> Option<Vec<String>> {
+    let comment = line.trim_left();
+    if !comment.starts_with("//") {
+        return None;
+    }
+    let comment = comment.trim_left_matches('/').trim();
+    if !comment.starts_with(PREFIX) {
+        return None;
+    }
+    let allow = comment[PREFIX.len()..].trim();
+    if !allow.starts_with("allow(") || !allow.ends_with(')') {
+        return None;
+    }
+    Some(allow["allow(".len()..allow.len() - 1]
+        .split(',')
+        .map(|code| code.trim().replace("_", "-"))
+        .filter(|code| !code.is_empty())
+        .collect())
+}
+
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_left().len()
+}
+
+impl Suppressions {
+    /// Finds the suppression comments of `source`
+    pub fn parse(source: &str) -> Suppressions {
+        let lines: Vec<_> = source.lines().collect();
+        let mut suppressions = Suppressions::default();
+        // The codes of the comments which are waiting for the next line of code
+        let mut pending = Vec::new();
+        let mut seen_code = false;
+        for (i, line) in lines.iter().enumerate() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() {
+                if !seen_code {
+                    suppressions.module.extend(pending.drain(..));
+                }
+                continue;
+            }
+            if trimmed.starts_with("//") {
+                pending.extend(allowed_codes(line).into_iter().flat_map(|codes| codes));
+                continue;
+            }
+            seen_code = true;
+            if pending.is_empty() {
+                continue;
+            }
+            let indent = indentation(line);
+            let mut end = i;
+            for (j, next) in lines.iter().enumerate().skip(i + 1) {
+                if next.trim().is_empty() {
+                    continue;
+                }
+                if indentation(next) <= indent {
+                    break;
+                }
+                end = j;
+            }
+            suppressions.items.push((pending.drain(..).collect(), i as u64, end as u64));
+        }
+        suppressions
+    }
+
+    /// Returns true if the diagnostics with the code `code` which start on `line` are allowed
+    pub fn allows(&self, code: &str, line: u64) -> bool {
+        self.module.iter().any(|allowed| allowed == code) ||
+        self.items.iter().any(|&(ref codes, start, end)| {
+            start <= line && line <= end && codes.iter().any(|allowed| allowed == code)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allow_lints_in_the_next_item() {
+        let suppressions = Suppressions::parse("// gluon-lint: allow(unused_binding, shadowing)\n\
+                                                let f x =\n    let unused = 1\n\n    x\n\
+                                                in\nlet g = 1\nin f");
+        assert!(suppressions.allows("unused-binding", 1));
+        assert!(suppressions.allows("shadowing", 4));
+        assert!(!suppressions.allows("unused-binding", 5));
+        assert!(!suppressions.allows("unused-binding", 6));
+        assert!(!suppressions.allows("unused-parameter", 2));
+    }
+
+    #[test]
+    fn allow_lints_in_the_module() {
+        let suppressions = Suppressions::parse("// A module\n// gluon-lint: allow(shadowing)\n\n\
+                                                // gluon-lint: allow(unused-binding)\nlet x = \
+                                                1\nin\n// gluon-lint: allow(type)\n1");
+        assert!(suppressions.allows("shadowing", 7));
+        assert!(suppressions.allows("unused-binding", 4));
+        assert!(!suppressions.allows("unused-binding", 5));
+        assert!(suppressions.allows("type", 7));
+        // Other comments are not suppressions
+        assert_eq!(Suppressions::parse("// gluon-lint allow(type)\n// gluon-lint: deny(type)\n1"),
+                   Suppressions::default());
+    }
+}
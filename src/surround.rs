@@ -0,0 +1,122 @@
+//! The skeletons which a selected expression can be surrounded with. The selection is trimmed of
+//! the whitespace around it and the lines after its first are indented further so that they stay
+//! inside the expression which surrounds them.
+use language_server::{Position, Range};
+
+const INDENT: &'static str = "    ";
+
+fn offset(source: &str, position: &Position) -> Option<usize> {
+    let mut line_start = 0;
+    for _ in 0..position.line {
+        line_start += match source[line_start..].find('\n') {
+            Some(end) => end + 1,
+            None => return None,
+        };
+    }
+    let line = &source[line_start..];
+    let line = &line[..line.find('\n').unwrap_or(line.len())];
+    let character = line.char_indices()
+        .nth(position.character as usize)
+        .map_or(line.len(), |c| c.0);
+    Some(line_start + character)
+}
+
+fn position(source: &str, offset: usize) -> Position {
+    let before = &source[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    Position {
+        line: before.matches('\n').count() as u64,
+        character: before[line_start..].chars().count() as u64,
+    }
+}
+
+/// A skeleton and the selection which it surrounds
+pub struct Surrounding {
+    pub title: &'static str,
+    pub range: Range,
+    pub new_text: String,
+}
+
+/// Returns the ways of surrounding the expression which `range` selects in `source`, a `let`
+/// which binds it, a `match` on it and a lambda which returns it. Nothing is offered for an empty
+/// selection.
+pub fn surroundings(source: &str, range: &Range) -> Vec<Surrounding> {
+    let (start, end) = match (offset(source, &range.start), offset(source, &range.end)) {
+        (Some(start), Some(end)) if start < end => (start, end),
+        _ => return vec![],
+    };
+    let selected = &source[start..end];
+    let trimmed = selected.trim_left();
+    let start = start + selected.len() - trimmed.len();
+    let selected = trimmed.trim_right();
+    if selected.is_empty() {
+        return vec![];
+    }
+    let end = start + selected.len();
+    let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+    let indent: String = source[line_start..start]
+        .chars()
+        .take_while(|&c| c == ' ' || c == '\t')
+        .collect();
+    let nested = selected.replace("\n", &format!("\n{}", INDENT));
+    let range = Range {
+        start: position(source, start),
+        end: position(source, end),
+    };
+    vec![Surrounding {
+             title: "Surround with `let`",
+             range: range,
+             new_text: format!("let x = {}\n{}in x", nested, indent),
+         },
+         Surrounding {
+             title: "Surround with `match`",
+             range: range,
+             new_text: format!("match {} with\n{}| x -> x", nested, indent),
+         },
+         Surrounding {
+             title: "Surround with a lambda",
+             range: range,
+             new_text: format!("(\\x -> {})", nested),
+         }]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use language_server::{Position, Range};
+
+    fn range(start: (u64, u64), end: (u64, u64)) -> Range {
+        Range {
+            start: Position {
+                line: start.0,
+                character: start.1,
+            },
+            end: Position {
+                line: end.0,
+                character: end.1,
+            },
+        }
+    }
+
+    #[test]
+    fn surround_selection() {
+        let source = "let f x =\n    g  x + 1 \nin f";
+        let found = surroundings(source, &range((1, 4), (1, 13)));
+        let texts: Vec<_> = found.iter().map(|surrounding| &surrounding.new_text[..]).collect();
+        assert_eq!(texts,
+                   vec!["let x = g  x + 1\n    in x", "match g  x + 1 with\n    | x -> x",
+                        "(\\x -> g  x + 1)"]);
+        // The whitespace around the selection is left out
+        assert_eq!(found[0].range.end.character, 12);
+        assert!(surroundings(source, &range((1, 2), (1, 4))).is_empty());
+        assert!(surroundings(source, &range((1, 5), (1, 5))).is_empty());
+    }
+
+    #[test]
+    fn lines_after_the_first_are_indented() {
+        let source = "  if a\n  then b\n  else c";
+        let found = surroundings(source, &range((0, 0), (2, 8)));
+        assert_eq!(found[0].range.start.character, 2);
+        assert_eq!(found[0].new_text, "let x = if a\n      then b\n      else c\n  in x");
+    }
+}
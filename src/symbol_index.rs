@@ -0,0 +1,128 @@
+//! The bindings of every module in the workspace which `workspace/symbol` searches. A module's
+//! symbols are replaced whenever it is typechecked or parsed again, so bindings which were added
+//! since the workspace was indexed are found as well.
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use fuzzy;
+use language_server::{Location, Range, SymbolInformation, SymbolKind};
+
+struct IndexedSymbol {
+    name: String,
+    kind: SymbolKind,
+    range: Range,
+    container: String,
+}
+
+pub struct SymbolIndex {
+    modules: Mutex<HashMap<String, Vec<IndexedSymbol>>>,
+}
+
+impl SymbolIndex {
+    pub fn new() -> SymbolIndex {
+        SymbolIndex { modules: Mutex::new(HashMap::new()) }
+    }
+
+    /// Replaces the symbols of the module at `uri`
+    pub fn update(&self, uri: &str, symbols: Vec<SymbolInformation>) {
+        let symbols = symbols.into_iter()
+            .map(|symbol| {
+                IndexedSymbol {
+                    name: symbol.name,
+                    kind: symbol.kind,
+                    range: symbol.location.range,
+                    container: symbol.container_name,
+                }
+            })
+            .collect();
+        self.modules.lock().unwrap().insert(uri.into(), symbols);
+    }
+
+    pub fn remove(&self, uri: &str) {
+        self.modules.lock().unwrap().remove(uri);
+    }
+
+    pub fn contains(&self, uri: &str) -> bool {
+        self.modules.lock().unwrap().contains_key(uri)
+    }
+
+    /// Returns the `limit` symbols which match `query` best, as scored by `fuzzy::score`. Equally
+    /// good matches are ordered by name and then by uri.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<SymbolInformation> {
+        let modules = self.modules.lock().unwrap();
+        let mut matches: Vec<_> = modules.iter()
+            .flat_map(|(uri, symbols)| symbols.iter().map(move |symbol| (uri, symbol)))
+            .filter_map(|(uri, symbol)| {
+                fuzzy::score(query, &symbol.name).map(|score| (score, uri, symbol))
+            })
+            .collect();
+        let position = |symbol: &IndexedSymbol| {
+            (symbol.range.start.line, symbol.range.start.character)
+        };
+        matches.sort_by(|l, r| {
+            (r.0, &l.2.name, l.1, position(l.2)).cmp(&(l.0, &r.2.name, r.1, position(r.2)))
+        });
+        matches.into_iter()
+            .take(limit)
+            .map(|(_, uri, symbol)| {
+                SymbolInformation {
+                    name: symbol.name.clone(),
+                    kind: symbol.kind,
+                    location: Location {
+                        uri: uri.clone(),
+                        range: symbol.range,
+                    },
+                    container_name: symbol.container.clone(),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use language_server::{Location, Position, Range, SymbolInformation, SymbolKind};
+
+    fn symbol(name: &str, line: u64) -> SymbolInformation {
+        let position = Position {
+            line: line,
+            character: 4,
+        };
+        SymbolInformation {
+            name: name.into(),
+            kind: SymbolKind::Variable,
+            location: Location {
+                uri: String::new(),
+                range: Range {
+                    start: position,
+                    end: position,
+                },
+            },
+            container_name: String::new(),
+        }
+    }
+
+    fn names(symbols: Vec<SymbolInformation>) -> Vec<(String, String)> {
+        symbols.into_iter().map(|symbol| (symbol.name, symbol.location.uri)).collect()
+    }
+
+    #[test]
+    fn updates_replace_the_symbols_of_a_module() {
+        let index = SymbolIndex::new();
+        index.update("file:///a.glu", vec![symbol("fold_left", 0), symbol("map", 1)]);
+        index.update("file:///b.glu", vec![symbol("filter", 0)]);
+        assert_eq!(names(index.search("fold", 10)),
+                   vec![("fold_left".into(), "file:///a.glu".into())]);
+
+        index.update("file:///a.glu", vec![symbol("flat_map", 0)]);
+        assert_eq!(names(index.search("flat", 10)),
+                   vec![("flat_map".into(), "file:///a.glu".into())]);
+        assert_eq!(names(index.search("", 1)),
+                   vec![("filter".into(), "file:///b.glu".into())]);
+
+        index.remove("file:///a.glu");
+        assert!(!index.contains("file:///a.glu"));
+        assert_eq!(index.search("map", 10).len(), 0);
+    }
+}
@@ -0,0 +1,239 @@
+//! Pretty printing of gluon ASTs, used to answer `gluon/syntaxTree` requests
+use std::fmt::Write;
+
+use gluon::base::ast::{self, Expr, LExpr, LPattern, LiteralEnum, Pattern};
+use gluon::base::types::TcIdent;
+
+use language_server::Range;
+use query::{before, expr_range};
+
+struct Printer {
+    range: Option<Range>,
+    depth: usize,
+    out: String,
+}
+
+impl Printer {
+    fn overlaps(&self, range: &Range) -> bool {
+        match self.range {
+            Some(ref filter) => {
+                !before(&range.end, &filter.start) && !before(&filter.end, &range.start)
+            }
+            None => true,
+        }
+    }
+
+    fn line(&mut self, label: &str, range: &Range) {
+        for _ in 0..self.depth {
+            self.out.push_str("  ");
+        }
+        writeln!(self.out,
+                 "{}@{}:{}..{}:{}",
+                 label,
+                 range.start.line,
+                 range.start.character,
+                 range.end.line,
+                 range.end.character)
+            .unwrap();
+    }
+
+    fn node<F>(&mut self, label: &str, range: &Range, f: F)
+        where F: FnOnce(&mut Printer)
+    {
+        self.line(label, range);
+        self.depth += 1;
+        f(self);
+        self.depth -= 1;
+    }
+
+    fn pattern(&mut self, pattern: &LPattern<TcIdent>) {
+        let range = ::span_to_range(&pattern.span());
+        if !self.overlaps(&range) {
+            return;
+        }
+        let label = match pattern.value {
+            Pattern::Identifier(ref id) => format!("IdentifierPattern `{}` : {}", id.name, id.typ),
+            Pattern::Constructor(ref id, ref args) => {
+                let mut label = format!("ConstructorPattern `{}`", id.name);
+                for arg in args {
+                    write!(label, " {}", arg.name).unwrap();
+                }
+                label
+            }
+            Pattern::Record { ref fields, .. } => {
+                let mut label = String::from("RecordPattern");
+                for &(ref field, ref alias) in fields {
+                    match *alias {
+                        Some(ref alias) => write!(label, " {} = {}", field, alias).unwrap(),
+                        None => write!(label, " {}", field).unwrap(),
+                    }
+                }
+                label
+            }
+        };
+        self.line(&label, &range);
+    }
+
+    fn expr(&mut self, expr: &LExpr<TcIdent>) {
+        let range = expr_range(expr);
+        if !self.overlaps(&range) {
+            return;
+        }
+        match expr.value {
+            Expr::Identifier(ref id) => {
+                self.line(&format!("Identifier `{}` : {}", id.name, id.typ), &range)
+            }
+            Expr::Literal(ref lit) => {
+                let label = match *lit {
+                    LiteralEnum::Byte(b) => format!("Literal {}b", b),
+                    LiteralEnum::Integer(i) => format!("Literal {}", i),
+                    LiteralEnum::Float(f) => format!("Literal {}", f),
+                    LiteralEnum::String(ref s) => format!("Literal {:?}", s),
+                    LiteralEnum::Char(c) => format!("Literal {:?}", c),
+                };
+                self.line(&label, &range)
+            }
+            Expr::Call(ref func, ref args) => {
+                self.node("Call", &range, |p| {
+                    p.expr(func);
+                    for arg in args {
+                        p.expr(arg);
+                    }
+                })
+            }
+            Expr::IfElse(ref pred, ref if_true, ref if_false) => {
+                self.node("IfElse", &range, |p| {
+                    p.expr(pred);
+                    p.expr(if_true);
+                    if let Some(ref if_false) = *if_false {
+                        p.expr(if_false);
+                    }
+                })
+            }
+            Expr::Match(ref scrutinee, ref alts) => {
+                self.node("Match", &range, |p| {
+                    p.expr(scrutinee);
+                    for alt in alts {
+                        let range = ::span_to_range(&alt.pattern.span());
+                        p.node("Alternative", &range, |p| {
+                            p.pattern(&alt.pattern);
+                            p.expr(&alt.expression);
+                        });
+                    }
+                })
+            }
+            Expr::BinOp(ref lhs, ref op, ref rhs) => {
+                self.node(&format!("BinOp `{}` : {}", op.name, op.typ), &range, |p| {
+                    p.expr(lhs);
+                    p.expr(rhs);
+                })
+            }
+            Expr::Let(ref bindings, ref body) => {
+                self.node("Let", &range, |p| {
+                    for bind in bindings {
+                        let mut label = String::from("Binding");
+                        for arg in &bind.arguments {
+                            write!(label, " {}", arg.name).unwrap();
+                        }
+                        if let Some(ref typ) = bind.typ {
+                            write!(label, " : {}", typ).unwrap();
+                        }
+                        let range = ::span_to_range(&bind.expression
+                            .span(&ast::EmptyEnv::new()));
+                        p.node(&label, &range, |p| {
+                            p.pattern(&bind.name);
+                            p.expr(&bind.expression);
+                        });
+                    }
+                    p.expr(body);
+                })
+            }
+            Expr::FieldAccess(ref record, ref field) => {
+                self.node(&format!("FieldAccess `{}` : {}", field.name, field.typ),
+                          &range,
+                          |p| p.expr(record))
+            }
+            Expr::Array(ref array) => {
+                self.node(&format!("Array : {}", array.id.typ), &range, |p| {
+                    for expr in &array.expressions {
+                        p.expr(expr);
+                    }
+                })
+            }
+            Expr::Record { ref typ, ref types, ref exprs } => {
+                self.node(&format!("Record : {}", typ.typ), &range, |p| {
+                    for &(ref name, ref typ) in types {
+                        match *typ {
+                            Some(ref typ) => {
+                                p.line(&format!("TypeField `{}` = {}", name, typ), &range)
+                            }
+                            None => p.line(&format!("TypeField `{}`", name), &range),
+                        }
+                    }
+                    for &(ref name, ref expr) in exprs {
+                        match *expr {
+                            Some(ref expr) => {
+                                let field_range =
+                                    ::span_to_range(&expr.span(&ast::EmptyEnv::new()));
+                                p.node(&format!("Field `{}`", name),
+                                       &field_range,
+                                       |p| p.expr(expr))
+                            }
+                            None => p.line(&format!("Field `{}` (punned)", name), &range),
+                        }
+                    }
+                })
+            }
+            Expr::Lambda(ref lambda) => {
+                let mut label = String::from("Lambda");
+                for arg in &lambda.arguments {
+                    write!(label, " {}", arg.name).unwrap();
+                }
+                write!(label, " : {}", lambda.id.typ).unwrap();
+                self.node(&label, &range, |p| p.expr(&lambda.body))
+            }
+            Expr::Tuple(ref exprs) => {
+                self.node("Tuple", &range, |p| {
+                    for expr in exprs {
+                        p.expr(expr);
+                    }
+                })
+            }
+            Expr::Type(ref bindings, ref body) => {
+                self.node("Type", &range, |p| {
+                    for bind in bindings {
+                        let mut label = format!("TypeBinding `{}`", bind.name);
+                        for arg in &bind.alias.args {
+                            write!(label, " {}", arg.id).unwrap();
+                        }
+                        match bind.alias.typ {
+                            Some(ref typ) => write!(label, " = {}", typ).unwrap(),
+                            None => label.push_str(" = <abstract>"),
+                        }
+                        p.line(&label, &range);
+                    }
+                    p.expr(body);
+                })
+            }
+            Expr::Block(ref exprs) => {
+                self.node("Block", &range, |p| {
+                    for expr in exprs {
+                        p.expr(expr);
+                    }
+                })
+            }
+        }
+    }
+}
+
+/// Renders `expr` as an indented tree with one node per line. If `range` is given only the nodes
+/// which overlap it are included.
+pub fn print(expr: &LExpr<TcIdent>, range: Option<Range>) -> String {
+    let mut printer = Printer {
+        range: range,
+        depth: 0,
+        out: String::new(),
+    };
+    printer.expr(expr);
+    printer.out
+}
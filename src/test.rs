@@ -0,0 +1,337 @@
+//! A client which runs the language server in the same process. `Server` talks to the server the
+//! way an editor does, so tests of the server and of editor extensions can script whole sessions
+//! without starting a separate process.
+//!
+//! ```ignore
+//! let mut server = Server::start();
+//! try!(server.initialize(Some(Path::new("."))));
+//! try!(server.open("file:///test.glu", "let x = 1 in x"));
+//! let diagnostics: PublishDiagnosticsParams =
+//!     try!(server.wait_for_notification("textDocument/publishDiagnostics"));
+//! ```
+use std::collections::VecDeque;
+use std::error::Error as StdError;
+use std::io::{self, BufRead, Read};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{self, Value};
+use serde_json::builder::ObjectBuilder;
+use serde_json::value::{from_value, to_value};
+
+use clock::{Clock, SystemClock, VirtualClock};
+use session::{self, Step};
+use transport;
+
+pub use debounce::DEFAULT_DELAY_MS as DIAGNOSTICS_DELAY_MS;
+
+/// How long `Server` waits for a message before it gives up
+pub const DEFAULT_TIMEOUT_MS: u64 = 30_000;
+
+/// The input of the server, which is written by `Server`. Input ends once `Server` is dropped.
+struct ChannelReader {
+    receiver: Receiver<Vec<u8>>,
+    buffer: Vec<u8>,
+    position: usize,
+}
+
+impl Read for ChannelReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = {
+            let available = try!(self.fill_buf());
+            let n = ::std::cmp::min(available.len(), buf.len());
+            buf[..n].copy_from_slice(&available[..n]);
+            n
+        };
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl BufRead for ChannelReader {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        while self.position == self.buffer.len() {
+            match self.receiver.recv() {
+                Ok(bytes) => {
+                    self.buffer = bytes;
+                    self.position = 0;
+                }
+                Err(_) => break,
+            }
+        }
+        Ok(&self.buffer[self.position..])
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.position += amount;
+    }
+}
+
+/// A language server running on a thread of the current process
+pub struct Server {
+    input: Option<Sender<Vec<u8>>>,
+    output: Receiver<String>,
+    /// Messages which have been received while waiting for another message
+    pending: VecDeque<Value>,
+    next_id: u64,
+    timeout: Duration,
+    /// Set if the server runs on virtual time
+    clock: Option<Arc<VirtualClock>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Server {
+    /// Starts a server. Like an editor, the caller should send `initialize` before anything else.
+    pub fn start() -> Server {
+        Server::start_with_clock(Arc::new(SystemClock), None)
+    }
+
+    /// Starts a server whose time stands still until it is moved with `advance`, so that the work
+    /// which the server delays, such as diagnostics, happens at a known point in a test
+    pub fn with_virtual_time() -> Server {
+        let clock = Arc::new(VirtualClock::new());
+        Server::start_with_clock(clock.clone(), Some(clock))
+    }
+
+    fn start_with_clock(clock: Arc<Clock>, virtual_clock: Option<Arc<VirtualClock>>) -> Server {
+        let (input, receiver) = mpsc::channel();
+        let (sender, output) = mpsc::channel();
+        let sender = Mutex::new(sender);
+        let write: transport::Output = Arc::new(move |message: &str| {
+            let _ = sender.lock().unwrap().send(message.into());
+        });
+        let reader = ChannelReader {
+            receiver: receiver,
+            buffer: Vec::new(),
+            position: 0,
+        };
+        let handle = thread::spawn(move || {
+            transport::with_output(write, || ::serve(reader, None, clock))
+        });
+        Server {
+            input: Some(input),
+            output: output,
+            pending: VecDeque::new(),
+            next_id: 1,
+            timeout: Duration::from_millis(DEFAULT_TIMEOUT_MS),
+            clock: virtual_clock,
+            handle: Some(handle),
+        }
+    }
+
+    /// Advances the virtual time of the server by `duration` and runs the work which is then due.
+    /// Returns an error if the server was not started `with_virtual_time`.
+    pub fn advance(&mut self, duration: Duration) -> Result<(), Box<StdError>> {
+        match self.clock {
+            Some(ref clock) => clock.advance(duration),
+            None => return Err("The server does not run on virtual time".into()),
+        }
+        // Any message wakes the main loop, and notifications starting with `$/` may be ignored
+        self.notify("$/advanceTime", &Value::Null)
+    }
+
+    /// Sets how long to wait for a response or notification before returning an error
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    fn send(&mut self, message: Value) -> Result<(), Box<StdError>> {
+        let content = message.to_string();
+        let bytes = format!("Content-Length: {}\r\n\r\n{}", content.len(), content).into_bytes();
+        let sent = self.input.as_ref().map_or(false, |input| input.send(bytes).is_ok());
+        if sent {
+            Ok(())
+        } else {
+            Err("The server has stopped".into())
+        }
+    }
+
+    /// Returns the next message which `is_wanted`, keeping the messages before it for later
+    fn receive<F>(&mut self, description: &str, is_wanted: F) -> Result<Value, Box<StdError>>
+        where F: Fn(&Value) -> bool
+    {
+        if let Some(i) = self.pending.iter().position(&is_wanted) {
+            return Ok(self.pending.remove(i).unwrap());
+        }
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(format!("Timed out waiting for {}", description).into());
+            }
+            let message = match self.output.recv_timeout(deadline - now) {
+                Ok(message) => message,
+                Err(RecvTimeoutError::Timeout) => continue,
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(format!("The server stopped before sending {}", description)
+                        .into())
+                }
+            };
+            let message = try!(serde_json::from_str::<Value>(&message));
+            if is_wanted(&message) {
+                return Ok(message);
+            }
+            self.pending.push_back(message);
+        }
+    }
+
+    /// Sends the request `method` and waits for its result
+    pub fn request<P, R>(&mut self, method: &str, params: &P) -> Result<R, Box<StdError>>
+        where P: Serialize,
+              R: Deserialize
+    {
+        let id = self.next_id;
+        self.next_id += 1;
+        try!(self.send(ObjectBuilder::new()
+            .insert("jsonrpc", "2.0")
+            .insert("id", id)
+            .insert("method", method)
+            .insert("params", to_value(params))
+            .unwrap()));
+        let response = try!(self.receive(&format!("the response to `{}`", method), |message| {
+            message.find("id").and_then(|id| id.as_u64()) == Some(id)
+        }));
+        if let Some(error) = response.find("error") {
+            return Err(format!("`{}` failed: {}", method, error).into());
+        }
+        let result = response.find("result").cloned().unwrap_or(Value::Null);
+        Ok(try!(from_value(result)))
+    }
+
+    /// Sends the notification `method`
+    pub fn notify<P>(&mut self, method: &str, params: &P) -> Result<(), Box<StdError>>
+        where P: Serialize
+    {
+        self.send(ObjectBuilder::new()
+            .insert("jsonrpc", "2.0")
+            .insert("method", method)
+            .insert("params", to_value(params))
+            .unwrap())
+    }
+
+    /// Waits for the next notification `method` from the server and returns its parameters
+    pub fn wait_for_notification<R>(&mut self, method: &str) -> Result<R, Box<StdError>>
+        where R: Deserialize
+    {
+        let notification = try!(self.receive(&format!("`{}`", method), |message| {
+            message.find("id").is_none() &&
+            message.find("method").and_then(|m| m.as_string()) == Some(method)
+        }));
+        let params = notification.find("params").cloned().unwrap_or(Value::Null);
+        Ok(try!(from_value(params)))
+    }
+
+    /// Waits for the next request `method` from the server and returns its id and parameters
+    pub fn wait_for_request<R>(&mut self, method: &str) -> Result<(Value, R), Box<StdError>>
+        where R: Deserialize
+    {
+        let request = try!(self.receive(&format!("the request `{}`", method), |message| {
+            message.find("id").is_some() &&
+            message.find("method").and_then(|m| m.as_string()) == Some(method)
+        }));
+        let id = request.find("id").cloned().unwrap_or(Value::Null);
+        let params = request.find("params").cloned().unwrap_or(Value::Null);
+        Ok((id, try!(from_value(params))))
+    }
+
+    /// Responds to the request `id` from the server with `result`
+    pub fn respond<R>(&mut self, id: Value, result: &R) -> Result<(), Box<StdError>>
+        where R: Serialize
+    {
+        self.send(ObjectBuilder::new()
+            .insert("jsonrpc", "2.0")
+            .insert("id", id)
+            .insert("result", to_value(result))
+            .unwrap())
+    }
+
+    /// Returns the messages which have been received but not asked for, such as log messages
+    pub fn take_pending(&mut self) -> Vec<Value> {
+        while let Ok(message) = self.output.try_recv() {
+            if let Ok(message) = serde_json::from_str(&message) {
+                self.pending.push_back(message);
+            }
+        }
+        self.pending.drain(..).collect()
+    }
+
+    /// Sends `initialize` with `root_path` as the root of the workspace
+    pub fn initialize(&mut self, root_path: Option<&Path>) -> Result<Value, Box<StdError>> {
+        let params = ObjectBuilder::new()
+            .insert("processId", Value::Null)
+            .insert("rootPath",
+                    root_path.map(|path| path.to_string_lossy().into_owned()))
+            .insert("capabilities", ObjectBuilder::new().unwrap())
+            .unwrap();
+        self.request("initialize", &params)
+    }
+
+    /// Opens the gluon document `uri` with `text` as its contents
+    pub fn open(&mut self, uri: &str, text: &str) -> Result<(), Box<StdError>> {
+        let params = ObjectBuilder::new()
+            .insert_object("textDocument", |document| {
+                document.insert("uri", uri)
+                    .insert("languageId", "gluon")
+                    .insert("version", 1)
+                    .insert("text", text)
+            })
+            .unwrap();
+        self.notify("textDocument/didOpen", &params)
+    }
+
+    /// Replaces the contents of the open document `uri` with `text`, making it `version`
+    pub fn change(&mut self, uri: &str, version: u64, text: &str) -> Result<(), Box<StdError>> {
+        let params = ObjectBuilder::new()
+            .insert_object("textDocument",
+                           |document| document.insert("uri", uri).insert("version", version))
+            .insert_array("contentChanges",
+                          |changes| changes.push_object(|change| change.insert("text", text)))
+            .unwrap();
+        self.notify("textDocument/didChange", &params)
+    }
+
+    /// Sends `shutdown` and `exit` and waits for the server to stop
+    pub fn shutdown(mut self) -> Result<(), Box<StdError>> {
+        try!(self.request::<_, Value>("shutdown", &Value::Null));
+        try!(self.notify("exit", &Value::Null));
+        self.input.take();
+        match self.handle.take().map(|handle| handle.join()) {
+            Some(Err(_)) => Err("The server panicked".into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Replays the session recorded in `fixture` with the workspace root placeholders replaced by
+/// `root`, returning an error at the first message from the server which differs from the
+/// recorded one
+pub fn replay(fixture: &Path, root: &Path) -> Result<(), Box<StdError>> {
+    let mut server = Server::start();
+    for step in try!(session::read(fixture, root)) {
+        match step {
+            Step::Send(message) => try!(server.send(message)),
+            Step::Expect(expected) => {
+                let description = format!("`{}`", expected);
+                let actual = try!(server.receive(&description, |message| {
+                    session::is_counterpart(&expected, message)
+                }));
+                if !session::matches(&expected, &actual) {
+                    return Err(format!("Expected\n{}\nbut the server sent\n{}", expected, actual)
+                        .into());
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        // Ending the input stops the server
+        self.input.take();
+    }
+}
@@ -0,0 +1,119 @@
+//! The comments which mark work that is left to do, such as `// TODO: handle errors`. A marker
+//! only counts as a word of its own, so `TODOS` is not a `TODO`, and the markers inside string
+//! literals are not in comments.
+use language_server::{Position, Range};
+
+pub const DEFAULT_MARKERS: &'static [&'static str] = &["TODO", "FIXME", "HACK"];
+
+pub struct Todo {
+    /// The range from the marker to the end of its line in the comment
+    pub range: Range,
+    /// The text of the comment from the marker to the end of its line
+    pub text: String,
+}
+
+fn position(source: &str, offset: usize) -> Position {
+    let before = &source[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    Position {
+        line: before.matches('\n').count() as u64,
+        character: before[line_start..].chars().count() as u64,
+    }
+}
+
+/// Returns the start and end of the text of each comment of `source`, without the `//`, `/*` and
+/// `*/` around it
+fn comments(source: &str) -> Vec<(usize, usize)> {
+    let bytes = source.as_bytes();
+    let mut comments = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'"' {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+                i += 1;
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                let end = source[i..].find('\n').map_or(source.len(), |n| i + n);
+                comments.push((i + 2, end));
+                i = end;
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                let end = source[i + 2..].find("*/").map_or(source.len(), |n| i + 2 + n);
+                comments.push((i + 2, end));
+                i = end + 2;
+            }
+            _ => i += 1,
+        }
+    }
+    comments
+}
+
+fn is_word_char(c: char) -> bool {
+    c == '_' || c.is_alphanumeric()
+}
+
+/// Returns the comments of `source` with one of `markers` in the order of the markers in
+/// `source`
+pub fn todos(source: &str, markers: &[String]) -> Vec<Todo> {
+    let mut found = Vec::new();
+    for (start, end) in comments(source) {
+        let comment = &source[start..end];
+        for marker in markers.iter().filter(|marker| !marker.is_empty()) {
+            for (i, _) in comment.match_indices(&marker[..]) {
+                let before = comment[..i].chars().next_back();
+                let after = comment[i + marker.len()..].chars().next();
+                if before.map_or(false, is_word_char) || after.map_or(false, is_word_char) {
+                    continue;
+                }
+                let line_end = comment[i..].find('\n').map_or(comment.len(), |n| i + n);
+                let text = comment[i..line_end].trim_right();
+                found.push((start + i, text));
+            }
+        }
+    }
+    found.sort_by_key(|&(offset, _)| offset);
+    found.into_iter()
+        .map(|(offset, text)| {
+            Todo {
+                range: Range {
+                    start: position(source, offset),
+                    end: position(source, offset + text.len()),
+                },
+                text: text.into(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn markers() -> Vec<String> {
+        DEFAULT_MARKERS.iter().map(|&marker| marker.into()).collect()
+    }
+
+    #[test]
+    fn find_markers_in_comments() {
+        let source = "// FIXME: slow\nlet x = \"// TODO\" // TODOS\n/* a\n   HACK around  \n*/ x";
+        let found = todos(source, &markers());
+        let texts: Vec<_> = found.iter().map(|todo| &todo.text[..]).collect();
+        assert_eq!(texts, vec!["FIXME: slow", "HACK around"]);
+        let range = found[1].range;
+        assert_eq!((range.start.line, range.start.character, range.end.character), (3, 3, 14));
+    }
+
+    #[test]
+    fn configured_markers() {
+        let source = "let x = 1 // XXX remove, TODO: later";
+        assert_eq!(todos(source, &["XXX".into()]).len(), 1);
+        assert!(todos(source, &[]).is_empty());
+        let found = todos(source, &markers());
+        assert_eq!(found[0].text, "TODO: later");
+        assert_eq!(found[0].range.start.character, 25);
+    }
+}
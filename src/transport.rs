@@ -0,0 +1,61 @@
+//! The connection which messages to the client are written to. The server normally writes to
+//! stdout, but a different output can be installed for the thread that runs the server, which is
+//! how `test::Server` receives the messages of a server running in the same process. The threads
+//! the server starts must be started with `spawn` so that they write to the same output.
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+pub type Output = Arc<Fn(&str) + Send + Sync>;
+
+thread_local!(static OUTPUT: RefCell<Option<Output>> = RefCell::new(None));
+
+fn current() -> Option<Output> {
+    OUTPUT.with(|output| output.borrow().clone())
+}
+
+/// Runs `f` with the messages written on the current thread, and on the threads it spawns, passed
+/// to `output` instead of stdout
+pub fn with_output<F, R>(output: Output, f: F) -> R
+    where F: FnOnce() -> R
+{
+    let previous = OUTPUT.with(|current| current.borrow_mut().take());
+    OUTPUT.with(|current| *current.borrow_mut() = Some(output));
+    let result = f();
+    OUTPUT.with(|current| *current.borrow_mut() = previous);
+    result
+}
+
+/// Spawns a thread which writes its messages to the same output as the current thread
+pub fn spawn<F, T>(f: F) -> JoinHandle<T>
+    where F: FnOnce() -> T + Send + 'static,
+          T: Send + 'static
+{
+    match current() {
+        Some(output) => thread::spawn(move || with_output(output, f)),
+        None => thread::spawn(f),
+    }
+}
+
+/// Writes `message` to `writer` together with its header
+pub fn write_to<W>(writer: &mut W, message: &str) -> io::Result<()>
+    where W: Write
+{
+    try!(write!(writer, "Content-Length: {}\r\n\r\n{}", message.len(), message));
+    writer.flush()
+}
+
+/// Writes `message` to stdout together with its header
+pub fn write_stdout(message: &str) {
+    let stdout = io::stdout();
+    let _ = write_to(&mut stdout.lock(), message);
+}
+
+/// Writes `message` to the output of the current thread
+pub fn write_message(message: &str) {
+    match current() {
+        Some(output) => output(message),
+        None => write_stdout(message),
+    }
+}
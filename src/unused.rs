@@ -0,0 +1,148 @@
+//! Finding the imports which a module does not use. The `import` macro is replaced by a variable
+//! named after the module it imports before the module is typechecked, so a binding whose
+//! expression is such a variable is an import and the variables it binds are imported.
+use gluon::base::ast::{Binding, Expr, LExpr, Pattern};
+use gluon::base::symbol::Symbol;
+use gluon::base::types::TcIdent;
+
+use language_server::{Range, TextEdit};
+use move_binding::{after_in, in_after, keyword_before};
+use pipeline::{close_brackets, identifier_len, offset, position, skip_trivia};
+use query;
+use references;
+
+/// An import, or one of the names which a record pattern imports, which is not used
+pub struct UnusedImport {
+    pub name: String,
+    pub range: Range,
+    /// The edit which removes the import, unless it is bound together with other bindings by
+    /// `and`
+    pub removal: Option<TextEdit>,
+}
+
+fn range(source: &str, start: usize, end: usize) -> Range {
+    Range {
+        start: position(source, start),
+        end: position(source, end),
+    }
+}
+
+/// Returns true if `entry`, a field of a record pattern such as `x` or `x = y`, is the field `name`
+fn is_field(entry: &str, name: &str) -> bool {
+    &entry[..identifier_len(entry)] == name
+}
+
+/// Returns the edit which removes the binding whose pattern starts at `start` and ends at `end`
+/// if it is the only one of `bindings`
+fn binding_removal(source: &str,
+                   bindings: &[Binding<TcIdent>],
+                   start: usize,
+                   end: usize)
+                   -> Option<TextEdit> {
+    if bindings.len() != 1 {
+        return None;
+    }
+    let let_start = match keyword_before(source, start, "let") {
+        Some(let_start) => let_start,
+        None => return None,
+    };
+    in_after(source, end).map(|in_start| {
+        let line = source[..let_start].rfind('\n').map_or(0, |i| i + 1);
+        // A binding on a line of its own is removed together with its indentation
+        let start = if source[line..let_start].trim().is_empty() { line } else { let_start };
+        TextEdit {
+            range: range(source, start, after_in(source, in_start)),
+            new_text: String::new(),
+        }
+    })
+}
+
+/// Returns the imports of the typechecked module `expr`, which is parsed from `source`, which
+/// are never used. `is_module` tells if a variable is one which replaced an `import`.
+pub fn imports<F>(source: &str, expr: &LExpr<TcIdent>, is_module: &F) -> Vec<UnusedImport>
+    where F: Fn(&str) -> bool
+{
+    let mut unused = Vec::new();
+    if let Expr::Let(ref bindings, ref body) = expr.value {
+        // The bindings of a group may use each other
+        let used = |name: &Symbol| {
+            !references::uses(body, name).is_empty() ||
+            bindings.iter().any(|bind| !references::uses(&bind.expression, name).is_empty())
+        };
+        for bind in bindings {
+            let module = match bind.expression.value {
+                Expr::Identifier(ref id) if is_module(id.name.as_ref()) => id.name.as_ref(),
+                _ => continue,
+            };
+            let start = match offset(source, &bind.name.location) {
+                Some(start) => skip_trivia(source, start),
+                None => continue,
+            };
+            match bind.name.value {
+                Pattern::Identifier(ref id) if !used(&id.name) => {
+                    let end = start + identifier_len(&source[start..]);
+                    unused.push(UnusedImport {
+                        name: id.name.declared_name().into(),
+                        range: range(source, start, end),
+                        removal: binding_removal(source, bindings, start, end),
+                    });
+                }
+                Pattern::Record { ref types, ref fields, .. } => {
+                    let is_unused = |&&(ref field, ref alias): &&(Symbol, Option<Symbol>)| {
+                        !used(alias.as_ref().unwrap_or(field))
+                    };
+                    let unused_fields: Vec<_> = fields.iter().filter(&is_unused).collect();
+                    if unused_fields.is_empty() || !source[start..].starts_with('{') {
+                        continue;
+                    }
+                    let end = match close_brackets(source, start, start + 1) {
+                        Some(end) => end,
+                        None => continue,
+                    };
+                    // Nothing of the module is used so the whole import is unused
+                    if unused_fields.len() == fields.len() && types.is_empty() {
+                        unused.push(UnusedImport {
+                            name: module.into(),
+                            range: range(source, start, end),
+                            removal: binding_removal(source, bindings, start, end),
+                        });
+                        continue;
+                    }
+                    // The fields of the pattern with the offset of each
+                    let mut entries = Vec::new();
+                    let mut next = start + 1;
+                    for entry in source[start + 1..end - 1].split(',') {
+                        let trimmed = entry.trim_left();
+                        entries.push((next + entry.len() - trimmed.len(), trimmed.trim_right()));
+                        next += entry.len() + 1;
+                    }
+                    for &&(ref field, _) in &unused_fields {
+                        let name = field.declared_name();
+                        let (entry_start, entry) =
+                            match entries.iter().find(|&&(_, entry)| is_field(entry, name)) {
+                                Some(&found) => found,
+                                None => continue,
+                            };
+                        let rest: Vec<_> = entries.iter()
+                            .map(|&(_, entry)| entry)
+                            .filter(|entry| !entry.is_empty() && !is_field(entry, name))
+                            .collect();
+                        unused.push(UnusedImport {
+                            name: name.into(),
+                            range: range(source, entry_start, entry_start + entry.len()),
+                            removal: Some(TextEdit {
+                                range: range(source, start, end),
+                                new_text: format!("{{ {} }}", rest.join(", ")),
+                            }),
+                        });
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+    for child in query::children(expr) {
+        unused.extend(imports(source, child, is_module));
+    }
+    unused
+}
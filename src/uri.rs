@@ -0,0 +1,204 @@
+//! Conversion between `file` uris and paths. Clients differ in which characters they percent
+//! encode and in how they write Windows drive letters, so uris are normalized before they are used
+//! to look up documents and modules.
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const SCHEME: &'static str = "file://";
+
+/// True on platforms whose filesystems usually ignore the case of file names
+pub const CASE_INSENSITIVE: bool = cfg!(any(windows, target_os = "macos"));
+
+fn hex_value(byte: u8) -> Option<u8> {
+    (byte as char).to_digit(16).map(|digit| digit as u8)
+}
+
+fn is_ascii_letter(byte: u8) -> bool {
+    (b'a' <= byte && byte <= b'z') || (b'A' <= byte && byte <= b'Z')
+}
+
+/// Decodes the `%xx` escapes in `s`. Malformed escapes are kept as they are.
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            if let (Some(high), Some(low)) = (hex_value(bytes[i + 1]), hex_value(bytes[i + 2])) {
+                decoded.push(high * 16 + low);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Encodes every character of the path `s` except `/` and the unreserved characters of RFC 3986
+fn percent_encode(s: &str) -> String {
+    let mut encoded = String::with_capacity(s.len());
+    for &byte in s.as_bytes() {
+        let unreserved = is_ascii_letter(byte) || (b'0' <= byte && byte <= b'9') ||
+                         b"-._~/".contains(&byte);
+        if unreserved {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+/// Returns true if `path` starts with a drive letter such as `c:`
+fn has_drive_letter(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    bytes.len() >= 2 && is_ascii_letter(bytes[0]) && bytes[1] == b':' &&
+    (bytes.len() == 2 || bytes[2] == b'/' || bytes[2] == b'\\')
+}
+
+/// Returns true if `uri` refers to a file on disk rather than to a document which only exists in
+/// the client, such as an `untitled:` buffer
+pub fn is_file(uri: &str) -> bool {
+    uri.starts_with(SCHEME)
+}
+
+/// Returns the path of the `file` uri `uri`
+pub fn to_path(uri: &str) -> Option<PathBuf> {
+    if !is_file(uri) {
+        return None;
+    }
+    let rest = &uri[SCHEME.len()..];
+    let (authority, path) = match rest.find('/') {
+        Some(i) => (&rest[..i], percent_decode(&rest[i..])),
+        None => (rest, String::from("/")),
+    };
+    let path = if authority.is_empty() || authority == "localhost" {
+        // `/c:/dir` is the path `c:/dir`
+        if cfg!(windows) && has_drive_letter(&path[1..]) {
+            path[1..].into()
+        } else {
+            path
+        }
+    } else {
+        // A uri with a host refers to a UNC path
+        format!("//{}{}", percent_decode(authority), path)
+    };
+    if cfg!(windows) {
+        Some(PathBuf::from(path.replace('/', "\\")))
+    } else {
+        Some(PathBuf::from(path))
+    }
+}
+
+/// Returns the `file` uri of `path`. Drive letters are lowercased and the same characters are
+/// encoded as by VS Code, so a uri from VS Code is unchanged by `normalize`.
+pub fn from_path(path: &Path) -> String {
+    let mut path = path.to_string_lossy().into_owned();
+    if cfg!(windows) {
+        path = path.replace('\\', "/");
+    }
+    if path.starts_with("//") {
+        let (host, rest) = match path[2..].find('/') {
+            Some(i) => (&path[2..2 + i], &path[2 + i..]),
+            None => (&path[2..], ""),
+        };
+        return format!("{}{}{}", SCHEME, host.to_lowercase(), percent_encode(rest));
+    }
+    if has_drive_letter(&path) {
+        path = format!("/{}", path);
+    }
+    if path.len() > 1 && has_drive_letter(&path[1..]) {
+        path = format!("/{}{}", path[1..2].to_lowercase(), &path[2..]);
+    }
+    format!("{}{}", SCHEME, percent_encode(&path))
+}
+
+/// Normalizes the encoding of `uri`. Uris which are not `file` uris are returned unchanged.
+pub fn normalize(uri: &str) -> String {
+    to_path(uri).map_or_else(|| uri.into(), |path| from_path(&path))
+}
+
+/// Returns the normalized uri of the file which `uri` refers to once symlinks are resolved. Uris
+/// of files which don't exist and uris which are not `file` uris are only normalized.
+pub fn resolve(uri: &str) -> String {
+    let path = match to_path(uri).and_then(|path| fs::canonicalize(path).ok()) {
+        Some(path) => path,
+        None => return normalize(uri),
+    };
+    // Windows returns verbatim paths such as `\\?\C:\dir` and `\\?\UNC\server\share`
+    let path = path.to_string_lossy().into_owned();
+    let path = if path.starts_with(r"\\?\UNC\") {
+        format!(r"\\{}", &path[8..])
+    } else if path.starts_with(r"\\?\") {
+        path[4..].into()
+    } else {
+        path
+    };
+    from_path(Path::new(&path))
+}
+
+/// Returns true if the normalized uris `l` and `r` refer to the same file
+pub fn same_file(l: &str, r: &str) -> bool {
+    if CASE_INSENSITIVE {
+        l.to_lowercase() == r.to_lowercase()
+    } else {
+        l == r
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percent_encoded_characters() {
+        assert_eq!(normalize("file:///home/user/my%20project/a.glu"),
+                   "file:///home/user/my%20project/a.glu");
+        assert_eq!(normalize("file:///home/user/my project/a.glu"),
+                   "file:///home/user/my%20project/a.glu");
+        assert_eq!(normalize("file:///home/user/%C3%A5.glu"), "file:///home/user/%C3%A5.glu");
+        assert_eq!(normalize("file:///home/user/%c3%a5.glu"), "file:///home/user/%C3%A5.glu");
+    }
+
+    #[test]
+    fn drive_letters() {
+        assert_eq!(normalize("file:///C:/dir/a.glu"), "file:///c%3A/dir/a.glu");
+        assert_eq!(normalize("file:///c%3A/dir/a.glu"), "file:///c%3A/dir/a.glu");
+        assert_eq!(normalize("file:///C%3a/dir/a.glu"), "file:///c%3A/dir/a.glu");
+    }
+
+    #[test]
+    fn unc_paths() {
+        assert_eq!(normalize("file://Server/share/a.glu"), "file://server/share/a.glu");
+    }
+
+    #[test]
+    fn round_trip() {
+        let path = Path::new("/home/user/my project/a.glu");
+        assert_eq!(to_path(&from_path(path)).as_ref().map(|path| &**path), Some(path));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_are_resolved() {
+        use std::fs::{self, File};
+        use std::os::unix::fs::symlink;
+
+        let dir = ::std::env::temp_dir().join("gluon_language_server_uri_test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("src")).unwrap();
+        File::create(dir.join("src").join("a.glu")).unwrap();
+        symlink(dir.join("src"), dir.join("link")).unwrap();
+        let dir = fs::canonicalize(&dir).unwrap();
+        assert_eq!(resolve(&from_path(&dir.join("link").join("a.glu"))),
+                   from_path(&dir.join("src").join("a.glu")));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn other_schemes_are_unchanged() {
+        assert_eq!(normalize("untitled:Untitled-1"), "untitled:Untitled-1");
+    }
+}
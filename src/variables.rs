@@ -0,0 +1,142 @@
+//! Inspection of the values of a program which is stopped at a breakpoint
+use std::cmp;
+
+use gluon::Thread;
+use gluon::base::types::{TcType, Type, TypeEnv, arg_iter};
+use gluon::vm::Variants;
+use gluon::vm::internal::Value;
+use gluon::vm::thread::RootedValue;
+
+use debug_protocol::{Variable, VariablesArguments};
+use eval;
+
+/// Returns the names, values and types of the fields of a record or the arguments of a variant
+fn named_children(env: &TypeEnv, value: Value, typ: &TcType) -> Vec<(String, Value, TcType)> {
+    let typ = eval::remove_aliases(env, typ);
+    match (value, &*typ) {
+        (Value::Data(data), &Type::Record { ref fields, .. }) => {
+            fields.iter()
+                .zip(data.fields.iter())
+                .map(|(field, value)| {
+                    (String::from(field.name.declared_name()), *value, field.typ.clone())
+                })
+                .collect()
+        }
+        (Value::Data(data), &Type::Variants(ref variants)) => {
+            match variants.get(data.tag as usize) {
+                Some(variant) => {
+                    arg_iter(&variant.1)
+                        .zip(data.fields.iter())
+                        .enumerate()
+                        .map(|(i, (typ, value))| (i.to_string(), *value, typ.clone()))
+                        .collect()
+                }
+                None => vec![],
+            }
+        }
+        _ => vec![],
+    }
+}
+
+/// Returns the length and element type of `value` if it is an array
+fn indexed_children(env: &TypeEnv, value: Value, typ: &TcType) -> Option<(usize, TcType)> {
+    match (value, &*eval::remove_aliases(env, typ)) {
+        (Value::Array(array), &Type::Array(ref element_type)) => {
+            Some((array.len(), element_type.clone()))
+        }
+        _ => None,
+    }
+}
+
+/// Values which the client can expand. A value is referred to by its index plus one as zero
+/// means that a variable has no children.
+pub struct Handles<'vm> {
+    values: Vec<(Value, TcType)>,
+    /// Keeps the results of evaluations alive until the program continues
+    roots: Vec<RootedValue<&'vm Thread>>,
+}
+
+impl<'vm> Handles<'vm> {
+    pub fn new() -> Handles<'vm> {
+        Handles {
+            values: Vec::new(),
+            roots: Vec::new(),
+        }
+    }
+
+    /// Returns the reference which identifies `value`
+    pub fn insert(&mut self, value: Value, typ: TcType) -> i64 {
+        self.values.push((value, typ));
+        self.values.len() as i64
+    }
+
+    pub fn root(&mut self, value: RootedValue<&'vm Thread>) -> Value {
+        let raw = *value;
+        self.roots.push(value);
+        raw
+    }
+
+    pub fn variable(&mut self,
+                    env: &TypeEnv,
+                    name: String,
+                    value: Value,
+                    typ: &TcType)
+                    -> Variable {
+        let named = named_children(env, value, typ).len();
+        let indexed = indexed_children(env, value, typ).map(|(len, _)| len);
+        let formatted = match indexed {
+            Some(len) => format!("[...] ({} elements)", len),
+            None => {
+                let variants = unsafe { Variants::new(&value) };
+                eval::format_value(env, variants.as_ref(), typ)
+            }
+        };
+        let variables_reference = if named != 0 || indexed.map_or(false, |len| len != 0) {
+            self.insert(value, typ.clone())
+        } else {
+            0
+        };
+        Variable {
+            name: name,
+            value: formatted,
+            typ: format!("{}", typ),
+            variables_reference: variables_reference,
+            named_variables: if named != 0 { Some(named as i64) } else { None },
+            indexed_variables: indexed.map(|len| len as i64),
+        }
+    }
+
+    /// Returns the children of the value identified by `args.variables_reference`. Arrays are
+    /// paged using `args.start` and `args.count` so that long arrays are only read on demand.
+    pub fn children(&mut self,
+                    env: &TypeEnv,
+                    args: &VariablesArguments)
+                    -> Result<Vec<Variable>, String> {
+        let (value, typ) = match self.values.get((args.variables_reference - 1) as usize) {
+            Some(&(value, ref typ)) if args.variables_reference > 0 => (value, typ.clone()),
+            _ => return Err(format!("Unknown variable reference {}", args.variables_reference)),
+        };
+        let filter = args.filter.as_ref().map(|filter| &filter[..]);
+        let mut variables = Vec::new();
+        if filter != Some("indexed") {
+            for (name, value, typ) in named_children(env, value, &typ) {
+                variables.push(self.variable(env, name, value, &typ));
+            }
+        }
+        if filter != Some("named") {
+            if let (Value::Array(array), Some((len, element_type))) =
+                   (value, indexed_children(env, value, &typ)) {
+                let start = cmp::max(args.start.unwrap_or(0), 0) as usize;
+                let end = match args.count {
+                    Some(count) if count > 0 => cmp::min(len, start + count as usize),
+                    _ => len,
+                };
+                for i in start..end {
+                    let name = format!("[{}]", i);
+                    variables.push(self.variable(env, name, array.get(i), &element_type));
+                }
+            }
+        }
+        Ok(variables)
+    }
+}
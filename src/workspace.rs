@@ -0,0 +1,557 @@
+//! State about the workspace which is shared between the request handlers
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use gluon::filename_to_module;
+
+use analysis_cache::AnalysisCache;
+use bench;
+use clock::{Clock, SystemClock};
+use dead_code::ModuleUse;
+use debounce::Debouncer;
+use document::Document;
+use glob::Exclude;
+use language_server::{Diagnostic, Position, Range, TextDocumentContentChangeEvent};
+use memory::MemoryBudget;
+use metrics::Metrics;
+use parse_cache::ParseCache;
+use project::{self, LintLevel, Project};
+use status::Status;
+use symbol_index::SymbolIndex;
+use todo;
+use uri;
+
+/// The outcome of applying a `textDocument/didChange` notification
+pub enum Change {
+    Applied,
+    NotOpen,
+    /// The change was not newer than the stored version of the document, which is `current`.
+    /// Applying it would corrupt the text since its ranges refer to an older version.
+    Stale { current: u64 },
+}
+
+/// How much hovers show, set by the `gluon.hover` settings
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HoverSettings {
+    /// The number of characters after which types are cut off, or 0 if they are shown in full
+    pub max_type_length: usize,
+    /// How many times the aliases in types are replaced by the types they alias
+    pub alias_depth: usize,
+    /// Whether documentation comments are shown
+    pub documentation: bool,
+}
+
+impl Default for HoverSettings {
+    fn default() -> HoverSettings {
+        HoverSettings {
+            max_type_length: 0,
+            alias_depth: 0,
+            documentation: true,
+        }
+    }
+}
+
+pub struct Workspace {
+    clock: Arc<Clock>,
+    /// When the server started
+    started: Instant,
+    root_path: RwLock<Option<PathBuf>>,
+    /// The project declared by the manifest at the root of the workspace
+    project: RwLock<Project>,
+    /// The directories which have been added to the paths of the importer
+    import_paths: Mutex<Vec<PathBuf>>,
+    /// The files and directories which are not indexed
+    exclude: RwLock<Exclude>,
+    /// The workspace folders whose settings are requested from the client
+    folders: RwLock<Vec<String>>,
+    /// The lint levels of the `gluon.lints` setting of the client
+    lints: RwLock<HashMap<String, LintLevel>>,
+    /// The lint levels of the `gluon.lints` setting of each workspace folder
+    folder_lints: RwLock<HashMap<String, HashMap<String, LintLevel>>>,
+    /// The module name of each module of the workspace, by its uri, with the fields it uses of
+    /// other modules
+    module_uses: RwLock<HashMap<String, (String, Vec<ModuleUse>)>>,
+    /// The markers of the comments which are reported by the `todo` lint
+    todo_markers: RwLock<Vec<String>>,
+    /// How many times the `gluon.runBench` command runs a benchmark
+    bench_iterations: AtomicUsize,
+    /// Whether the gluon snippets in Rust documents are analysed, see `embedded`
+    embedded: AtomicBool,
+    /// Whether the client accepts snippets as the insert text of completion items
+    snippets: AtomicBool,
+    /// Whether functions are completed with placeholders for their arguments
+    call_snippets: AtomicBool,
+    /// Whether the client renders the contents of hovers as markdown
+    markdown_hover: AtomicBool,
+    /// Whether the client accepts document symbols nested in the symbols they are bound in
+    hierarchical_symbols: AtomicBool,
+    /// Whether the client can rename files as part of a workspace edit
+    rename_files: AtomicBool,
+    /// Whether the client can create files as part of a workspace edit
+    create_files: AtomicBool,
+    hover: RwLock<HoverSettings>,
+    /// The open documents and the version of their contents
+    documents: Mutex<HashMap<String, (u64, Document)>>,
+    /// The latest version of each document which the client has sent, which may be newer than
+    /// the stored version if the change has been received but not handled yet
+    announced_versions: Mutex<HashMap<String, u64>>,
+    /// The uri of each uri once symlinks are resolved, see `uri::resolve`
+    resolved_uris: Mutex<HashMap<String, String>>,
+    diagnostics: Debouncer,
+    parse_cache: ParseCache,
+    memory: MemoryBudget,
+    metrics: Metrics,
+    analysis_cache: AnalysisCache,
+    symbol_index: SymbolIndex,
+    status: Status,
+}
+
+impl Workspace {
+    pub fn new() -> Workspace {
+        Workspace::with_clock(Arc::new(SystemClock))
+    }
+
+    pub fn with_clock(clock: Arc<Clock>) -> Workspace {
+        Workspace {
+            clock: clock.clone(),
+            started: clock.now(),
+            root_path: RwLock::new(None),
+            project: RwLock::new(Project::default()),
+            import_paths: Mutex::new(Vec::new()),
+            exclude: RwLock::new(Exclude::default()),
+            folders: RwLock::new(Vec::new()),
+            lints: RwLock::new(HashMap::new()),
+            folder_lints: RwLock::new(HashMap::new()),
+            module_uses: RwLock::new(HashMap::new()),
+            todo_markers: RwLock::new(todo::DEFAULT_MARKERS
+                .iter()
+                .map(|&marker| marker.into())
+                .collect()),
+            bench_iterations: AtomicUsize::new(bench::DEFAULT_ITERATIONS),
+            embedded: AtomicBool::new(false),
+            snippets: AtomicBool::new(false),
+            call_snippets: AtomicBool::new(false),
+            markdown_hover: AtomicBool::new(false),
+            hierarchical_symbols: AtomicBool::new(false),
+            rename_files: AtomicBool::new(false),
+            create_files: AtomicBool::new(false),
+            hover: RwLock::new(HoverSettings::default()),
+            documents: Mutex::new(HashMap::new()),
+            announced_versions: Mutex::new(HashMap::new()),
+            resolved_uris: Mutex::new(HashMap::new()),
+            diagnostics: Debouncer::new(clock),
+            parse_cache: ParseCache::new(),
+            memory: MemoryBudget::new(),
+            metrics: Metrics::new(),
+            analysis_cache: AnalysisCache::new(),
+            symbol_index: SymbolIndex::new(),
+            status: Status::new(),
+        }
+    }
+
+    /// The time which the work of the server is scheduled by
+    pub fn clock(&self) -> &Clock {
+        &*self.clock
+    }
+
+    /// The documents whose diagnostics are waiting for the changes to them to settle
+    pub fn diagnostics(&self) -> &Debouncer {
+        &self.diagnostics
+    }
+
+    /// The parsed expressions of the documents and modules the server has typechecked
+    pub fn parse_cache(&self) -> &ParseCache {
+        &self.parse_cache
+    }
+
+    /// The memory used by the analysis results of each document
+    pub fn memory(&self) -> &MemoryBudget {
+        &self.memory
+    }
+
+    /// The latencies of the requests the server has handled
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    /// The diagnostics of the workspace modules which are kept between sessions
+    pub fn analysis_cache(&self) -> &AnalysisCache {
+        &self.analysis_cache
+    }
+
+    /// The bindings of the workspace modules which `workspace/symbol` searches
+    pub fn symbol_index(&self) -> &SymbolIndex {
+        &self.symbol_index
+    }
+
+    /// How long the server has been running
+    pub fn uptime(&self) -> Duration {
+        self.clock.now().duration_since(self.started)
+    }
+
+    /// What the server is doing, as reported to the client
+    pub fn status(&self) -> &Status {
+        &self.status
+    }
+
+    pub fn root_path(&self) -> Option<PathBuf> {
+        self.root_path.read().unwrap().clone()
+    }
+
+    pub fn set_root_path<P: Into<PathBuf>>(&self, path: P) {
+        *self.root_path.write().unwrap() = Some(path.into());
+    }
+
+    pub fn project(&self) -> Project {
+        self.project.read().unwrap().clone()
+    }
+
+    pub fn set_project(&self, project: Project) {
+        *self.project.write().unwrap() = project;
+    }
+
+    pub fn exclude(&self) -> Exclude {
+        self.exclude.read().unwrap().clone()
+    }
+
+    pub fn set_exclude(&self, exclude: Exclude) {
+        *self.exclude.write().unwrap() = exclude;
+    }
+
+    pub fn folders(&self) -> Vec<String> {
+        self.folders.read().unwrap().clone()
+    }
+
+    pub fn set_folders(&self, folders: Vec<String>) {
+        *self.folders.write().unwrap() = folders;
+    }
+
+    /// Sets the lint levels of the client's settings. Returns false if they are unchanged.
+    pub fn set_lints(&self, lints: HashMap<String, LintLevel>) -> bool {
+        let mut current = self.lints.write().unwrap();
+        if *current == lints {
+            return false;
+        }
+        *current = lints;
+        true
+    }
+
+    /// Sets the lint levels of the settings of the workspace folder `folder`
+    pub fn set_folder_lints(&self, folder: &str, lints: HashMap<String, LintLevel>) {
+        self.folder_lints.write().unwrap().insert(folder.into(), lints);
+    }
+
+    /// Applies the lint levels to the diagnostics of the document at `uri`. The levels of the
+    /// manifest are overridden by the client's settings, which are overridden by the settings of
+    /// the innermost workspace folder which contains the document.
+    pub fn apply_lints(&self, uri: &str, diagnostics: Vec<Diagnostic>) -> Vec<Diagnostic> {
+        let mut lints = self.project().lints;
+        lints.extend(self.lints.read().unwrap().clone());
+        let folder_lints = self.folder_lints.read().unwrap();
+        let folder = folder_lints.keys()
+            .filter(|folder| uri.starts_with(&format!("{}/", folder.trim_right_matches('/'))))
+            .max_by_key(|folder| folder.len());
+        if let Some(folder) = folder {
+            lints.extend(folder_lints[folder].clone());
+        }
+        project::apply_lints(&lints, diagnostics)
+    }
+
+    /// Sets the module name of every module of the workspace, by its uri, with the fields it uses
+    /// of other modules
+    pub fn set_module_uses(&self, module_uses: HashMap<String, (String, Vec<ModuleUse>)>) {
+        *self.module_uses.write().unwrap() = module_uses;
+    }
+
+    /// Updates the uses of other modules of the workspace module at `uri`. Returns false if they
+    /// are unchanged or if the document is not a module of the workspace.
+    pub fn update_module_uses(&self, uri: &str, uses: Vec<ModuleUse>) -> bool {
+        match self.module_uses.write().unwrap().get_mut(uri) {
+            Some(&mut (_, ref mut current)) if *current != uses => {
+                *current = uses;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns true if the field `field` of the module at `uri` may be used by another module.
+    /// Every field of an entry module, and of a module outside of the workspace, may be used.
+    pub fn is_export_used(&self, uri: &str, field: &str) -> bool {
+        let module_uses = self.module_uses.read().unwrap();
+        let module = match module_uses.get(uri) {
+            Some(&(ref module, _)) => module,
+            None => return true,
+        };
+        let entry_modules = self.project().entry_modules;
+        if entry_modules.contains(module) {
+            return true;
+        }
+        let mut imported = false;
+        for (_, &(_, ref uses)) in module_uses.iter().filter(|&(other, _)| other != uri) {
+            for module_use in uses.iter().filter(|module_use| module_use.module == *module) {
+                imported = true;
+                match module_use.field {
+                    Some(ref used) if used != field => (),
+                    _ => return true,
+                }
+            }
+        }
+        // The modules which no module imports are the entry modules unless they are declared
+        !imported && entry_modules.is_empty()
+    }
+
+    pub fn todo_markers(&self) -> Vec<String> {
+        self.todo_markers.read().unwrap().clone()
+    }
+
+    /// Sets the markers of the comments which are reported by the `todo` lint. Returns false if
+    /// they are unchanged.
+    pub fn set_todo_markers(&self, markers: Vec<String>) -> bool {
+        let mut current = self.todo_markers.write().unwrap();
+        if *current == markers {
+            return false;
+        }
+        *current = markers;
+        true
+    }
+
+    pub fn bench_iterations(&self) -> usize {
+        self.bench_iterations.load(Ordering::SeqCst)
+    }
+
+    pub fn set_bench_iterations(&self, iterations: usize) {
+        self.bench_iterations.store(iterations, Ordering::SeqCst)
+    }
+
+    pub fn embedded(&self) -> bool {
+        self.embedded.load(Ordering::SeqCst)
+    }
+
+    /// Enables or disables the analysis of gluon snippets in Rust documents. Returns false if the
+    /// setting is unchanged.
+    pub fn set_embedded(&self, embedded: bool) -> bool {
+        self.embedded.swap(embedded, Ordering::SeqCst) != embedded
+    }
+
+    pub fn snippets(&self) -> bool {
+        self.snippets.load(Ordering::SeqCst)
+    }
+
+    pub fn set_snippets(&self, snippets: bool) {
+        self.snippets.store(snippets, Ordering::SeqCst)
+    }
+
+    /// Returns true if functions should be completed with placeholders for their arguments, which
+    /// requires that the client accepts snippets
+    pub fn call_snippets(&self) -> bool {
+        self.snippets() && self.call_snippets.load(Ordering::SeqCst)
+    }
+
+    pub fn set_call_snippets(&self, call_snippets: bool) {
+        self.call_snippets.store(call_snippets, Ordering::SeqCst)
+    }
+
+    pub fn markdown_hover(&self) -> bool {
+        self.markdown_hover.load(Ordering::SeqCst)
+    }
+
+    pub fn set_markdown_hover(&self, markdown_hover: bool) {
+        self.markdown_hover.store(markdown_hover, Ordering::SeqCst)
+    }
+
+    pub fn hierarchical_symbols(&self) -> bool {
+        self.hierarchical_symbols.load(Ordering::SeqCst)
+    }
+
+    pub fn set_hierarchical_symbols(&self, hierarchical_symbols: bool) {
+        self.hierarchical_symbols.store(hierarchical_symbols, Ordering::SeqCst)
+    }
+
+    pub fn rename_files(&self) -> bool {
+        self.rename_files.load(Ordering::SeqCst)
+    }
+
+    pub fn set_rename_files(&self, rename_files: bool) {
+        self.rename_files.store(rename_files, Ordering::SeqCst)
+    }
+
+    pub fn create_files(&self) -> bool {
+        self.create_files.load(Ordering::SeqCst)
+    }
+
+    pub fn set_create_files(&self, create_files: bool) {
+        self.create_files.store(create_files, Ordering::SeqCst)
+    }
+
+    pub fn hover(&self) -> HoverSettings {
+        *self.hover.read().unwrap()
+    }
+
+    pub fn set_hover(&self, hover: HoverSettings) {
+        *self.hover.write().unwrap() = hover;
+    }
+
+    /// Records that the importer searches `dir`. Returns false if it already did.
+    pub fn add_import_path(&self, dir: &Path) -> bool {
+        let mut import_paths = self.import_paths.lock().unwrap();
+        if import_paths.iter().any(|path| path == dir) {
+            return false;
+        }
+        import_paths.push(dir.into());
+        true
+    }
+
+    /// Stores `version` of the contents of the document at `uri`, replacing the stored contents
+    /// even if they have a newer version
+    pub fn update_document(&self, uri: &str, version: u64, text: String) {
+        self.documents.lock().unwrap().insert(uri.into(), (version, Document::new(text)));
+        self.announced_versions.lock().unwrap().insert(uri.into(), version);
+    }
+
+    pub fn is_open(&self, uri: &str) -> bool {
+        self.documents.lock().unwrap().contains_key(uri)
+    }
+
+    /// Returns the uris of the open documents
+    pub fn open_documents(&self) -> Vec<String> {
+        self.documents.lock().unwrap().keys().cloned().collect()
+    }
+
+    /// Removes the document at `uri`. Returns false if it was not open.
+    pub fn close_document(&self, uri: &str) -> bool {
+        self.announced_versions.lock().unwrap().remove(uri);
+        self.resolved_uris.lock().unwrap().remove(uri);
+        self.documents.lock().unwrap().remove(uri).is_some()
+    }
+
+    /// Applies `changes` to the document at `uri` in order, making it `version` of the contents.
+    /// Changes which arrive out of order or twice are dropped.
+    pub fn change_document(&self,
+                           uri: &str,
+                           version: u64,
+                           changes: &[TextDocumentContentChangeEvent])
+                           -> Change {
+        {
+            let mut documents = self.documents.lock().unwrap();
+            let entry = match documents.get_mut(uri) {
+                Some(entry) => entry,
+                None => return Change::NotOpen,
+            };
+            if version <= entry.0 {
+                return Change::Stale { current: entry.0 };
+            }
+            for change in changes {
+                entry.1.apply_change(change.range.as_ref(), &change.text);
+            }
+            entry.0 = version;
+        }
+        self.announce_version(uri, version);
+        Change::Applied
+    }
+
+    /// Records that the client has sent `version` of the document at `uri`
+    pub fn announce_version(&self, uri: &str, version: u64) {
+        let mut versions = self.announced_versions.lock().unwrap();
+        let latest = versions.entry(uri.into()).or_insert(version);
+        if *latest < version {
+            *latest = version;
+        }
+    }
+
+    /// Returns true if the client has sent a newer version of the document at `uri` than
+    /// `version`
+    pub fn is_superseded(&self, uri: &str, version: u64) -> bool {
+        self.announced_versions
+            .lock()
+            .unwrap()
+            .get(uri)
+            .map_or(false, |&latest| latest > version)
+    }
+
+    /// Calls `f` with the document at `uri` if the document has been opened
+    pub fn with_document<F, R>(&self, uri: &str, f: F) -> Option<R>
+        where F: FnOnce(&Document) -> R
+    {
+        self.documents.lock().unwrap().get(uri).map(|&(_, ref document)| f(document))
+    }
+
+    /// Converts `position` in the document at `uri` from the client's UTF-16 columns to gluon's
+    /// columns. Positions in documents which are not open are returned unchanged.
+    pub fn from_utf16(&self, uri: &str, position: &Position) -> Position {
+        self.with_document(uri, |document| document.from_utf16(position)).unwrap_or(*position)
+    }
+
+    pub fn range_from_utf16(&self, uri: &str, range: &Range) -> Range {
+        self.with_document(uri, |document| document.range_from_utf16(range)).unwrap_or(*range)
+    }
+
+    /// Converts `position` in the document at `uri` from gluon's columns to the client's UTF-16
+    /// columns
+    pub fn to_utf16(&self, uri: &str, position: &Position) -> Position {
+        self.with_document(uri, |document| document.to_utf16(position)).unwrap_or(*position)
+    }
+
+    pub fn range_to_utf16(&self, uri: &str, range: &Range) -> Range {
+        self.with_document(uri, |document| document.range_to_utf16(range)).unwrap_or(*range)
+    }
+
+    /// Returns the version and the text of the document at `uri`
+    pub fn document_version(&self, uri: &str) -> Option<u64> {
+        self.documents.lock().unwrap().get(uri).map(|&(version, _)| version)
+    }
+
+    pub fn document_text(&self, uri: &str) -> Option<(u64, String)> {
+        self.documents
+            .lock()
+            .unwrap()
+            .get(uri)
+            .map(|&(version, ref document)| (version, document.text.clone()))
+    }
+
+    /// Returns the number of open documents and the number of bytes used by their text
+    pub fn document_usage(&self) -> (usize, usize) {
+        let documents = self.documents.lock().unwrap();
+        let bytes = documents.values().map(|&(_, ref document)| document.text.len()).sum();
+        (documents.len(), bytes)
+    }
+
+    /// Resolves the symlinks of `uri`. The result is remembered as the file system is queried.
+    fn resolve_uri(&self, uri: &str) -> String {
+        if let Some(resolved) = self.resolved_uris.lock().unwrap().get(uri) {
+            return resolved.clone();
+        }
+        let resolved = uri::resolve(uri);
+        self.resolved_uris.lock().unwrap().insert(uri.into(), resolved.clone());
+        resolved
+    }
+
+    /// Forgets the resolved symlinks so that they are resolved again
+    pub fn forget_resolved_uris(&self) {
+        self.resolved_uris.lock().unwrap().clear();
+    }
+
+    /// Normalizes `uri`, returning the uri of the open document if it refers to the same file,
+    /// possibly through a symlink
+    pub fn canonical_uri(&self, uri: &str) -> String {
+        let uri = uri::normalize(uri);
+        let resolved = self.resolve_uri(&uri);
+        let open: Vec<String> = self.documents.lock().unwrap().keys().cloned().collect();
+        open.into_iter()
+            .find(|open| uri::same_file(open, &uri) || self.resolve_uri(open) == resolved)
+            .unwrap_or(uri)
+    }
+
+    /// Returns the uri of the open document which is loaded as `module`
+    pub fn module_uri(&self, module: &str) -> Option<String> {
+        self.documents
+            .lock()
+            .unwrap()
+            .keys()
+            .find(|uri| filename_to_module(uri) == module)
+            .cloned()
+    }
+}
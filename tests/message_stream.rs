@@ -0,0 +1,47 @@
+//! Inputs which the `message_stream` fuzz target has been run with, checked on every test run
+extern crate gluon_language_server;
+
+use gluon_language_server::fuzz_message_stream;
+
+fn framed(content: &str) -> Vec<u8> {
+    format!("Content-Length: {}\r\n\r\n{}", content.len(), content).into_bytes()
+}
+
+#[test]
+fn valid_messages_are_decoded() {
+    let mut bytes = framed(r#"{"jsonrpc": "2.0", "id": 1, "method": "shutdown"}"#);
+    bytes.extend(framed(r#"{"jsonrpc": "2.0", "method": "textDocument/didChange",
+                            "params": {"textDocument": {"uri": "file:///a%20b.glu",
+                                                        "version": 2}}}"#));
+    let messages = fuzz_message_stream(&bytes);
+    assert_eq!(messages.len(), 2);
+    assert!(messages.iter().all(|message| message.is_ok()));
+}
+
+#[test]
+fn malformed_input_is_an_error() {
+    let inputs: &[&[u8]] = &[b"Content-Length: x\r\n\r\n",
+                             b"Content-Length: 99999999999999999999\r\n\r\n",
+                             b"Content-Length: 4294967295\r\n\r\n{}",
+                             b"Content-Length: 3\r\n\r\n\xff\xfe\xfd",
+                             b"Content-Length: 2\r\n\r\n{"];
+    for input in inputs {
+        let messages = fuzz_message_stream(input);
+        assert!(messages.last().map_or(false, |message| message.is_err()),
+                "{:?}",
+                String::from_utf8_lossy(input));
+    }
+}
+
+#[test]
+fn invalid_json_is_an_error() {
+    let messages = fuzz_message_stream(&framed(r#"{"params": {"textDocument": {"uri": 1}"#));
+    assert_eq!(messages.len(), 1);
+    assert!(messages[0].is_err());
+}
+
+#[test]
+fn truncated_headers_end_the_stream() {
+    assert!(fuzz_message_stream(b"Content-Length: 10").is_empty());
+    assert!(fuzz_message_stream(b"").is_empty());
+}
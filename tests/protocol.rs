@@ -0,0 +1,382 @@
+//! Checks the protocol types against the example payloads of the language server protocol
+//! specification, so that a renamed or mistyped field is caught before a client sees it
+extern crate gluon_language_server;
+extern crate serde;
+extern crate serde_json;
+
+use serde::Serialize;
+use serde_json::Value;
+use serde_json::value::to_value;
+
+use gluon_language_server::language_server::*;
+
+fn json(s: &str) -> Value {
+    serde_json::from_str(s).unwrap()
+}
+
+fn assert_serializes<T>(value: &T, expected: &str)
+    where T: Serialize
+{
+    assert_eq!(to_value(value), json(expected));
+}
+
+fn range(start_line: u64, start_character: u64, end_line: u64, end_character: u64) -> Range {
+    Range {
+        start: Position {
+            line: start_line,
+            character: start_character,
+        },
+        end: Position {
+            line: end_line,
+            character: end_character,
+        },
+    }
+}
+
+#[test]
+fn initialize_params() {
+    let params: InitializeParams = serde_json::from_str(r#"{
+        "processId": null,
+        "rootPath": "/home/user/project",
+        "capabilities": {
+            "textDocument": {
+                "synchronization": {
+                    "dynamicRegistration": false,
+                    "willSave": true,
+                    "willSaveWaitUntil": false,
+                    "didSave": true
+                }
+            }
+        },
+        "trace": "off"
+    }"#)
+        .unwrap();
+    assert_eq!(params.process_id, None);
+    assert_eq!(params.root_path, Some("/home/user/project".into()));
+    let synchronization = params.capabilities
+        .text_document
+        .and_then(|text_document| text_document.synchronization)
+        .unwrap();
+    assert_eq!(synchronization.will_save, Some(true));
+    assert_eq!(synchronization.did_save, Some(true));
+
+    let params: InitializeParams =
+        serde_json::from_str(r#"{"processId": 1234, "rootPath": null, "capabilities": {}}"#)
+            .unwrap();
+    assert_eq!(params.process_id, Some(1234));
+    assert!(params.capabilities.text_document.is_none());
+}
+
+#[test]
+fn initialize_result() {
+    let result = InitializeResult {
+        capabilities: ServerCapabilities {
+            text_document_sync: Some(TextDocumentSyncOptions {
+                open_close: Some(true),
+                change: Some(TextDocumentSyncKind::Incremental),
+                save: Some(SaveOptions { include_text: Some(true) }),
+                ..TextDocumentSyncOptions::default()
+            }),
+            hover_provider: Some(true),
+            completion_provider: Some(CompletionOptions {
+                resolve_provider: None,
+                trigger_characters: vec![".".into()],
+                all_commit_characters: vec![],
+            }),
+            ..ServerCapabilities::default()
+        },
+    };
+    assert_serializes(&result,
+                      r#"{
+        "capabilities": {
+            "textDocumentSync": {
+                "openClose": true,
+                "change": 2,
+                "save": { "includeText": true }
+            },
+            "hoverProvider": true,
+            "completionProvider": { "triggerCharacters": ["."] }
+        }
+    }"#);
+}
+
+#[test]
+fn did_open_text_document_params() {
+    let params: DidOpenTextDocumentParams = serde_json::from_str(r#"{
+        "textDocument": {
+            "uri": "file:///home/user/project/main.glu",
+            "languageId": "gluon",
+            "version": 1,
+            "text": "let x = 1\nin x"
+        }
+    }"#)
+        .unwrap();
+    assert_eq!(params.text_document.uri, "file:///home/user/project/main.glu");
+    assert_eq!(params.text_document.language_id, "gluon");
+    assert_eq!(params.text_document.version, 1);
+    assert_eq!(params.text_document.text, "let x = 1\nin x");
+}
+
+#[test]
+fn did_change_text_document_params() {
+    let params: DidChangeTextDocumentParams = serde_json::from_str(r#"{
+        "textDocument": { "uri": "file:///main.glu", "version": 2 },
+        "contentChanges": [
+            {
+                "range": {
+                    "start": { "line": 0, "character": 4 },
+                    "end": { "line": 0, "character": 5 }
+                },
+                "rangeLength": 1,
+                "text": "y"
+            },
+            { "text": "let y = 1\nin y" }
+        ]
+    }"#)
+        .unwrap();
+    assert_eq!(params.text_document.version, 2);
+    let changes = &params.content_changes;
+    assert_eq!(changes.len(), 2);
+    let change_range = changes[0].range.unwrap();
+    assert_eq!((change_range.start.line, change_range.start.character), (0, 4));
+    assert_eq!((change_range.end.line, change_range.end.character), (0, 5));
+    assert_eq!(changes[0].range_length, Some(1));
+    assert!(changes[1].range.is_none());
+    assert_eq!(changes[1].text, "let y = 1\nin y");
+}
+
+#[test]
+fn did_close_and_save_text_document_params() {
+    let params: DidCloseTextDocumentParams =
+        serde_json::from_str(r#"{ "textDocument": { "uri": "file:///main.glu" } }"#).unwrap();
+    assert_eq!(params.text_document.uri, "file:///main.glu");
+
+    let params: WillSaveTextDocumentParams =
+        serde_json::from_str(r#"{ "textDocument": { "uri": "file:///main.glu" }, "reason": 1 }"#)
+            .unwrap();
+    assert_eq!(params.reason, 1);
+
+    let params: DidSaveTextDocumentParams =
+        serde_json::from_str(r#"{ "textDocument": { "uri": "file:///main.glu" }, "text": "1" }"#)
+            .unwrap();
+    assert_eq!(params.text, Some("1".into()));
+    let params: DidSaveTextDocumentParams =
+        serde_json::from_str(r#"{ "textDocument": { "uri": "file:///main.glu" } }"#).unwrap();
+    assert_eq!(params.text, None);
+}
+
+#[test]
+fn did_change_watched_files_params() {
+    let params: DidChangeWatchedFilesParams = serde_json::from_str(r#"{
+        "changes": [{ "uri": "file:///main.glu", "type": 2 }]
+    }"#)
+        .unwrap();
+    assert_eq!(params.changes[0].uri, "file:///main.glu");
+    match params.changes[0].typ {
+        FileChangeType::Changed => (),
+        _ => panic!("Expected a changed file"),
+    }
+}
+
+#[test]
+fn cancel_params() {
+    let params: CancelParams = serde_json::from_str(r#"{ "id": 12 }"#).unwrap();
+    assert_eq!(params.id, Value::U64(12));
+    let params: CancelParams = serde_json::from_str(r#"{ "id": "12" }"#).unwrap();
+    assert_eq!(params.id, Value::String("12".into()));
+}
+
+#[test]
+fn text_document_position_params() {
+    let params: TextDocumentPositionParams = serde_json::from_str(r#"{
+        "textDocument": { "uri": "file:///main.glu" },
+        "position": { "line": 3, "character": 12 }
+    }"#)
+        .unwrap();
+    assert_eq!(params.text_document.uri, "file:///main.glu");
+    assert_eq!((params.position.line, params.position.character), (3, 12));
+}
+
+#[test]
+fn completion_list() {
+    let list = CompletionList {
+        is_incomplete: true,
+        items: vec![CompletionItem {
+                        label: "map".into(),
+                        kind: Some(CompletionItemKind::Function),
+                        detail: Some("(a -> b) -> List a -> List b".into()),
+                        sort_text: Some("0map".into()),
+                        ..CompletionItem::default()
+                    }],
+    };
+    assert_serializes(&list,
+                      r#"{
+        "isIncomplete": true,
+        "items": [
+            {
+                "label": "map",
+                "kind": 3,
+                "detail": "(a -> b) -> List a -> List b",
+                "sortText": "0map"
+            }
+        ]
+    }"#);
+}
+
+#[test]
+fn hover() {
+    let hover = Hover {
+        contents: vec![MarkedString::LanguageString {
+                           language: "gluon".into(),
+                           value: "Int".into(),
+                       },
+                       MarkedString::String("An integer".into())],
+        range: Some(range(0, 4, 0, 5)),
+    };
+    assert_serializes(&hover,
+                      r#"{
+        "contents": [{ "language": "gluon", "value": "Int" }, "An integer"],
+        "range": {
+            "start": { "line": 0, "character": 4 },
+            "end": { "line": 0, "character": 5 }
+        }
+    }"#);
+    let hover = Hover {
+        contents: vec![MarkedString::String("Int".into())],
+        range: None,
+    };
+    assert_serializes(&hover, r#"{ "contents": ["Int"] }"#);
+}
+
+#[test]
+fn symbol_information() {
+    let symbol = SymbolInformation {
+        name: "x".into(),
+        kind: SymbolKind::Variable,
+        location: Location {
+            uri: "file:///main.glu".into(),
+            range: range(0, 4, 0, 9),
+        },
+        container_name: "".into(),
+    };
+    assert_serializes(&symbol,
+                      r#"{
+        "name": "x",
+        "kind": 13,
+        "location": {
+            "uri": "file:///main.glu",
+            "range": {
+                "start": { "line": 0, "character": 4 },
+                "end": { "line": 0, "character": 9 }
+            }
+        },
+        "containerName": ""
+    }"#);
+}
+
+#[test]
+fn folding_range() {
+    let folding_range = FoldingRange {
+        start_line: 1,
+        end_line: 4,
+        kind: None,
+    };
+    assert_serializes(&folding_range, r#"{ "startLine": 1, "endLine": 4 }"#);
+}
+
+#[test]
+fn publish_diagnostics_params() {
+    let params = PublishDiagnosticsParams {
+        uri: "file:///main.glu".into(),
+        version: Some(3),
+        diagnostics: vec![Diagnostic {
+                              range: range(1, 0, 1, 3),
+                              severity: Some(DiagnosticSeverity::Error),
+                              message: "Expected the type `Int`".into(),
+                              ..Diagnostic::default()
+                          }],
+    };
+    assert_serializes(&params,
+                      r#"{
+        "uri": "file:///main.glu",
+        "version": 3,
+        "diagnostics": [
+            {
+                "range": {
+                    "start": { "line": 1, "character": 0 },
+                    "end": { "line": 1, "character": 3 }
+                },
+                "severity": 1,
+                "message": "Expected the type `Int`"
+            }
+        ]
+    }"#);
+}
+
+#[test]
+fn diagnostics_from_the_client() {
+    let params: CodeActionParams = serde_json::from_str(r#"{
+        "textDocument": { "uri": "file:///main.glu" },
+        "range": {
+            "start": { "line": 1, "character": 0 },
+            "end": { "line": 1, "character": 3 }
+        },
+        "context": {
+            "diagnostics": [
+                {
+                    "range": {
+                        "start": { "line": 1, "character": 0 },
+                        "end": { "line": 1, "character": 3 }
+                    },
+                    "message": "Expected the type `Int`"
+                }
+            ]
+        }
+    }"#)
+        .unwrap();
+    let diagnostic = &params.context.diagnostics[0];
+    assert!(diagnostic.severity.is_none());
+    assert!(diagnostic.code.is_none());
+    assert_eq!(diagnostic.message, "Expected the type `Int`");
+}
+
+#[test]
+fn messages() {
+    assert_serializes(&LogMessageParams {
+                          typ: MessageType::Log,
+                          message: "Indexing".into(),
+                      },
+                      r#"{ "type": 4, "message": "Indexing" }"#);
+    assert_serializes(&ShowMessageParams {
+                          typ: MessageType::Warning,
+                          message: "Indexing".into(),
+                      },
+                      r#"{ "type": 2, "message": "Indexing" }"#);
+}
+
+#[test]
+fn text_edit() {
+    assert_serializes(&TextEdit {
+                          range: range(0, 0, 0, 3),
+                          new_text: "let".into(),
+                      },
+                      r#"{
+        "range": {
+            "start": { "line": 0, "character": 0 },
+            "end": { "line": 0, "character": 3 }
+        },
+        "newText": "let"
+    }"#);
+}
+
+#[test]
+fn execute_command_params() {
+    let params: ExecuteCommandParams =
+        serde_json::from_str(r#"{ "command": "gluon.run", "arguments": ["file:///main.glu"] }"#)
+            .unwrap();
+    assert_eq!(params.command, "gluon.run");
+    assert_eq!(params.arguments, vec![Value::String("file:///main.glu".into())]);
+    let params: ExecuteCommandParams = serde_json::from_str(r#"{ "command": "gluon.run" }"#)
+        .unwrap();
+    assert!(params.arguments.is_empty());
+}
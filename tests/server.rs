@@ -0,0 +1,1666 @@
+extern crate gluon_language_server;
+extern crate serde_json;
+
+use std::env;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde_json::Value;
+use serde_json::builder::ObjectBuilder;
+
+use gluon_language_server::test::{DIAGNOSTICS_DELAY_MS, Server};
+
+const URI: &'static str = "file:///test.glu";
+
+fn position(line: u64, character: u64) -> Value {
+    ObjectBuilder::new()
+        .insert_object("textDocument", |document| document.insert("uri", URI))
+        .insert_object("position", |position| {
+            position.insert("line", line).insert("character", character)
+        })
+        .unwrap()
+}
+
+#[test]
+fn publish_diagnostics_of_opened_document() {
+    let mut server = Server::start();
+    server.initialize(None).unwrap();
+    server.open(URI, "let x: Int = \"\" in x").unwrap();
+    let params: Value = server.wait_for_notification("textDocument/publishDiagnostics").unwrap();
+    assert_eq!(params.find("uri").and_then(|uri| uri.as_string()), Some(URI));
+    let diagnostics = params.find("diagnostics").and_then(|diagnostics| diagnostics.as_array());
+    assert_eq!(diagnostics.map(|diagnostics| diagnostics.len()), Some(1));
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn complete_after_change() {
+    let mut server = Server::start();
+    server.initialize(None).unwrap();
+    server.open(URI, "let abc = 1 in 1").unwrap();
+    server.change(URI, 2, "let abc = 1 in ab").unwrap();
+    let list: Value = server.request("textDocument/completion", &position(0, 17)).unwrap();
+    let labels: Vec<_> = list.find("items")
+        .and_then(|items| items.as_array())
+        .unwrap()
+        .iter()
+        .filter_map(|item| item.find("label").and_then(|label| label.as_string()))
+        .collect();
+    assert!(labels.contains(&"abc"), "{:?}", labels);
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn publish_diagnostics_once_after_changes() {
+    let mut server = Server::with_virtual_time();
+    server.initialize(None).unwrap();
+    server.open(URI, "let x = 1 in x").unwrap();
+    let params: Value = server.wait_for_notification("textDocument/publishDiagnostics").unwrap();
+    assert_eq!(params.find("version").and_then(|version| version.as_u64()), Some(1));
+
+    server.change(URI, 2, "let x = 1 in y").unwrap();
+    server.change(URI, 3, "let x = 1 in x").unwrap();
+    server.advance(Duration::from_millis(DIAGNOSTICS_DELAY_MS)).unwrap();
+    let params: Value = server.wait_for_notification("textDocument/publishDiagnostics").unwrap();
+    assert_eq!(params.find("version").and_then(|version| version.as_u64()), Some(3));
+
+    server.request::<_, Value>("shutdown", &Value::Null).unwrap();
+    let published: Vec<_> = server.take_pending()
+        .into_iter()
+        .filter(|message| {
+            message.find("method").and_then(|method| method.as_string()) ==
+            Some("textDocument/publishDiagnostics")
+        })
+        .collect();
+    assert!(published.is_empty(), "{:?}", published);
+}
+
+#[test]
+fn drop_stale_changes() {
+    let mut server = Server::with_virtual_time();
+    server.initialize(None).unwrap();
+    server.open(URI, "let x = 1 in x").unwrap();
+    let params: Value = server.wait_for_notification("textDocument/publishDiagnostics").unwrap();
+    assert_eq!(params.find("version").and_then(|version| version.as_u64()), Some(1));
+
+    server.change(URI, 3, "let x = 1 in y").unwrap();
+    // Neither an older version nor the same version again replace the text
+    server.change(URI, 2, "let x = 1 in x").unwrap();
+    server.change(URI, 3, "let x = 1 in x").unwrap();
+    server.advance(Duration::from_millis(DIAGNOSTICS_DELAY_MS)).unwrap();
+    let params: Value = server.wait_for_notification("textDocument/publishDiagnostics").unwrap();
+    assert_eq!(params.find("version").and_then(|version| version.as_u64()), Some(3));
+    let diagnostics = params.find("diagnostics").and_then(|diagnostics| diagnostics.as_array());
+    assert_eq!(diagnostics.map(|diagnostics| diagnostics.len()), Some(1), "{:?}", params);
+    server.shutdown().unwrap();
+}
+
+/// Waits until the diagnostics of `uri` are published and returns how many there are
+fn wait_for_diagnostics(server: &mut Server, uri: &str) -> usize {
+    loop {
+        let params: Value = server.wait_for_notification("textDocument/publishDiagnostics")
+            .unwrap();
+        if params.find("uri").and_then(|uri| uri.as_string()) == Some(uri) {
+            return params.find("diagnostics")
+                .and_then(|diagnostics| diagnostics.as_array())
+                .map_or(0, |diagnostics| diagnostics.len());
+        }
+    }
+}
+
+#[test]
+fn tolerate_irregular_open_and_close() {
+    let mut server = Server::start();
+    server.initialize(None).unwrap();
+    server.open(URI, "let x = 1 in y").unwrap();
+    assert_eq!(wait_for_diagnostics(&mut server, URI), 1);
+    // A second didOpen replaces the text of the document
+    server.open(URI, "let x = 1 in x").unwrap();
+    assert_eq!(wait_for_diagnostics(&mut server, URI), 0);
+
+    let unknown = ObjectBuilder::new()
+        .insert_object("textDocument",
+                       |document| document.insert("uri", "file:///unknown.glu"))
+        .unwrap();
+    server.notify("textDocument/didClose", &unknown).unwrap();
+    // The changes of a document which was never opened apply to its contents on disk, of which
+    // there are none
+    let unopened = "file:///unopened.glu";
+    server.change(unopened, 2, "let z = 1 in w").unwrap();
+    assert_eq!(wait_for_diagnostics(&mut server, unopened), 1);
+
+    let hover: Value = server.request("textDocument/hover", &position(0, 13)).unwrap();
+    assert!(hover.find("contents").is_some(), "{:?}", hover);
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn requests_see_the_changes_before_them() {
+    let mut server = Server::start();
+    server.initialize(None).unwrap();
+    server.open(URI, "1").unwrap();
+    let mut bindings = String::new();
+    for version in 2..6 {
+        bindings.push_str(&format!("let binding_{} = 1 in\n", version));
+        server.change(URI, version, &format!("{}bind", bindings)).unwrap();
+        let list: Value = server.request("textDocument/completion", &position(version - 1, 4))
+            .unwrap();
+        let labels: Vec<_> = list.find("items")
+            .and_then(|items| items.as_array())
+            .unwrap()
+            .iter()
+            .filter_map(|item| item.find("label").and_then(|label| label.as_string()))
+            .collect();
+        let latest = format!("binding_{}", version);
+        assert!(labels.contains(&&latest[..]), "{:?}", labels);
+    }
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn lints_of_workspace_folder() {
+    let mut server = Server::start();
+    let params = ObjectBuilder::new()
+        .insert("processId", Value::Null)
+        .insert("rootPath", Value::Null)
+        .insert_object("capabilities", |capabilities| {
+            capabilities.insert_object("workspace",
+                                       |workspace| workspace.insert("configuration", true))
+        })
+        .insert_array("workspaceFolders", |folders| {
+            folders.push_object(|folder| {
+                    folder.insert("uri", "file:///strict").insert("name", "strict")
+                })
+                .push_object(|folder| folder.insert("uri", "file:///lax").insert("name", "lax"))
+        })
+        .unwrap();
+    server.request::<_, Value>("initialize", &params).unwrap();
+    server.notify("initialized", &ObjectBuilder::new().unwrap()).unwrap();
+    let (id, params): (Value, Value) = server.wait_for_request("workspace/configuration").unwrap();
+    let scopes: Vec<_> = params.find("items")
+        .and_then(|items| items.as_array())
+        .unwrap()
+        .iter()
+        .filter_map(|item| item.find("scopeUri").and_then(|uri| uri.as_string()))
+        .collect();
+    assert_eq!(scopes, ["file:///strict", "file:///lax"]);
+    let settings = ObjectBuilder::new().unwrap();
+    let lax = ObjectBuilder::new()
+        .insert_object("lints", |lints| lints.insert("type", "allow"))
+        .unwrap();
+    server.respond(id, &vec![settings, lax]).unwrap();
+
+    let source = "let x: Int = \"\" in x";
+    server.open("file:///strict/test.glu", source).unwrap();
+    let params: Value = server.wait_for_notification("textDocument/publishDiagnostics").unwrap();
+    let diagnostics = params.find("diagnostics").and_then(|diagnostics| diagnostics.as_array());
+    assert_eq!(diagnostics.map(|diagnostics| diagnostics.len()), Some(1));
+
+    server.open("file:///lax/test.glu", source).unwrap();
+    let params: Value = server.wait_for_notification("textDocument/publishDiagnostics").unwrap();
+    assert_eq!(params.find("uri").and_then(|uri| uri.as_string()),
+               Some("file:///lax/test.glu"));
+    let diagnostics = params.find("diagnostics").and_then(|diagnostics| diagnostics.as_array());
+    assert_eq!(diagnostics.map(|diagnostics| diagnostics.len()), Some(0));
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn configure_lint_levels() {
+    let mut server = Server::start();
+    server.initialize(None).unwrap();
+    server.open(URI,
+                "let unused = 1 in\nlet x = 2 in\nmatch x with\n| y -> y\n| z -> z")
+        .unwrap();
+    let lints = |params: &Value| -> Vec<(Option<String>, Option<u64>)> {
+        params.find("diagnostics")
+            .and_then(|diagnostics| diagnostics.as_array())
+            .unwrap()
+            .iter()
+            .map(|diagnostic| {
+                (diagnostic.find("code").and_then(|code| code.as_string()).map(String::from),
+                 diagnostic.find("severity").and_then(|severity| severity.as_u64()))
+            })
+            .collect()
+    };
+    let params: Value = server.wait_for_notification("textDocument/publishDiagnostics").unwrap();
+    assert_eq!(lints(&params),
+               vec![(Some("unused-binding".into()), Some(2)),
+                    (Some("unreachable-arm".into()), Some(2))]);
+
+    let settings = ObjectBuilder::new()
+        .insert_object("settings", |settings| {
+            settings.insert_object("gluon", |gluon| {
+                gluon.insert_object("lints", |lints| {
+                    lints.insert("unused-binding", "deny").insert("unreachable-arm", "allow")
+                })
+            })
+        })
+        .unwrap();
+    server.notify("workspace/didChangeConfiguration", &settings).unwrap();
+    let params: Value = server.wait_for_notification("textDocument/publishDiagnostics").unwrap();
+    assert_eq!(lints(&params), vec![(Some("unused-binding".into()), Some(1))]);
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn remap_diagnostic_severities() {
+    let mut server = Server::start();
+    server.initialize(None).unwrap();
+    server.open(URI, "let x = 1 in\nlet x = x + 1 in\nx").unwrap();
+    let params: Value = server.wait_for_notification("textDocument/publishDiagnostics").unwrap();
+    assert_eq!(params.pointer("/diagnostics").and_then(|d| d.as_array()).map(|d| d.len()),
+               Some(0));
+
+    let settings = ObjectBuilder::new()
+        .insert_object("settings", |settings| {
+            settings.insert_object("gluon", |gluon| {
+                gluon.insert_object("lints", |lints| lints.insert("shadowing", "hint"))
+            })
+        })
+        .unwrap();
+    server.notify("workspace/didChangeConfiguration", &settings).unwrap();
+    let params: Value = server.wait_for_notification("textDocument/publishDiagnostics").unwrap();
+    assert_eq!(params.pointer("/diagnostics/0/code").and_then(|code| code.as_string()),
+               Some("shadowing"));
+    assert_eq!(params.pointer("/diagnostics/0/severity").and_then(|s| s.as_u64()),
+               Some(4));
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn suppress_lints_with_comments() {
+    let mut server = Server::start();
+    server.initialize(None).unwrap();
+    server.open(URI,
+                "// gluon-lint: allow(unused_binding)\nlet unused = 1 in\nlet other = 2 in\n3")
+        .unwrap();
+    let params: Value = server.wait_for_notification("textDocument/publishDiagnostics").unwrap();
+    let diagnostics = params.find("diagnostics").and_then(|d| d.as_array()).unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].pointer("/range/start/line").and_then(|line| line.as_u64()),
+               Some(2));
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn report_todo_comments() {
+    let mut server = Server::start();
+    server.initialize(None).unwrap();
+    let settings = ObjectBuilder::new()
+        .insert_object("settings", |settings| {
+            settings.insert_object("gluon", |gluon| {
+                gluon.insert_object("lints", |lints| lints.insert("todo", "info"))
+                    .insert_object("todos", |todos| {
+                        todos.insert_array("markers", |markers| markers.push("XXX"))
+                    })
+            })
+        })
+        .unwrap();
+    server.notify("workspace/didChangeConfiguration", &settings).unwrap();
+    server.open(URI, "// TODO: not a marker\n1 // XXX check this").unwrap();
+    let params: Value = server.wait_for_notification("textDocument/publishDiagnostics").unwrap();
+    let diagnostics = params.find("diagnostics").and_then(|d| d.as_array()).unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].find("message").and_then(|message| message.as_string()),
+               Some("XXX check this"));
+    assert_eq!(diagnostics[0].find("severity").and_then(|severity| severity.as_u64()),
+               Some(3));
+    assert_eq!(diagnostics[0].pointer("/range/start/character").and_then(|c| c.as_u64()),
+               Some(5));
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn report_arms_after_every_constructor() {
+    let mut server = Server::start();
+    server.initialize(None).unwrap();
+    server.open(URI,
+                "type Color = | Red | Green in\nlet c = Red in\nmatch c with\n| Red -> 1\n\
+                 | Green -> 2\n| Red -> 3\n| other -> 4")
+        .unwrap();
+    let params: Value = server.wait_for_notification("textDocument/publishDiagnostics").unwrap();
+    let lines: Vec<_> = params.find("diagnostics")
+        .and_then(|diagnostics| diagnostics.as_array())
+        .unwrap()
+        .iter()
+        .filter(|diagnostic| {
+            diagnostic.find("code").and_then(|code| code.as_string()) == Some("unreachable-arm")
+        })
+        .filter_map(|diagnostic| diagnostic.pointer("/range/start/line"))
+        .filter_map(|line| line.as_u64())
+        .collect();
+    assert_eq!(lines, vec![5, 6]);
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn fix_redundant_lambda() {
+    let mut server = Server::start();
+    server.initialize(None).unwrap();
+    server.open(URI, "let f x = x in\nlet g = \\y -> f y in\ng (1)").unwrap();
+    let params: Value = server.wait_for_notification("textDocument/publishDiagnostics").unwrap();
+    let diagnostics = params.find("diagnostics")
+        .and_then(|diagnostics| diagnostics.as_array())
+        .unwrap()
+        .clone();
+    let codes: Vec<_> = diagnostics.iter()
+        .map(|diagnostic| diagnostic.find("code").and_then(|code| code.as_string()))
+        .collect();
+    assert_eq!(codes, vec![Some("redundant-lambda"), Some("redundant-parens")]);
+    let quickfix = quickfix(&mut server, &diagnostics[0]);
+    assert_eq!(quickfix.find("title").and_then(|title| title.as_string()),
+               Some("Replace the lambda by `f`"));
+    let edit = format!("/edit/changes/{}/0/newText", URI.replace("/", "~1"));
+    assert_eq!(quickfix.pointer(&edit).and_then(|text| text.as_string()), Some("f"));
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn make_binding_recursive() {
+    let mut server = Server::start();
+    server.initialize(None).unwrap();
+    server.open(URI, "let f = \\x -> f x in\nf 1").unwrap();
+    let params: Value = server.wait_for_notification("textDocument/publishDiagnostics").unwrap();
+    let diagnostic = params.pointer("/diagnostics/0").unwrap().clone();
+    let message = diagnostic.find("message").and_then(|message| message.as_string()).unwrap();
+    assert!(message.contains("`f` refers to itself"), "{}", message);
+    let quickfix = quickfix(&mut server, &diagnostic);
+    assert_eq!(quickfix.find("title").and_then(|title| title.as_string()),
+               Some("Bind the arguments of `f` so that it is recursive"));
+    let edit = format!("/edit/changes/{}/0/newText", URI.replace("/", "~1"));
+    assert_eq!(quickfix.pointer(&edit).and_then(|text| text.as_string()), Some(" x ="));
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn rename_unused_parameter() {
+    let mut server = Server::start();
+    server.initialize(None).unwrap();
+    server.open(URI, "let first x y = x in\nfirst 1 2").unwrap();
+    let params: Value = server.wait_for_notification("textDocument/publishDiagnostics").unwrap();
+    let diagnostic = params.pointer("/diagnostics/0").unwrap().clone();
+    assert_eq!(diagnostic.find("code").and_then(|code| code.as_string()),
+               Some("unused-parameter"));
+    assert_eq!(diagnostic.pointer("/range/start/character").and_then(|c| c.as_u64()),
+               Some(12));
+    let quickfix = quickfix(&mut server, &diagnostic);
+    assert_eq!(quickfix.find("title").and_then(|title| title.as_string()),
+               Some("Rename `y` to `_y`"));
+    let edit = format!("/edit/changes/{}/0/newText", URI.replace("/", "~1"));
+    assert_eq!(quickfix.pointer(&edit).and_then(|text| text.as_string()), Some("_y"));
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn diagnostics_of_gluon_in_rust() {
+    let mut server = Server::start();
+    server.initialize(None).unwrap();
+    let settings = ObjectBuilder::new()
+        .insert_object("settings", |settings| {
+            settings.insert_object("gluon", |gluon| {
+                gluon.insert_object("embedded", |embedded| embedded.insert("enable", true))
+            })
+        })
+        .unwrap();
+    server.notify("workspace/didChangeConfiguration", &settings).unwrap();
+    let uri = "file:///main.rs";
+    server.open(uri,
+                "fn main() {\n    let vm = new_vm();\n    Compiler::new().run_expr::<i32>(&vm, \
+                 \"test\", r#\"let x: Int = \"\"\nin x\"#).unwrap();\n}\n")
+        .unwrap();
+    let params: Value = server.wait_for_notification("textDocument/publishDiagnostics").unwrap();
+    assert_eq!(params.find("uri").and_then(|uri| uri.as_string()), Some(uri));
+    let diagnostics = params.find("diagnostics").and_then(|diagnostics| diagnostics.as_array());
+    let lines: Vec<_> = diagnostics.unwrap()
+        .iter()
+        .filter_map(|diagnostic| diagnostic.find_path(&["range", "start", "line"]))
+        .filter_map(|line| line.as_u64())
+        .collect();
+    assert_eq!(lines, vec![2]);
+    server.shutdown().unwrap();
+}
+
+/// Waits for the `gluon/status` notification which reports `state` and returns its parameters
+fn wait_for_status(server: &mut Server, state: &str) -> Value {
+    loop {
+        let status: Value = server.wait_for_notification("gluon/status").unwrap();
+        if status.find("state").and_then(|state| state.as_string()) == Some(state) {
+            return status;
+        }
+    }
+}
+
+#[test]
+fn report_status_while_checking() {
+    let mut server = Server::start();
+    server.initialize(None).unwrap();
+    server.open(URI, "let x = 1 in x").unwrap();
+    let status = wait_for_status(&mut server, "checking");
+    assert_eq!(status.find("message").and_then(|uri| uri.as_string()), Some(URI));
+    wait_for_status(&mut server, "idle");
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn server_status() {
+    let mut server = Server::start();
+    server.initialize(None).unwrap();
+    let status: Value = server.request("gluon/serverStatus", &ObjectBuilder::new().unwrap())
+        .unwrap();
+    assert_eq!(status.find("version").and_then(|version| version.as_string()),
+               Some(env!("CARGO_PKG_VERSION")));
+    assert_eq!(status.find("lastError"), Some(&Value::Null));
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn reload_workspace_republishes_diagnostics() {
+    let mut server = Server::start();
+    server.initialize(None).unwrap();
+    server.open(URI, "let x: Int = \"\" in x").unwrap();
+    server.wait_for_notification::<Value>("textDocument/publishDiagnostics").unwrap();
+    let params = ObjectBuilder::new()
+        .insert("command", "gluon.reloadWorkspace")
+        .insert_array("arguments", |arguments| arguments)
+        .unwrap();
+    server.request::<_, Value>("workspace/executeCommand", &params).unwrap();
+    let params: Value = server.wait_for_notification("textDocument/publishDiagnostics").unwrap();
+    let diagnostics = params.find("diagnostics").and_then(|diagnostics| diagnostics.as_array());
+    assert_eq!(diagnostics.map(|diagnostics| diagnostics.len()), Some(1));
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn complete_fuzzy_matches() {
+    let mut server = Server::start();
+    server.initialize(None).unwrap();
+    server.open(URI, "let fold_left_map x = x in\nlet flat = 1 in\nflm").unwrap();
+    let list: Value = server.request("textDocument/completion", &position(2, 3)).unwrap();
+    assert_eq!(list.find("isIncomplete").and_then(|value| value.as_boolean()), Some(true));
+    let items = list.find("items").and_then(|items| items.as_array()).unwrap();
+    let item = items.iter()
+        .find(|item| {
+            item.find("label").and_then(|label| label.as_string()) == Some("fold_left_map")
+        })
+        .unwrap();
+    assert_eq!(item.find("filterText").and_then(|text| text.as_string()), Some("flm"));
+    assert!(!items.iter()
+        .any(|item| item.find("label").and_then(|label| label.as_string()) == Some("flat")));
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn complete_missing_record_fields() {
+    let mut server = Server::start();
+    server.initialize(None).unwrap();
+    let text = "type Point = { x : Int, y : Int } in\nlet f p : Point -> Int = p.x in\nf { x = 1 }";
+    server.open(URI, text).unwrap();
+    let list: Value = server.request("textDocument/completion", &position(2, 10)).unwrap();
+    let items = list.find("items").and_then(|items| items.as_array()).unwrap();
+    let labels: Vec<_> = items.iter()
+        .filter(|item| item.find("kind").and_then(|kind| kind.as_u64()) == Some(5))
+        .filter_map(|item| item.find("label").and_then(|label| label.as_string()))
+        .collect();
+    assert_eq!(labels, ["y"]);
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn complete_only_missing_fields_in_record_literal() {
+    let mut server = Server::start();
+    server.initialize(None).unwrap();
+    let text = "type Point = { x : Int, y : Int } in\nlet p : Point = { y = 2 } in\np";
+    server.open(URI, text).unwrap();
+    let list: Value = server.request("textDocument/completion", &position(1, 24)).unwrap();
+    let items = list.find("items").and_then(|items| items.as_array()).unwrap();
+    assert_eq!(items.len(), 1);
+    let field = |key| items[0].find(key).and_then(|value| value.as_string());
+    assert_eq!(field("label"), Some("x"));
+    let new_text = items[0].find_path(&["textEdit", "newText"]).and_then(|text| text.as_string());
+    assert_eq!(new_text, Some("x = "));
+    assert_eq!(field("detail"), Some("Int"));
+    server.shutdown().unwrap();
+}
+
+/// Initializes `server` for a client which accepts snippets in completions
+fn initialize_with_snippets(server: &mut Server) {
+    let params = ObjectBuilder::new()
+        .insert("processId", Value::Null)
+        .insert("rootPath", Value::Null)
+        .insert_object("capabilities", |capabilities| {
+            capabilities.insert_object("textDocument", |text_document| {
+                text_document.insert_object("completion", |completion| {
+                    completion.insert_object("completionItem",
+                                             |item| item.insert("snippetSupport", true))
+                })
+            })
+        })
+        .unwrap();
+    server.request::<_, Value>("initialize", &params).unwrap();
+}
+
+#[test]
+fn complete_calls_with_snippets() {
+    let mut server = Server::start();
+    initialize_with_snippets(&mut server);
+    let settings = ObjectBuilder::new()
+        .insert_object("settings", |settings| {
+            settings.insert_object("gluon", |gluon| {
+                gluon.insert_object("completion",
+                                    |completion| completion.insert("callSnippets", true))
+            })
+        })
+        .unwrap();
+    server.notify("workspace/didChangeConfiguration", &settings).unwrap();
+    server.open(URI, "let add x y : Int -> Int -> Int = x + y in\nad").unwrap();
+    let list: Value = server.request("textDocument/completion", &position(1, 2)).unwrap();
+    let items = list.find("items").and_then(|items| items.as_array()).unwrap();
+    let add = items.iter()
+        .find(|item| item.find("label").and_then(|label| label.as_string()) == Some("add"))
+        .unwrap();
+    let new_text = add.find_path(&["textEdit", "newText"]).and_then(|text| text.as_string());
+    assert_eq!(new_text, Some("add $1 $2"));
+    assert_eq!(add.find("insertTextFormat").and_then(|format| format.as_u64()), Some(2));
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn complete_actions_of_bind_chain_monad() {
+    let mut server = Server::start();
+    initialize_with_snippets(&mut server);
+    let text = r"let (>>=) m f : Option Int -> (Int -> Option Int) -> Option Int =
+    match m with
+    | Some x -> f x
+    | None -> None
+in
+let bind_int x : Int -> Option Int = Some x in
+let bump x : Int -> Int = x + 1 in
+Some 1 >>= \x -> b";
+    server.open(URI, text).unwrap();
+    let list: Value = server.request("textDocument/completion", &position(7, 18)).unwrap();
+    let items = list.find("items").and_then(|items| items.as_array()).unwrap();
+    let item = |label| {
+        items.iter()
+            .find(|item| item.find("label").and_then(|value| value.as_string()) == Some(label))
+            .unwrap()
+    };
+    let sort_text = |label| item(label).find("sortText").and_then(|text| text.as_string());
+    assert!(sort_text("bind_int") < sort_text("bump"));
+    let bind = item("bind");
+    assert_eq!(bind.find("insertTextFormat").and_then(|format| format.as_u64()), Some(2));
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn completion_kinds() {
+    let mut server = Server::start();
+    server.initialize(None).unwrap();
+    server.open(URI, "let add x : Int -> Int = x in\nlet answer = 42 in\na").unwrap();
+    let list: Value = server.request("textDocument/completion", &position(2, 1)).unwrap();
+    let items = list.find("items").and_then(|items| items.as_array()).unwrap();
+    let kind = |label| {
+        items.iter()
+            .find(|item| item.find("label").and_then(|value| value.as_string()) == Some(label))
+            .and_then(|item| item.find("kind"))
+            .and_then(|kind| kind.as_u64())
+    };
+    assert_eq!(kind("add"), Some(3));
+    assert_eq!(kind("answer"), Some(6));
+    assert_eq!(kind("and"), Some(14));
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn commit_characters() {
+    let mut server = Server::start();
+    let result = server.initialize(None).unwrap();
+    let all = result.find_path(&["capabilities", "completionProvider", "allCommitCharacters"])
+        .and_then(|characters| characters.as_array())
+        .unwrap();
+    assert!(all.iter().any(|character| character.as_string() == Some(")")));
+    server.open(URI, "let answer = 42 in\na").unwrap();
+    let list: Value = server.request("textDocument/completion", &position(1, 1)).unwrap();
+    let items = list.find("items").and_then(|items| items.as_array()).unwrap();
+    let keyword = items.iter()
+        .find(|item| item.find("label").and_then(|label| label.as_string()) == Some("and"))
+        .unwrap();
+    let characters = keyword.find("commitCharacters").and_then(|characters| characters.as_array());
+    assert_eq!(characters.map(|characters| characters.len()), Some(1));
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn completion_replaces_whole_identifier() {
+    let mut server = Server::start();
+    server.initialize(None).unwrap();
+    server.open(URI, "let record = { length = 1 } in\nrecord.lenth").unwrap();
+    let list: Value = server.request("textDocument/completion", &position(1, 10)).unwrap();
+    let items = list.find("items").and_then(|items| items.as_array()).unwrap();
+    let length = items.iter()
+        .find(|item| item.find("label").and_then(|label| label.as_string()) == Some("length"))
+        .unwrap();
+    let range = length.find_path(&["textEdit", "range"]).unwrap();
+    let character = |key| range.find_path(&[key, "character"]).and_then(|c| c.as_u64());
+    assert_eq!((character("start"), character("end")), (Some(7), Some(12)));
+    server.shutdown().unwrap();
+}
+
+const POINT: &'static str = r"type Point = {
+    /// The horizontal coordinate
+    x : Int,
+    y : Int
+} in
+let p : Point = { x = 1, y = 2 } in
+p.x";
+
+#[test]
+fn goto_field_definition() {
+    let mut server = Server::start();
+    server.initialize(None).unwrap();
+    server.open(URI, POINT).unwrap();
+    let locations: Value = server.request("textDocument/definition", &position(6, 2)).unwrap();
+    let location = locations.pointer("/0").unwrap();
+    assert_eq!(location.find("uri").and_then(|uri| uri.as_string()), Some(URI));
+    let range = |path| location.lookup(path).and_then(|value| value.as_u64());
+    assert_eq!((range("range.start.line"), range("range.start.character")),
+               (Some(2), Some(4)));
+    assert_eq!(range("range.end.character"), Some(5));
+
+    let locations: Value = server.request("textDocument/definition", &position(6, 0)).unwrap();
+    let line = locations.pointer("/0/range/start/line").and_then(|line| line.as_u64());
+    assert_eq!(line, Some(5));
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn goto_definition_maps_gluon_locations_to_positions() {
+    let mut server = Server::start();
+    server.initialize(None).unwrap();
+    server.open(URI, "let x = 1 in\nlet abc = x in\nabc").unwrap();
+    let locations: Value = server.request("textDocument/definition", &position(2, 1)).unwrap();
+    let number = |path| locations.pointer(path).and_then(|value| value.as_u64());
+    // `abc` is at row 2, column 5 in gluon, which count from 1
+    assert_eq!((number("/0/range/start/line"), number("/0/range/start/character")),
+               (Some(1), Some(4)));
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn goto_definition_of_implicit_instance() {
+    let mut server = Server::start();
+    server.initialize(None).unwrap();
+    let text = r#"type Describe a = { describe : a -> String } in
+let describe_Int : Describe Int = { describe = \x -> "an Int" } in
+let { describe } = describe_Int in
+describe 1"#;
+    server.open(URI, text).unwrap();
+    let locations: Value = server.request("textDocument/definition", &position(3, 0)).unwrap();
+    let number = |path| locations.pointer(path).and_then(|value| value.as_u64());
+    // The field of the instance's type and then the instance
+    assert_eq!((number("/0/range/start/line"), number("/0/range/start/character")),
+               (Some(0), Some(20)));
+    assert_eq!(number("/0/range/end/character"), Some(28));
+    assert_eq!(number("/1/range/start/line"), Some(1));
+    assert_eq!(locations.as_array().map(|locations| locations.len()), Some(2));
+    server.shutdown().unwrap();
+}
+
+fn references(line: u64, character: u64, include_declaration: bool) -> Value {
+    let mut params = position(line, character);
+    if let Value::Object(ref mut params) = params {
+        params.insert("context".into(),
+                      ObjectBuilder::new()
+                          .insert("includeDeclaration", include_declaration)
+                          .unwrap());
+    }
+    params
+}
+
+#[test]
+fn find_references_of_type() {
+    let mut server = Server::start();
+    server.initialize(None).unwrap();
+    let text = r"type Shape = | Circle Float | Square Float in
+let area : Shape -> Float = \s ->
+    match s with
+    | Circle r -> r
+    | Square l -> l
+in
+area (Circle 1.0)";
+    server.open(URI, text).unwrap();
+    let starts = |locations: Value| -> Vec<(u64, u64)> {
+        locations.as_array()
+            .unwrap()
+            .iter()
+            .map(|location| {
+                let number = |path| location.pointer(path).and_then(|n| n.as_u64()).unwrap();
+                (number("/range/start/line"), number("/range/start/character"))
+            })
+            .collect()
+    };
+    let locations: Value = server.request("textDocument/references", &references(1, 13, true))
+        .unwrap();
+    assert_eq!(starts(locations),
+               vec![(0, 5), (0, 15), (0, 30), (1, 11), (3, 6), (4, 6), (6, 6)]);
+    // Starting from a constructor finds the same uses, here without the declaration of the type
+    let locations: Value = server.request("textDocument/references", &references(6, 7, false))
+        .unwrap();
+    assert_eq!(starts(locations), vec![(0, 15), (0, 30), (1, 11), (3, 6), (4, 6), (6, 6)]);
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn find_references_of_variable() {
+    let mut server = Server::start();
+    server.initialize(None).unwrap();
+    server.open(URI, "let x = 1 in\nx + x").unwrap();
+    let locations: Value = server.request("textDocument/references", &references(1, 0, true))
+        .unwrap();
+    assert_eq!(locations.as_array().map(|locations| locations.len()), Some(3));
+    let locations: Value = server.request("textDocument/references", &references(1, 4, false))
+        .unwrap();
+    let lines: Vec<_> = locations.as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|location| location.pointer("/range/start/line").and_then(|n| n.as_u64()))
+        .collect();
+    assert_eq!(lines, vec![1, 1]);
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn highlight_reads_and_writes_of_reference() {
+    let mut server = Server::start();
+    server.initialize(None).unwrap();
+    server.open(URI, "let r = ref 0 in\nlet u = r <- 1 in\nload r").unwrap();
+    let highlights: Value = server.request("textDocument/documentHighlight", &position(2, 5))
+        .unwrap();
+    let highlights: Vec<_> = highlights.as_array()
+        .unwrap()
+        .iter()
+        .map(|highlight| {
+            let number = |path| highlight.pointer(path).and_then(|n| n.as_u64()).unwrap();
+            (number("/range/start/line"), number("/kind"))
+        })
+        .collect();
+    // Write = 3, Read = 2
+    assert_eq!(highlights, vec![(0, 3), (1, 3), (2, 2)]);
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn hierarchical_document_symbols() {
+    let mut server = Server::start();
+    let params = ObjectBuilder::new()
+        .insert("processId", Value::Null)
+        .insert("rootPath", Value::Null)
+        .insert_object("capabilities", |capabilities| {
+            capabilities.insert_object("textDocument", |text_document| {
+                text_document.insert_object("documentSymbol", |symbol| {
+                    symbol.insert("hierarchicalDocumentSymbolSupport", true)
+                })
+            })
+        })
+        .unwrap();
+    server.request::<_, Value>("initialize", &params).unwrap();
+    let text = "/// The origin\nlet origin = 0 in\nlet f x =\n    let y = x in\n    \
+                y\nin\nf origin";
+    server.open(URI, text).unwrap();
+    let params = ObjectBuilder::new()
+        .insert_object("textDocument", |document| document.insert("uri", URI))
+        .unwrap();
+    let symbols: Value = server.request("textDocument/documentSymbol", &params).unwrap();
+    let number = |path: &str| symbols.pointer(path).and_then(|n| n.as_u64());
+    let name = |path: &str| symbols.pointer(path).and_then(|name| name.as_string());
+    assert_eq!(name("/0/name"), Some("origin"));
+    // The range of a binding includes its documentation and the selection range is its name
+    assert_eq!(number("/0/range/start/line"), Some(0));
+    assert_eq!(number("/0/selectionRange/start/line"), Some(1));
+    assert_eq!(number("/0/selectionRange/end/character")
+                   .and_then(|end| number("/0/selectionRange/start/character").map(|s| end - s)),
+               Some(6));
+    assert_eq!(name("/1/name"), Some("f"));
+    assert_eq!((number("/1/range/start/line"), number("/1/range/end/line")),
+               (Some(2), Some(4)));
+    assert_eq!(name("/1/children/0/name"), Some("y"));
+    assert_eq!(number("/1/children/0/selectionRange/start/line"), Some(3));
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn workspace_symbols_include_new_bindings() {
+    let mut server = Server::start();
+    server.initialize(None).unwrap();
+    server.open(URI, "let alpha = 1 in alpha").unwrap();
+    server.change(URI, 2, "let alpha = 1 in\nlet beta_value = 2 in\nalpha").unwrap();
+    let params = ObjectBuilder::new().insert("query", "beta").unwrap();
+    let symbols: Value = server.request("workspace/symbol", &params).unwrap();
+    let found: Vec<_> = symbols.as_array()
+        .unwrap()
+        .iter()
+        .map(|symbol| {
+            (symbol.find("name").and_then(|name| name.as_string()),
+             symbol.pointer("/location/uri").and_then(|uri| uri.as_string()),
+             symbol.pointer("/location/range/start/line").and_then(|line| line.as_u64()))
+        })
+        .collect();
+    assert_eq!(found, vec![(Some("beta_value"), Some(URI), Some(1))]);
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn goto_imported_module() {
+    let dir = env::temp_dir().join("gluon_goto_imported_module");
+    fs::create_dir_all(&dir).unwrap();
+    File::create(dir.join("shapes.glu")).unwrap().write_all(b"let unit = 1.0 in { unit }").unwrap();
+    let mut server = Server::start();
+    server.initialize(Some(&dir)).unwrap();
+    server.open(URI, "let s = import \"shapes.glu\" in\ns.unit").unwrap();
+    let locations: Value = server.request("textDocument/definition", &position(0, 17)).unwrap();
+    let uri = locations.pointer("/0/uri").and_then(|uri| uri.as_string()).unwrap();
+    assert!(uri.ends_with("/gluon_goto_imported_module/shapes.glu"), "{}", uri);
+    assert_eq!(locations.pointer("/0/range/start/line").and_then(|line| line.as_u64()),
+               Some(0));
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn import_from_import_paths() {
+    let dir = env::temp_dir().join("gluon_import_paths_vendor");
+    fs::create_dir_all(&dir).unwrap();
+    File::create(dir.join("vendored.glu"))
+        .unwrap()
+        .write_all(b"let answer = 42 in { answer }")
+        .unwrap();
+    let mut server = Server::start();
+    server.initialize(None).unwrap();
+    let settings = ObjectBuilder::new()
+        .insert_object("settings", |settings| {
+            settings.insert_object("gluon", |gluon| {
+                gluon.insert_array("importPaths",
+                                   |paths| paths.push(dir.to_string_lossy().into_owned()))
+            })
+        })
+        .unwrap();
+    server.notify("workspace/didChangeConfiguration", &settings).unwrap();
+    server.open(URI, "let v = import \"vendored.glu\" in\nv.answer").unwrap();
+    let params: Value = server.wait_for_notification("textDocument/publishDiagnostics").unwrap();
+    let diagnostics = params.find("diagnostics").and_then(|diagnostics| diagnostics.as_array());
+    assert_eq!(diagnostics.map(|diagnostics| diagnostics.len()), Some(0), "{:?}", params);
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn import_from_manifest_dirs() {
+    let dir = env::temp_dir().join("gluon_manifest_dirs");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::create_dir_all(dir.join("vendor")).unwrap();
+    File::create(dir.join("glu-project.toml"))
+        .unwrap()
+        .write_all(b"source-dirs = [\"src\"]\nimport-paths = [\"vendor\"]\n")
+        .unwrap();
+    File::create(dir.join("src").join("shapes.glu"))
+        .unwrap()
+        .write_all(b"let sides = 4 in { sides }")
+        .unwrap();
+    File::create(dir.join("vendor").join("vendored.glu"))
+        .unwrap()
+        .write_all(b"let answer = 42 in { answer }")
+        .unwrap();
+    let mut server = Server::start();
+    server.initialize(Some(&dir)).unwrap();
+    server.open(URI,
+                "let s = import \"shapes.glu\" in\nlet v = import \"vendored.glu\" in\n\
+                 s.sides + v.answer")
+        .unwrap();
+    loop {
+        let params: Value = server.wait_for_notification("textDocument/publishDiagnostics")
+            .unwrap();
+        if params.find("uri").and_then(|uri| uri.as_string()) != Some(URI) {
+            continue;
+        }
+        let diagnostics = params.find("diagnostics").and_then(|d| d.as_array());
+        assert_eq!(diagnostics.map(|diagnostics| diagnostics.len()), Some(0), "{:?}", params);
+        break;
+    }
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn untitled_document_imports_from_first_workspace_folder() {
+    let dir = env::temp_dir().join("gluon_untitled_folder");
+    fs::create_dir_all(&dir).unwrap();
+    File::create(dir.join("shapes.glu"))
+        .unwrap()
+        .write_all(b"let sides = 4 in { sides }")
+        .unwrap();
+    let mut server = Server::start();
+    let params = ObjectBuilder::new()
+        .insert("processId", Value::Null)
+        .insert("rootPath", Value::Null)
+        .insert("capabilities", ObjectBuilder::new().unwrap())
+        .insert_array("workspaceFolders", |folders| {
+            folders.push_object(|folder| {
+                folder.insert("uri", format!("file://{}", dir.display())).insert("name", "shapes")
+            })
+        })
+        .unwrap();
+    server.request::<_, Value>("initialize", &params).unwrap();
+    let uri = "untitled:Untitled-1";
+    server.open(uri, "let s = import \"shapes.glu\" in\ns.sides").unwrap();
+    loop {
+        let params: Value = server.wait_for_notification("textDocument/publishDiagnostics")
+            .unwrap();
+        if params.find("uri").and_then(|uri| uri.as_string()) != Some(uri) {
+            continue;
+        }
+        let diagnostics = params.find("diagnostics").and_then(|d| d.as_array());
+        assert_eq!(diagnostics.map(|diagnostics| diagnostics.len()), Some(0), "{:?}", params);
+        break;
+    }
+    server.shutdown().unwrap();
+}
+
+/// A prelude with the bindings which the implicit prelude needs and `std_path_marker`, which only
+/// exists in this prelude
+const MARKED_PRELUDE: &'static str = r#"
+let { Bool, Option, Result, Ordering } = import "std/types.glu"
+let not x = if x then False else True
+type Eq a = { (==) : a -> a -> Bool }
+type Ord a = { compare : a -> a -> Ordering }
+type Num a = {
+    (+) : a -> a -> a, (-) : a -> a -> a, (*) : a -> a -> a, (/) : a -> a -> a
+}
+type Functor f = { map : (a -> b) -> f a -> f b }
+type Monad m = { (>>=) : m a -> (a -> m b) -> m b, return : a -> m a }
+type Show a = { show : a -> String }
+let eq_Int = { (==) = \l r -> l #Int== r }
+let eq_Float = { (==) = \l r -> l #Float== r }
+let eq_Char = { (==) = \l r -> l #Char== r }
+let ord_Int = { compare = \l r -> if l #Int< r then LT else if l #Int== r then EQ else GT }
+let ord_Float = {
+    compare = \l r -> if l #Float< r then LT else if l #Float== r then EQ else GT
+}
+let ord_Char = { compare = \l r -> if l #Char< r then LT else if l #Char== r then EQ else GT }
+let make_Ord ord =
+    let compare = ord.compare
+    {
+        (<=) = \l r ->
+            match compare l r with
+                | GT -> False
+                | _ -> True,
+        (<) = \l r ->
+            match compare l r with
+                | LT -> True
+                | _ -> False,
+        (>) = \l r ->
+            match compare l r with
+                | GT -> True
+                | _ -> False,
+        (>=) = \l r ->
+            match compare l r with
+                | LT -> False
+                | _ -> True
+    }
+let num_Int = {
+    (+) = \l r -> l #Int+ r,
+    (-) = \l r -> l #Int- r,
+    (*) = \l r -> l #Int* r,
+    (/) = \l r -> l #Int/ r
+}
+let num_Float = {
+    (+) = \l r -> l #Float+ r,
+    (-) = \l r -> l #Float- r,
+    (*) = \l r -> l #Float* r,
+    (/) = \l r -> l #Float/ r
+}
+let std_path_marker = 1
+in {
+    Bool, Option, Result, Ordering, Eq, Ord, Num, Functor, Monad, Show,
+    not, eq_Int, eq_Float, eq_Char, ord_Int, ord_Float, ord_Char, make_Ord, num_Int, num_Float,
+    std_path_marker
+}
+"#;
+
+#[test]
+fn std_modules_from_std_path() {
+    let dir = env::temp_dir().join("gluon_std_path");
+    fs::create_dir_all(&dir).unwrap();
+    File::create(dir.join("prelude.glu")).unwrap().write_all(MARKED_PRELUDE.as_bytes()).unwrap();
+    let mut server = Server::start();
+    let params = ObjectBuilder::new()
+        .insert("processId", Value::Null)
+        .insert("rootPath", Value::Null)
+        .insert("capabilities", ObjectBuilder::new().unwrap())
+        .insert_object("initializationOptions",
+                       |options| options.insert("stdPath", dir.to_string_lossy().into_owned()))
+        .unwrap();
+    server.request::<_, Value>("initialize", &params).unwrap();
+    server.open(URI,
+                "let { std_path_marker } = import \"std/prelude.glu\" in\nstd_path_marker")
+        .unwrap();
+    let params: Value = server.wait_for_notification("textDocument/publishDiagnostics").unwrap();
+    let diagnostics = params.find("diagnostics").and_then(|diagnostics| diagnostics.as_array());
+    assert_eq!(diagnostics.map(|diagnostics| diagnostics.len()), Some(0), "{:?}", params);
+    server.shutdown().unwrap();
+}
+
+/// Writes the module `shapes.glu` and a `main.glu` which imports it to a new workspace
+fn shapes_workspace(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    File::create(dir.join("shapes.glu")).unwrap().write_all(b"let unit = 1.0 in { unit }").unwrap();
+    File::create(dir.join("main.glu"))
+        .unwrap()
+        .write_all(b"let s = import \"shapes.glu\" in\ns.unit")
+        .unwrap();
+    dir
+}
+
+/// Waits until the workspace module `name` has been typechecked
+fn wait_for_module(server: &mut Server, name: &str) {
+    loop {
+        let params: Value = server.wait_for_notification("textDocument/publishDiagnostics")
+            .unwrap();
+        let uri = params.find("uri").and_then(|uri| uri.as_string());
+        if uri.map_or(false, |uri| uri.ends_with(name)) {
+            return;
+        }
+    }
+}
+
+#[test]
+fn rename_imported_module() {
+    let dir = shapes_workspace("gluon_rename_imported_module");
+    let mut server = Server::start();
+    let params = ObjectBuilder::new()
+        .insert("processId", Value::Null)
+        .insert("rootPath", dir.to_string_lossy().into_owned())
+        .insert_object("capabilities", |capabilities| {
+            capabilities.insert_object("workspace", |workspace| {
+                workspace.insert_object("workspaceEdit", |edit| {
+                    edit.insert("documentChanges", true)
+                        .insert_array("resourceOperations", |operations| operations.push("rename"))
+                })
+            })
+        })
+        .unwrap();
+    server.request::<_, Value>("initialize", &params).unwrap();
+    wait_for_module(&mut server, "/main.glu");
+    server.open(URI, "let s = import \"shapes.glu\" in\ns.unit").unwrap();
+    let params = ObjectBuilder::new()
+        .insert_object("textDocument", |document| document.insert("uri", URI))
+        .insert_object("position", |position| position.insert("line", 0).insert("character", 17))
+        .insert("newName", "geometry.shapes")
+        .unwrap();
+    let edit: Value = server.request("textDocument/rename", &params).unwrap();
+    let string = |path: &str| edit.pointer(path).and_then(|value| value.as_string()).unwrap();
+    assert_eq!(string("/documentChanges/0/kind"), "rename");
+    assert!(string("/documentChanges/0/oldUri")
+        .ends_with("/gluon_rename_imported_module/shapes.glu"));
+    assert!(string("/documentChanges/0/newUri")
+        .ends_with("/gluon_rename_imported_module/geometry/shapes.glu"));
+    // The edits are ordered by uri
+    assert_eq!(string("/documentChanges/1/textDocument/uri"), URI);
+    assert_eq!(edit.pointer("/documentChanges/1/textDocument/version").and_then(|v| v.as_u64()),
+               Some(1));
+    assert_eq!(string("/documentChanges/1/edits/0/newText"), "geometry/shapes.glu");
+    assert_eq!(edit.pointer("/documentChanges/1/edits/0/range/start/character")
+                   .and_then(|character| character.as_u64()),
+               Some(16));
+    assert!(string("/documentChanges/2/textDocument/uri").ends_with("/main.glu"));
+    assert_eq!(edit.pointer("/documentChanges/2/textDocument/version"), Some(&Value::Null));
+    assert_eq!(edit.pointer("/documentChanges/3"), None);
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn report_exports_which_no_module_uses() {
+    let dir = env::temp_dir().join("gluon_dead_code");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    File::create(dir.join("lib.glu"))
+        .unwrap()
+        .write_all(b"let used = 1 in\nlet unused = 2 in\n{ used, unused }")
+        .unwrap();
+    File::create(dir.join("main.glu"))
+        .unwrap()
+        .write_all(b"let lib = import \"lib.glu\" in\nlib.used")
+        .unwrap();
+    let mut server = Server::start();
+    server.initialize(Some(&dir)).unwrap();
+    loop {
+        let params: Value = server.wait_for_notification("textDocument/publishDiagnostics")
+            .unwrap();
+        if !params.find("uri").and_then(|uri| uri.as_string()).unwrap().ends_with("/lib.glu") {
+            continue;
+        }
+        let diagnostics = params.find("diagnostics").and_then(|d| d.as_array()).unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].find("code").and_then(|code| code.as_string()),
+                   Some("dead-code"));
+        assert_eq!(diagnostics[0].pointer("/range/start/line").and_then(|line| line.as_u64()),
+                   Some(1));
+        break;
+    }
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn move_binding_to_new_module() {
+    let dir = env::temp_dir().join("gluon_move_binding");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let shapes = "let unit = 1.0 in\nlet half = 0.5 in\n{ unit, half }";
+    File::create(dir.join("shapes.glu")).unwrap().write_all(shapes.as_bytes()).unwrap();
+    File::create(dir.join("main.glu"))
+        .unwrap()
+        .write_all(b"let { unit, half } = import \"shapes.glu\" in\nunit")
+        .unwrap();
+    let mut server = Server::start();
+    let params = ObjectBuilder::new()
+        .insert("processId", Value::Null)
+        .insert("rootPath", dir.to_string_lossy().into_owned())
+        .insert_object("capabilities", |capabilities| {
+            capabilities.insert_object("workspace", |workspace| {
+                workspace.insert_object("workspaceEdit", |edit| {
+                    edit.insert("documentChanges", true)
+                        .insert_array("resourceOperations", |operations| operations.push("create"))
+                })
+            })
+        })
+        .unwrap();
+    server.request::<_, Value>("initialize", &params).unwrap();
+    wait_for_module(&mut server, "/main.glu");
+    let uri = format!("file://{}", dir.join("shapes.glu").display());
+    server.open(&uri, shapes).unwrap();
+    let params = ObjectBuilder::new()
+        .insert("command", "gluon.moveBinding")
+        .insert_array("arguments", |arguments| {
+            arguments.push(&uri[..])
+                .push_object(|position| position.insert("line", 1).insert("character", 5))
+                .push("units")
+        })
+        .unwrap();
+    server.request::<_, Value>("workspace/executeCommand", &params).unwrap();
+    let (id, params): (Value, Value) = server.wait_for_request("workspace/applyEdit").unwrap();
+    let string = |path: &str| {
+        params.pointer(&format!("/edit/documentChanges/{}", path))
+            .and_then(|value| value.as_string())
+            .unwrap()
+    };
+    assert_eq!(string("0/kind"), "create");
+    assert!(string("0/uri").ends_with("/gluon_move_binding/units.glu"));
+    assert_eq!(string("1/textDocument/uri"), string("0/uri"));
+    assert_eq!(string("1/edits/0/newText"), "let half = 0.5 in\n{ half }\n");
+    // The module still exports the binding
+    assert_eq!(string("2/textDocument/uri"), uri);
+    assert_eq!(string("2/edits/0/newText"), "let { half } = import \"units.glu\" in\n");
+    assert!(string("3/textDocument/uri").ends_with("/main.glu"));
+    assert_eq!(string("3/edits/0/newText"), "{ unit }");
+    assert_eq!(string("3/edits/1/newText"), "let { half } = import \"units.glu\" in\n");
+    server.respond(id, &ObjectBuilder::new().insert("applied", true).unwrap()).unwrap();
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn will_rename_files_updates_imports() {
+    let dir = shapes_workspace("gluon_will_rename_files");
+    let mut server = Server::start();
+    server.initialize(Some(&dir)).unwrap();
+    wait_for_module(&mut server, "/main.glu");
+    server.open(URI, "let s = import \"shapes.glu\" in\ns.unit").unwrap();
+    let old_uri = format!("file://{}", dir.join("shapes.glu").display());
+    let new_uri = format!("file://{}", dir.join("geometry").join("shapes.glu").display());
+    let params = ObjectBuilder::new()
+        .insert_array("files", |files| {
+            files.push_object(|file| file.insert("oldUri", old_uri).insert("newUri", new_uri))
+        })
+        .unwrap();
+    let edit: Value = server.request("workspace/willRenameFiles", &params).unwrap();
+    let changes = edit.find("changes").and_then(|changes| changes.as_object()).unwrap();
+    assert_eq!(changes.len(), 2);
+    let edits = changes[URI].as_array().unwrap();
+    assert_eq!(edits[0].find("newText").and_then(|text| text.as_string()),
+               Some("geometry/shapes.glu"));
+    server.shutdown().unwrap();
+}
+
+fn code_actions(server: &mut Server, line: u64, character: u64) -> Value {
+    let params = ObjectBuilder::new()
+        .insert_object("textDocument", |document| document.insert("uri", URI))
+        .insert_object("range", |range| {
+            range.insert_object("start", |start| {
+                    start.insert("line", line).insert("character", character)
+                })
+                .insert_object("end", |end| end.insert("line", line).insert("character", character))
+        })
+        .insert_object("context",
+                       |context| context.insert_array("diagnostics", |diagnostics| diagnostics))
+        .unwrap();
+    server.request("textDocument/codeAction", &params).unwrap()
+}
+
+/// Returns the quickfix which is offered for `diagnostic`
+fn quickfix(server: &mut Server, diagnostic: &Value) -> Value {
+    let params = ObjectBuilder::new()
+        .insert_object("textDocument", |document| document.insert("uri", URI))
+        .insert("range", diagnostic.find("range").unwrap())
+        .insert_object("context", |context| {
+            context.insert_array("diagnostics", |builder| builder.push(diagnostic.clone()))
+        })
+        .unwrap();
+    let actions: Value = server.request("textDocument/codeAction", &params).unwrap();
+    actions.as_array()
+        .unwrap()
+        .iter()
+        .find(|action| action.find("kind").and_then(|kind| kind.as_string()) == Some("quickfix"))
+        .unwrap()
+        .clone()
+}
+
+#[test]
+fn convert_between_nested_applications_and_pipelines() {
+    let mut server = Server::start();
+    server.initialize(None).unwrap();
+    server.open(URI, "let (|>) x f = f x in\nlet f x = x in\nf ((+) 1 (f 2))").unwrap();
+    let actions = code_actions(&mut server, 2, 0);
+    let string = |actions: &Value, path: &str| {
+        actions.pointer(path).and_then(|value| value.as_string()).map(String::from)
+    };
+    assert_eq!(string(&actions, "/0/title"), Some("Convert to pipeline".into()));
+    let edit = format!("/0/edit/changes/{}/0", URI.replace("/", "~1"));
+    assert_eq!(string(&actions, &format!("{}/newText", edit)),
+               Some("2 |> f |> (+) 1 |> f".into()));
+    assert_eq!(actions.pointer(&format!("{}/range/end/character", edit))
+                   .and_then(|character| character.as_u64()),
+               Some(15));
+    assert_eq!(actions.pointer("/1"), None);
+
+    server.change(URI, 2, "let (|>) x f = f x in\nlet f x = x in\n2 |> f |> (+) 1 |> f").unwrap();
+    let actions = code_actions(&mut server, 2, 3);
+    assert_eq!(string(&actions, "/0/title"), Some("Convert to nested applications".into()));
+    assert_eq!(string(&actions, &format!("{}/newText", edit)), Some("f ((+) 1 (f 2))".into()));
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn pass_instance_explicitly() {
+    let mut server = Server::start();
+    server.initialize(None).unwrap();
+    server.open(URI,
+                "let show_Int = { show = \\x -> \"int\" } in\nlet { show } = show_Int in\nshow 1")
+        .unwrap();
+    let actions = code_actions(&mut server, 2, 1);
+    assert_eq!(actions.pointer("/0/title").and_then(|title| title.as_string()),
+               Some("Pass `show_Int` explicitly"));
+    let edit = actions.pointer(&format!("/0/edit/changes/{}/0", URI.replace("/", "~1"))).unwrap();
+    assert_eq!(edit.pointer("/newText").and_then(|text| text.as_string()),
+               Some("show_Int.show"));
+    assert_eq!(edit.pointer("/range/end/character").and_then(|character| character.as_u64()),
+               Some(4));
+
+    assert_eq!(code_actions(&mut server, 1, 7), Value::Array(vec![]));
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn convert_between_lambdas_and_arguments() {
+    let mut server = Server::start();
+    server.initialize(None).unwrap();
+    let edit = format!("/0/edit/changes/{}/0/newText", URI.replace("/", "~1"));
+    server.open(URI, "let add : Int -> Int -> Int = \\x -> \\y -> x + y in add").unwrap();
+    let actions = code_actions(&mut server, 0, 5);
+    assert_eq!(actions.pointer("/0/title").and_then(|title| title.as_string()),
+               Some("Convert to arguments"));
+    assert_eq!(actions.pointer(&edit).and_then(|text| text.as_string()),
+               Some(" x y : Int -> Int -> Int ="));
+
+    server.change(URI, 2, "let add x y = x + y in add").unwrap();
+    let actions = code_actions(&mut server, 0, 5);
+    assert_eq!(actions.pointer("/0/title").and_then(|title| title.as_string()),
+               Some("Convert to lambdas"));
+    assert_eq!(actions.pointer(&edit).and_then(|text| text.as_string()),
+               Some(" = \\x -> \\y ->"));
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn annotate_module() {
+    let mut server = Server::start();
+    server.initialize(None).unwrap();
+    server.open(URI,
+                "let one : Int = 1 in\nlet two = one in\nlet name = \"two\" in\ntwo")
+        .unwrap();
+    let edits = |edit: &Value| -> Vec<(Option<u64>, Option<String>)> {
+        edit.pointer(&format!("/changes/{}", URI.replace("/", "~1")))
+            .and_then(|edits| edits.as_array())
+            .unwrap()
+            .iter()
+            .map(|edit| {
+                (edit.pointer("/range/start/line").and_then(|line| line.as_u64()),
+                 edit.find("newText").and_then(|text| text.as_string()).map(String::from))
+            })
+            .collect()
+    };
+    let expected = vec![(Some(1), Some(" : Int".into())), (Some(2), Some(" : String".into()))];
+
+    let actions = code_actions(&mut server, 0, 0);
+    assert_eq!(actions.pointer("/0/kind").and_then(|kind| kind.as_string()),
+               Some("source"));
+    assert_eq!(edits(actions.pointer("/0/edit").unwrap()), expected);
+
+    let params = ObjectBuilder::new()
+        .insert("command", "gluon.annotateModule")
+        .insert_array("arguments", |arguments| arguments.push(URI))
+        .unwrap();
+    server.request::<_, Value>("workspace/executeCommand", &params).unwrap();
+    let (id, params): (Value, Value) = server.wait_for_request("workspace/applyEdit").unwrap();
+    assert_eq!(params.find("label").and_then(|label| label.as_string()),
+               Some("Annotate all top-level bindings"));
+    assert_eq!(edits(params.find("edit").unwrap()), expected);
+    server.respond(id, &ObjectBuilder::new().insert("applied", true).unwrap()).unwrap();
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn remove_unused_import() {
+    let mut server = Server::start();
+    server.initialize(None).unwrap();
+    server.open(URI,
+                "let prelude = import \"std/prelude.glu\" in\n\
+                 let { id, not } = import \"std/prelude.glu\" in\nnot True")
+        .unwrap();
+    let params: Value = server.wait_for_notification("textDocument/publishDiagnostics").unwrap();
+    let diagnostics = params.find("diagnostics").and_then(|diagnostics| diagnostics.as_array())
+        .unwrap()
+        .clone();
+    let messages: Vec<_> = diagnostics.iter()
+        .filter(|diagnostic| {
+            diagnostic.find("code").and_then(|code| code.as_string()) == Some("unused-import")
+        })
+        .map(|diagnostic| diagnostic.find("message").and_then(|message| message.as_string()))
+        .collect();
+    assert_eq!(messages,
+               vec![Some("`prelude` is imported but never used"),
+                    Some("`id` is imported but never used")]);
+
+    let params = ObjectBuilder::new()
+        .insert_object("textDocument", |document| document.insert("uri", URI))
+        .insert("range", diagnostics[1].find("range").unwrap())
+        .insert_object("context", |context| {
+            context.insert_array("diagnostics",
+                                 |builder| builder.push(diagnostics[1].clone()))
+        })
+        .unwrap();
+    let actions: Value = server.request("textDocument/codeAction", &params).unwrap();
+    let quickfix = actions.as_array()
+        .unwrap()
+        .iter()
+        .find(|action| action.find("kind").and_then(|kind| kind.as_string()) == Some("quickfix"))
+        .unwrap();
+    assert_eq!(quickfix.find("title").and_then(|title| title.as_string()),
+               Some("Remove unused import `id`"));
+    let edit = format!("/edit/changes/{}/0/newText", URI.replace("/", "~1"));
+    assert_eq!(quickfix.pointer(&edit).and_then(|text| text.as_string()),
+               Some("{ not }"));
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn hover_record_field() {
+    let mut server = Server::start();
+    server.initialize(None).unwrap();
+    server.open(URI, POINT).unwrap();
+    let hover: Value = server.request("textDocument/hover", &position(6, 2)).unwrap();
+    let contents: Vec<_> = hover.find("contents")
+        .and_then(|contents| contents.as_array())
+        .unwrap()
+        .iter()
+        .filter_map(|content| content.as_string())
+        .collect();
+    assert_eq!(contents[0], "Int");
+    assert!(contents[1].starts_with("Field of `Point`"), "{}", contents[1]);
+    assert_eq!(contents[2], "The horizontal coordinate");
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn hover_literal() {
+    let mut server = Server::start();
+    server.initialize(None).unwrap();
+    server.open(URI, "let x = 1.5 in\nx").unwrap();
+    let hover: Value = server.request("textDocument/hover", &position(0, 9)).unwrap();
+    let contents: Vec<_> = hover.find("contents")
+        .and_then(|contents| contents.as_array())
+        .unwrap()
+        .iter()
+        .filter_map(|content| content.as_string())
+        .collect();
+    assert_eq!(contents, ["Float", "Float literal"]);
+    let start = hover.lookup("range.start.character").and_then(|c| c.as_u64());
+    let end = hover.lookup("range.end.character").and_then(|c| c.as_u64());
+    assert_eq!((start, end), (Some(8), Some(11)));
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn hover_definition() {
+    let mut server = Server::start();
+    server.initialize(None).unwrap();
+    server.open(URI, "let id x = x in\nid (not True)").unwrap();
+    let defined_in = |server: &mut Server, position: Value| {
+        let hover: Value = server.request("textDocument/hover", &position).unwrap();
+        hover.find("contents")
+            .and_then(|contents| contents.as_array())
+            .and_then(|contents| contents.last())
+            .and_then(|content| content.as_string())
+            .map(String::from)
+    };
+    let local = defined_in(&mut server, position(1, 0)).unwrap();
+    assert!(local.ends_with("test.glu:1`"), "{}", local);
+    let prelude = defined_in(&mut server, position(1, 5));
+    assert_eq!(prelude.as_ref().map(|s| &s[..]),
+               Some("Defined in `std/prelude.glu:72`"));
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn hover_settings() {
+    let mut server = Server::start();
+    server.initialize(None).unwrap();
+    let settings = ObjectBuilder::new()
+        .insert_object("settings", |settings| {
+            settings.insert_object("gluon", |gluon| {
+                gluon.insert_object("hover", |hover| {
+                    hover.insert("maxTypeLength", 3)
+                        .insert("aliasExpansionDepth", 1)
+                        .insert("documentation", false)
+                })
+            })
+        })
+        .unwrap();
+    server.notify("workspace/didChangeConfiguration", &settings).unwrap();
+    let text = "type Point = { x : Int, y : Int } in\n/// The origin\nlet origin : Point = { x = \
+                0, y = 0 } in\norigin";
+    server.open(URI, text).unwrap();
+    let hover: Value = server.request("textDocument/hover", &position(3, 0)).unwrap();
+    let contents: Vec<_> = hover.find("contents")
+        .and_then(|contents| contents.as_array())
+        .unwrap()
+        .iter()
+        .filter_map(|content| content.as_string())
+        .collect();
+    // The alias is expanded into the record type before it is cut off
+    assert!(contents[0].starts_with('{') && contents[0].ends_with('…'),
+            "{}",
+            contents[0]);
+    assert_eq!(contents[0].chars().count(), 4);
+    assert!(contents[1].contains("gluon.showType"));
+    assert!(!contents.contains(&"The origin"));
+    let params = ObjectBuilder::new()
+        .insert("command", "gluon.showType")
+        .insert_array("arguments", |arguments| {
+            arguments.push(URI)
+                .push_object(|position| position.insert("line", 3).insert("character", 0))
+        })
+        .unwrap();
+    let result: Value = server.request("workspace/executeCommand", &params).unwrap();
+    let typ = result.find("text").and_then(|text| text.as_string()).unwrap();
+    assert!(typ.starts_with('{') && typ.contains("Int") && !typ.ends_with('…'), "{}", typ);
+    server.shutdown().unwrap();
+}
+
+const DOCUMENTED_ADD: &'static str = r"/// Adds two numbers
+/// @param x The left operand
+/// @param y The right operand
+let add x y : Int -> Int -> Int = x + y in
+add 1 2";
+
+#[test]
+fn view_bytecode() {
+    let mut server = Server::start();
+    server.initialize(None).unwrap();
+    server.open(URI, DOCUMENTED_ADD).unwrap();
+    let params = ObjectBuilder::new()
+        .insert("command", "gluon.viewBytecode")
+        .insert_array("arguments", |arguments| arguments.push(URI))
+        .unwrap();
+    let result: Value = server.request("workspace/executeCommand", &params).unwrap();
+    let listing = result.find("text").and_then(|text| text.as_string()).unwrap();
+    assert!(listing.contains("\nadd (2 arguments)  // line 4\n"), "{}", listing);
+    assert!(listing.contains("AddInt"), "{}", listing);
+    let params = ObjectBuilder::new()
+        .insert("command", "gluon.viewBytecode")
+        .insert_array("arguments", |arguments| {
+            arguments.push(URI)
+                .push_object(|position| position.insert("line", 3).insert("character", 36))
+        })
+        .unwrap();
+    let result: Value = server.request("workspace/executeCommand", &params).unwrap();
+    let listing = result.find("text").and_then(|text| text.as_string()).unwrap();
+    assert!(listing.starts_with("add (2 arguments)"), "{}", listing);
+    let result: Value = server.request("gluon/viewBytecode", &position(3, 36)).unwrap();
+    let listing = result.find("text").and_then(|text| text.as_string()).unwrap();
+    assert!(listing.starts_with("add (2 arguments)"), "{}", listing);
+    assert!(listing.contains("AddInt  // line 4\n"), "{}", listing);
+    server.shutdown().unwrap();
+}
+
+const SCALE: &'static str = r"let scale n : Int -> Int =
+    let doubled = n + n
+    let tripled = doubled + n
+    doubled * tripled
+in
+scale 2";
+
+#[test]
+fn view_bytecode_of_function_with_locals() {
+    let mut server = Server::start();
+    server.initialize(None).unwrap();
+    server.open(URI, SCALE).unwrap();
+    let result: Value = server.request("gluon/viewBytecode", &position(3, 4)).unwrap();
+    let listing = result.find("text").and_then(|text| text.as_string()).unwrap();
+    assert!(listing.starts_with("scale (1 arguments)  // line 1\n"), "{}", listing);
+    // The markers shift the stack indices of the locals in the compilation which finds the lines
+    let lines: Vec<_> = listing.lines()
+        .filter(|line| line.contains("AddInt") || line.contains("MultiplyInt"))
+        .map(|line| line.split("  // ").nth(1))
+        .collect();
+    assert_eq!(lines, vec![Some("line 2"), Some("line 3"), Some("line 4")]);
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn run_bench_from_code_lens() {
+    let mut server = Server::start();
+    server.initialize(None).unwrap();
+    server.open(URI,
+                "let sum n = if n == 0 then 0 else n + sum (n - 1) in\nlet bench_sum _ = sum 100 \
+                 in\n{ sum }")
+        .unwrap();
+    let settings = ObjectBuilder::new()
+        .insert_object("settings", |settings| {
+            settings.insert_object("gluon", |gluon| {
+                gluon.insert_object("bench", |bench| bench.insert("iterations", 5))
+            })
+        })
+        .unwrap();
+    server.notify("workspace/didChangeConfiguration", &settings).unwrap();
+    let params = ObjectBuilder::new()
+        .insert_object("textDocument", |document| document.insert("uri", URI))
+        .unwrap();
+    let lenses: Value = server.request("textDocument/codeLens", &params).unwrap();
+    let lenses = lenses.as_array().unwrap();
+    assert_eq!(lenses.len(), 1);
+    assert_eq!(lenses[0].pointer("/range/start/line").and_then(|line| line.as_u64()),
+               Some(1));
+    assert_eq!(lenses[0].pointer("/command/title").and_then(|title| title.as_string()),
+               Some("Run bench"));
+    let params = ObjectBuilder::new()
+        .insert("command", "gluon.runBench")
+        .insert("arguments", lenses[0].pointer("/command/arguments").unwrap())
+        .unwrap();
+    let result: Value = server.request("workspace/executeCommand", &params).unwrap();
+    assert_eq!(result.find("name").and_then(|name| name.as_string()), Some("bench_sum"));
+    assert_eq!(result.find("iterations").and_then(|n| n.as_u64()), Some(5));
+    let min = result.find("min").and_then(|min| min.as_f64()).unwrap();
+    let p95 = result.find("p95").and_then(|p95| p95.as_f64()).unwrap();
+    assert!(min <= p95, "{:?}", result);
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn hover_renders_doc_sections() {
+    let mut server = Server::start();
+    server.initialize(None).unwrap();
+    server.open(URI, DOCUMENTED_ADD).unwrap();
+    let hover: Value = server.request("textDocument/hover", &position(4, 0)).unwrap();
+    let contents = hover.find("contents").and_then(|contents| contents.as_array()).unwrap();
+    assert!(contents.iter().any(|content| {
+        content.as_string() ==
+        Some("Adds two numbers\n\n**Parameters**\n\n- `x`: The left operand\n- `y`: The right \
+              operand")
+    }));
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn signature_help() {
+    let mut server = Server::start();
+    server.initialize(None).unwrap();
+    server.open(URI, DOCUMENTED_ADD).unwrap();
+    let help: Value = server.request("textDocument/signatureHelp", &position(4, 6)).unwrap();
+    assert_eq!(help.find("activeParameter").and_then(|active| active.as_u64()), Some(1));
+    let signature = help.pointer("/signatures/0").unwrap();
+    assert_eq!(signature.find("label").and_then(|label| label.as_string()),
+               Some("add : Int -> Int -> Int"));
+    assert_eq!(signature.find("documentation").and_then(|doc| doc.as_string()),
+               Some("Adds two numbers"));
+    let parameter = signature.pointer("/parameters/1").unwrap();
+    assert_eq!(parameter.find("label").and_then(|label| label.as_string()), Some("Int"));
+    assert_eq!(parameter.find("documentation").and_then(|doc| doc.as_string()),
+               Some("`y`: The right operand"));
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn complete_operator() {
+    let mut server = Server::start();
+    server.initialize(None).unwrap();
+    server.open(URI, "let x = 1 + 2 in x").unwrap();
+    let list: Value = server.request("textDocument/completion", &position(0, 11)).unwrap();
+    let items = list.find("items").and_then(|items| items.as_array()).unwrap();
+    let plus = items.iter()
+        .find(|item| item.find("label").and_then(|label| label.as_string()) == Some("+"))
+        .unwrap();
+    let detail = plus.find("detail").and_then(|detail| detail.as_string()).unwrap();
+    assert!(detail.contains("Int -> Int -> Int"), "{}", detail);
+    assert!(detail.ends_with("(infixl 6)"), "{}", detail);
+    server.shutdown().unwrap();
+}
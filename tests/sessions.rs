@@ -0,0 +1,26 @@
+//! Replays the sessions recorded in `tests/sessions`. A session which needs files is replayed with
+//! the directory of the same name as the root of its workspace.
+extern crate gluon_language_server;
+
+use std::fs;
+use std::path::Path;
+
+use gluon_language_server::test;
+
+#[test]
+fn replay_sessions() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("sessions");
+    let mut failures = Vec::new();
+    for entry in fs::read_dir(&dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().map_or(true, |ext| ext != "jsonl") {
+            continue;
+        }
+        let root = path.with_extension("");
+        let root = if root.is_dir() { root } else { dir.clone() };
+        if let Err(err) = test::replay(&path, &root) {
+            failures.push(format!("{}: {}", path.display(), err));
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n\n"));
+}